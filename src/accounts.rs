@@ -1,4 +1,4 @@
-use anyhow::{Result, anyhow};
+use crate::error::{NostrError, Result};
 use secrecy::{ExposeSecret, SecretString};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -7,7 +7,9 @@ use std::path::{Path, PathBuf};
 use uuid::Uuid;
 
 use crate::keystore::{DecryptedKeys, EncryptedKeystore, KeystoreManager};
-use crate::nostr::{NostrKeypair, generate_keypair, keypair_from_hex};
+use crate::nostr::nip17::PrivateMessage;
+use crate::nostr::nip59::UnwrappedGift;
+use crate::nostr::{NostrEvent, NostrKeypair, generate_keypair, keypair_from_hex};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AccountInfo {
@@ -39,6 +41,13 @@ pub struct UnlockedAccount {
     pub keypair: NostrKeypair,
 }
 
+/// Where accounts and the keystore live unless a command is pointed elsewhere
+pub fn default_config_dir() -> PathBuf {
+    dirs::config_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("nosotros")
+}
+
 pub struct AccountManager {
     config_dir: PathBuf,
     keystore_manager: KeystoreManager,
@@ -94,6 +103,11 @@ impl AccountManager {
         self.unlocked_keys.is_some()
     }
 
+    /// Whether a keystore exists yet; the first unlock creates it with the given password
+    pub fn has_keystore(&self) -> bool {
+        self.keystore_path().exists()
+    }
+
     pub fn create_account(&mut self, name: &str, password: &SecretString) -> Result<AccountInfo> {
         if !self.is_unlocked() {
             self.unlock_keystore(password)?;
@@ -144,7 +158,7 @@ impl AccountManager {
             .iter()
             .any(|acc| acc.public_key_hex == public_key_hex)
         {
-            return Err(anyhow!("Account with this public key already exists"));
+            return Err(NostrError::InvalidInput("Account with this public key already exists".to_string()));
         }
 
         let account_info = AccountInfo {
@@ -179,7 +193,7 @@ impl AccountManager {
             .accounts
             .iter()
             .position(|acc| acc.id == account_id)
-            .ok_or_else(|| anyhow!("Account not found"))?;
+            .ok_or_else(|| NostrError::NotFound("Account not found".to_string()))?;
 
         let was_active =
             self.accounts_config.active_account_id.as_ref() == Some(&account_id.to_string());
@@ -193,6 +207,10 @@ impl AccountManager {
                 .accounts
                 .first()
                 .map(|acc| acc.id.clone());
+
+            if let Some(first) = self.accounts_config.accounts.first_mut() {
+                first.is_active = true;
+            }
         }
 
         self.save_accounts_config()?;
@@ -207,7 +225,7 @@ impl AccountManager {
             .iter()
             .any(|acc| acc.id == account_id)
         {
-            return Err(anyhow!("Account not found"));
+            return Err(NostrError::NotFound("Account not found".to_string()));
         }
 
         for account in &mut self.accounts_config.accounts {
@@ -224,7 +242,7 @@ impl AccountManager {
         let unlocked_keys = self
             .unlocked_keys
             .as_ref()
-            .ok_or_else(|| anyhow!("Keystore is locked"))?;
+            .ok_or(NostrError::KeystoreLocked)?;
 
         let active_id = match &self.accounts_config.active_account_id {
             Some(id) => id,
@@ -236,11 +254,11 @@ impl AccountManager {
             .accounts
             .iter()
             .find(|acc| acc.id == *active_id)
-            .ok_or_else(|| anyhow!("Active account not found in config"))?;
+            .ok_or_else(|| NostrError::NotFound("Active account not found in config".to_string()))?;
 
         let private_key = unlocked_keys
             .get_key(active_id)
-            .ok_or_else(|| anyhow!("Private key not found for active account"))?;
+            .ok_or_else(|| NostrError::NotFound("Private key not found for active account".to_string()))?;
 
         let keypair = keypair_from_hex(private_key.expose_secret())?;
 
@@ -254,7 +272,7 @@ impl AccountManager {
         let unlocked_keys = self
             .unlocked_keys
             .as_ref()
-            .ok_or_else(|| anyhow!("Keystore is locked"))?;
+            .ok_or(NostrError::KeystoreLocked)?;
 
         let account_info = match self
             .accounts_config
@@ -279,6 +297,78 @@ impl AccountManager {
         }))
     }
 
+    /// Open a gift wrap addressed to any account whose key is unlocked,
+    /// returning the account it was for along with its contents
+    pub fn unwrap_gift_wrap(&self, wrap: &NostrEvent) -> Result<(AccountInfo, UnwrappedGift)> {
+        let (account, keypair) = self
+            .unlocked_keypair_for(wrap.tagged_pubkeys())?
+            .ok_or_else(|| {
+                NostrError::DecryptionFailed("Gift wrap is not addressed to any unlocked account".to_string())
+            })?;
+
+        Ok((account, wrap.unwrap_gift(&keypair)?))
+    }
+
+    /// Decrypt a legacy NIP-04 message sent by or to any account whose key is unlocked
+    pub fn decrypt_legacy_message(&self, event: &NostrEvent) -> Result<(AccountInfo, PrivateMessage)> {
+        let participants = std::iter::once(event.pubkey.as_str()).chain(event.tagged_pubkeys());
+        let (account, keypair) = self
+            .unlocked_keypair_for(participants)?
+            .ok_or_else(|| {
+                NostrError::DecryptionFailed("Message is not from or to any unlocked account".to_string())
+            })?;
+
+        Ok((account, PrivateMessage::from_legacy_event(event, &keypair)?))
+    }
+
+    /// The first of `pubkeys` that belongs to an account with an unlocked key
+    fn unlocked_keypair_for<'a>(
+        &self,
+        pubkeys: impl IntoIterator<Item = &'a str>,
+    ) -> Result<Option<(AccountInfo, NostrKeypair)>> {
+        let unlocked_keys = self
+            .unlocked_keys
+            .as_ref()
+            .ok_or(NostrError::KeystoreLocked)?;
+
+        for pubkey in pubkeys {
+            let Some(account) = self
+                .accounts_config
+                .accounts
+                .iter()
+                .find(|acc| acc.public_key_hex == pubkey)
+            else {
+                continue;
+            };
+
+            if let Some(private_key) = unlocked_keys.get_key(&account.id) {
+                let keypair = keypair_from_hex(private_key.expose_secret())?;
+                return Ok(Some((account.clone(), keypair)));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Look an account up by id, or by name when no id matches
+    pub fn find_account(&self, name_or_id: &str) -> Result<&AccountInfo> {
+        let accounts = &self.accounts_config.accounts;
+
+        if let Some(account) = accounts.iter().find(|acc| acc.id == name_or_id) {
+            return Ok(account);
+        }
+
+        let mut named = accounts.iter().filter(|acc| acc.name == name_or_id);
+        match (named.next(), named.next()) {
+            (Some(account), None) => Ok(account),
+            (Some(_), Some(_)) => Err(NostrError::InvalidInput(format!(
+                "Several accounts are named '{}', use the account id instead",
+                name_or_id
+            ))),
+            (None, _) => Err(NostrError::NotFound(format!("No account named '{}'", name_or_id))),
+        }
+    }
+
     pub fn list_accounts(&self) -> &[AccountInfo] {
         &self.accounts_config.accounts
     }
@@ -0,0 +1,202 @@
+use crate::error::{NostrError, Result};
+use secrecy::SecretString;
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+use crate::accounts::{AccountInfo, AccountManager};
+use crate::nostr::{NostrKeypair, keypair_from_secret};
+
+/// `account` subcommands: manage the accounts stored in the encrypted keystore
+pub struct AccountCommand {
+    account_manager: AccountManager,
+}
+
+impl AccountCommand {
+    pub fn new(config_dir: PathBuf) -> Result<Self> {
+        Ok(Self {
+            account_manager: AccountManager::new(config_dir)?,
+        })
+    }
+
+    /// Generate a new key pair and store it as a named account
+    pub fn create(&mut self, name: &str) -> Result<()> {
+        let password = self.prompt_keystore_password()?;
+        let account = self.account_manager.create_account(name, &password)?;
+
+        println!("Created account:");
+        print_account(&account);
+        Ok(())
+    }
+
+    /// Store an existing nsec or hex private key as a named account
+    pub fn import(&mut self, name: &str) -> Result<()> {
+        let secret = rpassword::prompt_password("Private key (nsec or hex): ")?;
+        let keypair = keypair_from_secret(&secret)?;
+
+        let password = self.prompt_keystore_password()?;
+        let account = self
+            .account_manager
+            .import_account(name, &keypair.secret_key_hex(), &password)?;
+
+        println!("Imported account:");
+        print_account(&account);
+        Ok(())
+    }
+
+    pub fn list(&self) {
+        let accounts = self.account_manager.list_accounts();
+
+        if accounts.is_empty() {
+            println!("No accounts yet. Create one with `nosotros account new <name>`.");
+            return;
+        }
+
+        for account in accounts {
+            let marker = if account.is_active { "*" } else { " " };
+            println!("{} {}  {}  {}", marker, account.name, account.public_key_npub, account.id);
+        }
+    }
+
+    /// Make an account the default for `post` and the TUI
+    pub fn use_account(&mut self, name_or_id: &str) -> Result<()> {
+        let account = self.account_manager.find_account(name_or_id)?.clone();
+        self.account_manager.set_active_account(&account.id)?;
+
+        println!("Active account is now '{}' ({})", account.name, account.public_key_npub);
+        Ok(())
+    }
+
+    /// Delete an account and its private key, asking first unless `confirmed`
+    pub fn remove(&mut self, name_or_id: &str, confirmed: bool) -> Result<()> {
+        let account = self.account_manager.find_account(name_or_id)?.clone();
+
+        if !confirmed {
+            let question = format!(
+                "Delete account '{}' ({})? Its private key cannot be recovered without a backup. [y/N] ",
+                account.name, account.public_key_npub
+            );
+            if !confirm(&question)? {
+                println!("Kept account '{}'", account.name);
+                return Ok(());
+            }
+        }
+
+        let password = prompt_password("Keystore password: ")?;
+        self.account_manager.delete_account(&account.id, &password)?;
+
+        println!("Deleted account '{}'", account.name);
+        Ok(())
+    }
+
+    /// Print an account's public details, the active account by default
+    pub fn show(&self, name_or_id: Option<&str>) -> Result<()> {
+        let account = match name_or_id {
+            Some(name_or_id) => self.account_manager.find_account(name_or_id)?,
+            None => active_account_info(&self.account_manager)?,
+        };
+
+        print_account(account);
+        Ok(())
+    }
+
+    /// Ask for the keystore password, twice when it is about to create the keystore
+    fn prompt_keystore_password(&self) -> Result<SecretString> {
+        if self.account_manager.has_keystore() {
+            return prompt_password("Keystore password: ");
+        }
+
+        println!("No keystore yet, choose a password to encrypt your keys with.");
+        let password = rpassword::prompt_password("New keystore password: ")?;
+        let repeated = rpassword::prompt_password("Repeat password: ")?;
+
+        if password != repeated {
+            return Err(NostrError::InvalidInput("Passwords do not match".to_string()));
+        }
+        if password.is_empty() {
+            return Err(NostrError::InvalidInput("Password cannot be empty".to_string()));
+        }
+
+        Ok(SecretString::from(password))
+    }
+}
+
+/// Unlock the keystore and load the signing key of the named account, or of
+/// the active account when no name is given
+pub fn load_account_keypair(config_dir: PathBuf, name_or_id: Option<&str>) -> Result<NostrKeypair> {
+    let mut account_manager = AccountManager::new(config_dir)?;
+
+    let account = match name_or_id {
+        Some(name_or_id) => account_manager.find_account(name_or_id)?,
+        None => active_account_info(&account_manager)?,
+    }
+    .clone();
+
+    let password = prompt_password(&format!("Keystore password for '{}': ", account.name))?;
+    account_manager.unlock_keystore(&password)?;
+
+    account_manager
+        .get_account(&account.id)?
+        .map(|unlocked| unlocked.keypair)
+        .ok_or_else(|| {
+            NostrError::NotFound(format!(
+                "Private key for account '{}' is missing from the keystore",
+                account.name
+            ))
+        })
+}
+
+/// Open the account store and unlock every account's key with the keystore password
+pub fn unlock_account_manager(config_dir: PathBuf) -> Result<AccountManager> {
+    let mut account_manager = AccountManager::new(config_dir)?;
+    if account_manager.list_accounts().is_empty() {
+        return Err(NostrError::NotFound(
+            "No accounts yet. Create one with `nosotros account new <name>`".to_string(),
+        ));
+    }
+
+    let password = prompt_password("Keystore password: ")?;
+    account_manager.unlock_keystore(&password)?;
+    Ok(account_manager)
+}
+
+/// Public key (hex) of the active account, without unlocking the keystore
+pub fn active_public_key(config_dir: PathBuf) -> Result<String> {
+    let account_manager = AccountManager::new(config_dir)?;
+    Ok(active_account_info(&account_manager)?.public_key_hex.clone())
+}
+
+fn active_account_info(account_manager: &AccountManager) -> Result<&AccountInfo> {
+    let active_id = account_manager
+        .active_account_id()
+        .ok_or_else(|| {
+            NostrError::NotFound(
+                "No active account. Create one with `nosotros account new <name>`".to_string(),
+            )
+        })?;
+
+    account_manager.find_account(active_id)
+}
+
+fn print_account(account: &AccountInfo) {
+    println!("Name:       {}", account.name);
+    println!("ID:         {}", account.id);
+    println!("Public key: {}", account.public_key_npub);
+    println!("Hex:        {}", account.public_key_hex);
+    println!("Created:    {}", account.created_at);
+    println!("Active:     {}", if account.is_active { "yes" } else { "no" });
+}
+
+/// Read a password from the terminal without echoing it
+fn prompt_password(prompt: &str) -> Result<SecretString> {
+    Ok(SecretString::from(rpassword::prompt_password(prompt)?))
+}
+
+/// Ask a yes/no question on the terminal, no unless answered with y or yes
+pub fn confirm(question: &str) -> Result<bool> {
+    print!("{}", question);
+    io::stdout().flush()?;
+
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+    Ok(matches!(answer.trim(), "y" | "Y" | "yes"))
+}
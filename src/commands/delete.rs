@@ -0,0 +1,142 @@
+use crate::error::{NostrError, Result};
+use std::path::PathBuf;
+
+use crate::commands::relay::answer_auth_requests;
+use crate::connection::ReconnectPolicy;
+use crate::event_store::EventStore;
+use crate::nostr::nip09::DeletionRequest;
+use crate::nostr::{Filter, Nip19, NostrKeypair};
+use crate::pool::{RelayPool, none_accepted};
+use crate::relay_settings::RelaySettings;
+
+/// `delete`: ask relays to drop one of your events (NIP-09).
+///
+/// The request goes to every relay the event store recorded as having
+/// accepted the event, plus any given. Relays and other clients may still
+/// keep a copy; deletion is a request, not a guarantee.
+pub struct DeleteCommand {
+    relay_urls: Vec<String>,
+    /// Decides whether relays requiring NIP-42 authentication get it
+    relay_settings: RelaySettings,
+    event_store: EventStore,
+}
+
+impl DeleteCommand {
+    pub fn new(config_dir: PathBuf, relay_urls: Vec<String>) -> Result<Self> {
+        Ok(Self {
+            relay_urls,
+            relay_settings: RelaySettings::default(),
+            event_store: EventStore::open(&config_dir)?,
+        })
+    }
+
+    pub fn with_relay_settings(mut self, relay_settings: RelaySettings) -> Self {
+        self.relay_settings = relay_settings;
+        self
+    }
+
+    /// Delete the event `target` points to: a note id, an nevent, or an
+    /// naddr for every version of an addressable event
+    pub async fn delete(&mut self, keypair: &NostrKeypair, target: &Nip19, reason: Option<&str>) -> Result<()> {
+        let own_pubkey = keypair.public_key_hex();
+        let (mut request, mut relay_urls) = match target {
+            Nip19::Note(id) => self.event_request(id, &own_pubkey)?,
+            Nip19::Event(pointer) => {
+                let (request, mut relay_urls) = self.event_request(&pointer.id, &own_pubkey)?;
+                relay_urls.extend(pointer.relays.iter().cloned());
+                (request, relay_urls)
+            }
+            Nip19::Address(pointer) => {
+                if pointer.pubkey != own_pubkey {
+                    return Err(NostrError::InvalidInput(
+                        "That address belongs to another author; only its author can delete it".to_string(),
+                    ));
+                }
+
+                let mut request = DeletionRequest::new()
+                    .with_address(&format!("{}:{}:{}", pointer.kind, pointer.pubkey, pointer.identifier));
+                request.kinds.push(pointer.kind);
+
+                let filter = Filter::new()
+                    .with_kinds(vec![pointer.kind])
+                    .with_authors(vec![own_pubkey.clone()])
+                    .with_tag('d', vec![pointer.identifier.clone()]);
+                let mut relay_urls = pointer.relays.clone();
+                for event in self.event_store.query(&[filter])? {
+                    relay_urls.extend(self.event_store.published_relays(&event.id)?);
+                }
+                (request, relay_urls)
+            }
+            _ => {
+                return Err(NostrError::InvalidInput(format!(
+                    "Expected a note id, nevent or naddr, got an {}",
+                    target.prefix()
+                )));
+            }
+        };
+
+        relay_urls.extend(self.relay_urls.iter().cloned());
+        relay_urls.sort();
+        relay_urls.dedup();
+        if relay_urls.is_empty() {
+            return Err(NostrError::InvalidInput(
+                "Not known which relays the event was sent to; pass them with --relay".to_string(),
+            ));
+        }
+
+        if let Some(reason) = reason {
+            request = request.with_reason(reason);
+        }
+        let deletion = request.to_event(keypair)?;
+
+        let relay_pool = RelayPool::with_reconnect_policy(ReconnectPolicy::none());
+        self.relay_settings.apply(relay_pool.auth());
+        relay_pool.auth().set_keypair(Some(keypair.clone()));
+        let auth_prompts = answer_auth_requests(&relay_pool);
+
+        let result = async {
+            for relay_url in &relay_urls {
+                relay_pool.add_relay(relay_url)?;
+            }
+            Ok::<_, NostrError>(relay_pool.publish_to(&relay_urls, &deletion).await)
+        }
+        .await;
+
+        auth_prompts.abort();
+        relay_pool.shutdown().await;
+        let outcomes = result?;
+
+        for outcome in &outcomes {
+            match &outcome.result {
+                Ok(message) => println!("📨 {} accepted deletion request: {}", outcome.relay_url, message),
+                Err(e) => println!("❌ {}: {}", outcome.relay_url, e),
+            }
+        }
+
+        let accepted = outcomes.iter().filter(|outcome| outcome.is_accepted()).count();
+        if accepted == 0 {
+            return Err(none_accepted("deletion request", &outcomes));
+        }
+
+        // Drops our own copy too, and keeps it from being stored again
+        self.event_store.save(&deletion)?;
+        println!("✅ Deletion request sent to {}/{} relays", accepted, outcomes.len());
+        Ok(())
+    }
+
+    /// A request deleting the event with id `event_id`, and the relays it
+    /// was published to. Events in the store are checked to be our own.
+    fn event_request(&self, event_id: &str, own_pubkey: &str) -> Result<(DeletionRequest, Vec<String>)> {
+        let Some(event) = self.event_store.get(event_id)? else {
+            return Ok((DeletionRequest::new().with_event_id(event_id), Vec::new()));
+        };
+
+        if event.pubkey != own_pubkey {
+            return Err(NostrError::InvalidInput(format!(
+                "Event {} was published by someone else; only its author can delete it",
+                event_id
+            )));
+        }
+        Ok((DeletionRequest::new().with_event(&event), self.event_store.published_relays(event_id)?))
+    }
+}
@@ -0,0 +1,199 @@
+use crate::error::Result;
+use std::collections::HashSet;
+use std::time::Duration;
+
+use crate::accounts::AccountManager;
+use crate::connection::ReconnectPolicy;
+use crate::nostr::nip04::KIND_ENCRYPTED_DIRECT_MESSAGE;
+use crate::nostr::nip17::{PrivateMessage, wrap_private_message};
+use crate::nostr::nip59::KIND_GIFT_WRAP;
+use crate::nostr::{Filter, Nip19, NostrEvent, NostrKeypair};
+use crate::pool::{RelayPool, none_accepted};
+
+/// How long `dm read` waits for relays to send their stored messages
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// `dm` subcommands: NIP-17 private messages, gift wrapped as described in NIP-59,
+/// plus legacy NIP-04 messages for older conversations
+pub struct DmCommand {
+    relay_urls: Vec<String>,
+}
+
+impl DmCommand {
+    pub fn new(relay_urls: Vec<String>) -> Self {
+        Self { relay_urls }
+    }
+
+    /// Send a private message to `recipient_pubkey` (hex), along with a copy
+    /// for the sender so it shows up in their other clients
+    pub async fn send(&self, sender: &NostrKeypair, recipient_pubkey: &str, content: &str) -> Result<()> {
+        let wraps = wrap_private_message(sender, &[recipient_pubkey.to_string()], content)?;
+        let relay_pool = self.connect()?;
+
+        let mut delivered = false;
+        let mut recipient_outcomes = Vec::new();
+        for (receiver, wrap) in &wraps {
+            let outcomes = relay_pool.publish_to(&self.relay_urls, wrap).await;
+            let accepted = outcomes.iter().filter(|outcome| outcome.is_accepted()).count();
+
+            let copy = if *receiver == sender.public_key_hex() { "Your copy" } else { "Message" };
+            println!("📨 {} accepted by {}/{} relays", copy, accepted, outcomes.len());
+            for outcome in &outcomes {
+                if let Err(e) = &outcome.result {
+                    println!("❌ {}: {}", outcome.relay_url, e);
+                }
+            }
+
+            if *receiver != sender.public_key_hex() {
+                delivered |= accepted > 0;
+                recipient_outcomes.extend(outcomes);
+            }
+        }
+
+        relay_pool.shutdown().await;
+
+        if !delivered {
+            return Err(none_accepted("message", &recipient_outcomes));
+        }
+
+        println!("✅ Message sent to {}", npub(recipient_pubkey));
+        Ok(())
+    }
+
+    /// Send a legacy NIP-04 message. Relays learn who is talking to whom and
+    /// when, so this is only for contacts whose clients don't support NIP-17.
+    pub async fn send_legacy(&self, sender: &NostrKeypair, recipient_pubkey: &str, content: &str) -> Result<()> {
+        let event = NostrEvent::new_legacy_direct_message(content, sender, recipient_pubkey)?;
+        let relay_pool = self.connect()?;
+
+        eprintln!("⚠️  Sending a legacy NIP-04 message: relays can see who you are talking to and when.");
+        let outcomes = relay_pool.publish_to(&self.relay_urls, &event).await;
+        relay_pool.shutdown().await;
+
+        for outcome in &outcomes {
+            match &outcome.result {
+                Ok(message) => println!("📨 {} accepted message: {}", outcome.relay_url, message),
+                Err(e) => println!("❌ {}: {}", outcome.relay_url, e),
+            }
+        }
+
+        if !outcomes.iter().any(|outcome| outcome.is_accepted()) {
+            return Err(none_accepted("message", &outcomes));
+        }
+
+        println!("✅ Legacy message sent to {}", npub(recipient_pubkey));
+        Ok(())
+    }
+
+    /// Print the private messages sent to or from `pubkeys`, oldest first,
+    /// including legacy NIP-04 ones. With `with_pubkey`, only conversations
+    /// that include that key are shown.
+    pub async fn read(&self, account_manager: &AccountManager, pubkeys: &[String], with_pubkey: Option<&str>) -> Result<()> {
+        let relay_pool = self.connect()?;
+        let filters = vec![
+            Filter::new()
+                .with_kinds(vec![KIND_GIFT_WRAP, KIND_ENCRYPTED_DIRECT_MESSAGE])
+                .with_pubkey_refs(pubkeys.to_vec()),
+            Filter::new()
+                .with_kinds(vec![KIND_ENCRYPTED_DIRECT_MESSAGE])
+                .with_authors(pubkeys.to_vec()),
+        ];
+
+        let events = relay_pool.fetch(filters, READ_TIMEOUT).await?;
+        relay_pool.shutdown().await;
+
+        let mut seen_message_ids = HashSet::new();
+        let mut messages = Vec::new();
+        let mut unreadable = 0;
+
+        for event in &events {
+            let opened = match event.kind {
+                KIND_GIFT_WRAP => account_manager
+                    .unwrap_gift_wrap(event)
+                    .and_then(|(account, gift)| Ok((account, PrivateMessage::from_gift(gift)?))),
+                _ => account_manager.decrypt_legacy_message(event),
+            };
+
+            match opened {
+                Ok((account, message)) => {
+                    let in_conversation = with_pubkey.is_none_or(|with_pubkey| {
+                        message.participants_except(&account.public_key_hex).iter().any(|pubkey| pubkey == with_pubkey)
+                    });
+
+                    // A message between two of our own accounts arrives twice
+                    if in_conversation && seen_message_ids.insert(message.id.clone()) {
+                        messages.push(message);
+                    }
+                }
+                Err(_) => unreadable += 1,
+            }
+        }
+
+        if unreadable > 0 {
+            eprintln!("Skipped {} messages that could not be decrypted", unreadable);
+        }
+
+        if messages.is_empty() {
+            println!("No private messages found");
+            return Ok(());
+        }
+
+        if messages.iter().any(|message| message.legacy) {
+            println!("Messages marked [NIP-04] use legacy encryption that leaks who talked to whom and when.\n");
+        }
+
+        messages.sort_by_key(|message| message.created_at);
+        for message in &messages {
+            let recipients: Vec<String> = message
+                .recipients
+                .iter()
+                .filter(|recipient| **recipient != message.sender)
+                .map(|recipient| display_name(account_manager, recipient))
+                .collect();
+
+            println!(
+                "[{}]{} {} → {}: {}",
+                format_timestamp(message.created_at),
+                if message.legacy { " [NIP-04]" } else { "" },
+                display_name(account_manager, &message.sender),
+                recipients.join(", "),
+                message.content
+            );
+        }
+
+        Ok(())
+    }
+
+    /// A pool for a one-shot command, which reports unreachable relays instead of retrying them
+    fn connect(&self) -> Result<RelayPool> {
+        let relay_pool = RelayPool::with_reconnect_policy(ReconnectPolicy::none());
+
+        for relay_url in &self.relay_urls {
+            relay_pool.add_relay(relay_url)?;
+        }
+
+        Ok(relay_pool)
+    }
+}
+
+/// Our own accounts by name, everyone else by npub
+fn display_name(account_manager: &AccountManager, pubkey: &str) -> String {
+    account_manager
+        .list_accounts()
+        .iter()
+        .find(|account| account.public_key_hex == pubkey)
+        .map(|account| account.name.clone())
+        .unwrap_or_else(|| npub(pubkey))
+}
+
+fn npub(pubkey: &str) -> String {
+    Nip19::Pubkey(pubkey.to_string())
+        .to_bech32()
+        .unwrap_or_else(|_| pubkey.to_string())
+}
+
+fn format_timestamp(created_at: u64) -> String {
+    chrono::DateTime::from_timestamp(created_at as i64, 0)
+        .map(|date| date.format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_default()
+}
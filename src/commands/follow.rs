@@ -0,0 +1,185 @@
+use crate::error::{NostrError, Result};
+use std::time::Duration;
+
+use crate::commands::relay::answer_auth_requests;
+use crate::connection::ReconnectPolicy;
+use crate::nostr::nip02::{ContactList, KIND_CONTACT_LIST};
+use crate::nostr::{Filter, Nip19, NostrKeypair};
+use crate::pool::{RelayPool, none_accepted};
+use crate::relay_settings::RelaySettings;
+
+/// How long to wait for relays to send the current contact list
+const FETCH_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// `follow`, `unfollow` and `following`: the NIP-02 contact list.
+///
+/// Changes are made to the newest list found on the relays, so follows made
+/// in other clients are kept.
+pub struct FollowCommand {
+    relay_urls: Vec<String>,
+    /// Decides whether relays requiring NIP-42 authentication get it
+    relay_settings: RelaySettings,
+}
+
+impl FollowCommand {
+    pub fn new(relay_urls: Vec<String>) -> Self {
+        Self {
+            relay_urls,
+            relay_settings: RelaySettings::default(),
+        }
+    }
+
+    pub fn with_relay_settings(mut self, relay_settings: RelaySettings) -> Self {
+        self.relay_settings = relay_settings;
+        self
+    }
+
+    /// Follow `pubkey` (hex). Without a published contact list this fails
+    /// unless `new_list` is set, since the relays may just not have sent it.
+    pub async fn follow(
+        &self,
+        keypair: &NostrKeypair,
+        pubkey: &str,
+        relay_url: Option<&str>,
+        petname: Option<&str>,
+        new_list: bool,
+    ) -> Result<()> {
+        self.update(keypair, false, |published| {
+            let mut contact_list = match published {
+                Some(published) => published.clone(),
+                None if new_list => ContactList::new(),
+                None => {
+                    return Err(NostrError::NotFound(
+                        "No contact list found on these relays. Pass --new to start a new one, \
+                         which replaces any list published elsewhere"
+                            .to_string(),
+                    ));
+                }
+            };
+
+            if !contact_list.follow(pubkey, relay_url, petname) {
+                return Err(NostrError::InvalidInput(format!("Already following {}", npub(pubkey))));
+            }
+            Ok(contact_list)
+        })
+        .await?;
+
+        println!("Following {}", npub(pubkey));
+        Ok(())
+    }
+
+    /// Unfollow `pubkey` (hex). Unfollowing the last follow needs `force`.
+    pub async fn unfollow(&self, keypair: &NostrKeypair, pubkey: &str, force: bool) -> Result<()> {
+        self.update(keypair, force, |published| {
+            let mut contact_list = published
+                .cloned()
+                .ok_or_else(|| NostrError::NotFound("No contact list found on these relays".to_string()))?;
+
+            if !contact_list.unfollow(pubkey) {
+                return Err(NostrError::InvalidInput(format!("Not following {}", npub(pubkey))));
+            }
+            Ok(contact_list)
+        })
+        .await?;
+
+        println!("Unfollowed {}", npub(pubkey));
+        Ok(())
+    }
+
+    /// Print everyone `pubkey` (hex) follows, with relay hints and petnames
+    pub async fn list(&self, pubkey: &str) -> Result<()> {
+        let relay_pool = self.connect(None)?;
+        let newest = newest_contact_list(&relay_pool, pubkey).await;
+        relay_pool.shutdown().await;
+
+        let Some(contact_list) = newest? else {
+            println!("No contact list found for {}", npub(pubkey));
+            return Ok(());
+        };
+
+        let contacts = contact_list.contacts();
+        println!("{} follows {} accounts:", npub(pubkey), contacts.len());
+        for contact in &contacts {
+            let petname = contact.petname.as_deref().map(|petname| format!(" ({})", petname)).unwrap_or_default();
+            let relay = contact.relay_url.as_deref().map(|relay| format!("  {}", relay)).unwrap_or_default();
+            println!("{}{}{}", npub(&contact.pubkey), petname, relay);
+        }
+        Ok(())
+    }
+
+    /// Fetch the newest contact list, change it and publish the result,
+    /// refusing lists that lost most of their follows unless `force` is set
+    async fn update<F>(&self, keypair: &NostrKeypair, force: bool, change: F) -> Result<()>
+    where
+        F: FnOnce(Option<&ContactList>) -> Result<ContactList>,
+    {
+        let relay_pool = self.connect(Some(keypair))?;
+        let auth_prompts = answer_auth_requests(&relay_pool);
+
+        let result = async {
+            let published = newest_contact_list(&relay_pool, &keypair.public_key_hex()).await?;
+            let contact_list = change(published.as_ref())?;
+            if let Some(published) = &published
+                && !force
+            {
+                contact_list
+                    .check_replaces(published)
+                    .map_err(|e| {
+                        NostrError::InvalidInput(format!("{}. Pass --force if that is intended", e))
+                    })?;
+            }
+
+            let event = contact_list.to_event(keypair)?;
+            Ok::<_, NostrError>(relay_pool.publish_to(&self.relay_urls, &event).await)
+        }
+        .await;
+
+        auth_prompts.abort();
+        relay_pool.shutdown().await;
+        let outcomes = result?;
+
+        for outcome in &outcomes {
+            if let Err(e) = &outcome.result {
+                println!("❌ {}: {}", outcome.relay_url, e);
+            }
+        }
+
+        let accepted = outcomes.iter().filter(|outcome| outcome.is_accepted()).count();
+        if accepted == 0 {
+            return Err(none_accepted("contact list", &outcomes));
+        }
+
+        println!("✅ Contact list published to {}/{} relays", accepted, outcomes.len());
+        Ok(())
+    }
+
+    /// A pool for a one-shot command, which reports unreachable relays instead of retrying them
+    fn connect(&self, keypair: Option<&NostrKeypair>) -> Result<RelayPool> {
+        let relay_pool = RelayPool::with_reconnect_policy(ReconnectPolicy::none());
+        self.relay_settings.apply(relay_pool.auth());
+        relay_pool.auth().set_keypair(keypair.cloned());
+
+        for relay_url in &self.relay_urls {
+            relay_pool.add_relay(relay_url)?;
+        }
+
+        Ok(relay_pool)
+    }
+}
+
+/// The newest kind-3 event of `pubkey` on any relay in the pool
+async fn newest_contact_list(relay_pool: &RelayPool, pubkey: &str) -> Result<Option<ContactList>> {
+    let filter = Filter::new().with_kinds(vec![KIND_CONTACT_LIST]).with_authors(vec![pubkey.to_string()]);
+    let events = relay_pool.fetch(vec![filter], FETCH_TIMEOUT).await?;
+
+    events
+        .iter()
+        .filter(|event| event.pubkey == pubkey)
+        .max_by(|a, b| a.created_at.cmp(&b.created_at).then_with(|| b.id.cmp(&a.id)))
+        .map(ContactList::from_event)
+        .transpose()
+}
+
+fn npub(pubkey: &str) -> String {
+    Nip19::Pubkey(pubkey.to_string()).to_bech32().unwrap_or_else(|_| pubkey.to_string())
+}
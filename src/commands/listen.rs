@@ -0,0 +1,267 @@
+use crate::error::{NostrError, Result};
+use std::collections::HashSet;
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::connection::{RelayNotification, RelayStatus};
+use crate::nostr::{Filter, RelayMessage, event_id_from_str, public_key_from_str};
+use crate::pool::RelayPool;
+
+pub struct ListenCommand {
+    pub relay_urls: Vec<String>,
+    pub filter: Filter,
+}
+
+/// Per-run state of a listener
+struct ListenState {
+    subscription_id: String,
+    seen_event_ids: HashSet<String>,
+    open_relays: HashSet<String>,
+    /// Why the last relay to close the subscription closed it
+    closed_reason: Option<NostrError>,
+}
+
+impl ListenCommand {
+    pub fn new(relay_urls: Vec<String>, filter: Filter) -> Self {
+        Self { relay_urls, filter }
+    }
+
+    /// Subscribe on every relay and stream matching events to stdout as JSON
+    /// lines, de-duplicated across relays, until every relay has closed the
+    /// subscription or Ctrl+C is pressed. Dropped connections are retried with
+    /// backoff, so events sent while a relay was unreachable are not lost.
+    pub async fn execute(&self) -> Result<()> {
+        let relay_pool = RelayPool::new();
+        let mut notifications = relay_pool.notifications();
+
+        for relay_url in &self.relay_urls {
+            eprintln!("Connecting to relay: {}", relay_url);
+            relay_pool.add_relay(relay_url)?;
+        }
+
+        let subscription_id = relay_pool.subscribe(vec![self.filter.clone()])?;
+        eprintln!("📡 Subscribed as {} with filter {}", subscription_id, serde_json::to_string(&self.filter)?);
+
+        let mut state = ListenState {
+            subscription_id,
+            seen_event_ids: HashSet::new(),
+            open_relays: self.relay_urls.iter().cloned().collect(),
+            closed_reason: None,
+        };
+
+        let shutdown = tokio::signal::ctrl_c();
+        tokio::pin!(shutdown);
+
+        let result = loop {
+            tokio::select! {
+                _ = &mut shutdown => {
+                    eprintln!("Closing subscription {}...", state.subscription_id);
+                    relay_pool.unsubscribe(&state.subscription_id);
+                    break Ok(());
+                }
+                notification = notifications.recv() => {
+                    match notification {
+                        Ok(notification) => {
+                            if let Err(e) = self.handle_notification(notification, &mut state) {
+                                break Err(e);
+                            }
+                        }
+                        Err(RecvError::Lagged(skipped)) => {
+                            eprintln!("Listener fell behind and skipped {} relay messages", skipped);
+                        }
+                        Err(RecvError::Closed) => {
+                            break Err(NostrError::RelayConnectionFailed("Relay pool shut down".to_string()));
+                        }
+                    }
+                }
+            }
+        };
+
+        relay_pool.shutdown().await;
+        result
+    }
+
+    fn handle_notification(&self, notification: RelayNotification, state: &mut ListenState) -> Result<()> {
+        match notification {
+            RelayNotification::Message { relay_url, message } => match message {
+                RelayMessage::Event { subscription_id, event } if subscription_id == state.subscription_id => {
+                    let is_new_event = state.seen_event_ids.insert(event.id.clone());
+                    if is_new_event {
+                        println!("{}", event.to_json()?);
+                    }
+                }
+                RelayMessage::EndOfStoredEvents { subscription_id } if subscription_id == state.subscription_id => {
+                    eprintln!("📭 End of stored events from {}, waiting for new events...", relay_url);
+                }
+                RelayMessage::Closed { subscription_id, message } if subscription_id == state.subscription_id => {
+                    eprintln!("Relay {} closed subscription: {}", relay_url, message);
+                    state.open_relays.remove(&relay_url);
+                    state.closed_reason = Some(NostrError::from_relay_message(&message));
+                }
+                RelayMessage::Notice { message } => {
+                    eprintln!("📢 Notice from {}: {}", relay_url, message);
+                }
+                _ => {}
+            },
+            RelayNotification::StatusChanged { relay_url, status } => match status {
+                RelayStatus::Connected => eprintln!("Connected to relay: {}", relay_url),
+                // The pool reconnects and replays the subscription on its own
+                RelayStatus::Disconnected => eprintln!("Lost connection to relay {}, reconnecting...", relay_url),
+                RelayStatus::Failed => eprintln!("Could not connect to relay {}, retrying...", relay_url),
+                RelayStatus::Connecting => {}
+            },
+            // Listening is anonymous; there is no key to authenticate with
+            RelayNotification::AuthRequested { .. } => {}
+        }
+
+        if state.open_relays.is_empty() {
+            // The last relay's reason, already printed, says why nothing is left
+            return Err(state.closed_reason.take().unwrap_or_else(|| {
+                NostrError::RelayConnectionFailed("No relays left to listen to".to_string())
+            }));
+        }
+
+        Ok(())
+    }
+}
+
+/// Build a NIP-01 filter from the `listen` command line flags.
+///
+/// Tag filters are given as `<letter>=<value>` and values for the same letter are combined.
+/// Authors and `p`/`e` tag values may be given in their NIP-19 forms.
+pub fn build_filter(
+    kinds: &[u16],
+    authors: &[String],
+    since: Option<u64>,
+    until: Option<u64>,
+    limit: Option<u64>,
+    tags: &[String],
+) -> Result<Filter> {
+    let mut filter = Filter::new();
+
+    if !kinds.is_empty() {
+        filter = filter.with_kinds(kinds.to_vec());
+    }
+    if !authors.is_empty() {
+        let authors = authors.iter().map(|author| parse_pubkey(author)).collect::<Result<Vec<_>>>()?;
+        filter = filter.with_authors(authors);
+    }
+    if let Some(since) = since {
+        filter = filter.with_since(since);
+    }
+    if let Some(until) = until {
+        filter = filter.with_until(until);
+    }
+    if let Some(limit) = limit {
+        filter = filter.with_limit(limit);
+    }
+
+    for tag in tags {
+        let (name, value) = tag.split_once('=')
+            .ok_or_else(|| {
+                NostrError::InvalidInput(format!("Invalid tag filter '{}', expected <letter>=<value>", tag))
+            })?;
+
+        let mut letters = name.chars();
+        let letter = match (letters.next(), letters.next()) {
+            (Some(letter), None) if letter.is_ascii_alphabetic() => letter,
+            _ => {
+                return Err(NostrError::InvalidInput(format!(
+                    "Tag filter name must be a single letter, got '{}'",
+                    name
+                )));
+            }
+        };
+
+        let value = match letter {
+            'p' => parse_pubkey(value)?,
+            'e' if value.starts_with("note1") || value.starts_with("nevent1") => event_id_from_str(value)?,
+            _ => value.to_string(),
+        };
+
+        filter = filter.with_tag(letter, vec![value]);
+    }
+
+    Ok(filter)
+}
+
+/// Decode `npub`/`nprofile` keys, leaving hex (and hex prefixes) untouched
+fn parse_pubkey(value: &str) -> Result<String> {
+    if value.starts_with("npub1") || value.starts_with("nprofile1") {
+        public_key_from_str(value)
+    } else {
+        Ok(value.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nostr::nip19::{EventPointer, Nip19, ProfilePointer};
+
+    const PUBKEY: &str = "3bf0c63fcb93463407af97a5e5ee64fa883d107ef9e558472c4eb9aaaefa459d";
+    const EVENT_ID: &str = "b9f5441e45ca39179320e0031cfb18e34078673dcc3d3e3a3b3a981760aa5696";
+    const REPLY_ID: &str = "d94a3f4dd87b9a3b0bed183b32e916fa29c8020107845d1752d72697fe5309a5";
+
+    fn tag_filter(tags: &[&str]) -> Result<Filter> {
+        let tags: Vec<String> = tags.iter().map(|tag| tag.to_string()).collect();
+        build_filter(&[], &[], None, None, None, &tags)
+    }
+
+    #[test]
+    fn test_tag_filters_need_a_single_letter_and_a_value() {
+        assert!(tag_filter(&["t"]).is_err(), "Missing '='");
+        assert!(tag_filter(&["topic=nostr"]).is_err());
+        assert!(tag_filter(&["1=nostr"]).is_err());
+        assert!(tag_filter(&["=nostr"]).is_err());
+
+        let filter = tag_filter(&["t=nostr", "T=Nostr"]).unwrap();
+        assert_eq!(filter.tags[&'t'], vec!["nostr"]);
+        assert_eq!(filter.tags[&'T'], vec!["Nostr"], "Tag names are case-sensitive");
+    }
+
+    #[test]
+    fn test_repeated_letters_are_merged() {
+        let filter = tag_filter(&["t=nostr", "e=abc", "t=rust"]).unwrap();
+        assert_eq!(filter.tags.len(), 2);
+        assert_eq!(filter.tags[&'t'], vec!["nostr", "rust"]);
+        assert_eq!(filter.tags[&'e'], vec!["abc"]);
+    }
+
+    #[test]
+    fn test_nip19_keys_and_ids_are_decoded() {
+        let npub = Nip19::Pubkey(PUBKEY.to_string()).to_bech32().unwrap();
+        let nprofile = Nip19::Profile(ProfilePointer {
+            pubkey: PUBKEY.to_string(),
+            relays: vec!["wss://relay.example.com".to_string()],
+        })
+        .to_bech32()
+        .unwrap();
+        let note = Nip19::Note(EVENT_ID.to_string()).to_bech32().unwrap();
+        let nevent = Nip19::Event(EventPointer {
+            id: REPLY_ID.to_string(),
+            relays: Vec::new(),
+            author: Some(PUBKEY.to_string()),
+            kind: Some(1),
+        })
+        .to_bech32()
+        .unwrap();
+
+        let filter = build_filter(
+            &[1],
+            &[npub.clone(), "3bf0c6".to_string()],
+            Some(100),
+            None,
+            Some(10),
+            &[format!("p={}", nprofile), format!("e={}", note), format!("e={}", nevent)],
+        )
+        .unwrap();
+        assert_eq!(filter.authors, Some(vec![PUBKEY.to_string(), "3bf0c6".to_string()]), "Hex prefixes are kept");
+        assert_eq!(filter.tags[&'p'], vec![PUBKEY]);
+        assert_eq!(filter.tags[&'e'], vec![EVENT_ID, REPLY_ID]);
+        assert_eq!((filter.kinds, filter.since, filter.until, filter.limit), (Some(vec![1]), Some(100), None, Some(10)));
+
+        assert!(build_filter(&[], &["npub1invalid".to_string()], None, None, None, &[]).is_err());
+        assert!(tag_filter(&["e=note1invalid"]).is_err());
+        assert_eq!(tag_filter(&[&format!("a={}", note)]).unwrap().tags[&'a'], vec![note], "Only p and e are decoded");
+    }
+}
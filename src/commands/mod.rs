@@ -1,3 +1,21 @@
+pub mod account;
+pub mod delete;
+pub mod dm;
+pub mod follow;
+pub mod listen;
+pub mod nip19;
 pub mod post;
+pub mod profile;
+pub mod react;
+pub mod relay;
 
-pub use post::PostCommand;
\ No newline at end of file
+pub use account::AccountCommand;
+pub use delete::DeleteCommand;
+pub use dm::DmCommand;
+pub use follow::FollowCommand;
+pub use listen::ListenCommand;
+pub use nip19::{DecodeCommand, EncodeCommand};
+pub use post::PostCommand;
+pub use profile::ProfileCommand;
+pub use react::ReactCommand;
+pub use relay::RelayCommand;
@@ -0,0 +1,70 @@
+use crate::error::Result;
+
+use crate::nostr::Nip19;
+
+/// `decode`: print what a NIP-19 entity contains
+pub struct DecodeCommand {
+    pub input: String,
+}
+
+impl DecodeCommand {
+    pub fn new(input: String) -> Self {
+        Self { input }
+    }
+
+    pub fn execute(&self) -> Result<()> {
+        let entity = Nip19::from_bech32(&self.input)?;
+        println!("type: {}", entity.prefix());
+
+        match entity {
+            Nip19::Pubkey(pubkey) => println!("pubkey: {}", pubkey),
+            Nip19::SecretKey(secret_key) => println!("secret key: {}", secret_key),
+            Nip19::Note(id) => println!("id: {}", id),
+            Nip19::Profile(profile) => {
+                println!("pubkey: {}", profile.pubkey);
+                print_relays(&profile.relays);
+            }
+            Nip19::Event(event) => {
+                println!("id: {}", event.id);
+                if let Some(author) = event.author {
+                    println!("author: {}", author);
+                }
+                if let Some(kind) = event.kind {
+                    println!("kind: {}", kind);
+                }
+                print_relays(&event.relays);
+            }
+            Nip19::Address(address) => {
+                println!("identifier: {}", address.identifier);
+                println!("author: {}", address.pubkey);
+                println!("kind: {}", address.kind);
+                print_relays(&address.relays);
+            }
+            Nip19::Relay(url) => println!("relay: {}", url),
+        }
+
+        Ok(())
+    }
+}
+
+/// `encode`: print the bech32 form of an entity
+pub struct EncodeCommand {
+    pub entity: Nip19,
+}
+
+impl EncodeCommand {
+    pub fn new(entity: Nip19) -> Self {
+        Self { entity }
+    }
+
+    pub fn execute(&self) -> Result<()> {
+        println!("{}", self.entity.to_bech32()?);
+        Ok(())
+    }
+}
+
+fn print_relays(relays: &[String]) {
+    for relay in relays {
+        println!("relay: {}", relay);
+    }
+}
@@ -1,140 +1,268 @@
-use anyhow::Result;
-use futures_util::{SinkExt, StreamExt};
-use serde_json::{self, Value};
-use std::time::Duration;
-use tokio::time::timeout;
-use tokio_tungstenite::tungstenite::Message;
+use crate::error::{NostrError, Remedy, Result};
+use std::path::{Path, PathBuf};
 
-use crate::connection::RelayManager;
-use crate::nostr::{keypair_from_hex, NostrEvent};
+use crate::commands::account::confirm;
+use crate::commands::relay::answer_auth_requests;
+use crate::connection::{AuthPolicy, ReconnectPolicy, RelayNotification, RelayStatus};
+use crate::event_store::EventStore;
+use crate::nostr::event::UnsignedEvent;
+use crate::nostr::nip13::{Miner, difficulty};
+use crate::nostr::{Nip19, NostrEvent, NostrKeypair, RelayMessage};
+use crate::pool::{PublishOutcome, RelayPool, none_accepted};
+use crate::relay_settings::RelaySettings;
 
 pub struct PostCommand {
     pub message_content: String,
-    pub relay_url: String,
-    pub author_private_key_hex: String,
+    pub relay_urls: Vec<String>,
+    pub author_keypair: NostrKeypair,
+    /// Decides whether relays requiring NIP-42 authentication get it
+    pub relay_settings: RelaySettings,
+    /// Config directory whose event store keeps the post and the relays that
+    /// accepted it, so `delete` knows where to send a deletion request
+    pub event_store_dir: Option<PathBuf>,
+    /// Leading zero bits of proof of work (NIP-13) to mine before publishing
+    pub pow: Option<u32>,
 }
 
 impl PostCommand {
-    pub fn new(text: String, relay_url: String, private_key_hex: String) -> Self {
+    pub fn new(text: String, relay_urls: Vec<String>, author_keypair: NostrKeypair) -> Self {
         Self {
             message_content: text,
-            relay_url,
-            author_private_key_hex: private_key_hex,
+            relay_urls,
+            author_keypair,
+            relay_settings: RelaySettings::default(),
+            event_store_dir: None,
+            pow: None,
         }
     }
 
+    pub fn with_relay_settings(mut self, relay_settings: RelaySettings) -> Self {
+        self.relay_settings = relay_settings;
+        self
+    }
+
+    pub fn with_event_store(mut self, config_dir: &Path) -> Self {
+        self.event_store_dir = Some(config_dir.to_path_buf());
+        self
+    }
+
+    pub fn with_pow(mut self, difficulty: u32) -> Self {
+        self.pow = Some(difficulty);
+        self
+    }
+
     pub async fn execute(&self) -> Result<String> {
         println!("Creating and posting event: {}", self.message_content);
 
-        let author_keypair = keypair_from_hex(&self.author_private_key_hex)
-            .map_err(|e| anyhow::anyhow!("Failed to load keypair: {}", e))?;
-
-        let text_note_event = NostrEvent::new_text_note(self.message_content.clone(), &author_keypair)
-            .map_err(|e| anyhow::anyhow!("Failed to create event: {}", e))?;
+        let text_note_event = match self.pow {
+            Some(target) => mine(self.unsigned_note(), target).await?.sign(&self.author_keypair),
+            None => NostrEvent::new_text_note(self.message_content.clone(), &self.author_keypair),
+        }
+        .map_err(|e| NostrError::EventCreationFailed(e.to_string()))?;
 
         println!("Created event with ID: {}", text_note_event.id);
         println!("Public key: {}", text_note_event.pubkey);
 
-        let mut relay_manager = RelayManager::new();
-        relay_manager.add_relay(&self.relay_url).await
-            .map_err(|e| anyhow::anyhow!("Invalid relay URL: {}", e))?;
+        // A one-shot publish reports unreachable relays instead of retrying them
+        let relay_pool = RelayPool::with_reconnect_policy(ReconnectPolicy::none());
 
-        let mut relay_connection = relay_manager.connect_relay(&self.relay_url).await
-            .map_err(|e| anyhow::anyhow!("Failed to connect to relay: {}", e))?;
+        // Relays that require NIP-42 authentication learn the author's key
+        self.relay_settings.apply(relay_pool.auth());
+        relay_pool.auth().set_keypair(Some(self.author_keypair.clone()));
+        let auth_prompts = answer_auth_requests(&relay_pool);
 
-        println!("Connected to relay: {}", self.relay_url);
+        for relay_url in &self.relay_urls {
+            relay_pool.add_relay(relay_url)?;
+        }
 
-        let event_json = text_note_event.to_json_value()?;
-        let event_message = serde_json::json!(["EVENT", event_json]);
-        let event_message_text = serde_json::to_string(&event_message)?;
+        println!("📤 Publishing event to {} relay(s), waiting for responses...", self.relay_urls.len());
+        let mut outcomes = publish(&relay_pool, &self.relay_urls, &text_note_event).await;
 
-        relay_connection.send(Message::Text(event_message_text.into())).await
-            .map_err(|e| anyhow::anyhow!("Failed to send event to relay: {}", e))?;
+        // Relays the settings keep from authenticating may still get it this once
+        let authenticate: Vec<String> = outcomes
+            .iter()
+            .filter(|outcome| outcome.rejection().map(NostrError::remedy) == Some(Remedy::Authenticate))
+            .map(|outcome| outcome.relay_url.clone())
+            .filter(|relay_url| self.allow_auth_once(&relay_pool, relay_url))
+            .collect();
+        if !authenticate.is_empty() {
+            for relay_url in &authenticate {
+                // The refusing connection has given up on authenticating, a new one starts over
+                relay_pool.remove_relay(relay_url).await;
+            }
+            replace_outcomes(&mut outcomes, publish(&relay_pool, &authenticate, &text_note_event).await);
+        }
 
-        println!("📤 Event sent, waiting for relay response...");
+        // Relays asking for more proof of work get a copy mined to their difficulty
+        let mut mined_copy = None;
+        if let Some((bits, relay_urls)) = more_work_wanted(&outcomes, difficulty(&text_note_event.id)) {
+            println!("💡 {} want {} bits of proof of work, mining a copy for them", relay_urls.join(", "), bits);
+            let copy = mine(self.unsigned_note().with_timestamp(text_note_event.created_at), bits)
+                .await?
+                .sign(&self.author_keypair)
+                .map_err(|e| NostrError::EventCreationFailed(e.to_string()))?;
+            replace_outcomes(&mut outcomes, publish(&relay_pool, &relay_urls, &copy).await);
+            mined_copy = Some((copy, relay_urls));
+        }
 
-        let relay_response = timeout(Duration::from_secs(10), relay_connection.next()).await
-            .map_err(|_| anyhow::anyhow!("Timeout waiting for relay response"))?;
+        auth_prompts.abort();
+        relay_pool.shutdown().await;
 
-        match relay_response {
-            Some(Ok(Message::Text(response_text))) => {
-                let response_json: Value = serde_json::from_str(&response_text.to_string())
-                    .map_err(|e| anyhow::anyhow!("Failed to parse relay response: {}", e))?;
+        let accepted = outcomes.iter().filter(|outcome| outcome.is_accepted()).count();
+        if accepted == 0 {
+            return Err(none_accepted("event", &outcomes));
+        }
 
-                self.handle_relay_response(&response_json, &text_note_event.id)?;
+        let accepted_by = |event: &NostrEvent| -> Vec<String> {
+            outcomes
+                .iter()
+                .filter(|outcome| outcome.is_accepted())
+                .filter(|outcome| match &mined_copy {
+                    Some((copy, relay_urls)) => relay_urls.contains(&outcome.relay_url) == (copy.id == event.id),
+                    None => true,
+                })
+                .map(|outcome| outcome.relay_url.clone())
+                .collect()
+        };
+        let published: Vec<(&NostrEvent, Vec<String>)> = std::iter::once(&text_note_event)
+            .chain(mined_copy.as_ref().map(|(copy, _)| copy))
+            .map(|event| (event, accepted_by(event)))
+            .filter(|(_, relay_urls)| !relay_urls.is_empty())
+            .collect();
 
-                println!("✅ Event published successfully!");
-                println!("Event ID: {}", text_note_event.id);
-                Ok(text_note_event.id)
-            }
-            Some(Ok(Message::Close(_))) => {
-                Err(anyhow::anyhow!("Relay closed connection before responding"))
-            }
-            Some(Ok(Message::Binary(_))) => {
-                Err(anyhow::anyhow!("Received unexpected binary message from relay"))
-            }
-            Some(Ok(Message::Ping(_))) | Some(Ok(Message::Pong(_))) => {
-                Err(anyhow::anyhow!("Received ping/pong instead of response"))
-            }
-            Some(Ok(Message::Frame(_))) => {
-                Err(anyhow::anyhow!("Received unexpected frame message"))
-            }
-            Some(Err(e)) => {
-                Err(anyhow::anyhow!("WebSocket error while waiting for response: {}", e))
-            }
-            None => {
-                Err(anyhow::anyhow!("No response received from relay"))
+        if let Some(config_dir) = &self.event_store_dir {
+            let recorded = EventStore::open(config_dir).and_then(|mut event_store| {
+                for (event, relay_urls) in &published {
+                    event_store.save(event)?;
+                    event_store.record_published(&event.id, relay_urls)?;
+                }
+                Ok(())
+            });
+            if let Err(e) = recorded {
+                println!("⚠️ Could not record where the event was published: {}", e);
             }
         }
+
+        println!("✅ Event published to {}/{} relays!", accepted, outcomes.len());
+        for (event, _) in &published {
+            println!("Event ID: {}", event.id);
+        }
+        Ok(published[0].0.id.clone())
     }
 
-    fn handle_relay_response(&self, response: &Value, expected_event_id: &str) -> Result<()> {
-        if let Some(response_array) = response.as_array() {
-            if response_array.is_empty() {
-                return Err(anyhow::anyhow!("Empty response from relay"));
-            }
+    fn unsigned_note(&self) -> UnsignedEvent {
+        UnsignedEvent::new_text_note(self.message_content.clone(), self.author_keypair.public_key_hex())
+    }
 
-            match response_array[0].as_str() {
-                Some("OK") => {
-                    if response_array.len() < 4 {
-                        return Err(anyhow::anyhow!("Invalid OK response format"));
-                    }
+    /// Ask whether to authenticate to a relay whose settings say never, and
+    /// allow it for this pool when the answer is yes
+    fn allow_auth_once(&self, relay_pool: &RelayPool, relay_url: &str) -> bool {
+        if relay_pool.auth().policy(relay_url) != AuthPolicy::Never {
+            // Asked already, or authenticated and still refused
+            return false;
+        }
 
-                    let event_id = response_array[1].as_str().unwrap_or("");
-                    let accepted = response_array[2].as_bool().unwrap_or(false);
-                    let message = response_array[3].as_str().unwrap_or("");
-
-                    // Verify the event ID matches what we sent
-                    if event_id != expected_event_id {
-                        return Err(anyhow::anyhow!(
-                            "Event ID mismatch: expected {}, got {}",
-                            expected_event_id,
-                            event_id
-                        ));
-                    }
+        let npub = Nip19::Pubkey(self.author_keypair.public_key_hex())
+            .to_bech32()
+            .unwrap_or_else(|_| self.author_keypair.public_key_hex());
+        let question = format!(
+            "🔐 {} requires authentication, which your relay settings refuse. Authenticate as {} this time? [y/N] ",
+            relay_url, npub
+        );
+        if !confirm(&question).unwrap_or(false) {
+            return false;
+        }
 
-                    if accepted {
-                        println!("📨 Relay accepted event: {}", message);
-                        Ok(())
-                    } else {
-                        Err(anyhow::anyhow!("Relay rejected event: {}", message))
+        relay_pool.auth().set_policy(relay_url, AuthPolicy::Always);
+        true
+    }
+}
+
+/// Publish to the relays, printing their notices and answers as they come
+async fn publish(relay_pool: &RelayPool, relay_urls: &[String], event: &NostrEvent) -> Vec<PublishOutcome> {
+    let mut notifications = relay_pool.notifications();
+    let publish = relay_pool.publish_to(relay_urls, event);
+    tokio::pin!(publish);
+
+    let outcomes = loop {
+        tokio::select! {
+            outcomes = &mut publish => break outcomes,
+            Ok(notification) = notifications.recv() => {
+                match notification {
+                    RelayNotification::StatusChanged { relay_url, status: RelayStatus::Connected } => {
+                        println!("Connected to relay: {}", relay_url);
                     }
-                }
-                Some("NOTICE") => {
-                    let notice_message = response_array.get(1)
-                        .and_then(|v| v.as_str())
-                        .unwrap_or("Unknown notice");
-                    Err(anyhow::anyhow!("Relay notice: {}", notice_message))
-                }
-                Some(other) => {
-                    Err(anyhow::anyhow!("Unexpected response type: {}", other))
-                }
-                None => {
-                    Err(anyhow::anyhow!("Invalid response format"))
+                    RelayNotification::Message { relay_url, message: RelayMessage::Notice { message } } => {
+                        println!("📢 Notice from {}: {}", relay_url, message);
+                    }
+                    _ => {}
                 }
             }
-        } else {
-            Err(anyhow::anyhow!("Response is not a JSON array"))
+        }
+    };
+
+    for outcome in &outcomes {
+        match &outcome.result {
+            Ok(message) => println!("📨 {} accepted event: {}", outcome.relay_url, message),
+            Err(e) => println!("❌ {}: {}", outcome.relay_url, e),
+        }
+    }
+    outcomes
+}
+
+/// The most proof of work asked for by relays refusing an event with
+/// `difficulty` bits for too little, and those relays
+fn more_work_wanted(outcomes: &[PublishOutcome], difficulty: u32) -> Option<(u32, Vec<String>)> {
+    let wanted: Vec<(u32, String)> = outcomes
+        .iter()
+        .filter_map(|outcome| match outcome.rejection()?.remedy() {
+            Remedy::MineProofOfWork(Some(bits)) if bits > difficulty => Some((bits, outcome.relay_url.clone())),
+            _ => None,
+        })
+        .collect();
+
+    let bits = wanted.iter().map(|(bits, _)| *bits).max()?;
+    Some((bits, wanted.into_iter().map(|(_, relay_url)| relay_url).collect()))
+}
+
+/// Put the outcomes of publishing again in place of the earlier ones
+fn replace_outcomes(outcomes: &mut Vec<PublishOutcome>, retried: Vec<PublishOutcome>) {
+    outcomes.retain(|outcome| !retried.iter().any(|retry| retry.relay_url == outcome.relay_url));
+    outcomes.extend(retried);
+}
+
+/// Mine `target` bits of proof of work on a blocking thread, printing
+/// progress. Ctrl-C gives up.
+async fn mine(event: UnsignedEvent, target: u32) -> Result<UnsignedEvent> {
+    println!("⛏️ Mining {} bits of proof of work, Ctrl-C to give up...", target);
+
+    let miner = Miner::new(target);
+    let cancel = miner.cancel_handle();
+    let mining = tokio::task::spawn_blocking(move || {
+        miner.mine(event, |progress| {
+            let seconds = progress.elapsed.as_secs_f64();
+            println!(
+                "⛏️ {} hashes in {:.0}s ({:.0}/s), best {} bits",
+                progress.attempts,
+                seconds,
+                progress.attempts as f64 / seconds,
+                progress.best_difficulty
+            );
+        })
+    });
+    tokio::pin!(mining);
+
+    let mined = tokio::select! {
+        mined = &mut mining => mined,
+        _ = tokio::signal::ctrl_c() => {
+            cancel.cancel();
+            mining.await
         }
     }
-}
\ No newline at end of file
+    .map_err(|e| NostrError::Other(format!("Mining stopped unexpectedly: {}", e)))??;
+
+    if let Ok(id) = mined.calculate_id() {
+        println!("⛏️ Mined an id with {} leading zero bits", difficulty(&id));
+    }
+    Ok(mined)
+}
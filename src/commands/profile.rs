@@ -0,0 +1,129 @@
+use crate::error::{NostrError, Result};
+use std::time::Duration;
+
+use crate::commands::relay::answer_auth_requests;
+use crate::connection::ReconnectPolicy;
+use crate::nostr::metadata::{KIND_METADATA, METADATA_FIELDS, Metadata};
+use crate::nostr::{Filter, Nip19, NostrEvent, NostrKeypair};
+use crate::pool::{RelayPool, none_accepted};
+use crate::relay_settings::RelaySettings;
+
+/// How long to wait for relays to send the current profile
+const FETCH_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// `profile` subcommands: kind-0 profile metadata
+pub struct ProfileCommand {
+    relay_urls: Vec<String>,
+    /// Decides whether relays requiring NIP-42 authentication get it
+    relay_settings: RelaySettings,
+}
+
+impl ProfileCommand {
+    pub fn new(relay_urls: Vec<String>) -> Self {
+        Self {
+            relay_urls,
+            relay_settings: RelaySettings::default(),
+        }
+    }
+
+    pub fn with_relay_settings(mut self, relay_settings: RelaySettings) -> Self {
+        self.relay_settings = relay_settings;
+        self
+    }
+
+    /// Print the newest profile `pubkey` (hex) published to the relays
+    pub async fn show(&self, pubkey: &str) -> Result<()> {
+        let relay_pool = self.connect(None)?;
+        let newest = newest_profile(&relay_pool, pubkey).await;
+        relay_pool.shutdown().await;
+
+        let npub = Nip19::Pubkey(pubkey.to_string()).to_bech32().unwrap_or(pubkey.to_string());
+        let Some(event) = newest? else {
+            println!("No profile found for {}", npub);
+            return Ok(());
+        };
+
+        let metadata = Metadata::from_event(&event)?;
+        println!("Profile of {}:", npub);
+        for field in METADATA_FIELDS {
+            if let Some(value) = metadata.get(field) {
+                println!("{:<13}  {}", field, value);
+            }
+        }
+        for (field, value) in &metadata.extra {
+            println!("{:<13}  {}", field, value);
+        }
+        Ok(())
+    }
+
+    /// Change fields of the author's profile and publish it. The newest
+    /// published profile is fetched first, so fields that aren't changed,
+    /// including ones set by other clients, are kept.
+    pub async fn set(&self, keypair: &NostrKeypair, changes: &[(&str, String)]) -> Result<()> {
+        if changes.is_empty() {
+            return Err(NostrError::InvalidInput(
+                "Nothing to change; pass at least one field, e.g. --name".to_string(),
+            ));
+        }
+
+        let relay_pool = self.connect(Some(keypair))?;
+        let auth_prompts = answer_auth_requests(&relay_pool);
+
+        let result = async {
+            let mut metadata = match newest_profile(&relay_pool, &keypair.public_key_hex()).await? {
+                Some(event) => Metadata::from_event(&event)?,
+                None => Metadata::new(),
+            };
+            for (field, value) in changes {
+                metadata.set(field, value)?;
+            }
+
+            let event = metadata.to_event(keypair)?;
+            Ok::<_, NostrError>(relay_pool.publish_to(&self.relay_urls, &event).await)
+        }
+        .await;
+
+        auth_prompts.abort();
+        relay_pool.shutdown().await;
+        let outcomes = result?;
+
+        for outcome in &outcomes {
+            match &outcome.result {
+                Ok(message) => println!("📨 {} accepted profile: {}", outcome.relay_url, message),
+                Err(e) => println!("❌ {}: {}", outcome.relay_url, e),
+            }
+        }
+
+        let accepted = outcomes.iter().filter(|outcome| outcome.is_accepted()).count();
+        if accepted == 0 {
+            return Err(none_accepted("profile", &outcomes));
+        }
+
+        println!("✅ Profile published to {}/{} relays", accepted, outcomes.len());
+        Ok(())
+    }
+
+    /// A pool for a one-shot command, which reports unreachable relays instead of retrying them
+    fn connect(&self, keypair: Option<&NostrKeypair>) -> Result<RelayPool> {
+        let relay_pool = RelayPool::with_reconnect_policy(ReconnectPolicy::none());
+        self.relay_settings.apply(relay_pool.auth());
+        relay_pool.auth().set_keypair(keypair.cloned());
+
+        for relay_url in &self.relay_urls {
+            relay_pool.add_relay(relay_url)?;
+        }
+
+        Ok(relay_pool)
+    }
+}
+
+/// The newest kind-0 event of `pubkey` on any relay in the pool
+async fn newest_profile(relay_pool: &RelayPool, pubkey: &str) -> Result<Option<NostrEvent>> {
+    let filter = Filter::new().with_kinds(vec![KIND_METADATA]).with_authors(vec![pubkey.to_string()]);
+    let events = relay_pool.fetch(vec![filter], FETCH_TIMEOUT).await?;
+
+    Ok(events
+        .into_iter()
+        .filter(|event| event.pubkey == pubkey)
+        .max_by(|a, b| a.created_at.cmp(&b.created_at).then_with(|| b.id.cmp(&a.id))))
+}
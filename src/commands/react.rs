@@ -0,0 +1,119 @@
+use crate::error::{NostrError, Result};
+use std::time::Duration;
+
+use crate::commands::relay::answer_auth_requests;
+use crate::connection::ReconnectPolicy;
+use crate::nostr::event::UnsignedEvent;
+use crate::nostr::nip25::Reaction;
+use crate::nostr::{Filter, NostrEvent, NostrKeypair};
+use crate::pool::{RelayPool, none_accepted};
+use crate::relay_settings::RelaySettings;
+
+/// How long to wait for relays to send the note being reacted to
+const FETCH_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// `react` and `repost`: answering someone else's note (NIP-25, NIP-18).
+///
+/// The note is fetched from the relays first, since reactions and reposts
+/// tag its author and reposts embed it.
+pub struct ReactCommand {
+    relay_urls: Vec<String>,
+    /// Decides whether relays requiring NIP-42 authentication get it
+    relay_settings: RelaySettings,
+}
+
+impl ReactCommand {
+    pub fn new(relay_urls: Vec<String>) -> Self {
+        Self {
+            relay_urls,
+            relay_settings: RelaySettings::default(),
+        }
+    }
+
+    pub fn with_relay_settings(mut self, relay_settings: RelaySettings) -> Self {
+        self.relay_settings = relay_settings;
+        self
+    }
+
+    /// React to the note with id `note_id` (hex)
+    pub async fn react(&self, keypair: &NostrKeypair, note_id: &str, reaction: &Reaction) -> Result<()> {
+        self.publish(keypair, note_id, "Reaction", |note, relay_url| {
+            Ok(UnsignedEvent::new_reaction(note, reaction, relay_url, keypair.public_key_hex()))
+        })
+        .await
+    }
+
+    /// Repost the note with id `note_id` (hex), or with `quote` publish a
+    /// note of your own quoting it
+    pub async fn repost(&self, keypair: &NostrKeypair, note_id: &str, quote: Option<&str>) -> Result<()> {
+        match quote {
+            Some(text) => {
+                self.publish(keypair, note_id, "Quote", |note, relay_url| {
+                    UnsignedEvent::new_text_note(text.to_string(), keypair.public_key_hex()).with_quote(note, relay_url)
+                })
+                .await
+            }
+            None => {
+                self.publish(keypair, note_id, "Repost", |note, relay_url| {
+                    UnsignedEvent::new_repost(note, relay_url, keypair.public_key_hex())
+                })
+                .await
+            }
+        }
+    }
+
+    /// Fetch the note, build the event answering it and publish that
+    async fn publish<F>(&self, keypair: &NostrKeypair, note_id: &str, what: &str, build: F) -> Result<()>
+    where
+        F: FnOnce(&NostrEvent, Option<&str>) -> Result<UnsignedEvent>,
+    {
+        let relay_pool = self.connect(keypair)?;
+        let auth_prompts = answer_auth_requests(&relay_pool);
+
+        let result = async {
+            let (note, relay_url) = relay_pool
+                .fetch_with_relays(vec![Filter::new().with_ids(vec![note_id.to_string()])], FETCH_TIMEOUT)
+                .await?
+                .into_iter()
+                .find(|(event, _)| event.id == note_id)
+                .ok_or_else(|| NostrError::NotFound(format!("Note {} not found on these relays", note_id)))?;
+
+            // Point at the relay that actually has the note
+            let event = build(&note, Some(&relay_url))?.sign(keypair)?;
+            Ok::<_, NostrError>(relay_pool.publish_to(&self.relay_urls, &event).await)
+        }
+        .await;
+
+        auth_prompts.abort();
+        relay_pool.shutdown().await;
+        let outcomes = result?;
+
+        for outcome in &outcomes {
+            match &outcome.result {
+                Ok(message) => println!("📨 {} accepted {}: {}", outcome.relay_url, what.to_lowercase(), message),
+                Err(e) => println!("❌ {}: {}", outcome.relay_url, e),
+            }
+        }
+
+        let accepted = outcomes.iter().filter(|outcome| outcome.is_accepted()).count();
+        if accepted == 0 {
+            return Err(none_accepted(&what.to_lowercase(), &outcomes));
+        }
+
+        println!("✅ {} published to {}/{} relays", what, accepted, outcomes.len());
+        Ok(())
+    }
+
+    /// A pool for a one-shot command, which reports unreachable relays instead of retrying them
+    fn connect(&self, keypair: &NostrKeypair) -> Result<RelayPool> {
+        let relay_pool = RelayPool::with_reconnect_policy(ReconnectPolicy::none());
+        self.relay_settings.apply(relay_pool.auth());
+        relay_pool.auth().set_keypair(Some(keypair.clone()));
+
+        for relay_url in &self.relay_urls {
+            relay_pool.add_relay(relay_url)?;
+        }
+
+        Ok(relay_pool)
+    }
+}
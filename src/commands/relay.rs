@@ -0,0 +1,204 @@
+use crate::error::{NostrError, Result};
+use std::path::PathBuf;
+use std::time::Duration;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+
+use crate::commands::account::confirm;
+use crate::connection::{AuthPolicy, ReconnectPolicy, RelayNotification, validate_relay_url};
+use crate::nostr::nip11::RelayInformation;
+use crate::nostr::nip65::{KIND_RELAY_LIST, RelayList};
+use crate::nostr::{Filter, Nip19, NostrKeypair};
+use crate::pool::{RelayPool, none_accepted};
+use crate::relay_info::fetch_relay_information;
+use crate::relay_settings::RelaySettings;
+
+/// How long `relay list` waits for relays to send the relay list
+const FETCH_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// `relay` subcommands: per-relay settings and NIP-65 relay lists
+pub struct RelayCommand {
+    config_dir: PathBuf,
+    settings: RelaySettings,
+}
+
+impl RelayCommand {
+    pub fn new(config_dir: PathBuf) -> Result<Self> {
+        let settings = RelaySettings::load(&config_dir)?;
+        Ok(Self { config_dir, settings })
+    }
+
+    /// Fetch and print a relay's NIP-11 information document
+    pub async fn info(&self, relay_url: &str) -> Result<()> {
+        validate_relay_url(relay_url)?;
+        let information = fetch_relay_information(&reqwest::Client::new(), relay_url).await?;
+
+        print_information(&information);
+        println!("Auth policy:     {}", self.settings.auth_policy(relay_url));
+        Ok(())
+    }
+
+    /// Set how a relay's NIP-42 challenges are answered
+    pub fn set_auth(&mut self, relay_url: &str, policy: AuthPolicy) -> Result<()> {
+        validate_relay_url(relay_url)?;
+        self.settings.set_auth_policy(relay_url, policy);
+        self.settings.save(&self.config_dir)?;
+
+        println!("Authentication to {} is now '{}'", relay_url, policy);
+        Ok(())
+    }
+
+    /// Print a relay's auth policy, or every relay that has one set
+    pub fn show_auth(&self, relay_url: Option<&str>) {
+        if let Some(relay_url) = relay_url {
+            println!("{}  {}", self.settings.auth_policy(relay_url), relay_url);
+            return;
+        }
+
+        if self.settings.auth.is_empty() {
+            println!("No relay has an auth policy set; every relay asks before authenticating.");
+            return;
+        }
+
+        for (relay_url, policy) in &self.settings.auth {
+            println!("{:<6}  {}", policy.to_string(), relay_url);
+        }
+    }
+
+    /// Fetch and print the newest relay list (NIP-65) `pubkey` (hex) published
+    /// to any of `relay_urls`
+    pub async fn show_list(&self, pubkey: &str, relay_urls: &[String]) -> Result<()> {
+        let relay_pool = self.connect(relay_urls, None)?;
+        let filter = Filter::new().with_kinds(vec![KIND_RELAY_LIST]).with_authors(vec![pubkey.to_string()]);
+        let events = relay_pool.fetch(vec![filter], FETCH_TIMEOUT).await?;
+        relay_pool.shutdown().await;
+
+        let npub = Nip19::Pubkey(pubkey.to_string()).to_bech32().unwrap_or(pubkey.to_string());
+        let Some(event) = events.iter().filter(|event| event.pubkey == pubkey).max_by_key(|event| event.created_at) else {
+            println!("No relay list found for {}", npub);
+            return Ok(());
+        };
+
+        let relay_list = RelayList::from_event(event)?;
+        println!("Relay list of {} ({} relays):", npub, relay_list.relays.len());
+        for (relay_url, usage) in &relay_list.relays {
+            let usage = match (usage.is_read(), usage.is_write()) {
+                (true, true) => "read+write",
+                (true, false) => "read",
+                _ => "write",
+            };
+            println!("{:<10}  {}", usage, relay_url);
+        }
+        Ok(())
+    }
+
+    /// Publish `relay_list` as the author's relay list, to `relay_urls` and to
+    /// every relay on the list so others find it where they look for it
+    pub async fn publish_list(&self, relay_list: &RelayList, keypair: &NostrKeypair, relay_urls: &[String]) -> Result<()> {
+        if relay_list.relays.is_empty() {
+            return Err(NostrError::InvalidInput("A relay list needs at least one relay".to_string()));
+        }
+
+        let event = relay_list.to_event(keypair)?;
+        let mut targets: Vec<String> = relay_urls.to_vec();
+        for (relay_url, _) in &relay_list.relays {
+            if !targets.contains(relay_url) {
+                targets.push(relay_url.clone());
+            }
+        }
+
+        let relay_pool = self.connect(&targets, Some(keypair))?;
+        let auth_prompts = answer_auth_requests(&relay_pool);
+        let outcomes = relay_pool.publish_to(&targets, &event).await;
+        auth_prompts.abort();
+        relay_pool.shutdown().await;
+
+        for outcome in &outcomes {
+            match &outcome.result {
+                Ok(message) => println!("📨 {} accepted relay list: {}", outcome.relay_url, message),
+                Err(e) => println!("❌ {}: {}", outcome.relay_url, e),
+            }
+        }
+
+        let accepted = outcomes.iter().filter(|outcome| outcome.is_accepted()).count();
+        if accepted == 0 {
+            return Err(none_accepted("relay list", &outcomes));
+        }
+
+        println!("✅ Relay list published to {}/{} relays", accepted, outcomes.len());
+        Ok(())
+    }
+
+    /// A one-shot pool for `relay_urls`, authenticating as `keypair` where the
+    /// relay settings allow it
+    fn connect(&self, relay_urls: &[String], keypair: Option<&NostrKeypair>) -> Result<RelayPool> {
+        let relay_pool = RelayPool::with_reconnect_policy(ReconnectPolicy::none());
+        self.settings.apply(relay_pool.auth());
+        relay_pool.auth().set_keypair(keypair.cloned());
+
+        for relay_url in relay_urls {
+            relay_pool.add_relay(relay_url)?;
+        }
+        Ok(relay_pool)
+    }
+}
+
+fn print_information(information: &RelayInformation) {
+    let unset = "-".to_string();
+    let nips: Vec<String> = information.supported_nips.iter().map(|nip| format!("{:02}", nip)).collect();
+
+    println!("Name:            {}", information.name.as_ref().unwrap_or(&unset));
+    println!("Description:     {}", information.description.as_ref().unwrap_or(&unset));
+    println!("Contact:         {}", information.contact.as_ref().unwrap_or(&unset));
+    if let Some(pubkey) = &information.pubkey {
+        println!("Admin:           {}", Nip19::Pubkey(pubkey.clone()).to_bech32().unwrap_or(pubkey.clone()));
+    }
+    println!("Software:        {} {}", information.software.as_ref().unwrap_or(&unset), information.version.as_deref().unwrap_or(""));
+    println!("Supported NIPs:  {}", if nips.is_empty() { unset.clone() } else { nips.join(", ") });
+
+    let limitation = information.limitation();
+    let limit = |value: Option<String>| value.unwrap_or_else(|| unset.clone());
+    println!("Max message:     {}", limit(limitation.max_message_length.map(|n| format!("{} bytes", n))));
+    println!("Max content:     {}", limit(limitation.max_content_length.map(|n| format!("{} characters", n))));
+    println!("Max event tags:  {}", limit(limitation.max_event_tags.map(|n| n.to_string())));
+    println!("Max subs:        {}", limit(limitation.max_subscriptions.map(|n| n.to_string())));
+    println!("Max limit:       {}", limit(limitation.max_limit.map(|n| n.to_string())));
+    println!("Min PoW:         {}", limit(limitation.min_pow_difficulty.map(|n| format!("{} bits", n))));
+    println!("Auth required:   {}", if limitation.auth_required { "yes" } else { "no" });
+    println!("Payment:         {}", match (&information.payments_url, limitation.payment_required) {
+        (Some(url), true) => format!("required ({})", url),
+        (None, true) => "required".to_string(),
+        (_, false) => "no".to_string(),
+    });
+    println!("Restricted:      {}", if limitation.restricted_writes { "yes" } else { "no" });
+}
+
+/// Answer `AuthPolicy::Ask` challenges of a one-shot command's pool by asking
+/// on the terminal. Runs until the pool shuts down or the handle is aborted.
+pub fn answer_auth_requests(relay_pool: &RelayPool) -> JoinHandle<()> {
+    let relay_pool = relay_pool.clone();
+    let mut notifications = relay_pool.notifications();
+
+    tokio::spawn(async move {
+        loop {
+            match notifications.recv().await {
+                Ok(RelayNotification::AuthRequested { relay_url, pubkey }) => {
+                    let npub = Nip19::Pubkey(pubkey.clone()).to_bech32().unwrap_or(pubkey);
+                    let question = format!("🔐 {} requires authentication. Authenticate as {}? [y/N] ", relay_url, npub);
+
+                    // A plain thread, so an unanswered prompt doesn't keep the runtime from exiting
+                    let (answer_tx, answer_rx) = oneshot::channel();
+                    std::thread::spawn(move || {
+                        let _ = answer_tx.send(confirm(&question).unwrap_or(false));
+                    });
+
+                    let approved = answer_rx.await.unwrap_or(false);
+                    let _ = relay_pool.answer_auth(&relay_url, approved);
+                }
+                Ok(_) | Err(RecvError::Lagged(_)) => {}
+                Err(RecvError::Closed) => break,
+            }
+        }
+    })
+}
@@ -1,71 +1,882 @@
-use anyhow::Result;
-use std::collections::HashMap;
+use crate::error::{NostrError, Result};
+use futures_util::{SinkExt, StreamExt};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::str::FromStr;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use tokio::net::TcpStream;
+use tokio::sync::{broadcast, mpsc, oneshot};
+use tokio_tungstenite::tungstenite::Message;
 use tokio_tungstenite::{connect_async, MaybeTlsStream, WebSocketStream};
 use url::Url;
 
-pub type RelayConnection = WebSocketStream<MaybeTlsStream<TcpStream>>;
+use crate::nostr::nip42::is_auth_required;
+use crate::nostr::nip65::normalize_relay_url;
+use crate::nostr::{ClientMessage, Filter, NostrEvent, NostrKeypair, RelayMessage};
 
-#[derive(Debug, Clone)]
-pub struct RelayManager {
-    relays: HashMap<String, RelayStatus>,
-}
+pub type RelayConnection = WebSocketStream<MaybeTlsStream<TcpStream>>;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum RelayStatus {
+    /// The websocket is open
     Connected,
+    /// A connection attempt is in progress
     Connecting,
+    /// An open connection was lost or shut down
     Disconnected,
+    /// The last connection attempt failed
     Failed,
 }
 
+/// How long a subscription's `since` is moved back on replay, to tolerate
+/// clock skew between event authors and us
+const REPLAY_SINCE_OVERLAP_SECS: u64 = 60;
 
-impl RelayManager {
-    pub fn new() -> Self {
+/// When and how often a relay connection is re-established after it fails or drops
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReconnectPolicy {
+    /// Delay before the first retry
+    pub initial_delay: Duration,
+    /// Upper bound for the exponentially growing delay
+    pub max_delay: Duration,
+    /// Consecutive retries before giving up, `None` retries forever
+    pub max_retries: Option<u32>,
+}
+
+impl ReconnectPolicy {
+    /// Never reconnect, for one-shot commands that should fail fast
+    pub fn none() -> Self {
+        Self {
+            max_retries: Some(0),
+            ..Self::default()
+        }
+    }
+
+    pub fn allows_retry(&self, attempt: u32) -> bool {
+        self.max_retries.is_none_or(|max_retries| attempt < max_retries)
+    }
+
+    /// Jittered exponential backoff: the delay doubles with every attempt up to
+    /// `max_delay`, and a random value between half and all of it is used so
+    /// clients don't reconnect in lockstep after a relay restart
+    pub fn delay_for_attempt(&self, attempt: u32) -> Duration {
+        let backoff = self
+            .initial_delay
+            .saturating_mul(2u32.saturating_pow(attempt))
+            .min(self.max_delay);
+
+        let millis = backoff.as_millis() as u64;
+        Duration::from_millis(rand::rng().random_range(millis / 2..=millis))
+    }
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
         Self {
-            relays: HashMap::new(),
+            initial_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(300),
+            max_retries: None,
+        }
+    }
+}
+
+/// How a relay's NIP-42 AUTH challenge is answered once it refuses a command
+/// with `auth-required:`. Authenticating tells the relay which key we are.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AuthPolicy {
+    /// Authenticate without asking
+    Always,
+    /// Broadcast `RelayNotification::AuthRequested` and wait for an answer
+    #[default]
+    Ask,
+    /// Never authenticate; refused commands fail
+    Never,
+}
+
+impl fmt::Display for AuthPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AuthPolicy::Always => write!(f, "always"),
+            AuthPolicy::Ask => write!(f, "ask"),
+            AuthPolicy::Never => write!(f, "never"),
+        }
+    }
+}
+
+impl FromStr for AuthPolicy {
+    type Err = NostrError;
+
+    fn from_str(policy: &str) -> Result<Self> {
+        match policy {
+            "always" => Ok(AuthPolicy::Always),
+            "ask" => Ok(AuthPolicy::Ask),
+            "never" => Ok(AuthPolicy::Never),
+            other => Err(NostrError::InvalidInput(format!(
+                "Unknown auth policy {} (expected always, ask or never)",
+                other
+            ))),
         }
     }
+}
 
-    pub async fn add_relay(&mut self, url: &str) -> Result<()> {
-        let relay_url = Url::parse(url)?;
+/// The key relays are authenticated with and each relay's `AuthPolicy`.
+///
+/// Cloning is cheap and clones share the same settings, so switching accounts
+/// takes effect on every open connection.
+#[derive(Debug, Clone, Default)]
+pub struct RelayAuth {
+    keypair: Arc<RwLock<Option<NostrKeypair>>>,
+    policies: Arc<RwLock<HashMap<String, AuthPolicy>>>,
+}
+
+impl RelayAuth {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Authenticate as `keypair` from now on, or never when `None`
+    pub fn set_keypair(&self, keypair: Option<NostrKeypair>) {
+        if let Ok(mut current) = self.keypair.write() {
+            *current = keypair;
+        }
+    }
+
+    pub fn keypair(&self) -> Option<NostrKeypair> {
+        self.keypair.read().ok().and_then(|keypair| keypair.clone())
+    }
 
-        if relay_url.scheme() != "ws" && relay_url.scheme() != "wss" {
-            return Err(anyhow::anyhow!("Invalid relay URL scheme: {}", relay_url.scheme()));
+    pub fn set_policy(&self, relay_url: &str, policy: AuthPolicy) {
+        if let Ok(mut policies) = self.policies.write() {
+            policies.insert(settings_key(relay_url), policy);
         }
+    }
+
+    /// Whether a relay requiring authentication could be answered at all
+    pub fn can_authenticate(&self, relay_url: &str) -> bool {
+        self.keypair().is_some() && self.policy(relay_url) != AuthPolicy::Never
+    }
+
+    /// The relay's policy, `AuthPolicy::Ask` unless one was set
+    pub fn policy(&self, relay_url: &str) -> AuthPolicy {
+        self.policies
+            .read()
+            .ok()
+            .and_then(|policies| policies.get(&settings_key(relay_url)).copied())
+            .unwrap_or_default()
+    }
+}
+
+/// The form of a relay URL per-relay settings are kept under, so that e.g.
+/// `wss://relay.example.com` and `wss://relay.example.com/` share them
+pub fn settings_key(relay_url: &str) -> String {
+    normalize_relay_url(relay_url).unwrap_or_else(|_| relay_url.to_string())
+}
+
+/// Notifications broadcast by relay connection tasks
+#[derive(Debug, Clone)]
+pub enum RelayNotification {
+    /// A message received from a relay
+    Message {
+        relay_url: String,
+        message: RelayMessage,
+    },
+    /// A relay moved to a new connection status
+    StatusChanged {
+        relay_url: String,
+        status: RelayStatus,
+    },
+    /// A relay with `AuthPolicy::Ask` requires authentication. Commands it
+    /// refused are held until `Relay::answer_auth` is called.
+    AuthRequested {
+        relay_url: String,
+        /// Public key we would authenticate as
+        pubkey: String,
+    },
+}
+
+/// Commands sent from a `Relay` handle to its connection task
+enum RelayCommand {
+    Publish {
+        event: NostrEvent,
+        result: oneshot::Sender<Result<String>>,
+    },
+    Subscribe {
+        subscription_id: String,
+        filters: Vec<Filter>,
+    },
+    Unsubscribe {
+        subscription_id: String,
+    },
+    AnswerAuth {
+        approved: bool,
+    },
+    Shutdown {
+        done: oneshot::Sender<()>,
+    },
+}
 
-        self.relays.insert(url.to_string(), RelayStatus::Disconnected);
+/// Handle to a long-lived connection to a single relay.
+///
+/// The websocket is owned by a background task; cloning the handle is cheap and
+/// every clone talks to the same connection.
+#[derive(Debug, Clone)]
+pub struct Relay {
+    url: String,
+    status: Arc<RwLock<RelayStatus>>,
+    commands: mpsc::UnboundedSender<RelayCommand>,
+}
+
+/// Validate that a relay URL is well formed and uses the ws or wss scheme
+pub fn validate_relay_url(url: &str) -> Result<Url> {
+    let relay_url = Url::parse(url).map_err(|e| NostrError::InvalidUrl(format!("{}: {}", url, e)))?;
+
+    if relay_url.scheme() != "ws" && relay_url.scheme() != "wss" {
+        return Err(NostrError::InvalidUrl(format!("{} is not a ws:// or wss:// URL", relay_url)));
+    }
+
+    Ok(relay_url)
+}
+
+impl Relay {
+    /// Start a connection task for the relay. Must be called from within a tokio runtime.
+    pub fn spawn(
+        url: &str,
+        notifications: broadcast::Sender<RelayNotification>,
+        reconnect_policy: ReconnectPolicy,
+        auth: RelayAuth,
+    ) -> Result<Self> {
+        validate_relay_url(url)?;
+
+        let status = Arc::new(RwLock::new(RelayStatus::Disconnected));
+        let (command_tx, command_rx) = mpsc::unbounded_channel();
+
+        let task = RelayTask {
+            url: url.to_string(),
+            status: status.clone(),
+            commands: command_rx,
+            notifications,
+            reconnect_policy,
+            auth,
+            subscriptions: HashMap::new(),
+            completed_subscriptions: HashSet::new(),
+            pending_publishes: HashMap::new(),
+            queued_publishes: Vec::new(),
+            auth_challenge: None,
+            auth_state: AuthState::Unauthenticated,
+            auth_blocked_publishes: Vec::new(),
+            auth_blocked_subscriptions: Vec::new(),
+        };
+        tokio::spawn(task.run());
+
+        Ok(Self {
+            url: url.to_string(),
+            status,
+            commands: command_tx,
+        })
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    pub fn status(&self) -> RelayStatus {
+        self.status.read().map(|status| status.clone()).unwrap_or(RelayStatus::Failed)
+    }
+
+    /// Send an event and wait for the relay's OK.
+    ///
+    /// Resolves to the relay's message when the event is accepted.
+    pub async fn publish(&self, event: NostrEvent) -> Result<String> {
+        let (result_tx, result_rx) = oneshot::channel();
+
+        self.commands
+            .send(RelayCommand::Publish { event, result: result_tx })
+            .map_err(|_| NostrError::RelayConnectionFailed(format!("Relay {} is not connected", self.url)))?;
+
+        result_rx
+            .await
+            .map_err(|_| {
+                NostrError::RelayConnectionFailed(format!(
+                    "Relay {} closed the connection before responding",
+                    self.url
+                ))
+            })?
+    }
+
+    pub fn subscribe(&self, subscription_id: String, filters: Vec<Filter>) -> Result<()> {
+        self.commands
+            .send(RelayCommand::Subscribe { subscription_id, filters })
+            .map_err(|_| NostrError::RelayConnectionFailed(format!("Relay {} is not connected", self.url)))
+    }
+
+    pub fn unsubscribe(&self, subscription_id: String) -> Result<()> {
+        self.commands
+            .send(RelayCommand::Unsubscribe { subscription_id })
+            .map_err(|_| NostrError::RelayConnectionFailed(format!("Relay {} is not connected", self.url)))
+    }
+
+    /// Allow or refuse authenticating after a `RelayNotification::AuthRequested`
+    pub fn answer_auth(&self, approved: bool) -> Result<()> {
+        self.commands
+            .send(RelayCommand::AnswerAuth { approved })
+            .map_err(|_| NostrError::RelayConnectionFailed(format!("Relay {} is not connected", self.url)))
+    }
+
+    /// Close the connection after any queued commands have been sent
+    pub async fn shutdown(&self) {
+        let (done_tx, done_rx) = oneshot::channel();
+        if self.commands.send(RelayCommand::Shutdown { done: done_tx }).is_ok() {
+            let _ = done_rx.await;
+        }
+    }
+}
+
+/// How a single websocket connection ended
+enum ConnectionEnd {
+    Shutdown(Option<oneshot::Sender<()>>),
+    Dropped(String),
+}
+
+/// Progress of NIP-42 authentication on the current connection
+#[derive(Debug, Clone, PartialEq)]
+enum AuthState {
+    /// The relay has not required authentication yet
+    Unauthenticated,
+    /// Authentication is required but the relay has not sent a challenge yet
+    AwaitingChallenge,
+    /// Waiting for `Relay::answer_auth` under `AuthPolicy::Ask`
+    AwaitingApproval,
+    /// Our AUTH event was sent and its OK is pending
+    Pending { event_id: String },
+    Authenticated,
+    /// Refused by policy, by the user or by the relay; later refusals are
+    /// reported as they are
+    Declined,
+}
+
+/// Background task owning the websocket for one relay
+struct RelayTask {
+    url: String,
+    status: Arc<RwLock<RelayStatus>>,
+    commands: mpsc::UnboundedReceiver<RelayCommand>,
+    notifications: broadcast::Sender<RelayNotification>,
+    reconnect_policy: ReconnectPolicy,
+    auth: RelayAuth,
+    /// Active subscriptions, replayed after reconnecting
+    subscriptions: HashMap<String, Vec<Filter>>,
+    /// Subscriptions whose stored events were fully received (EOSE), so a
+    /// replay only needs what happened since the connection dropped
+    completed_subscriptions: HashSet<String>,
+    /// Events sent on the current connection that are waiting for an OK, kept
+    /// so they can be sent again after authenticating
+    pending_publishes: HashMap<String, (NostrEvent, oneshot::Sender<Result<String>>)>,
+    /// Events published while disconnected, sent once a connection is up
+    queued_publishes: Vec<(NostrEvent, oneshot::Sender<Result<String>>)>,
+    /// Latest AUTH challenge received on the current connection
+    auth_challenge: Option<String>,
+    auth_state: AuthState,
+    /// Events refused with `auth-required:` and the relay's message, sent
+    /// again once authenticated
+    auth_blocked_publishes: Vec<(NostrEvent, oneshot::Sender<Result<String>>, String)>,
+    /// Subscriptions closed with `auth-required:` and the relay's message,
+    /// re-opened once authenticated
+    auth_blocked_subscriptions: Vec<(String, String)>,
+}
+
+impl RelayTask {
+    async fn run(mut self) {
+        let mut attempt = 0;
+        let mut disconnected_at = None;
+
+        loop {
+            self.set_status(RelayStatus::Connecting);
+
+            let give_up_reason = match connect_async(self.url.as_str()).await {
+                Ok((mut connection, _)) => {
+                    attempt = 0;
+                    self.set_status(RelayStatus::Connected);
+
+                    let end = match self.resume(&mut connection, disconnected_at).await {
+                        Ok(()) => self.run_connection(connection).await,
+                        Err(e) => ConnectionEnd::Dropped(e.to_string()),
+                    };
+
+                    match end {
+                        ConnectionEnd::Shutdown(done) => {
+                            self.shut_down(done);
+                            return;
+                        }
+                        ConnectionEnd::Dropped(reason) => {
+                            let reason = format!("Connection to relay {} lost: {}", self.url, reason);
+                            self.fail_pending_publishes(&reason);
+                            self.set_status(RelayStatus::Disconnected);
+                            disconnected_at = Some(unix_now());
+                            reason
+                        }
+                    }
+                }
+                Err(e) => {
+                    self.set_status(RelayStatus::Failed);
+                    format!("Failed to connect to relay {}: {}", self.url, e)
+                }
+            };
+
+            if !self.reconnect_policy.allows_retry(attempt) {
+                self.fail_queued_commands(&give_up_reason);
+                return;
+            }
+
+            let delay = self.reconnect_policy.delay_for_attempt(attempt);
+            attempt += 1;
+
+            if let Some(done) = self.wait_before_reconnect(delay).await {
+                self.shut_down(done);
+                return;
+            }
+        }
+    }
+
+    /// Wait out the backoff delay while still accepting commands. Returns the
+    /// shutdown acknowledgement channel if a shutdown was requested.
+    async fn wait_before_reconnect(&mut self, delay: Duration) -> Option<Option<oneshot::Sender<()>>> {
+        let sleep = tokio::time::sleep(delay);
+        tokio::pin!(sleep);
+
+        loop {
+            tokio::select! {
+                _ = &mut sleep => return None,
+                command = self.commands.recv() => match command {
+                    Some(RelayCommand::Publish { event, result }) => self.queued_publishes.push((event, result)),
+                    Some(RelayCommand::Subscribe { subscription_id, filters }) => {
+                        self.subscriptions.insert(subscription_id, filters);
+                    }
+                    Some(RelayCommand::Unsubscribe { subscription_id }) => {
+                        self.subscriptions.remove(&subscription_id);
+                    }
+                    // The next connection starts unauthenticated anyway
+                    Some(RelayCommand::AnswerAuth { .. }) => {}
+                    Some(RelayCommand::Shutdown { done }) => return Some(Some(done)),
+                    None => return Some(None),
+                },
+            }
+        }
+    }
+
+    /// Bring a fresh connection up to date: re-issue active subscriptions and
+    /// send events published while disconnected
+    async fn resume(&mut self, connection: &mut RelayConnection, disconnected_at: Option<u64>) -> Result<()> {
+        // Authentication is per connection; blocked subscriptions are replayed below
+        self.auth_challenge = None;
+        self.auth_state = AuthState::Unauthenticated;
+        self.auth_blocked_subscriptions.clear();
+
+        let completed_subscriptions = std::mem::take(&mut self.completed_subscriptions);
+
+        for (subscription_id, filters) in &self.subscriptions {
+            let filters = match disconnected_at {
+                Some(disconnected_at) if completed_subscriptions.contains(subscription_id) => {
+                    replay_filters(filters, disconnected_at)
+                }
+                _ => filters.clone(),
+            };
+
+            let message = ClientMessage::Req { subscription_id: subscription_id.clone(), filters };
+            connection.send(Message::Text(message.to_json()?.into())).await?;
+        }
+
+        for (event, result) in std::mem::take(&mut self.queued_publishes) {
+            let message = ClientMessage::Event(event.clone());
+            self.pending_publishes.insert(event.id.clone(), (event, result));
+            connection.send(Message::Text(message.to_json()?.into())).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn run_connection(&mut self, mut connection: RelayConnection) -> ConnectionEnd {
+        loop {
+            tokio::select! {
+                command = self.commands.recv() => {
+                    match command {
+                        Some(RelayCommand::Shutdown { done }) => {
+                            let _ = connection.close(None).await;
+                            return ConnectionEnd::Shutdown(Some(done));
+                        }
+                        None => {
+                            let _ = connection.close(None).await;
+                            return ConnectionEnd::Shutdown(None);
+                        }
+                        Some(command) => {
+                            if let Err(e) = self.handle_command(&mut connection, command).await {
+                                return ConnectionEnd::Dropped(e.to_string());
+                            }
+                        }
+                    }
+                }
+                message = connection.next() => {
+                    match message {
+                        Some(Ok(Message::Text(text))) => {
+                            for reply in self.handle_message(text.as_str()) {
+                                if let Err(e) = send_message(&mut connection, &reply).await {
+                                    return ConnectionEnd::Dropped(e.to_string());
+                                }
+                            }
+                        }
+                        Some(Ok(Message::Close(_))) | None => {
+                            return ConnectionEnd::Dropped("closed by relay".to_string());
+                        }
+                        Some(Ok(_)) => {}
+                        Some(Err(e)) => return ConnectionEnd::Dropped(e.to_string()),
+                    }
+                }
+            }
+        }
+    }
+
+    async fn handle_command(&mut self, connection: &mut RelayConnection, command: RelayCommand) -> Result<()> {
+        let messages = match command {
+            RelayCommand::Publish { event, result } => {
+                let message = ClientMessage::Event(event.clone());
+                self.pending_publishes.insert(event.id.clone(), (event, result));
+                vec![message]
+            }
+            RelayCommand::Subscribe { subscription_id, filters } => {
+                self.subscriptions.insert(subscription_id.clone(), filters.clone());
+                self.completed_subscriptions.remove(&subscription_id);
+                self.auth_blocked_subscriptions.retain(|(id, _)| *id != subscription_id);
+                vec![ClientMessage::Req { subscription_id, filters }]
+            }
+            RelayCommand::Unsubscribe { subscription_id } => {
+                self.completed_subscriptions.remove(&subscription_id);
+                self.auth_blocked_subscriptions.retain(|(id, _)| *id != subscription_id);
+                if self.subscriptions.remove(&subscription_id).is_none() {
+                    return Ok(());
+                }
+                vec![ClientMessage::Close { subscription_id }]
+            }
+            RelayCommand::AnswerAuth { approved } => {
+                if self.auth_state != AuthState::AwaitingApproval {
+                    return Ok(());
+                }
+                if approved { self.send_auth() } else { self.decline_auth() }
+            }
+            RelayCommand::Shutdown { .. } => return Ok(()),
+        };
+
+        for message in &messages {
+            send_message(connection, message).await?;
+        }
         Ok(())
     }
 
-    pub async fn connect_relay(&mut self, url: &str) -> Result<RelayConnection> {
-        self.relays.insert(url.to_string(), RelayStatus::Connecting);
+    /// Handle a message from the relay and return anything that must be sent
+    /// back, e.g. an AUTH event or commands retried after authenticating
+    fn handle_message(&mut self, text: &str) -> Vec<ClientMessage> {
+        // Relays occasionally send garbage; there is nobody to report it to
+        let Ok(message) = RelayMessage::from_json(text) else {
+            return Vec::new();
+        };
+
+        let mut replies = Vec::new();
+        match &message {
+            RelayMessage::Ok { event_id, accepted, message: reason } => {
+                if self.auth_state == (AuthState::Pending { event_id: event_id.clone() }) {
+                    replies = if *accepted { self.finish_auth() } else { self.decline_auth() };
+                } else if let Some((event, result)) = self.pending_publishes.remove(event_id) {
+                    if !*accepted && is_auth_required(reason) && self.can_retry_after_auth() {
+                        // Answered after authenticating, so the refusal isn't broadcast
+                        self.auth_blocked_publishes.push((event, result, reason.clone()));
+                        return self.require_auth();
+                    }
+
+                    let outcome = if *accepted {
+                        Ok(reason.clone())
+                    } else {
+                        Err(NostrError::from_relay_message(reason))
+                    };
+                    let _ = result.send(outcome);
+                }
+            }
+            RelayMessage::EndOfStoredEvents { subscription_id } => {
+                self.completed_subscriptions.insert(subscription_id.clone());
+            }
+            RelayMessage::Closed { subscription_id, message: reason } => {
+                if is_auth_required(reason)
+                    && self.subscriptions.contains_key(subscription_id)
+                    && self.can_retry_after_auth()
+                {
+                    // Kept open and re-sent once authenticated, so listeners
+                    // only hear about it if authentication fails
+                    self.auth_blocked_subscriptions.push((subscription_id.clone(), reason.clone()));
+                    return self.require_auth();
+                }
+
+                self.subscriptions.remove(subscription_id);
+                self.completed_subscriptions.remove(subscription_id);
+            }
+            RelayMessage::Auth { challenge } => {
+                self.auth_challenge = Some(challenge.clone());
+                if self.auth_state == AuthState::AwaitingChallenge {
+                    replies = self.begin_auth();
+                }
+            }
+            _ => {}
+        }
+
+        let _ = self.notifications.send(RelayNotification::Message {
+            relay_url: self.url.clone(),
+            message,
+        });
+        replies
+    }
+
+    /// Whether a command refused with `auth-required:` should wait for
+    /// authentication rather than fail right away
+    fn can_retry_after_auth(&self) -> bool {
+        !matches!(self.auth_state, AuthState::Authenticated | AuthState::Declined)
+    }
+
+    /// Start authenticating unless an attempt is already under way
+    fn require_auth(&mut self) -> Vec<ClientMessage> {
+        match self.auth_state {
+            AuthState::Unauthenticated => self.begin_auth(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Answer the challenge according to the relay's policy
+    fn begin_auth(&mut self) -> Vec<ClientMessage> {
+        if self.auth_challenge.is_none() {
+            self.auth_state = AuthState::AwaitingChallenge;
+            return Vec::new();
+        }
+
+        let Some(keypair) = self.auth.keypair() else {
+            return self.decline_auth();
+        };
+
+        match self.auth.policy(&self.url) {
+            AuthPolicy::Always => self.send_auth(),
+            AuthPolicy::Never => self.decline_auth(),
+            AuthPolicy::Ask => {
+                self.auth_state = AuthState::AwaitingApproval;
+                let _ = self.notifications.send(RelayNotification::AuthRequested {
+                    relay_url: self.url.clone(),
+                    pubkey: keypair.public_key_hex(),
+                });
+                Vec::new()
+            }
+        }
+    }
+
+    fn send_auth(&mut self) -> Vec<ClientMessage> {
+        let event = match (&self.auth_challenge, self.auth.keypair()) {
+            (Some(challenge), Some(keypair)) => NostrEvent::new_auth(challenge, &self.url, &keypair),
+            _ => return self.decline_auth(),
+        };
+
+        match event {
+            Ok(event) => {
+                self.auth_state = AuthState::Pending { event_id: event.id.clone() };
+                vec![ClientMessage::Auth(event)]
+            }
+            Err(_) => self.decline_auth(),
+        }
+    }
+
+    /// The relay accepted our AUTH: retry everything it refused
+    fn finish_auth(&mut self) -> Vec<ClientMessage> {
+        self.auth_state = AuthState::Authenticated;
+        let mut retries = Vec::new();
 
-        match connect_async(url).await {
-            Ok((ws_stream, _)) => {
-                self.relays.insert(url.to_string(), RelayStatus::Connected);
-                println!("Connected to relay: {}", url);
-                Ok(ws_stream)
+        for (event, result, _) in std::mem::take(&mut self.auth_blocked_publishes) {
+            retries.push(ClientMessage::Event(event.clone()));
+            self.pending_publishes.insert(event.id.clone(), (event, result));
+        }
+
+        for (subscription_id, _) in std::mem::take(&mut self.auth_blocked_subscriptions) {
+            if let Some(filters) = self.subscriptions.get(&subscription_id) {
+                retries.push(ClientMessage::Req { subscription_id, filters: filters.clone() });
             }
-            Err(e) => {
-                self.relays.insert(url.to_string(), RelayStatus::Failed);
-                Err(anyhow::anyhow!("Failed to connect to relay {}: {}", url, e))
+        }
+
+        retries
+    }
+
+    /// Give up on authenticating this connection and report what was refused
+    fn decline_auth(&mut self) -> Vec<ClientMessage> {
+        self.auth_state = AuthState::Declined;
+
+        for (_, result, reason) in self.auth_blocked_publishes.drain(..) {
+            let _ = result.send(Err(NostrError::from_relay_message(&reason)));
+        }
+
+        for (subscription_id, reason) in std::mem::take(&mut self.auth_blocked_subscriptions) {
+            self.subscriptions.remove(&subscription_id);
+            self.completed_subscriptions.remove(&subscription_id);
+            let _ = self.notifications.send(RelayNotification::Message {
+                relay_url: self.url.clone(),
+                message: RelayMessage::Closed { subscription_id, message: reason },
+            });
+        }
+
+        Vec::new()
+    }
+
+    fn set_status(&self, status: RelayStatus) {
+        if let Ok(mut current) = self.status.write() {
+            *current = status.clone();
+        }
+
+        let _ = self.notifications.send(RelayNotification::StatusChanged {
+            relay_url: self.url.clone(),
+            status,
+        });
+    }
+
+    fn shut_down(&mut self, done: Option<oneshot::Sender<()>>) {
+        self.fail_pending_publishes("Relay connection was shut down");
+        self.fail_queued_commands("Relay connection was shut down");
+        self.set_status(RelayStatus::Disconnected);
+        if let Some(done) = done {
+            let _ = done.send(());
+        }
+    }
+
+    fn fail_pending_publishes(&mut self, reason: &str) {
+        for (_, (_, result)) in self.pending_publishes.drain() {
+            let _ = result.send(Err(NostrError::RelayConnectionFailed(reason.to_string())));
+        }
+        for (_, result, _) in self.auth_blocked_publishes.drain(..) {
+            let _ = result.send(Err(NostrError::RelayConnectionFailed(reason.to_string())));
+        }
+    }
+
+    /// Answer everything still waiting for a connection when the task gives up
+    fn fail_queued_commands(&mut self, reason: &str) {
+        self.commands.close();
+
+        for (_, result) in self.queued_publishes.drain(..) {
+            let _ = result.send(Err(NostrError::RelayConnectionFailed(reason.to_string())));
+        }
+
+        while let Ok(command) = self.commands.try_recv() {
+            match command {
+                RelayCommand::Publish { result, .. } => {
+                    let _ = result.send(Err(NostrError::RelayConnectionFailed(reason.to_string())));
+                }
+                RelayCommand::Shutdown { done } => {
+                    let _ = done.send(());
+                }
+                RelayCommand::Subscribe { .. } | RelayCommand::Unsubscribe { .. } | RelayCommand::AnswerAuth { .. } => {}
             }
         }
     }
+}
+
+async fn send_message(connection: &mut RelayConnection, message: &ClientMessage) -> Result<()> {
+    connection.send(Message::Text(message.to_json()?.into())).await?;
+    Ok(())
+}
+
+/// Filters to re-issue after a reconnect. Only subscriptions whose stored events
+/// were already delivered are narrowed to what happened while disconnected.
+fn replay_filters(filters: &[Filter], disconnected_at: u64) -> Vec<Filter> {
+    let since = disconnected_at.saturating_sub(REPLAY_SINCE_OVERLAP_SECS);
+
+    filters
+        .iter()
+        .map(|filter| {
+            let mut filter = filter.clone();
+            filter.since = Some(filter.since.map_or(since, |original| original.max(since)));
+            filter
+        })
+        .collect()
+}
 
-    #[allow(dead_code)]
-    pub fn get_relay_status(&self, url: &str) -> Option<&RelayStatus> {
-        self.relays.get(url)
+pub(crate) fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_auth_policies_ignore_trailing_slashes() {
+        let auth = RelayAuth::new();
+        auth.set_policy("wss://relay.example.com/", AuthPolicy::Never);
+
+        assert_eq!(auth.policy("wss://relay.example.com"), AuthPolicy::Never);
+        assert_eq!(auth.policy("wss://other.example.com"), AuthPolicy::Ask);
     }
 
-    #[allow(dead_code)]
-    pub fn connected_relays(&self) -> Vec<&String> {
-        self.relays
-            .iter()
-            .filter_map(|(url, status)| {
-                matches!(status, RelayStatus::Connected).then_some(url)
-            })
-            .collect()
+    #[test]
+    fn test_backoff_grows_exponentially_with_jitter() {
+        let policy = ReconnectPolicy {
+            initial_delay: Duration::from_millis(100),
+            max_delay: Duration::from_secs(10),
+            max_retries: None,
+        };
+
+        for (attempt, full_delay) in [(0, 100), (1, 200), (2, 400), (5, 3200)] {
+            for _ in 0..20 {
+                let delay = policy.delay_for_attempt(attempt).as_millis() as u64;
+                assert!(
+                    (full_delay / 2..=full_delay).contains(&delay),
+                    "attempt {} gave {}ms",
+                    attempt,
+                    delay
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_backoff_is_capped() {
+        let policy = ReconnectPolicy {
+            initial_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(30),
+            max_retries: None,
+        };
+
+        assert!(policy.delay_for_attempt(10) <= Duration::from_secs(30));
+        assert!(policy.delay_for_attempt(u32::MAX) <= Duration::from_secs(30));
+        assert!(policy.delay_for_attempt(u32::MAX) >= Duration::from_secs(15));
+    }
+
+    #[test]
+    fn test_retry_limits() {
+        assert!(ReconnectPolicy::default().allows_retry(1000));
+        assert!(!ReconnectPolicy::none().allows_retry(0));
+
+        let policy = ReconnectPolicy {
+            max_retries: Some(2),
+            ..ReconnectPolicy::default()
+        };
+        assert!(policy.allows_retry(1));
+        assert!(!policy.allows_retry(2));
+    }
+
+    #[test]
+    fn test_replay_filters_only_ask_for_missed_events() {
+        let filters = vec![
+            Filter::new().with_kinds(vec![1]),
+            Filter::new().with_since(10_000),
+            Filter::new().with_since(100),
+        ];
+
+        let replayed = replay_filters(&filters, 5_000);
+
+        assert_eq!(replayed[0], Filter::new().with_kinds(vec![1]).with_since(5_000 - REPLAY_SINCE_OVERLAP_SECS));
+        assert_eq!(replayed[1].since, Some(10_000));
+        assert_eq!(replayed[2].since, Some(5_000 - REPLAY_SINCE_OVERLAP_SECS));
     }
-}
\ No newline at end of file
+}
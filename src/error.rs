@@ -1,7 +1,10 @@
 use std::fmt;
 
-#[derive(Debug)]
-#[allow(dead_code)]
+/// Result of the library's fallible operations
+pub type Result<T, E = NostrError> = std::result::Result<T, E>;
+
+/// Everything that can go wrong in the library, for callers to match on
+#[derive(Debug, Clone, PartialEq)]
 pub enum NostrError {
     InvalidPrivateKey(String),
     InvalidPublicKey(String),
@@ -9,12 +12,139 @@ pub enum NostrError {
     EventCreationFailed(String),
     RelayConnectionFailed(String),
     RelayResponseTimeout,
+    /// Refused without one of the machine-readable prefixes below
     RelayRejectedEvent(String),
+    /// `duplicate:` the relay already has the event
+    Duplicate(String),
+    /// `pow:` the event lacks the proof of work (NIP-13) the relay requires
+    ProofOfWorkRequired(String),
+    /// `blocked:` the author or client is banned from the relay
+    Blocked(String),
+    /// `rate-limited:` too many events or subscriptions, try again later
+    RateLimited(String),
+    /// `invalid:` the event or filter is malformed
+    InvalidEvent(String),
+    /// `restricted:` the relay only serves some authors or kinds
+    Restricted(String),
+    /// `auth-required:` the client must authenticate (NIP-42) first
+    AuthRequired(String),
+    /// `error:` the relay failed on its own side
+    RelayError(String),
+    /// No relay accepted an event, with the first refusal that said why
+    NoRelayAccepted { what: String, rejection: Option<Box<NostrError>> },
+    /// A relay's limits (NIP-11) rule the event out before it is sent
+    RelayLimitExceeded(String),
     InvalidEventId(String),
+    /// An event lacks the kind, tags or content its NIP requires
+    MalformedEvent(String),
+    /// A relay or client message that isn't valid NIP-01
+    InvalidMessage(String),
+    /// Malformed hex, bech32, base64 or UTF-8
+    InvalidEncoding(String),
+    /// Arguments that make no sense, e.g. a filter tag without a letter
+    InvalidInput(String),
     SerializationFailed(String),
     CryptographicError(String),
+    /// A message or key can't be decrypted, e.g. because of a wrong password
+    DecryptionFailed(String),
     InvalidUrl(String),
     NetworkError(String),
+    /// An HTTP request, e.g. for a NIP-11 document, failed
+    HttpError(String),
+    /// Reading or writing the config directory failed
+    IoError(String),
+    /// The local event store failed
+    StorageError(String),
+    /// An account, event or key that was asked for doesn't exist
+    NotFound(String),
+    /// Secret keys are needed but the keystore hasn't been unlocked
+    KeystoreLocked,
+    /// Stopped before finishing, e.g. mining proof of work
+    Cancelled(String),
+    /// An error from outside the library that fits no other variant
+    Other(String),
+}
+
+/// What to do about a relay refusing a command
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Remedy {
+    /// Nothing; the relay already has what was sent
+    Nothing,
+    /// Send it again, the failure was the relay's or the network's
+    Retry,
+    /// Send it again after waiting a while
+    BackOff,
+    /// Authenticate (NIP-42), then send it again
+    Authenticate,
+    /// Mine at least this many bits of proof of work, when the relay said
+    MineProofOfWork(Option<u32>),
+    /// Sending it again won't help
+    GiveUp,
+}
+
+impl NostrError {
+    /// Classify the message of a refusing OK or CLOSED by its NIP-01 prefix
+    pub fn from_relay_message(message: &str) -> Self {
+        let Some((prefix, reason)) = message.split_once(':') else {
+            return NostrError::RelayRejectedEvent(message.to_string());
+        };
+
+        let reason = reason.trim().to_string();
+        match prefix {
+            "duplicate" => NostrError::Duplicate(reason),
+            "pow" => NostrError::ProofOfWorkRequired(reason),
+            "blocked" => NostrError::Blocked(reason),
+            "rate-limited" => NostrError::RateLimited(reason),
+            "invalid" => NostrError::InvalidEvent(reason),
+            "restricted" => NostrError::Restricted(reason),
+            "auth-required" => NostrError::AuthRequired(reason),
+            "error" => NostrError::RelayError(reason),
+            _ => NostrError::RelayRejectedEvent(message.to_string()),
+        }
+    }
+
+    pub fn remedy(&self) -> Remedy {
+        match self {
+            NostrError::Duplicate(_) => Remedy::Nothing,
+            NostrError::RelayError(_)
+            | NostrError::RelayResponseTimeout
+            | NostrError::RelayConnectionFailed(_)
+            | NostrError::NetworkError(_) => Remedy::Retry,
+            NostrError::RateLimited(_) => Remedy::BackOff,
+            NostrError::AuthRequired(_) => Remedy::Authenticate,
+            NostrError::ProofOfWorkRequired(reason) => Remedy::MineProofOfWork(required_difficulty(reason)),
+            _ => Remedy::GiveUp,
+        }
+    }
+
+    /// Process exit code for a command failing with this error, one per class
+    /// of relay refusal so scripts can tell them apart; 1 for anything else
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            NostrError::RelayRejectedEvent(_) => 10,
+            NostrError::Duplicate(_) => 11,
+            NostrError::ProofOfWorkRequired(_) => 12,
+            NostrError::Blocked(_) => 13,
+            NostrError::RateLimited(_) => 14,
+            NostrError::InvalidEvent(_) => 15,
+            NostrError::Restricted(_) => 16,
+            NostrError::AuthRequired(_) => 17,
+            NostrError::RelayError(_) => 18,
+            NostrError::RelayResponseTimeout => 19,
+            NostrError::NoRelayAccepted { rejection: Some(rejection), .. } => rejection.exit_code(),
+            _ => 1,
+        }
+    }
+}
+
+/// The difficulty a `pow:` refusal asks for. Relays word it freely, e.g.
+/// "difficulty 12 is less than 20" or "difficulty 12<20", but name the
+/// requirement last.
+fn required_difficulty(reason: &str) -> Option<u32> {
+    reason
+        .split(|c: char| !c.is_ascii_digit())
+        .rfind(|number| !number.is_empty())
+        .and_then(|number| number.parse().ok())
 }
 
 impl fmt::Display for NostrError {
@@ -27,20 +157,47 @@ impl fmt::Display for NostrError {
             NostrError::RelayConnectionFailed(msg) => write!(f, "Relay connection failed: {}", msg),
             NostrError::RelayResponseTimeout => write!(f, "Relay response timeout"),
             NostrError::RelayRejectedEvent(msg) => write!(f, "Relay rejected event: {}", msg),
+            NostrError::Duplicate(msg) => write!(f, "Relay already has the event: {}", msg),
+            NostrError::ProofOfWorkRequired(msg) => write!(f, "Relay requires more proof of work: {}", msg),
+            NostrError::Blocked(msg) => write!(f, "Blocked by relay: {}", msg),
+            NostrError::RateLimited(msg) => write!(f, "Rate limited by relay: {}", msg),
+            NostrError::InvalidEvent(msg) => write!(f, "Relay found the event invalid: {}", msg),
+            NostrError::Restricted(msg) => write!(f, "Restricted by relay: {}", msg),
+            NostrError::AuthRequired(msg) => write!(f, "Relay requires authentication: {}", msg),
+            NostrError::RelayError(msg) => write!(f, "Relay error: {}", msg),
+            NostrError::NoRelayAccepted { what, .. } => write!(f, "No relay accepted the {}", what),
+            NostrError::RelayLimitExceeded(msg)
+            | NostrError::MalformedEvent(msg)
+            | NostrError::InvalidInput(msg)
+            | NostrError::NotFound(msg)
+            | NostrError::Other(msg) => write!(f, "{}", msg),
             NostrError::InvalidEventId(msg) => write!(f, "Invalid event ID: {}", msg),
+            NostrError::InvalidMessage(msg) => write!(f, "Invalid message: {}", msg),
+            NostrError::InvalidEncoding(msg) => write!(f, "Invalid encoding: {}", msg),
             NostrError::SerializationFailed(msg) => write!(f, "Serialization failed: {}", msg),
             NostrError::CryptographicError(msg) => write!(f, "Cryptographic error: {}", msg),
+            NostrError::DecryptionFailed(msg) => write!(f, "Decryption failed: {}", msg),
             NostrError::InvalidUrl(msg) => write!(f, "Invalid URL: {}", msg),
             NostrError::NetworkError(msg) => write!(f, "Network error: {}", msg),
+            NostrError::HttpError(msg) => write!(f, "HTTP error: {}", msg),
+            NostrError::IoError(msg) => write!(f, "I/O error: {}", msg),
+            NostrError::StorageError(msg) => write!(f, "Event store error: {}", msg),
+            NostrError::KeystoreLocked => write!(f, "Keystore is locked"),
+            NostrError::Cancelled(msg) => write!(f, "Cancelled: {}", msg),
         }
     }
 }
 
 impl std::error::Error for NostrError {}
 
+/// Errors from the binary's own code; a `NostrError` anywhere among the causes
+/// is kept as it is
 impl From<anyhow::Error> for NostrError {
     fn from(err: anyhow::Error) -> Self {
-        NostrError::CryptographicError(err.to_string())
+        match err.chain().find_map(|cause| cause.downcast_ref::<NostrError>()) {
+            Some(nostr_error) => nostr_error.clone(),
+            None => NostrError::Other(err.to_string()),
+        }
     }
 }
 
@@ -52,7 +209,7 @@ impl From<serde_json::Error> for NostrError {
 
 impl From<hex::FromHexError> for NostrError {
     fn from(err: hex::FromHexError) -> Self {
-        NostrError::InvalidPrivateKey(err.to_string())
+        NostrError::InvalidEncoding(err.to_string())
     }
 }
 
@@ -72,4 +229,94 @@ impl From<tokio_tungstenite::tungstenite::Error> for NostrError {
     fn from(err: tokio_tungstenite::tungstenite::Error) -> Self {
         NostrError::NetworkError(err.to_string())
     }
-}
\ No newline at end of file
+}
+
+impl From<std::io::Error> for NostrError {
+    fn from(err: std::io::Error) -> Self {
+        NostrError::IoError(err.to_string())
+    }
+}
+
+impl From<rusqlite::Error> for NostrError {
+    fn from(err: rusqlite::Error) -> Self {
+        NostrError::StorageError(err.to_string())
+    }
+}
+
+impl From<reqwest::Error> for NostrError {
+    fn from(err: reqwest::Error) -> Self {
+        NostrError::HttpError(err.to_string())
+    }
+}
+
+impl From<base64::DecodeError> for NostrError {
+    fn from(err: base64::DecodeError) -> Self {
+        NostrError::InvalidEncoding(err.to_string())
+    }
+}
+
+impl From<std::array::TryFromSliceError> for NostrError {
+    fn from(err: std::array::TryFromSliceError) -> Self {
+        NostrError::CryptographicError(err.to_string())
+    }
+}
+
+impl From<hmac::digest::InvalidLength> for NostrError {
+    fn from(err: hmac::digest::InvalidLength) -> Self {
+        NostrError::CryptographicError(err.to_string())
+    }
+}
+
+impl From<std::string::FromUtf8Error> for NostrError {
+    fn from(err: std::string::FromUtf8Error) -> Self {
+        NostrError::InvalidEncoding(err.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_relay_messages_are_classified_by_prefix() {
+        assert_eq!(
+            NostrError::from_relay_message("blocked: you are banned"),
+            NostrError::Blocked("you are banned".to_string())
+        );
+        assert_eq!(NostrError::from_relay_message("rate-limited:slow down").remedy(), Remedy::BackOff);
+        assert_eq!(
+            NostrError::from_relay_message("auth-required: members only").remedy(),
+            Remedy::Authenticate
+        );
+        assert_eq!(NostrError::from_relay_message("error: database is down").remedy(), Remedy::Retry);
+        assert_eq!(NostrError::from_relay_message("duplicate: already have it").remedy(), Remedy::Nothing);
+        assert_eq!(NostrError::from_relay_message("invalid: bad signature").remedy(), Remedy::GiveUp);
+
+        let unprefixed = NostrError::from_relay_message("no thanks: not today");
+        assert_eq!(unprefixed, NostrError::RelayRejectedEvent("no thanks: not today".to_string()));
+        assert_eq!(NostrError::from_relay_message("").exit_code(), 10);
+    }
+
+    #[test]
+    fn test_pow_refusals_name_the_required_difficulty() {
+        let remedy = |message: &str| NostrError::from_relay_message(message).remedy();
+        assert_eq!(remedy("pow: difficulty 12 is less than 20"), Remedy::MineProofOfWork(Some(20)));
+        assert_eq!(remedy("pow: difficulty 12<28"), Remedy::MineProofOfWork(Some(28)));
+        assert_eq!(remedy("pow: not enough work"), Remedy::MineProofOfWork(None));
+    }
+
+    #[test]
+    fn test_exit_code_comes_from_the_cause() {
+        let rejection = NostrError::Restricted("paid relay".to_string());
+        let error = NostrError::NoRelayAccepted {
+            what: "event".to_string(),
+            rejection: Some(Box::new(rejection.clone())),
+        };
+        assert_eq!(error.exit_code(), 16);
+
+        assert_eq!(NostrError::from(anyhow::Error::new(rejection.clone()).context("Post failed")), rejection);
+        let other = NostrError::from(anyhow::anyhow!("Terminal too small"));
+        assert_eq!(other, NostrError::Other("Terminal too small".to_string()));
+        assert_eq!(other.exit_code(), 1);
+    }
+}
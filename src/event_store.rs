@@ -0,0 +1,335 @@
+use crate::error::Result;
+use rusqlite::types::Value;
+use rusqlite::{Connection, OptionalExtension, Transaction, params, params_from_iter};
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+
+use crate::nostr::event::is_ephemeral;
+use crate::nostr::{Filter, NostrEvent};
+
+pub use crate::nostr::nip09::KIND_DELETION;
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS events (
+        id TEXT PRIMARY KEY,
+        pubkey TEXT NOT NULL,
+        kind INTEGER NOT NULL,
+        created_at INTEGER NOT NULL,
+        -- `kind:pubkey:d` for addressable events, `kind:pubkey:` for replaceable ones
+        address TEXT,
+        json TEXT NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS events_pubkey ON events (pubkey, created_at);
+    CREATE INDEX IF NOT EXISTS events_kind ON events (kind, created_at);
+    CREATE INDEX IF NOT EXISTS events_created_at ON events (created_at);
+    CREATE INDEX IF NOT EXISTS events_address ON events (address);
+
+    -- Single-letter tags, the ones filters can match on
+    CREATE TABLE IF NOT EXISTS tags (
+        event_id TEXT NOT NULL REFERENCES events (id) ON DELETE CASCADE,
+        name TEXT NOT NULL,
+        value TEXT NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS tags_name_value ON tags (name, value);
+    CREATE INDEX IF NOT EXISTS tags_event_id ON tags (event_id);
+
+    -- Targets of deletion requests, so deleted events are not stored again
+    CREATE TABLE IF NOT EXISTS deletions (
+        target TEXT NOT NULL,
+        pubkey TEXT NOT NULL,
+        created_at INTEGER NOT NULL,
+        PRIMARY KEY (target, pubkey)
+    );
+
+    -- Relays that accepted events we published, where deletion requests go
+    CREATE TABLE IF NOT EXISTS published (
+        event_id TEXT NOT NULL REFERENCES events (id) ON DELETE CASCADE,
+        relay_url TEXT NOT NULL,
+        PRIMARY KEY (event_id, relay_url)
+    );
+";
+
+/// Events received from relays, stored as `events.db` in the config directory.
+///
+/// Replaceable events only keep their newest version, and kind-5 deletion
+/// requests remove the author's referenced events. Signatures are not checked
+/// here; callers store events they have verified.
+pub struct EventStore {
+    connection: Connection,
+}
+
+impl EventStore {
+    pub fn open(config_dir: &Path) -> Result<Self> {
+        fs::create_dir_all(config_dir)?;
+        Self::with_connection(Connection::open(config_dir.join("events.db"))?)
+    }
+
+    #[allow(dead_code)]
+    pub fn open_in_memory() -> Result<Self> {
+        Self::with_connection(Connection::open_in_memory()?)
+    }
+
+    fn with_connection(connection: Connection) -> Result<Self> {
+        connection.pragma_update(None, "foreign_keys", true)?;
+        connection.pragma_update(None, "journal_mode", "WAL")?;
+        connection.execute_batch(SCHEMA)?;
+        Ok(Self { connection })
+    }
+
+    /// Store an event. Returns false when it wasn't stored: already known,
+    /// ephemeral, deleted, or older than the stored version of a replaceable event.
+    pub fn save(&mut self, event: &NostrEvent) -> Result<bool> {
+        if is_ephemeral(event.kind) {
+            return Ok(false);
+        }
+
+        let transaction = self.connection.transaction()?;
+        let saved = save_event(&transaction, event)?;
+        transaction.commit()?;
+        Ok(saved)
+    }
+
+    /// Stored events matching any of the filters, newest first. Each filter's
+    /// `limit` applies to that filter's matches.
+    pub fn query(&self, filters: &[Filter]) -> Result<Vec<NostrEvent>> {
+        let mut seen_event_ids = HashSet::new();
+        let mut events = Vec::new();
+
+        for filter in filters {
+            for event in self.query_filter(filter)? {
+                if seen_event_ids.insert(event.id.clone()) {
+                    events.push(event);
+                }
+            }
+        }
+
+        events.sort_by(|a, b| b.created_at.cmp(&a.created_at).then_with(|| a.id.cmp(&b.id)));
+        Ok(events)
+    }
+
+    pub fn get(&self, event_id: &str) -> Result<Option<NostrEvent>> {
+        let json: Option<String> = self
+            .connection
+            .query_row("SELECT json FROM events WHERE id = ?1", [event_id], |row| row.get(0))
+            .optional()?;
+
+        Ok(json.map(|json| serde_json::from_str(&json)).transpose()?)
+    }
+
+    /// Remember that these relays accepted a stored event we published
+    pub fn record_published(&self, event_id: &str, relay_urls: &[String]) -> Result<()> {
+        for relay_url in relay_urls {
+            self.connection.execute(
+                "INSERT OR IGNORE INTO published (event_id, relay_url) VALUES (?1, ?2)",
+                params![event_id, relay_url],
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Relays recorded as having accepted the event, sorted
+    pub fn published_relays(&self, event_id: &str) -> Result<Vec<String>> {
+        let mut statement = self
+            .connection
+            .prepare("SELECT relay_url FROM published WHERE event_id = ?1 ORDER BY relay_url")?;
+        let relay_urls = statement
+            .query_map([event_id], |row| row.get(0))?
+            .collect::<rusqlite::Result<Vec<String>>>()?;
+        Ok(relay_urls)
+    }
+
+    fn query_filter(&self, filter: &Filter) -> Result<Vec<NostrEvent>> {
+        let mut conditions = Vec::new();
+        let mut values: Vec<Value> = Vec::new();
+
+        for (column, prefixes) in [("id", &filter.ids), ("pubkey", &filter.authors)] {
+            let Some(prefixes) = prefixes else {
+                continue;
+            };
+
+            // Prefixes are hex, so anything else matches nothing
+            let prefixes: Vec<&String> = prefixes.iter().filter(|prefix| is_hex(prefix)).collect();
+            if prefixes.is_empty() {
+                return Ok(Vec::new());
+            }
+
+            let alternatives: Vec<String> = prefixes
+                .iter()
+                .map(|prefix| {
+                    if prefix.len() == 64 {
+                        values.push(Value::Text(prefix.to_lowercase()));
+                        format!("{} = ?", column)
+                    } else {
+                        values.push(Value::Text(format!("{}%", prefix.to_lowercase())));
+                        format!("{} LIKE ?", column)
+                    }
+                })
+                .collect();
+            conditions.push(format!("({})", alternatives.join(" OR ")));
+        }
+
+        if let Some(kinds) = &filter.kinds {
+            if kinds.is_empty() {
+                return Ok(Vec::new());
+            }
+            conditions.push(format!("kind IN ({})", placeholders(kinds.len())));
+            values.extend(kinds.iter().map(|kind| Value::Integer(*kind as i64)));
+        }
+
+        for (letter, tag_values) in &filter.tags {
+            conditions.push(format!(
+                "EXISTS (SELECT 1 FROM tags WHERE tags.event_id = events.id AND tags.name = ? AND tags.value IN ({}))",
+                placeholders(tag_values.len())
+            ));
+            values.push(Value::Text(letter.to_string()));
+            values.extend(tag_values.iter().map(|value| Value::Text(value.clone())));
+        }
+
+        if let Some(since) = filter.since {
+            conditions.push("created_at >= ?".to_string());
+            values.push(Value::Integer(since as i64));
+        }
+
+        if let Some(until) = filter.until {
+            conditions.push("created_at <= ?".to_string());
+            values.push(Value::Integer(until as i64));
+        }
+
+        let mut sql = "SELECT json FROM events".to_string();
+        if !conditions.is_empty() {
+            sql.push_str(" WHERE ");
+            sql.push_str(&conditions.join(" AND "));
+        }
+        sql.push_str(" ORDER BY created_at DESC, id");
+        if let Some(limit) = filter.limit {
+            sql.push_str(" LIMIT ?");
+            values.push(Value::Integer(limit.min(i64::MAX as u64) as i64));
+        }
+
+        let mut statement = self.connection.prepare(&sql)?;
+        let rows = statement.query_map(params_from_iter(values), |row| row.get::<_, String>(0))?;
+
+        let mut events = Vec::new();
+        for json in rows {
+            events.push(serde_json::from_str(&json?)?);
+        }
+        Ok(events)
+    }
+}
+
+fn save_event(transaction: &Transaction, event: &NostrEvent) -> Result<bool> {
+    let known: bool = transaction.query_row("SELECT EXISTS (SELECT 1 FROM events WHERE id = ?1)", [&event.id], |row| {
+        row.get(0)
+    })?;
+    if known || is_deleted(transaction, event)? {
+        return Ok(false);
+    }
+
+    let address = event.address();
+    if let Some(address) = &address {
+        let newest: Option<(String, u64)> = transaction
+            .query_row(
+                "SELECT id, created_at FROM events WHERE address = ?1 ORDER BY created_at DESC, id LIMIT 1",
+                [address],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()?;
+
+        // On equal timestamps the lowest id wins (NIP-01)
+        if let Some((id, created_at)) = newest
+            && (created_at, std::cmp::Reverse(id)) > (event.created_at, std::cmp::Reverse(event.id.clone()))
+        {
+            return Ok(false);
+        }
+
+        transaction.execute("DELETE FROM events WHERE address = ?1", [address])?;
+    }
+
+    transaction.execute(
+        "INSERT INTO events (id, pubkey, kind, created_at, address, json) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        params![event.id, event.pubkey, event.kind, event.created_at, address, serde_json::to_string(event)?],
+    )?;
+
+    for tag in &event.tags {
+        if tag.len() >= 2 && tag[0].chars().count() == 1 {
+            transaction.execute(
+                "INSERT INTO tags (event_id, name, value) VALUES (?1, ?2, ?3)",
+                params![event.id, tag[0], tag[1]],
+            )?;
+        }
+    }
+
+    if event.kind == KIND_DELETION {
+        apply_deletion(transaction, event)?;
+    }
+
+    Ok(true)
+}
+
+/// Remove the events a deletion request refers to, as long as they were
+/// published by the same author
+fn apply_deletion(transaction: &Transaction, deletion: &NostrEvent) -> Result<()> {
+    for tag in &deletion.tags {
+        if tag.len() < 2 {
+            continue;
+        }
+
+        match tag[0].as_str() {
+            "e" => {
+                transaction.execute(
+                    "DELETE FROM events WHERE id = ?1 AND pubkey = ?2 AND kind != ?3",
+                    params![tag[1], deletion.pubkey, KIND_DELETION],
+                )?;
+            }
+            // Only versions up to the deletion's created_at are deleted
+            "a" if tag[1].split(':').nth(1) == Some(deletion.pubkey.as_str()) => {
+                transaction.execute(
+                    "DELETE FROM events WHERE address = ?1 AND created_at <= ?2",
+                    params![tag[1], deletion.created_at],
+                )?;
+            }
+            _ => continue,
+        }
+
+        transaction.execute(
+            "INSERT INTO deletions (target, pubkey, created_at) VALUES (?1, ?2, ?3)
+             ON CONFLICT (target, pubkey) DO UPDATE SET created_at = MAX(created_at, excluded.created_at)",
+            params![tag[1], deletion.pubkey, deletion.created_at],
+        )?;
+    }
+
+    Ok(())
+}
+
+fn is_deleted(transaction: &Transaction, event: &NostrEvent) -> Result<bool> {
+    if event.kind == KIND_DELETION {
+        return Ok(false);
+    }
+
+    let deleted_by_id: bool = transaction.query_row(
+        "SELECT EXISTS (SELECT 1 FROM deletions WHERE target = ?1 AND pubkey = ?2)",
+        params![event.id, event.pubkey],
+        |row| row.get(0),
+    )?;
+    if deleted_by_id {
+        return Ok(true);
+    }
+
+    let Some(address) = event.address() else {
+        return Ok(false);
+    };
+    Ok(transaction.query_row(
+        "SELECT EXISTS (SELECT 1 FROM deletions WHERE target = ?1 AND pubkey = ?2 AND created_at >= ?3)",
+        params![address, event.pubkey, event.created_at],
+        |row| row.get(0),
+    )?)
+}
+
+fn is_hex(value: &str) -> bool {
+    value.len() <= 64 && value.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+fn placeholders(count: usize) -> String {
+    vec!["?"; count].join(", ")
+}
@@ -1,4 +1,4 @@
-use anyhow::{anyhow, Result};
+use crate::error::{NostrError, Result};
 use argon2::{
     password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
     Argon2,
@@ -46,19 +46,19 @@ impl KeystoreManager {
         let password_hash = self
             .argon2
             .hash_password(password.expose_secret().as_bytes(), &salt)
-            .map_err(|e| anyhow!("Password hashing failed: {}", e))?
+            .map_err(|e| NostrError::CryptographicError(format!("Password hashing failed: {}", e)))?
             .to_string();
 
         let encryption_key = self.derive_encryption_key(password, &salt)?;
 
         let keys_json = serde_json::to_string(keys)
-            .map_err(|e| anyhow!("Failed to serialize keys: {}", e))?;
+            .map_err(|e| NostrError::SerializationFailed(format!("Failed to serialize keys: {}", e)))?;
 
         let cipher = ChaCha20Poly1305::new(&encryption_key);
         let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
         let encrypted_data = cipher
             .encrypt(&nonce, keys_json.as_bytes())
-            .map_err(|e| anyhow!("Encryption failed: {}", e))?;
+            .map_err(|e| NostrError::CryptographicError(format!("Encryption failed: {}", e)))?;
 
         Ok(EncryptedKeystore {
             salt: salt.to_string(),
@@ -77,7 +77,7 @@ impl KeystoreManager {
         self.verify_password(keystore, password)?;
 
         let salt = SaltString::from_b64(&keystore.salt)
-            .map_err(|e| anyhow!("Invalid salt format: {}", e))?;
+            .map_err(|e| NostrError::CryptographicError(format!("Invalid salt format: {}", e)))?;
 
         let encryption_key = self.derive_encryption_key(password, &salt)?;
 
@@ -85,13 +85,13 @@ impl KeystoreManager {
         let nonce = Nonce::from_slice(&keystore.nonce);
         let decrypted_data = cipher
             .decrypt(nonce, keystore.encrypted_data.as_slice())
-            .map_err(|e| anyhow!("Decryption failed: {}", e))?;
+            .map_err(|e| NostrError::DecryptionFailed(format!("Decryption failed: {}", e)))?;
 
         let keys_json = String::from_utf8(decrypted_data)
-            .map_err(|e| anyhow!("Invalid UTF-8 in decrypted data: {}", e))?;
+            .map_err(|e| NostrError::DecryptionFailed(format!("Invalid UTF-8 in decrypted data: {}", e)))?;
 
         let keys_map: HashMap<String, String> = serde_json::from_str(&keys_json)
-            .map_err(|e| anyhow!("Failed to parse decrypted keys: {}", e))?;
+            .map_err(|e| NostrError::DecryptionFailed(format!("Failed to parse decrypted keys: {}", e)))?;
 
         let secure_keys: HashMap<String, SecretString> = keys_map
             .into_iter()
@@ -151,11 +151,11 @@ impl KeystoreManager {
         password: &SecretString,
     ) -> Result<()> {
         let parsed_hash = PasswordHash::new(&keystore.password_hash)
-            .map_err(|e| anyhow!("Invalid password hash format: {}", e))?;
+            .map_err(|e| NostrError::DecryptionFailed(format!("Invalid password hash format: {}", e)))?;
 
         self.argon2
             .verify_password(password.expose_secret().as_bytes(), &parsed_hash)
-            .map_err(|_| anyhow!("Invalid password"))?;
+            .map_err(|_| NostrError::DecryptionFailed("Invalid password".to_string()))?;
 
         Ok(())
     }
@@ -168,13 +168,15 @@ impl KeystoreManager {
         let password_hash = self
             .argon2
             .hash_password(password.expose_secret().as_bytes(), salt)
-            .map_err(|e| anyhow!("Key derivation failed: {}", e))?;
+            .map_err(|e| NostrError::CryptographicError(format!("Key derivation failed: {}", e)))?;
 
         // Extract the first 32 bytes of the hash for ChaCha20Poly1305 key
         let hash = password_hash.hash.unwrap();
         let hash_bytes = hash.as_bytes();
         if hash_bytes.len() < 32 {
-            return Err(anyhow!("Derived hash too short for encryption key"));
+            return Err(NostrError::CryptographicError(
+                "Derived hash too short for encryption key".to_string(),
+            ));
         }
 
         let mut key_bytes = [0u8; 32];
@@ -1,4 +1,11 @@
 pub mod nostr;
 pub mod connection;
+pub mod pool;
+pub mod outbox;
 pub mod commands;
+pub mod keystore;
+pub mod accounts;
+pub mod event_store;
+pub mod relay_info;
+pub mod relay_settings;
 pub mod error;
\ No newline at end of file
@@ -1,15 +1,32 @@
 mod nostr;
 mod connection;
+mod pool;
+mod outbox;
 mod commands;
 mod keystore;
 mod accounts;
+mod event_store;
+mod relay_info;
+mod relay_settings;
 mod tui;
 mod error;
+#[cfg(test)]
+#[path = "../tests/temp_config_dir.rs"]
+mod temp_config_dir;
 
-use anyhow::Result;
+use error::{NostrError, Result};
 use clap::{Parser, Subcommand};
-use commands::PostCommand;
-use nostr::generate_keypair;
+use accounts::default_config_dir;
+use commands::{
+    AccountCommand, DecodeCommand, DeleteCommand, DmCommand, EncodeCommand, FollowCommand, ListenCommand, PostCommand, ProfileCommand, ReactCommand,
+    RelayCommand,
+};
+use connection::AuthPolicy;
+use nostr::nip19::{AddressPointer, EventPointer, ProfilePointer};
+use nostr::nip25::Reaction;
+use nostr::nip65::{RelayList, RelayUsage};
+use nostr::{Nip19, event_id_from_str, generate_keypair, keypair_from_secret, public_key_from_str};
+use relay_settings::RelaySettings;
 
 #[derive(Parser)]
 #[command(name = "nosotros")]
@@ -22,67 +39,703 @@ struct Cli {
 #[derive(Subcommand)]
 enum Commands {
     /// Launch the terminal user interface
-    Tui,
+    Tui {
+        /// Hide notes with less than this many bits of proof of work (NIP-13)
+        #[arg(long, value_name = "BITS", default_value_t = 0)]
+        min_pow: u32,
+    },
     /// Generate a new keypair
     Keygen,
     /// Post a text note
     Post {
         text: String,
+        /// Relay to publish to (repeatable)
+        #[arg(long = "relay", required = true)]
+        relays: Vec<String>,
+        /// Account to sign with, by name or id (defaults to the active account)
         #[arg(long)]
-        relay: String,
+        account: Option<String>,
+        /// Mine this many leading zero bits of proof of work (NIP-13), for
+        /// relays that require it
+        #[arg(long, value_name = "BITS")]
+        pow: Option<u32>,
+    },
+    /// Ask relays to delete one of your events (NIP-09)
+    Delete {
+        /// Note id (note, nevent or hex), or naddr of an addressable event
+        target: String,
+        /// Why it is deleted, shown by some clients
         #[arg(long)]
-        key: String,
+        reason: Option<String>,
+        /// Relay to send the request to, besides the ones the event was
+        /// published to (repeatable)
+        #[arg(long = "relay")]
+        relays: Vec<String>,
+        /// Account to sign with, by name or id (defaults to the active account)
+        #[arg(long)]
+        account: Option<String>,
+    },
+    /// Manage accounts in the encrypted keystore
+    Account {
+        #[command(subcommand)]
+        action: AccountAction,
+    },
+    /// Follow, unfollow and list contacts (NIP-02)
+    #[command(flatten)]
+    Follow(FollowAction),
+    /// React to and repost notes (NIP-25, NIP-18)
+    #[command(flatten)]
+    React(ReactAction),
+    /// Publish and show profile metadata (kind 0)
+    Profile {
+        #[command(subcommand)]
+        action: ProfileAction,
+    },
+    /// Per-relay settings
+    Relay {
+        #[command(subcommand)]
+        action: RelayAction,
+    },
+    /// Send and read private messages (NIP-17)
+    Dm {
+        #[command(subcommand)]
+        action: DmAction,
+    },
+    /// Decode a NIP-19 entity (npub, nsec, note, nprofile, nevent, naddr, nrelay)
+    Decode {
+        entity: String,
+    },
+    /// Encode keys, ids and pointers as NIP-19 entities
+    Encode {
+        #[command(subcommand)]
+        entity: EncodeEntity,
     },
     /// Connect to relay and listen for events
-    Listen { relay_url: String },
+    Listen {
+        /// Relays to subscribe on
+        #[arg(required = true)]
+        relay_urls: Vec<String>,
+        /// Only match events of this kind (repeatable)
+        #[arg(long = "kind")]
+        kinds: Vec<u16>,
+        /// Only match events from this public key (repeatable)
+        #[arg(long = "author")]
+        authors: Vec<String>,
+        /// Only match events created at or after this unix timestamp
+        #[arg(long)]
+        since: Option<u64>,
+        /// Only match events created at or before this unix timestamp
+        #[arg(long)]
+        until: Option<u64>,
+        /// Maximum number of stored events to request
+        #[arg(long)]
+        limit: Option<u64>,
+        /// Tag filter as <letter>=<value>, e.g. t=nostr (repeatable)
+        #[arg(long = "tag")]
+        tags: Vec<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum AccountAction {
+    /// Generate a new account
+    New {
+        name: String,
+    },
+    /// Import an existing private key (nsec or hex), read without echo
+    Import {
+        name: String,
+    },
+    /// List accounts, marking the active one with *
+    List,
+    /// Make an account the active one
+    Use {
+        /// Account name or id
+        account: String,
+    },
+    /// Delete an account and its private key
+    Remove {
+        /// Account name or id
+        account: String,
+        /// Don't ask for confirmation
+        #[arg(long)]
+        yes: bool,
+    },
+    /// Show an account's public details
+    Show {
+        /// Account name or id (defaults to the active account)
+        account: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum FollowAction {
+    /// Follow someone by adding them to your contact list (NIP-02)
+    Follow {
+        /// Public key (npub, nprofile or hex)
+        pubkey: String,
+        /// Your own name for them
+        #[arg(long)]
+        petname: Option<String>,
+        /// Relay where their notes can be found
+        #[arg(long)]
+        relay_hint: Option<String>,
+        /// Relay to read your contact list from and publish it to (repeatable)
+        #[arg(long = "relay", required = true)]
+        relays: Vec<String>,
+        /// Account to sign with, by name or id (defaults to the active account)
+        #[arg(long)]
+        account: Option<String>,
+        /// Start a new contact list when none is found on the relays
+        #[arg(long)]
+        new: bool,
+    },
+    /// Remove someone from your contact list
+    Unfollow {
+        /// Public key (npub, nprofile or hex)
+        pubkey: String,
+        /// Relay to read your contact list from and publish it to (repeatable)
+        #[arg(long = "relay", required = true)]
+        relays: Vec<String>,
+        /// Account to sign with, by name or id (defaults to the active account)
+        #[arg(long)]
+        account: Option<String>,
+        /// Publish even if the list ends up empty
+        #[arg(long)]
+        force: bool,
+    },
+    /// List who someone follows
+    Following {
+        /// Public key (npub, nprofile or hex; defaults to the active account)
+        pubkey: Option<String>,
+        /// Relay to look for the contact list on (repeatable)
+        #[arg(long = "relay", required = true)]
+        relays: Vec<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum ReactAction {
+    /// React to a note (NIP-25): + to like, - to dislike, or an emoji
+    React {
+        /// Note id (note, nevent or hex)
+        note: String,
+        /// +, -, an emoji, or :shortcode: of a custom emoji given with --emoji-url
+        #[arg(default_value = "+", allow_hyphen_values = true)]
+        reaction: String,
+        /// Image of the custom emoji (NIP-30)
+        #[arg(long)]
+        emoji_url: Option<String>,
+        /// Relay to find the note on and publish to (repeatable)
+        #[arg(long = "relay", required = true)]
+        relays: Vec<String>,
+        /// Account to sign with, by name or id (defaults to the active account)
+        #[arg(long)]
+        account: Option<String>,
+    },
+    /// Repost a note (NIP-18), or quote it in a note of your own
+    Repost {
+        /// Note id (note, nevent or hex)
+        note: String,
+        /// Publish a note with this text quoting the original instead
+        #[arg(long)]
+        quote: Option<String>,
+        /// Relay to find the note on and publish to (repeatable)
+        #[arg(long = "relay", required = true)]
+        relays: Vec<String>,
+        /// Account to sign with, by name or id (defaults to the active account)
+        #[arg(long)]
+        account: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum ProfileAction {
+    /// Update your profile. Fields not given keep their published value;
+    /// pass "" to remove one.
+    Set {
+        #[arg(long)]
+        name: Option<String>,
+        #[arg(long)]
+        display_name: Option<String>,
+        #[arg(long)]
+        about: Option<String>,
+        /// Avatar URL
+        #[arg(long)]
+        picture: Option<String>,
+        /// Banner image URL
+        #[arg(long)]
+        banner: Option<String>,
+        /// Internet identifier (NIP-05), e.g. bob@example.com
+        #[arg(long)]
+        nip05: Option<String>,
+        /// Lightning address
+        #[arg(long)]
+        lud16: Option<String>,
+        #[arg(long)]
+        website: Option<String>,
+        /// Relay to publish to (repeatable)
+        #[arg(long = "relay", required = true)]
+        relays: Vec<String>,
+        /// Account to sign with, by name or id (defaults to the active account)
+        #[arg(long)]
+        account: Option<String>,
+    },
+    /// Show someone's profile
+    Show {
+        /// Public key (npub, nprofile or hex; defaults to the active account)
+        pubkey: Option<String>,
+        /// Relay to look for the profile on (repeatable)
+        #[arg(long = "relay", required = true)]
+        relays: Vec<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum RelayAction {
+    /// Fetch a relay's NIP-11 information document: supported NIPs and limits
+    Info {
+        relay: String,
+    },
+    /// Show or set how a relay's NIP-42 authentication requests are answered
+    Auth {
+        /// Relay URL (defaults to every relay with a policy set)
+        relay: Option<String>,
+        /// always, ask or never
+        #[arg(requires = "relay")]
+        policy: Option<AuthPolicy>,
+    },
+    /// Show someone's relay list (NIP-65): where they write and read mentions
+    List {
+        /// Public key (npub, nprofile or hex; defaults to the active account)
+        pubkey: Option<String>,
+        /// Relay to look for the list on (repeatable)
+        #[arg(long = "relay", required = true)]
+        relays: Vec<String>,
+    },
+    /// Publish your relay list (NIP-65), replacing the previous one
+    SetList {
+        /// Relay you read mentions from (repeatable)
+        #[arg(long)]
+        read: Vec<String>,
+        /// Relay you publish to (repeatable)
+        #[arg(long)]
+        write: Vec<String>,
+        /// Relay you both read from and publish to (repeatable)
+        #[arg(long)]
+        both: Vec<String>,
+        /// Extra relay to publish the list to, besides the listed ones (repeatable)
+        #[arg(long = "relay")]
+        relays: Vec<String>,
+        /// Account to sign with, by name or id (defaults to the active account)
+        #[arg(long)]
+        account: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum DmAction {
+    /// Send a private message
+    Send {
+        /// Recipient public key (npub, nprofile or hex)
+        recipient: String,
+        message: String,
+        /// Relay to publish to (repeatable)
+        #[arg(long = "relay", required = true)]
+        relays: Vec<String>,
+        /// Account to send from, by name or id (defaults to the active account)
+        #[arg(long)]
+        account: Option<String>,
+        /// Send as a legacy NIP-04 message, which reveals to relays who you
+        /// talk to and when. Only for contacts whose clients lack NIP-17.
+        #[arg(long)]
+        legacy_nip04: bool,
+    },
+    /// Read private messages sent to and from your accounts
+    Read {
+        /// Relay to read from (repeatable)
+        #[arg(long = "relay", required = true)]
+        relays: Vec<String>,
+        /// Only read messages of this account, by name or id (defaults to every account)
+        #[arg(long)]
+        account: Option<String>,
+        /// Only show conversations with this public key
+        #[arg(long)]
+        with: Option<String>,
+    },
+}
+
+/// Keys and ids may be given as hex or in any NIP-19 form that contains them
+#[derive(Subcommand)]
+enum EncodeEntity {
+    /// Public key
+    Npub {
+        pubkey: String,
+    },
+    /// Secret key, read without echo
+    Nsec,
+    /// Event id
+    Note {
+        id: String,
+    },
+    /// Public key with relay hints
+    Nprofile {
+        pubkey: String,
+        /// Relay the profile can be found on (repeatable)
+        #[arg(long = "relay")]
+        relays: Vec<String>,
+    },
+    /// Event id with relay, author and kind hints
+    Nevent {
+        id: String,
+        /// Relay the event can be found on (repeatable)
+        #[arg(long = "relay")]
+        relays: Vec<String>,
+        #[arg(long)]
+        author: Option<String>,
+        #[arg(long)]
+        kind: Option<u16>,
+    },
+    /// Coordinate of an addressable event
+    Naddr {
+        /// The event's d tag
+        identifier: String,
+        #[arg(long)]
+        author: String,
+        #[arg(long)]
+        kind: u16,
+        /// Relay the event can be found on (repeatable)
+        #[arg(long = "relay")]
+        relays: Vec<String>,
+    },
+    /// Relay URL (deprecated by NIP-19)
+    Nrelay {
+        url: String,
+    },
 }
 
 #[tokio::main]
-async fn main() -> Result<()> {
+async fn main() -> anyhow::Result<()> {
     let cli = Cli::parse();
 
     match cli.command {
-        Commands::Tui => {
-            if let Err(e) = tui::run() {
-                eprintln!("TUI error: {}", e);
+        Commands::Tui { min_pow } => {
+            if let Err(e) = tui::run(min_pow).await {
+                fail("TUI error", e);
             }
         }
         Commands::Keygen => {
             let keypair = generate_keypair()?;
             println!("Generated new keypair:");
             println!("Private key: {}", keypair.secret_key_hex());
+            println!("Private key (nsec): {}", keypair.secret_key_nsec()?);
             println!("Public key (hex): {}", keypair.public_key_hex());
             println!("Public key (npub): {}", keypair.public_key_npub()?);
         }
-        Commands::Post { text, relay, key } => {
-            let post_command = PostCommand::new(text, relay, key);
+        Commands::Post { text, relays, account, pow } => {
+            let keypair = match commands::account::load_account_keypair(default_config_dir(), account.as_deref()) {
+                Ok(keypair) => keypair,
+                Err(e) => fail("Post command failed", e),
+            };
+
+            let relay_settings = match RelaySettings::load(&default_config_dir()) {
+                Ok(relay_settings) => relay_settings,
+                Err(e) => fail("Post command failed", e),
+            };
+
+            let mut post_command = PostCommand::new(text, relays, keypair)
+                .with_relay_settings(relay_settings)
+                .with_event_store(&default_config_dir());
+            if let Some(bits) = pow {
+                post_command = post_command.with_pow(bits);
+            }
             if let Err(e) = post_command.execute().await {
-                eprintln!("Post command failed: {}", e);
-            }
-        }
-        Commands::Listen { relay_url } => {
-            println!("Connecting to relay: {}", relay_url);
-            let mut relay_manager = connection::RelayManager::new();
-
-            match relay_manager.add_relay(&relay_url).await {
-                Ok(()) => {
-                    match relay_manager.connect_relay(&relay_url).await {
-                        Ok(_connection) => {
-                            println!("Successfully connected to relay: {}", relay_url);
-                            println!("Connection established - ready to listen for events");
-                            // TODO: Implement actual event listening
-                        }
-                        Err(e) => {
-                            eprintln!("Failed to connect to relay: {}", e);
-                        }
-                    }
-                }
-                Err(e) => {
-                    eprintln!("Invalid relay URL: {}", e);
-                }
+                fail("Post command failed", e);
+            }
+        }
+        Commands::Delete { target, reason, relays, account } => {
+            if let Err(e) = run_delete_command(&target, reason.as_deref(), relays, account.as_deref()).await {
+                fail("Delete command failed", e);
+            }
+        }
+        Commands::Decode { entity } => {
+            if let Err(e) = DecodeCommand::new(entity).execute() {
+                fail("Decode failed", e);
+            }
+        }
+        Commands::Encode { entity } => {
+            if let Err(e) = build_entity(entity).and_then(|entity| EncodeCommand::new(entity).execute()) {
+                fail("Encode failed", e);
+            }
+        }
+        Commands::Account { action } => {
+            if let Err(e) = run_account_command(action) {
+                fail("Account command failed", e);
+            }
+        }
+        Commands::Follow(action) => {
+            if let Err(e) = run_follow_command(action).await {
+                fail("Follow command failed", e);
+            }
+        }
+        Commands::React(action) => {
+            if let Err(e) = run_react_command(action).await {
+                fail("React command failed", e);
+            }
+        }
+        Commands::Profile { action } => {
+            if let Err(e) = run_profile_command(action).await {
+                fail("Profile command failed", e);
+            }
+        }
+        Commands::Relay { action } => {
+            if let Err(e) = run_relay_command(action).await {
+                fail("Relay command failed", e);
+            }
+        }
+        Commands::Dm { action } => {
+            if let Err(e) = run_dm_command(action).await {
+                fail("DM command failed", e);
+            }
+        }
+        Commands::Listen { relay_urls, kinds, authors, since, until, limit, tags } => {
+            let filter = commands::listen::build_filter(&kinds, &authors, since, until, limit, &tags)?;
+            let listen_command = ListenCommand::new(relay_urls, filter);
+            if let Err(e) = listen_command.execute().await {
+                fail("Listen command failed", e);
             }
         }
     }
 
     Ok(())
 }
+
+/// Report a failed command and exit, with a code telling scripts what kind
+/// of failure it was
+fn fail<E: std::fmt::Display + Into<NostrError>>(what: &str, error: E) -> ! {
+    eprintln!("{}: {}", what, error);
+    std::process::exit(error.into().exit_code())
+}
+
+fn run_account_command(action: AccountAction) -> Result<()> {
+    let mut account_command = AccountCommand::new(default_config_dir())?;
+
+    match action {
+        AccountAction::New { name } => account_command.create(&name),
+        AccountAction::Import { name } => account_command.import(&name),
+        AccountAction::List => {
+            account_command.list();
+            Ok(())
+        }
+        AccountAction::Use { account } => account_command.use_account(&account),
+        AccountAction::Remove { account, yes } => account_command.remove(&account, yes),
+        AccountAction::Show { account } => account_command.show(account.as_deref()),
+    }
+}
+
+async fn run_delete_command(target: &str, reason: Option<&str>, relays: Vec<String>, account: Option<&str>) -> Result<()> {
+    let target = match Nip19::from_bech32(target) {
+        Ok(entity) => entity,
+        Err(_) => Nip19::Note(event_id_from_str(target)?),
+    };
+    let keypair = commands::account::load_account_keypair(default_config_dir(), account)?;
+    let relay_settings = RelaySettings::load(&default_config_dir())?;
+
+    DeleteCommand::new(default_config_dir(), relays)?
+        .with_relay_settings(relay_settings)
+        .delete(&keypair, &target, reason)
+        .await
+}
+
+async fn run_follow_command(action: FollowAction) -> Result<()> {
+    let relay_settings = RelaySettings::load(&default_config_dir())?;
+
+    match action {
+        FollowAction::Follow { pubkey, petname, relay_hint, relays, account, new } => {
+            let pubkey = public_key_from_str(&pubkey)?;
+            if let Some(relay_hint) = &relay_hint {
+                connection::validate_relay_url(relay_hint)?;
+            }
+            let keypair = commands::account::load_account_keypair(default_config_dir(), account.as_deref())?;
+            FollowCommand::new(relays)
+                .with_relay_settings(relay_settings)
+                .follow(&keypair, &pubkey, relay_hint.as_deref(), petname.as_deref(), new)
+                .await
+        }
+        FollowAction::Unfollow { pubkey, relays, account, force } => {
+            let pubkey = public_key_from_str(&pubkey)?;
+            let keypair = commands::account::load_account_keypair(default_config_dir(), account.as_deref())?;
+            FollowCommand::new(relays)
+                .with_relay_settings(relay_settings)
+                .unfollow(&keypair, &pubkey, force)
+                .await
+        }
+        FollowAction::Following { pubkey, relays } => {
+            let pubkey = match pubkey {
+                Some(pubkey) => public_key_from_str(&pubkey)?,
+                None => commands::account::active_public_key(default_config_dir())?,
+            };
+            FollowCommand::new(relays).with_relay_settings(relay_settings).list(&pubkey).await
+        }
+    }
+}
+
+async fn run_react_command(action: ReactAction) -> Result<()> {
+    let relay_settings = RelaySettings::load(&default_config_dir())?;
+
+    match action {
+        ReactAction::React { note, reaction, emoji_url, relays, account } => {
+            let note_id = event_id_from_str(&note)?;
+            let reaction = Reaction::parse(&reaction, emoji_url.as_deref())?;
+            let keypair = commands::account::load_account_keypair(default_config_dir(), account.as_deref())?;
+            ReactCommand::new(relays)
+                .with_relay_settings(relay_settings)
+                .react(&keypair, &note_id, &reaction)
+                .await
+        }
+        ReactAction::Repost { note, quote, relays, account } => {
+            let note_id = event_id_from_str(&note)?;
+            let keypair = commands::account::load_account_keypair(default_config_dir(), account.as_deref())?;
+            ReactCommand::new(relays)
+                .with_relay_settings(relay_settings)
+                .repost(&keypair, &note_id, quote.as_deref())
+                .await
+        }
+    }
+}
+
+async fn run_profile_command(action: ProfileAction) -> Result<()> {
+    match action {
+        ProfileAction::Set {
+            name,
+            display_name,
+            about,
+            picture,
+            banner,
+            nip05,
+            lud16,
+            website,
+            relays,
+            account,
+        } => {
+            let changes: Vec<(&str, String)> = [
+                ("name", name),
+                ("display_name", display_name),
+                ("about", about),
+                ("picture", picture),
+                ("banner", banner),
+                ("nip05", nip05),
+                ("lud16", lud16),
+                ("website", website),
+            ]
+            .into_iter()
+            .filter_map(|(field, value)| Some((field, value?)))
+            .collect();
+
+            let keypair = commands::account::load_account_keypair(default_config_dir(), account.as_deref())?;
+            let relay_settings = RelaySettings::load(&default_config_dir())?;
+            ProfileCommand::new(relays)
+                .with_relay_settings(relay_settings)
+                .set(&keypair, &changes)
+                .await
+        }
+        ProfileAction::Show { pubkey, relays } => {
+            let pubkey = match pubkey {
+                Some(pubkey) => public_key_from_str(&pubkey)?,
+                None => commands::account::active_public_key(default_config_dir())?,
+            };
+            let relay_settings = RelaySettings::load(&default_config_dir())?;
+            ProfileCommand::new(relays).with_relay_settings(relay_settings).show(&pubkey).await
+        }
+    }
+}
+
+async fn run_relay_command(action: RelayAction) -> Result<()> {
+    let mut relay_command = RelayCommand::new(default_config_dir())?;
+
+    match action {
+        RelayAction::Info { relay } => relay_command.info(&relay).await,
+        RelayAction::Auth { relay: Some(relay), policy: Some(policy) } => relay_command.set_auth(&relay, policy),
+        RelayAction::Auth { relay, .. } => {
+            relay_command.show_auth(relay.as_deref());
+            Ok(())
+        }
+        RelayAction::List { pubkey, relays } => {
+            let pubkey = match pubkey {
+                Some(pubkey) => public_key_from_str(&pubkey)?,
+                None => commands::account::active_public_key(default_config_dir())?,
+            };
+            relay_command.show_list(&pubkey, &relays).await
+        }
+        RelayAction::SetList { read, write, both, relays, account } => {
+            let mut relay_list = RelayList::new();
+            for (urls, usage) in [(read, RelayUsage::Read), (write, RelayUsage::Write), (both, RelayUsage::Both)] {
+                for url in urls {
+                    relay_list.add_relay(&url, usage)?;
+                }
+            }
+
+            let keypair = commands::account::load_account_keypair(default_config_dir(), account.as_deref())?;
+            relay_command.publish_list(&relay_list, &keypair, &relays).await
+        }
+    }
+}
+
+async fn run_dm_command(action: DmAction) -> Result<()> {
+    match action {
+        DmAction::Send { recipient, message, relays, account, legacy_nip04 } => {
+            let recipient = public_key_from_str(&recipient)?;
+            let keypair = commands::account::load_account_keypair(default_config_dir(), account.as_deref())?;
+            let dm_command = DmCommand::new(relays);
+
+            if legacy_nip04 {
+                dm_command.send_legacy(&keypair, &recipient, &message).await
+            } else {
+                dm_command.send(&keypair, &recipient, &message).await
+            }
+        }
+        DmAction::Read { relays, account, with } => {
+            let with = with.as_deref().map(public_key_from_str).transpose()?;
+            let account_manager = commands::account::unlock_account_manager(default_config_dir())?;
+
+            let pubkeys = match account {
+                Some(account) => vec![account_manager.find_account(&account)?.public_key_hex.clone()],
+                None => account_manager
+                    .list_accounts()
+                    .iter()
+                    .map(|account| account.public_key_hex.clone())
+                    .collect(),
+            };
+
+            DmCommand::new(relays).read(&account_manager, &pubkeys, with.as_deref()).await
+        }
+    }
+}
+
+fn build_entity(entity: EncodeEntity) -> Result<Nip19> {
+    Ok(match entity {
+        EncodeEntity::Npub { pubkey } => Nip19::Pubkey(public_key_from_str(&pubkey)?),
+        EncodeEntity::Nsec => {
+            let secret = rpassword::prompt_password("Private key (hex or nsec): ")?;
+            Nip19::SecretKey(keypair_from_secret(&secret)?.secret_key_hex())
+        }
+        EncodeEntity::Note { id } => Nip19::Note(event_id_from_str(&id)?),
+        EncodeEntity::Nprofile { pubkey, relays } => Nip19::Profile(ProfilePointer {
+            pubkey: public_key_from_str(&pubkey)?,
+            relays,
+        }),
+        EncodeEntity::Nevent { id, relays, author, kind } => Nip19::Event(EventPointer {
+            id: event_id_from_str(&id)?,
+            relays,
+            author: author.as_deref().map(public_key_from_str).transpose()?,
+            kind,
+        }),
+        EncodeEntity::Naddr { identifier, author, kind, relays } => Nip19::Address(AddressPointer {
+            identifier,
+            pubkey: public_key_from_str(&author)?,
+            kind,
+            relays,
+        }),
+        EncodeEntity::Nrelay { url } => Nip19::Relay(url),
+    })
+}
@@ -1,4 +1,4 @@
-use anyhow::Result;
+use crate::error::{NostrError, Result};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use sha2::{Digest, Sha256};
@@ -6,7 +6,7 @@ use std::time::{SystemTime, UNIX_EPOCH};
 
 use crate::nostr::keys::NostrKeypair;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct NostrEvent {
     pub id: String,
     pub pubkey: String,
@@ -118,13 +118,19 @@ impl NostrEvent {
 
         let secp = secp256k1::Secp256k1::new();
 
-        let sig_array: [u8; 64] = sig_bytes.try_into().map_err(|_| anyhow::anyhow!("Invalid signature length"))?;
+        let sig_array: [u8; 64] = sig_bytes
+            .try_into()
+            .map_err(|_| NostrError::CryptographicError("Invalid signature length".to_string()))?;
         let signature = secp256k1::schnorr::Signature::from_byte_array(sig_array);
 
-        let pubkey_array: [u8; 32] = pubkey_bytes.try_into().map_err(|_| anyhow::anyhow!("Invalid public key length"))?;
+        let pubkey_array: [u8; 32] = pubkey_bytes
+            .try_into()
+            .map_err(|_| NostrError::CryptographicError("Invalid public key length".to_string()))?;
         let x_only_pubkey = secp256k1::XOnlyPublicKey::from_byte_array(pubkey_array)?;
 
-        let id_array: [u8; 32] = id_bytes.try_into().map_err(|_| anyhow::anyhow!("Invalid message length"))?;
+        let id_array: [u8; 32] = id_bytes
+            .try_into()
+            .map_err(|_| NostrError::CryptographicError("Invalid message length".to_string()))?;
 
         match secp.verify_schnorr(&signature, &id_array, &x_only_pubkey) {
             Ok(_) => Ok(true),
@@ -132,6 +138,43 @@ impl NostrEvent {
         }
     }
 
+    /// Check that the id matches the event's content and is signed by its
+    /// pubkey, as required before trusting anything received from a relay
+    pub fn verify(&self) -> bool {
+        let unsigned = UnsignedEvent {
+            pubkey: self.pubkey.clone(),
+            created_at: self.created_at,
+            kind: self.kind,
+            tags: self.tags.clone(),
+            content: self.content.clone(),
+        };
+
+        unsigned.calculate_id().is_ok_and(|id| id == self.id)
+            && self.verify_signature(&self.pubkey).unwrap_or(false)
+    }
+
+    /// Value of the first tag named `name`
+    pub fn tag_value(&self, name: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|tag| tag.len() >= 2 && tag[0] == name)
+            .map(|tag| tag[1].as_str())
+    }
+
+    /// Where only the newest version is kept, in the form `a` tags refer to
+    /// it by: `kind:pubkey:` for replaceable events and `kind:pubkey:d-tag`
+    /// for addressable ones. `None` for every other event.
+    pub fn address(&self) -> Option<String> {
+        if is_replaceable(self.kind) {
+            Some(format!("{}:{}:", self.kind, self.pubkey))
+        } else if is_addressable(self.kind) {
+            let identifier = self.tag_value("d").unwrap_or_default();
+            Some(format!("{}:{}:{}", self.kind, self.pubkey, identifier))
+        } else {
+            None
+        }
+    }
+
     #[allow(dead_code)]
     pub fn to_json(&self) -> Result<String> {
         Ok(serde_json::to_string(self)?)
@@ -142,6 +185,18 @@ impl NostrEvent {
     }
 }
 
+pub fn is_replaceable(kind: u16) -> bool {
+    kind == 0 || kind == 3 || (10_000..20_000).contains(&kind)
+}
+
+pub fn is_ephemeral(kind: u16) -> bool {
+    (20_000..30_000).contains(&kind)
+}
+
+pub fn is_addressable(kind: u16) -> bool {
+    (30_000..40_000).contains(&kind)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -162,6 +217,22 @@ mod tests {
         assert!(is_valid);
     }
 
+    #[test]
+    fn test_verify_rejects_tampered_events() {
+        let keypair = keys::generate_keypair().unwrap();
+        let event = NostrEvent::new_text_note("Original".to_string(), &keypair).unwrap();
+        assert!(event.verify());
+
+        let mut tampered = event.clone();
+        tampered.content = "Tampered".to_string();
+        assert!(!tampered.verify());
+
+        let other = keys::generate_keypair().unwrap();
+        let mut impersonated = event;
+        impersonated.pubkey = other.public_key_hex();
+        assert!(!impersonated.verify());
+    }
+
     #[test]
     fn test_json_serialization() {
         let keypair = keys::generate_keypair().unwrap();
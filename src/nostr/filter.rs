@@ -0,0 +1,284 @@
+use serde::de::{self, Deserializer};
+use serde::ser::{SerializeMap, Serializer};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+use crate::nostr::event::NostrEvent;
+
+/// A NIP-01 subscription filter.
+///
+/// `ids` and `authors` entries are matched as prefixes, so a full 64 character
+/// hex value is an exact match. Tag filters are keyed by their single-letter
+/// tag name and serialize to the `#<letter>` wire form.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Filter {
+    pub ids: Option<Vec<String>>,
+    pub authors: Option<Vec<String>>,
+    pub kinds: Option<Vec<u16>>,
+    pub tags: BTreeMap<char, Vec<String>>,
+    pub since: Option<u64>,
+    pub until: Option<u64>,
+    pub limit: Option<u64>,
+}
+
+impl Filter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[allow(dead_code)]
+    pub fn with_ids(mut self, ids: Vec<String>) -> Self {
+        self.ids.get_or_insert_with(Vec::new).extend(ids);
+        self
+    }
+
+    pub fn with_authors(mut self, authors: Vec<String>) -> Self {
+        self.authors.get_or_insert_with(Vec::new).extend(authors);
+        self
+    }
+
+    pub fn with_kinds(mut self, kinds: Vec<u16>) -> Self {
+        self.kinds.get_or_insert_with(Vec::new).extend(kinds);
+        self
+    }
+
+    /// Add values for a single-letter tag filter (`#e`, `#p`, `#t`, ...)
+    pub fn with_tag(mut self, letter: char, values: Vec<String>) -> Self {
+        self.tags.entry(letter).or_default().extend(values);
+        self
+    }
+
+    /// Match events referencing any of these event ids (`#e`)
+    #[allow(dead_code)]
+    pub fn with_event_refs(self, event_ids: Vec<String>) -> Self {
+        self.with_tag('e', event_ids)
+    }
+
+    /// Match events referencing any of these public keys (`#p`)
+    #[allow(dead_code)]
+    pub fn with_pubkey_refs(self, pubkeys: Vec<String>) -> Self {
+        self.with_tag('p', pubkeys)
+    }
+
+    pub fn with_since(mut self, since: u64) -> Self {
+        self.since = Some(since);
+        self
+    }
+
+    pub fn with_until(mut self, until: u64) -> Self {
+        self.until = Some(until);
+        self
+    }
+
+    pub fn with_limit(mut self, limit: u64) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Check whether an event satisfies every condition of this filter.
+    ///
+    /// `limit` only applies to the initial query against stored events and is
+    /// ignored here.
+    #[allow(dead_code)]
+    pub fn matches(&self, event: &NostrEvent) -> bool {
+        let matches_prefix = |prefixes: &Option<Vec<String>>, value: &str| {
+            prefixes
+                .as_ref()
+                .is_none_or(|prefixes| prefixes.iter().any(|prefix| value.starts_with(prefix.as_str())))
+        };
+
+        matches_prefix(&self.ids, &event.id)
+            && matches_prefix(&self.authors, &event.pubkey)
+            && self.kinds.as_ref().is_none_or(|kinds| kinds.contains(&event.kind))
+            && self.since.is_none_or(|since| event.created_at >= since)
+            && self.until.is_none_or(|until| event.created_at <= until)
+            && self.tags.iter().all(|(letter, values)| {
+                event.tags.iter().any(|tag| {
+                    tag.len() >= 2
+                        && tag[0].chars().eq(std::iter::once(*letter))
+                        && values.contains(&tag[1])
+                })
+            })
+    }
+}
+
+impl Serialize for Filter {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(None)?;
+
+        if let Some(ids) = &self.ids {
+            map.serialize_entry("ids", ids)?;
+        }
+        if let Some(authors) = &self.authors {
+            map.serialize_entry("authors", authors)?;
+        }
+        if let Some(kinds) = &self.kinds {
+            map.serialize_entry("kinds", kinds)?;
+        }
+        for (letter, values) in &self.tags {
+            map.serialize_entry(&format!("#{}", letter), values)?;
+        }
+        if let Some(since) = self.since {
+            map.serialize_entry("since", &since)?;
+        }
+        if let Some(until) = self.until {
+            map.serialize_entry("until", &until)?;
+        }
+        if let Some(limit) = self.limit {
+            map.serialize_entry("limit", &limit)?;
+        }
+
+        map.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for Filter {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        struct RawFilter {
+            ids: Option<Vec<String>>,
+            authors: Option<Vec<String>>,
+            kinds: Option<Vec<u16>>,
+            since: Option<u64>,
+            until: Option<u64>,
+            limit: Option<u64>,
+            #[serde(flatten)]
+            other: BTreeMap<String, Value>,
+        }
+
+        let raw = RawFilter::deserialize(deserializer)?;
+        let mut tags = BTreeMap::new();
+
+        // Unknown non-tag fields (e.g. NIP-50 "search") are ignored
+        for (key, value) in raw.other {
+            let Some(name) = key.strip_prefix('#') else {
+                continue;
+            };
+
+            let mut letters = name.chars();
+            let letter = match (letters.next(), letters.next()) {
+                (Some(letter), None) if letter.is_ascii_alphabetic() => letter,
+                _ => return Err(de::Error::custom(format!("invalid tag filter key: {}", key))),
+            };
+
+            let values: Vec<String> = serde_json::from_value(value)
+                .map_err(|e| de::Error::custom(format!("invalid values for {}: {}", key, e)))?;
+            tags.insert(letter, values);
+        }
+
+        Ok(Filter {
+            ids: raw.ids,
+            authors: raw.authors,
+            kinds: raw.kinds,
+            tags,
+            since: raw.since,
+            until: raw.until,
+            limit: raw.limit,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nostr::event::UnsignedEvent;
+    use crate::nostr::keys;
+
+    fn tagged_note(tags: Vec<Vec<String>>, created_at: u64) -> NostrEvent {
+        let keypair = keys::generate_keypair().unwrap();
+        UnsignedEvent::new_text_note("filter test".to_string(), keypair.public_key_hex())
+            .with_timestamp(created_at)
+            .with_tags(tags)
+            .sign(&keypair)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_filter_serializes_to_wire_form() {
+        let filter = Filter::new()
+            .with_kinds(vec![1, 6])
+            .with_authors(vec!["abcd".to_string()])
+            .with_event_refs(vec!["e1".to_string()])
+            .with_tag('t', vec!["nostr".to_string()])
+            .with_since(10)
+            .with_limit(5);
+
+        let value = serde_json::to_value(&filter).unwrap();
+        assert_eq!(
+            value,
+            serde_json::json!({
+                "authors": ["abcd"],
+                "kinds": [1, 6],
+                "#e": ["e1"],
+                "#t": ["nostr"],
+                "since": 10,
+                "limit": 5
+            })
+        );
+
+        let parsed: Filter = serde_json::from_value(value).unwrap();
+        assert_eq!(parsed, filter);
+    }
+
+    #[test]
+    fn test_filter_deserialization_rejects_invalid_tag_keys() {
+        assert!(serde_json::from_str::<Filter>(r##"{"#ab": ["x"]}"##).is_err());
+        assert!(serde_json::from_str::<Filter>(r##"{"#e": "not-an-array"}"##).is_err());
+        assert!(serde_json::from_str::<Filter>(r#"{"kinds": ["one"]}"#).is_err());
+
+        let filter: Filter = serde_json::from_str(r#"{"search": "ignored", "kinds": [1]}"#).unwrap();
+        assert_eq!(filter, Filter::new().with_kinds(vec![1]));
+    }
+
+    #[test]
+    fn test_empty_filter_matches_everything() {
+        let event = tagged_note(vec![], 1000);
+        assert!(Filter::new().matches(&event));
+    }
+
+    #[test]
+    fn test_filter_matches_ids_and_authors_by_prefix() {
+        let event = tagged_note(vec![], 1000);
+
+        assert!(Filter::new().with_ids(vec![event.id.clone()]).matches(&event));
+        assert!(Filter::new().with_ids(vec![event.id[..8].to_string()]).matches(&event));
+        assert!(Filter::new().with_authors(vec![event.pubkey[..4].to_string()]).matches(&event));
+        assert!(!Filter::new().with_ids(vec!["zz".to_string()]).matches(&event));
+        assert!(!Filter::new().with_authors(vec![]).matches(&event));
+    }
+
+    #[test]
+    fn test_filter_matches_kinds_and_time_range() {
+        let event = tagged_note(vec![], 1000);
+
+        assert!(Filter::new().with_kinds(vec![0, 1]).matches(&event));
+        assert!(!Filter::new().with_kinds(vec![7]).matches(&event));
+        assert!(Filter::new().with_since(1000).with_until(1000).matches(&event));
+        assert!(!Filter::new().with_since(1001).matches(&event));
+        assert!(!Filter::new().with_until(999).matches(&event));
+    }
+
+    #[test]
+    fn test_filter_matches_tags() {
+        let event = tagged_note(
+            vec![
+                vec!["e".to_string(), "event-a".to_string()],
+                vec!["p".to_string(), "pubkey-b".to_string(), "wss://relay".to_string()],
+                vec!["title".to_string(), "not indexed".to_string()],
+            ],
+            1000,
+        );
+
+        assert!(Filter::new().with_event_refs(vec!["event-a".to_string()]).matches(&event));
+        assert!(Filter::new().with_pubkey_refs(vec!["x".to_string(), "pubkey-b".to_string()]).matches(&event));
+        assert!(!Filter::new().with_event_refs(vec!["pubkey-b".to_string()]).matches(&event));
+        assert!(!Filter::new().with_tag('t', vec!["not indexed".to_string()]).matches(&event));
+
+        // Every tag filter must match
+        let filter = Filter::new()
+            .with_event_refs(vec!["event-a".to_string()])
+            .with_pubkey_refs(vec!["someone-else".to_string()]);
+        assert!(!filter.matches(&event));
+    }
+}
@@ -1,7 +1,8 @@
-use anyhow::Result;
-use secp256k1::{Secp256k1, SecretKey, PublicKey, Keypair};
+use crate::error::{NostrError, Result};
+use secp256k1::{Secp256k1, SecretKey, PublicKey, Keypair, Parity, XOnlyPublicKey};
 use secp256k1::rand;
-use bech32::{Bech32, Hrp};
+
+use crate::nostr::nip19::Nip19;
 
 #[derive(Debug, Clone)]
 pub struct NostrKeypair {
@@ -22,11 +23,11 @@ impl NostrKeypair {
     }
 
     pub fn public_key_npub(&self) -> Result<String> {
-        let pubkey_bytes = self.keypair.public_key().x_only_public_key().0.serialize();
-        let hrp = Hrp::parse("npub").map_err(|e| anyhow::anyhow!("Invalid HRP: {}", e))?;
-        let encoded = bech32::encode::<Bech32>(hrp, &pubkey_bytes)
-            .map_err(|e| anyhow::anyhow!("Bech32 encoding failed: {}", e))?;
-        Ok(encoded)
+        Nip19::Pubkey(self.public_key_hex()).to_bech32()
+    }
+
+    pub fn secret_key_nsec(&self) -> Result<String> {
+        Nip19::SecretKey(self.secret_key_hex()).to_bech32()
     }
 
     #[allow(dead_code)]
@@ -42,7 +43,7 @@ impl NostrKeypair {
     pub fn sign_message(&self, message: &[u8]) -> Result<Vec<u8>> {
         let secp = Secp256k1::new();
         let message_array: [u8; 32] = message.try_into()
-            .map_err(|_| anyhow::anyhow!("Message must be exactly 32 bytes"))?;
+            .map_err(|_| NostrError::SigningFailed("Message must be exactly 32 bytes".to_string()))?;
         let signature = secp.sign_schnorr(&message_array, &self.keypair);
         Ok(signature.as_ref().to_vec())
     }
@@ -56,19 +57,51 @@ pub fn generate_keypair() -> Result<NostrKeypair> {
 }
 
 pub fn keypair_from_hex(secret_hex: &str) -> Result<NostrKeypair> {
-    let secret_bytes = hex::decode(secret_hex)?;
+    let secret_bytes = hex::decode(secret_hex).map_err(|e| NostrError::InvalidPrivateKey(e.to_string()))?;
     let secp = Secp256k1::new();
     let secret_key = SecretKey::from_byte_array(
-        secret_bytes.try_into().map_err(|_| anyhow::anyhow!("Invalid secret key length"))?
-    )?;
+        secret_bytes.try_into().map_err(|_| NostrError::InvalidPrivateKey("expected 32 bytes".to_string()))?
+    )
+    .map_err(|e| NostrError::InvalidPrivateKey(e.to_string()))?;
     let keypair = Keypair::from_secret_key(&secp, &secret_key);
     Ok(NostrKeypair::new(keypair))
 }
 
+/// Load a keypair from a bech32 encoded `nsec1...` secret key
+pub fn keypair_from_nsec(nsec: &str) -> Result<NostrKeypair> {
+    match Nip19::from_bech32(nsec)? {
+        Nip19::SecretKey(secret_hex) => keypair_from_hex(&secret_hex),
+        other => Err(NostrError::InvalidPrivateKey(format!("Expected an nsec key, got {}", other.prefix()))),
+    }
+}
+
+/// Load a keypair from a secret key given either as nsec or as hex
+pub fn keypair_from_secret(secret: &str) -> Result<NostrKeypair> {
+    let secret = secret.trim();
+
+    if secret.starts_with("nsec1") {
+        keypair_from_nsec(secret)
+    } else {
+        keypair_from_hex(secret)
+    }
+}
+
+/// Unhashed x coordinate of the ECDH point shared with `pubkey_hex`, the
+/// secret both NIP-04 and NIP-44 encryption start from
+pub fn shared_secret_x(secret_key: &SecretKey, pubkey_hex: &str) -> Result<[u8; 32]> {
+    let pubkey_bytes: [u8; 32] = hex::decode(pubkey_hex)?
+        .try_into()
+        .map_err(|_| NostrError::InvalidPublicKey("expected 32 bytes".to_string()))?;
+    let x_only = XOnlyPublicKey::from_byte_array(pubkey_bytes)?;
+    let pubkey = PublicKey::from_x_only_public_key(x_only, Parity::Even);
+
+    let shared_point = secp256k1::ecdh::shared_secret_point(&pubkey, secret_key);
+    Ok(shared_point[..32].try_into()?)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use secp256k1::XOnlyPublicKey;
     use sha2::Digest;
 
     #[test]
@@ -80,8 +113,8 @@ mod tests {
         assert_eq!(keypair.public_key_hex().len(), 64); // 32 bytes * 2 hex chars
 
         // Verify keys are valid hex
-        assert!(hex::decode(&keypair.secret_key_hex()).is_ok());
-        assert!(hex::decode(&keypair.public_key_hex()).is_ok());
+        assert!(hex::decode(keypair.secret_key_hex()).is_ok());
+        assert!(hex::decode(keypair.public_key_hex()).is_ok());
     }
 
     #[test]
@@ -185,4 +218,23 @@ mod tests {
         // Different keypairs should produce different npubs
         assert_ne!(npub1, npub2);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_keypair_from_nsec() {
+        let keypair = generate_keypair().unwrap();
+        let nsec = keypair.secret_key_nsec().unwrap();
+        assert!(nsec.starts_with("nsec1"));
+
+        let decoded = keypair_from_nsec(&nsec).unwrap();
+        assert_eq!(decoded.public_key_hex(), keypair.public_key_hex());
+
+        let from_either = keypair_from_secret(&format!(" {} ", nsec)).unwrap();
+        assert_eq!(from_either.public_key_hex(), keypair.public_key_hex());
+        let from_hex = keypair_from_secret(&keypair.secret_key_hex()).unwrap();
+        assert_eq!(from_hex.public_key_hex(), keypair.public_key_hex());
+
+        let npub = keypair.public_key_npub().unwrap();
+        assert!(keypair_from_nsec(&npub).is_err());
+        assert!(keypair_from_nsec("nsec1invalid").is_err());
+    }
+}
@@ -0,0 +1,414 @@
+use crate::error::{NostrError, Result};
+use serde_json::{Value, json};
+
+use crate::nostr::event::NostrEvent;
+use crate::nostr::filter::Filter;
+
+/// Maximum subscription id length allowed by NIP-01
+const MAX_SUBSCRIPTION_ID_LEN: usize = 64;
+
+/// Messages sent from a relay to a client (NIP-01, NIP-42, NIP-45)
+#[derive(Debug, Clone, PartialEq)]
+pub enum RelayMessage {
+    Event {
+        subscription_id: String,
+        event: NostrEvent,
+    },
+    Ok {
+        event_id: String,
+        accepted: bool,
+        message: String,
+    },
+    EndOfStoredEvents {
+        subscription_id: String,
+    },
+    Closed {
+        subscription_id: String,
+        message: String,
+    },
+    Notice {
+        message: String,
+    },
+    Auth {
+        challenge: String,
+    },
+    Count {
+        subscription_id: String,
+        count: u64,
+        approximate: Option<bool>,
+    },
+}
+
+/// Messages sent from a client to a relay (NIP-01, NIP-42, NIP-45)
+#[derive(Debug, Clone, PartialEq)]
+pub enum ClientMessage {
+    Event(NostrEvent),
+    Req {
+        subscription_id: String,
+        filters: Vec<Filter>,
+    },
+    Close {
+        subscription_id: String,
+    },
+    #[allow(dead_code)]
+    Auth(NostrEvent),
+    #[allow(dead_code)]
+    Count {
+        subscription_id: String,
+        filters: Vec<Filter>,
+    },
+}
+
+impl RelayMessage {
+    pub fn from_json(json: &str) -> Result<Self> {
+        let value: Value = serde_json::from_str(json)
+            .map_err(|e| NostrError::InvalidMessage(format!("Relay message is not valid JSON: {}", e)))?;
+        Self::from_value(value)
+    }
+
+    pub fn from_value(value: Value) -> Result<Self> {
+        let (message_type, args) = split_message(value)?;
+
+        match message_type.as_str() {
+            "EVENT" => {
+                let [subscription_id, event] = expect_args::<2>(&message_type, args)?;
+                Ok(RelayMessage::Event {
+                    subscription_id: expect_subscription_id(subscription_id)?,
+                    event: serde_json::from_value(event)
+                        .map_err(|e| {
+                            NostrError::InvalidMessage(format!("Invalid event in EVENT message: {}", e))
+                        })?,
+                })
+            }
+            "OK" => {
+                let [event_id, accepted, message] = expect_args::<3>(&message_type, args)?;
+                Ok(RelayMessage::Ok {
+                    event_id: expect_string(event_id, "event id")?,
+                    accepted: accepted
+                        .as_bool()
+                        .ok_or_else(|| {
+                            NostrError::InvalidMessage("OK accepted flag must be a boolean".to_string())
+                        })?,
+                    message: expect_string(message, "OK message")?,
+                })
+            }
+            "EOSE" => {
+                let [subscription_id] = expect_args::<1>(&message_type, args)?;
+                Ok(RelayMessage::EndOfStoredEvents {
+                    subscription_id: expect_subscription_id(subscription_id)?,
+                })
+            }
+            "CLOSED" => {
+                let [subscription_id, message] = expect_args::<2>(&message_type, args)?;
+                Ok(RelayMessage::Closed {
+                    subscription_id: expect_subscription_id(subscription_id)?,
+                    message: expect_string(message, "CLOSED message")?,
+                })
+            }
+            "NOTICE" => {
+                let [message] = expect_args::<1>(&message_type, args)?;
+                Ok(RelayMessage::Notice {
+                    message: expect_string(message, "NOTICE message")?,
+                })
+            }
+            "AUTH" => {
+                let [challenge] = expect_args::<1>(&message_type, args)?;
+                Ok(RelayMessage::Auth {
+                    challenge: expect_string(challenge, "AUTH challenge")?,
+                })
+            }
+            "COUNT" => {
+                let [subscription_id, result] = expect_args::<2>(&message_type, args)?;
+                let count = result
+                    .get("count")
+                    .and_then(Value::as_u64)
+                    .ok_or_else(|| {
+                        NostrError::InvalidMessage("COUNT result must contain a numeric count".to_string())
+                    })?;
+                let approximate = match result.get("approximate") {
+                    None => None,
+                    Some(value) => Some(
+                        value
+                            .as_bool()
+                            .ok_or_else(|| {
+                                NostrError::InvalidMessage(
+                                    "COUNT approximate flag must be a boolean".to_string(),
+                                )
+                            })?,
+                    ),
+                };
+
+                Ok(RelayMessage::Count {
+                    subscription_id: expect_subscription_id(subscription_id)?,
+                    count,
+                    approximate,
+                })
+            }
+            other => Err(NostrError::InvalidMessage(format!("Unknown relay message type: {}", other))),
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn to_value(&self) -> Result<Value> {
+        Ok(match self {
+            RelayMessage::Event { subscription_id, event } => {
+                json!(["EVENT", subscription_id, event.to_json_value()?])
+            }
+            RelayMessage::Ok { event_id, accepted, message } => json!(["OK", event_id, accepted, message]),
+            RelayMessage::EndOfStoredEvents { subscription_id } => json!(["EOSE", subscription_id]),
+            RelayMessage::Closed { subscription_id, message } => json!(["CLOSED", subscription_id, message]),
+            RelayMessage::Notice { message } => json!(["NOTICE", message]),
+            RelayMessage::Auth { challenge } => json!(["AUTH", challenge]),
+            RelayMessage::Count { subscription_id, count, approximate } => {
+                let mut result = json!({ "count": count });
+                if let Some(approximate) = approximate {
+                    result["approximate"] = json!(approximate);
+                }
+                json!(["COUNT", subscription_id, result])
+            }
+        })
+    }
+
+    #[allow(dead_code)]
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string(&self.to_value()?)?)
+    }
+}
+
+impl ClientMessage {
+    #[allow(dead_code)]
+    pub fn from_json(json: &str) -> Result<Self> {
+        let value: Value = serde_json::from_str(json)
+            .map_err(|e| NostrError::InvalidMessage(format!("Client message is not valid JSON: {}", e)))?;
+        Self::from_value(value)
+    }
+
+    #[allow(dead_code)]
+    pub fn from_value(value: Value) -> Result<Self> {
+        let (message_type, mut args) = split_message(value)?;
+
+        match message_type.as_str() {
+            "EVENT" | "AUTH" => {
+                let [event] = expect_args::<1>(&message_type, args)?;
+                let event: NostrEvent = serde_json::from_value(event)
+                    .map_err(|e| {
+                        NostrError::InvalidMessage(format!(
+                            "Invalid event in {} message: {}",
+                            message_type, e
+                        ))
+                    })?;
+
+                Ok(if message_type == "EVENT" {
+                    ClientMessage::Event(event)
+                } else {
+                    ClientMessage::Auth(event)
+                })
+            }
+            "REQ" | "COUNT" => {
+                if args.len() < 2 {
+                    return Err(NostrError::InvalidMessage(format!(
+                        "{} message requires a subscription id and at least one filter",
+                        message_type
+                    )));
+                }
+
+                let subscription_id = expect_subscription_id(args.remove(0))?;
+                let filters = args
+                    .into_iter()
+                    .map(serde_json::from_value::<Filter>)
+                    .collect::<std::result::Result<Vec<_>, _>>()
+                    .map_err(|e| {
+                        NostrError::InvalidMessage(format!(
+                            "Invalid filter in {} message: {}",
+                            message_type, e
+                        ))
+                    })?;
+
+                Ok(if message_type == "REQ" {
+                    ClientMessage::Req { subscription_id, filters }
+                } else {
+                    ClientMessage::Count { subscription_id, filters }
+                })
+            }
+            "CLOSE" => {
+                let [subscription_id] = expect_args::<1>(&message_type, args)?;
+                Ok(ClientMessage::Close {
+                    subscription_id: expect_subscription_id(subscription_id)?,
+                })
+            }
+            other => Err(NostrError::InvalidMessage(format!("Unknown client message type: {}", other))),
+        }
+    }
+
+    pub fn to_value(&self) -> Result<Value> {
+        Ok(match self {
+            ClientMessage::Event(event) => json!(["EVENT", event.to_json_value()?]),
+            ClientMessage::Auth(event) => json!(["AUTH", event.to_json_value()?]),
+            ClientMessage::Req { subscription_id, filters } => {
+                let mut message = vec![json!("REQ"), json!(subscription_id)];
+                for filter in filters {
+                    message.push(serde_json::to_value(filter)?);
+                }
+                Value::Array(message)
+            }
+            ClientMessage::Count { subscription_id, filters } => {
+                let mut message = vec![json!("COUNT"), json!(subscription_id)];
+                for filter in filters {
+                    message.push(serde_json::to_value(filter)?);
+                }
+                Value::Array(message)
+            }
+            ClientMessage::Close { subscription_id } => json!(["CLOSE", subscription_id]),
+        })
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string(&self.to_value()?)?)
+    }
+}
+
+/// Split a wire message into its type label and remaining arguments
+fn split_message(value: Value) -> Result<(String, Vec<Value>)> {
+    let Value::Array(mut items) = value else {
+        return Err(NostrError::InvalidMessage("Message is not a JSON array".to_string()));
+    };
+
+    if items.is_empty() {
+        return Err(NostrError::InvalidMessage("Message is an empty array".to_string()));
+    }
+
+    match items.remove(0) {
+        Value::String(message_type) => Ok((message_type, items)),
+        _ => Err(NostrError::InvalidMessage("Message type must be a string".to_string())),
+    }
+}
+
+fn expect_args<const N: usize>(message_type: &str, args: Vec<Value>) -> Result<[Value; N]> {
+    let count = args.len();
+    args.try_into()
+        .map_err(|_| {
+            NostrError::InvalidMessage(format!(
+                "{} message expects {} arguments, got {}",
+                message_type, N, count
+            ))
+        })
+}
+
+fn expect_string(value: Value, field: &str) -> Result<String> {
+    match value {
+        Value::String(s) => Ok(s),
+        _ => Err(NostrError::InvalidMessage(format!("{} must be a string", field))),
+    }
+}
+
+fn expect_subscription_id(value: Value) -> Result<String> {
+    let subscription_id = expect_string(value, "Subscription id")?;
+
+    if subscription_id.is_empty() || subscription_id.len() > MAX_SUBSCRIPTION_ID_LEN {
+        return Err(NostrError::InvalidMessage(format!(
+            "Subscription id must be between 1 and {} characters",
+            MAX_SUBSCRIPTION_ID_LEN
+        )));
+    }
+
+    Ok(subscription_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nostr::keys;
+
+    fn sample_event() -> NostrEvent {
+        let keypair = keys::generate_keypair().unwrap();
+        NostrEvent::new_text_note("message test".to_string(), &keypair).unwrap()
+    }
+
+    #[test]
+    fn test_relay_message_roundtrip() {
+        let messages = vec![
+            RelayMessage::Event { subscription_id: "sub".to_string(), event: sample_event() },
+            RelayMessage::Ok { event_id: "ab".repeat(32), accepted: false, message: "blocked: nope".to_string() },
+            RelayMessage::EndOfStoredEvents { subscription_id: "sub".to_string() },
+            RelayMessage::Closed { subscription_id: "sub".to_string(), message: "error: shutting down".to_string() },
+            RelayMessage::Notice { message: "hello".to_string() },
+            RelayMessage::Auth { challenge: "challenge-string".to_string() },
+            RelayMessage::Count { subscription_id: "sub".to_string(), count: 42, approximate: Some(true) },
+            RelayMessage::Count { subscription_id: "sub".to_string(), count: 0, approximate: None },
+        ];
+
+        for message in messages {
+            let json = message.to_json().unwrap();
+            assert_eq!(RelayMessage::from_json(&json).unwrap(), message, "roundtrip of {}", json);
+        }
+    }
+
+    #[test]
+    fn test_relay_message_parsing() {
+        let ok = RelayMessage::from_json(r#"["OK", "abc", true, ""]"#).unwrap();
+        assert_eq!(ok, RelayMessage::Ok { event_id: "abc".to_string(), accepted: true, message: String::new() });
+
+        let count = RelayMessage::from_json(r#"["COUNT", "c1", {"count": 7}]"#).unwrap();
+        assert_eq!(count, RelayMessage::Count { subscription_id: "c1".to_string(), count: 7, approximate: None });
+    }
+
+    #[test]
+    fn test_relay_message_parsing_is_strict() {
+        let invalid = [
+            r#"{"OK": true}"#,
+            r#"[]"#,
+            r#"[1, "abc"]"#,
+            r#"["UNKNOWN", "abc"]"#,
+            r#"["OK", "abc", true]"#,
+            r#"["OK", "abc", "true", ""]"#,
+            r#"["EOSE"]"#,
+            r#"["EOSE", "sub", "extra"]"#,
+            r#"["CLOSED", "sub"]"#,
+            r#"["NOTICE", 42]"#,
+            r#"["EVENT", "sub", {"id": "missing fields"}]"#,
+            r#"["EVENT", "", {}]"#,
+            r#"["COUNT", "sub", {"count": "many"}]"#,
+        ];
+
+        for json in invalid {
+            assert!(RelayMessage::from_json(json).is_err(), "{} should be rejected", json);
+        }
+    }
+
+    #[test]
+    fn test_client_message_roundtrip() {
+        let event = sample_event();
+        let messages = vec![
+            ClientMessage::Event(event.clone()),
+            ClientMessage::Auth(event),
+            ClientMessage::Req {
+                subscription_id: "feed".to_string(),
+                filters: vec![Filter::new().with_kinds(vec![1]), Filter::new().with_limit(10)],
+            },
+            ClientMessage::Count { subscription_id: "count".to_string(), filters: vec![Filter::new()] },
+            ClientMessage::Close { subscription_id: "feed".to_string() },
+        ];
+
+        for message in messages {
+            let json = message.to_json().unwrap();
+            assert_eq!(ClientMessage::from_json(&json).unwrap(), message, "roundtrip of {}", json);
+        }
+    }
+
+    #[test]
+    fn test_client_message_parsing_is_strict() {
+        let too_long_id = "x".repeat(65);
+        let invalid = [
+            r#"["REQ", "sub"]"#.to_string(),
+            r#"["REQ", "sub", {"kinds": "1"}]"#.to_string(),
+            r#"["CLOSE"]"#.to_string(),
+            r#"["EVENT"]"#.to_string(),
+            r#"["OK", "abc", true, ""]"#.to_string(),
+            format!(r#"["CLOSE", "{}"]"#, too_long_id),
+        ];
+
+        for json in invalid {
+            assert!(ClientMessage::from_json(&json).is_err(), "{} should be rejected", json);
+        }
+    }
+}
@@ -0,0 +1,156 @@
+use crate::error::{NostrError, Result};
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::{Map, Value};
+
+use crate::nostr::event::{NostrEvent, UnsignedEvent};
+use crate::nostr::keys::NostrKeypair;
+
+/// Profile metadata: a JSON object in the content of a replaceable event
+pub const KIND_METADATA: u16 = 0;
+
+/// Fields `Metadata::get` and `Metadata::set` accept, in the order profiles
+/// are shown and edited
+pub const METADATA_FIELDS: [&str; 8] = ["name", "display_name", "about", "picture", "banner", "nip05", "lud16", "website"];
+
+/// Profile metadata (NIP-01, with the extra fields from NIP-24)
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Metadata {
+    #[serde(default, deserialize_with = "string_or_none", skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, deserialize_with = "string_or_none", skip_serializing_if = "Option::is_none")]
+    pub display_name: Option<String>,
+    #[serde(default, deserialize_with = "string_or_none", skip_serializing_if = "Option::is_none")]
+    pub about: Option<String>,
+    /// URL of the avatar
+    #[serde(default, deserialize_with = "string_or_none", skip_serializing_if = "Option::is_none")]
+    pub picture: Option<String>,
+    /// URL of a wide image shown behind the profile
+    #[serde(default, deserialize_with = "string_or_none", skip_serializing_if = "Option::is_none")]
+    pub banner: Option<String>,
+    /// Internet identifier (NIP-05), e.g. bob@example.com
+    #[serde(default, deserialize_with = "string_or_none", skip_serializing_if = "Option::is_none")]
+    pub nip05: Option<String>,
+    /// Lightning address for zaps
+    #[serde(default, deserialize_with = "string_or_none", skip_serializing_if = "Option::is_none")]
+    pub lud16: Option<String>,
+    #[serde(default, deserialize_with = "string_or_none", skip_serializing_if = "Option::is_none")]
+    pub website: Option<String>,
+    /// Fields set by other clients, kept so editing a profile doesn't drop them
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl Metadata {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn from_json(json: &str) -> Result<Self> {
+        Ok(serde_json::from_str(json)?)
+    }
+
+    /// Read the metadata in a kind-0 event
+    pub fn from_event(event: &NostrEvent) -> Result<Self> {
+        if event.kind != KIND_METADATA {
+            return Err(NostrError::MalformedEvent(format!(
+                "Expected kind {} metadata, got kind {}",
+                KIND_METADATA, event.kind
+            )));
+        }
+        Self::from_json(&event.content)
+    }
+
+    /// Sign the metadata as a kind-0 event
+    pub fn to_event(&self, keypair: &NostrKeypair) -> Result<NostrEvent> {
+        UnsignedEvent::new_text_note(serde_json::to_string(self)?, keypair.public_key_hex())
+            .with_kind(KIND_METADATA)
+            .sign(keypair)
+    }
+
+    /// The name to show for the author: `display_name`, falling back to `name`
+    pub fn display_name(&self) -> Option<&str> {
+        [&self.display_name, &self.name]
+            .into_iter()
+            .filter_map(|name| name.as_deref().map(str::trim))
+            .find(|name| !name.is_empty())
+    }
+
+    /// A field by its JSON name, one of `METADATA_FIELDS`
+    pub fn get(&self, field: &str) -> Option<&str> {
+        match field {
+            "name" => self.name.as_deref(),
+            "display_name" => self.display_name.as_deref(),
+            "about" => self.about.as_deref(),
+            "picture" => self.picture.as_deref(),
+            "banner" => self.banner.as_deref(),
+            "nip05" => self.nip05.as_deref(),
+            "lud16" => self.lud16.as_deref(),
+            "website" => self.website.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// Set a field by its JSON name. Blank values remove the field.
+    pub fn set(&mut self, field: &str, value: &str) -> Result<()> {
+        let slot = match field {
+            "name" => &mut self.name,
+            "display_name" => &mut self.display_name,
+            "about" => &mut self.about,
+            "picture" => &mut self.picture,
+            "banner" => &mut self.banner,
+            "nip05" => &mut self.nip05,
+            "lud16" => &mut self.lud16,
+            "website" => &mut self.website,
+            _ => return Err(NostrError::InvalidInput(format!("Unknown profile field: {}", field))),
+        };
+
+        let value = value.trim();
+        *slot = (!value.is_empty()).then(|| value.to_string());
+        Ok(())
+    }
+}
+
+/// Other clients publish nulls and numbers too; anything but a string counts as unset
+fn string_or_none<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<String>, D::Error> {
+    Ok(Value::deserialize(deserializer)?.as_str().map(str::to_string))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nostr::keys::generate_keypair;
+
+    #[test]
+    fn test_metadata_round_trip_keeps_unknown_fields() {
+        let json = r#"{"name":"bob","about":"hi","lud06":"lnurl1xyz","bot":true}"#;
+        let mut metadata = Metadata::from_json(json).unwrap();
+        assert_eq!(metadata.name.as_deref(), Some("bob"));
+        assert_eq!(metadata.extra.len(), 2);
+
+        metadata.set("display_name", "  Bob  ").unwrap();
+        metadata.set("about", "").unwrap();
+
+        let keypair = generate_keypair().unwrap();
+        let event = metadata.to_event(&keypair).unwrap();
+        assert_eq!(event.kind, KIND_METADATA);
+        assert!(event.verify());
+
+        let parsed = Metadata::from_event(&event).unwrap();
+        assert_eq!(parsed.display_name.as_deref(), Some("Bob"));
+        assert_eq!(parsed.about, None, "Blank values remove the field");
+        assert_eq!(parsed.extra.get("lud06"), Some(&Value::from("lnurl1xyz")));
+        assert_eq!(parsed.extra.get("bot"), Some(&Value::from(true)));
+    }
+
+    #[test]
+    fn test_display_name_falls_back_to_name() {
+        let metadata = Metadata::from_json(r#"{"name":"bob","display_name":"  ","picture":null,"website":42}"#).unwrap();
+        assert_eq!(metadata.display_name(), Some("bob"));
+        assert_eq!(metadata.picture, None);
+        assert_eq!(metadata.website, None);
+
+        assert_eq!(Metadata::new().display_name(), None);
+        assert!(Metadata::new().set("nickname", "bobby").is_err());
+        assert!(Metadata::from_json("[]").is_err());
+    }
+}
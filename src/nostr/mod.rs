@@ -1,5 +1,25 @@
 pub mod event;
+pub mod filter;
 pub mod keys;
+pub mod message;
+pub mod metadata;
+pub mod nip02;
+pub mod nip04;
+pub mod nip09;
+pub mod nip10;
+pub mod nip11;
+pub mod nip13;
+pub mod nip17;
+pub mod nip18;
+pub mod nip19;
+pub mod nip25;
+pub mod nip42;
+pub mod nip44;
+pub mod nip59;
+pub mod nip65;
 
 pub use event::NostrEvent;
-pub use keys::{NostrKeypair, generate_keypair, keypair_from_hex};
\ No newline at end of file
+pub use filter::Filter;
+pub use keys::{NostrKeypair, generate_keypair, keypair_from_hex, keypair_from_secret};
+pub use message::{ClientMessage, RelayMessage};
+pub use nip19::{Nip19, event_id_from_str, public_key_from_str};
@@ -0,0 +1,205 @@
+use crate::error::{NostrError, Result};
+
+use crate::nostr::event::{NostrEvent, UnsignedEvent};
+use crate::nostr::keys::NostrKeypair;
+
+/// Follow list: one `p` tag per followed public key
+pub const KIND_CONTACT_LIST: u16 = 3;
+
+/// A published list this long is only replaced by one that keeps at least
+/// half of it, unless the caller checks with the user first
+const LARGE_LIST: usize = 10;
+
+/// A followed public key with its optional relay hint and petname
+#[derive(Debug, Clone, PartialEq)]
+pub struct Contact {
+    pub pubkey: String,
+    pub relay_url: Option<String>,
+    pub petname: Option<String>,
+}
+
+/// A contact list (NIP-02).
+///
+/// The list keeps the event's tags and content as published, so tags other
+/// clients added survive a follow or unfollow.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ContactList {
+    tags: Vec<Vec<String>>,
+    /// Some older clients keep their relays here, so it is passed on as is
+    content: String,
+    pub created_at: u64,
+}
+
+impl ContactList {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Read a kind-3 event
+    pub fn from_event(event: &NostrEvent) -> Result<Self> {
+        if event.kind != KIND_CONTACT_LIST {
+            return Err(NostrError::MalformedEvent(format!(
+                "Expected a kind {} contact list, got kind {}",
+                KIND_CONTACT_LIST, event.kind
+            )));
+        }
+
+        Ok(Self {
+            tags: event.tags.clone(),
+            content: event.content.clone(),
+            created_at: event.created_at,
+        })
+    }
+
+    /// Sign the list as a kind-3 event
+    pub fn to_event(&self, keypair: &NostrKeypair) -> Result<NostrEvent> {
+        UnsignedEvent::new_text_note(self.content.clone(), keypair.public_key_hex())
+            .with_kind(KIND_CONTACT_LIST)
+            .with_tags(self.tags.clone())
+            .sign(keypair)
+    }
+
+    /// Followed public keys in list order, without duplicates
+    pub fn follows(&self) -> Vec<String> {
+        let mut follows: Vec<String> = Vec::new();
+        for tag in &self.tags {
+            if tag.len() >= 2 && tag[0] == "p" && !follows.contains(&tag[1]) {
+                follows.push(tag[1].clone());
+            }
+        }
+        follows
+    }
+
+    pub fn contacts(&self) -> Vec<Contact> {
+        let mut contacts: Vec<Contact> = Vec::new();
+        for tag in &self.tags {
+            if tag.len() < 2 || tag[0] != "p" || contacts.iter().any(|contact| contact.pubkey == tag[1]) {
+                continue;
+            }
+
+            let non_empty = |index: usize| tag.get(index).filter(|value| !value.is_empty()).cloned();
+            contacts.push(Contact {
+                pubkey: tag[1].clone(),
+                relay_url: non_empty(2),
+                petname: non_empty(3),
+            });
+        }
+        contacts
+    }
+
+    /// Follow `pubkey` (hex). Following someone already on the list updates
+    /// the relay hint and petname that are given. Returns whether the list changed.
+    pub fn follow(&mut self, pubkey: &str, relay_url: Option<&str>, petname: Option<&str>) -> bool {
+        let Some(tag) = self.tags.iter_mut().find(|tag| is_contact_tag(tag, pubkey)) else {
+            let mut tag = vec!["p".to_string(), pubkey.to_string()];
+            if relay_url.is_some() || petname.is_some() {
+                tag.push(relay_url.unwrap_or_default().to_string());
+            }
+            if let Some(petname) = petname {
+                tag.push(petname.to_string());
+            }
+            self.tags.push(tag);
+            return true;
+        };
+
+        let before = tag.clone();
+        for (index, value) in [(2, relay_url), (3, petname)] {
+            if let Some(value) = value {
+                if tag.len() <= index {
+                    tag.resize(index + 1, String::new());
+                }
+                tag[index] = value.to_string();
+            }
+        }
+        *tag != before
+    }
+
+    /// Stop following `pubkey`. Returns whether it was followed.
+    pub fn unfollow(&mut self, pubkey: &str) -> bool {
+        let count = self.tags.len();
+        self.tags.retain(|tag| !is_contact_tag(tag, pubkey));
+        self.tags.len() != count
+    }
+
+    /// Refuse to replace `published` with this list when it lost every
+    /// follow, or most of a large list: the usual sign of a list built
+    /// without the published one, e.g. because relays didn't send it.
+    pub fn check_replaces(&self, published: &ContactList) -> Result<()> {
+        let (before, after) = (published.follows().len(), self.follows().len());
+
+        if before > 0 && after == 0 {
+            return Err(NostrError::InvalidInput(format!(
+                "This would replace a list of {} follows with an empty one",
+                before
+            )));
+        }
+        if before >= LARGE_LIST && after < before / 2 {
+            return Err(NostrError::InvalidInput(format!(
+                "This would shrink the contact list from {} to {} follows",
+                before, after
+            )));
+        }
+        Ok(())
+    }
+}
+
+fn is_contact_tag(tag: &[String], pubkey: &str) -> bool {
+    tag.len() >= 2 && tag[0] == "p" && tag[1] == pubkey
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nostr::keys::generate_keypair;
+
+    fn tag(parts: &[&str]) -> Vec<String> {
+        parts.iter().map(|part| part.to_string()).collect()
+    }
+
+    #[test]
+    fn test_follow_and_unfollow_keep_other_tags() {
+        let keypair = generate_keypair().unwrap();
+        let event = UnsignedEvent::new_text_note(r#"{"wss://relay.example.com":{"read":true}}"#.to_string(), keypair.public_key_hex())
+            .with_kind(KIND_CONTACT_LIST)
+            .with_tags(vec![
+                tag(&["p", "alice", "wss://alice.example.com", "al"]),
+                tag(&["t", "nostr"]),
+                tag(&["p", "bob"]),
+            ])
+            .sign(&keypair)
+            .unwrap();
+
+        let mut list = ContactList::from_event(&event).unwrap();
+        assert!(list.follow("carol", Some("wss://carol.example.com"), None));
+        assert!(!list.follow("carol", None, None), "Already followed");
+        assert!(list.follow("bob", None, Some("bobby")));
+        assert!(list.unfollow("alice"));
+        assert!(!list.unfollow("alice"));
+
+        let published = ContactList::from_event(&list.to_event(&keypair).unwrap()).unwrap();
+        assert_eq!(published.follows(), vec!["bob", "carol"]);
+        assert_eq!(published.contacts()[0].petname.as_deref(), Some("bobby"));
+        assert_eq!(published.contacts()[0].relay_url, None);
+        assert_eq!(published.contacts()[1].relay_url.as_deref(), Some("wss://carol.example.com"));
+        assert!(published.tags.contains(&tag(&["t", "nostr"])), "Unknown tags are kept");
+        assert_eq!(published.content, event.content);
+    }
+
+    #[test]
+    fn test_large_lists_are_not_replaced_by_small_ones() {
+        let mut large = ContactList::new();
+        for i in 0..20 {
+            large.follow(&format!("pubkey{}", i), None, None);
+        }
+
+        let mut fresh = ContactList::new();
+        fresh.follow("pubkey0", None, None);
+        assert!(fresh.check_replaces(&large).is_err());
+        assert!(ContactList::new().check_replaces(&fresh).is_err(), "Unfollowing the last follow needs a check");
+
+        let mut unfollowed = large.clone();
+        unfollowed.unfollow("pubkey3");
+        assert!(unfollowed.check_replaces(&large).is_ok());
+        assert!(fresh.check_replaces(&ContactList::new()).is_ok());
+    }
+}
@@ -0,0 +1,177 @@
+use aes::Aes256;
+use aes::cipher::block_padding::Pkcs7;
+use aes::cipher::{BlockDecryptMut, BlockEncryptMut, KeyIvInit};
+use crate::error::{NostrError, Result};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+
+use crate::nostr::event::{NostrEvent, UnsignedEvent};
+use crate::nostr::keys::{NostrKeypair, shared_secret_x};
+use crate::nostr::nip17::PrivateMessage;
+
+/// Legacy encrypted direct message. Relays see who is talking to whom and
+/// when, so these are read for old conversations but only sent on request.
+pub const KIND_ENCRYPTED_DIRECT_MESSAGE: u16 = 4;
+
+type Aes256CbcEnc = cbc::Encryptor<Aes256>;
+type Aes256CbcDec = cbc::Decryptor<Aes256>;
+
+impl NostrKeypair {
+    /// Encrypt `plaintext` for `recipient_pubkey` (hex) as NIP-04 content:
+    /// AES-256-CBC keyed with the ECDH shared secret
+    pub fn nip04_encrypt(&self, recipient_pubkey: &str, plaintext: &str) -> Result<String> {
+        let iv: [u8; 16] = rand::random();
+        Ok(nip04_encrypt_with_iv(&shared_secret_x(&self.secret_key(), recipient_pubkey)?, plaintext, &iv))
+    }
+
+    /// Decrypt NIP-04 content exchanged with `other_pubkey` (hex), in either direction
+    pub fn nip04_decrypt(&self, other_pubkey: &str, content: &str) -> Result<String> {
+        let key = shared_secret_x(&self.secret_key(), other_pubkey)?;
+
+        let (ciphertext, iv) = content
+            .split_once("?iv=")
+            .ok_or_else(|| NostrError::DecryptionFailed("NIP-04 content is missing its IV".to_string()))?;
+        let ciphertext = BASE64
+            .decode(ciphertext)
+            .map_err(|e| NostrError::InvalidEncoding(format!("Invalid base64: {}", e)))?;
+        let iv: [u8; 16] = BASE64
+            .decode(iv)
+            .map_err(|e| NostrError::InvalidEncoding(format!("Invalid base64 IV: {}", e)))?
+            .try_into()
+            .map_err(|_| NostrError::DecryptionFailed("NIP-04 IV must be 16 bytes".to_string()))?;
+
+        let plaintext = Aes256CbcDec::new(&key.into(), &iv.into())
+            .decrypt_padded_vec_mut::<Pkcs7>(&ciphertext)
+            .map_err(|_| NostrError::DecryptionFailed("Failed to decrypt NIP-04 content".to_string()))?;
+
+        String::from_utf8(plaintext).map_err(|_| NostrError::DecryptionFailed(
+            "Decrypted NIP-04 content is not valid UTF-8".to_string(),
+        ))
+    }
+}
+
+fn nip04_encrypt_with_iv(key: &[u8; 32], plaintext: &str, iv: &[u8; 16]) -> String {
+    let ciphertext = Aes256CbcEnc::new(key.into(), iv.into()).encrypt_padded_vec_mut::<Pkcs7>(plaintext.as_bytes());
+    format!("{}?iv={}", BASE64.encode(ciphertext), BASE64.encode(iv))
+}
+
+impl NostrEvent {
+    /// A signed kind-4 message to `recipient_pubkey`. Prefer NIP-17; this is
+    /// only for contacts whose clients cannot read anything newer.
+    pub fn new_legacy_direct_message(content: &str, sender: &NostrKeypair, recipient_pubkey: &str) -> Result<Self> {
+        let encrypted = sender.nip04_encrypt(recipient_pubkey, content)?;
+
+        UnsignedEvent::new_text_note(encrypted, sender.public_key_hex())
+            .with_kind(KIND_ENCRYPTED_DIRECT_MESSAGE)
+            .with_tags(vec![vec!["p".to_string(), recipient_pubkey.to_string()]])
+            .sign(sender)
+    }
+}
+
+impl PrivateMessage {
+    /// Decrypt a kind-4 message sent by or to `keypair`
+    pub fn from_legacy_event(event: &NostrEvent, keypair: &NostrKeypair) -> Result<Self> {
+        if event.kind != KIND_ENCRYPTED_DIRECT_MESSAGE {
+            return Err(NostrError::MalformedEvent(format!(
+                "Expected a NIP-04 message, got kind {}",
+                event.kind
+            )));
+        }
+        if !event.verify() {
+            return Err(NostrError::MalformedEvent(
+                "NIP-04 message has an invalid id or signature".to_string(),
+            ));
+        }
+
+        let recipient = event
+            .tagged_pubkeys()
+            .next()
+            .ok_or_else(|| NostrError::MalformedEvent("NIP-04 message has no recipient".to_string()))?
+            .to_string();
+
+        let own_pubkey = keypair.public_key_hex();
+        let other_pubkey = if event.pubkey == own_pubkey {
+            &recipient
+        } else if recipient == own_pubkey {
+            &event.pubkey
+        } else {
+            return Err(NostrError::DecryptionFailed(
+                "NIP-04 message is neither from nor to this key".to_string(),
+            ));
+        };
+
+        Ok(Self {
+            id: event.id.clone(),
+            sender: event.pubkey.clone(),
+            recipients: vec![recipient.clone()],
+            created_at: event.created_at,
+            content: keypair.nip04_decrypt(other_pubkey, &event.content)?,
+            legacy: true,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nostr::keys::{generate_keypair, keypair_from_hex};
+
+    const ALICE_SECRET: &str = "8182a1283a6e4a2ee5c0e6fedcc003b3e810e2a93d864946df32ed2baccd71a5";
+    const BOB_SECRET: &str = "67dea2ed018072d675f5415ecfaed7d2597555e202d85b3d65ea4e58d2d92ffa";
+
+    // Produced independently with Python's `cryptography` package
+    const SHARED_SECRET: &str = "5d5bb3a22b81ebe8dd0dd31395dad201614cff64f466eae262ed74662ca1ec55";
+    const CONTENT: &str = "VTlnyat/3BEjVl9fXyKzerdTSmoncNFBfMU1VBElB0Q=?iv=AAECAwQFBgcICQoLDA0ODw==";
+    const PLAINTEXT: &str = "Hello from the old days 👋";
+
+    #[test]
+    fn test_known_ciphertext() {
+        let alice = keypair_from_hex(ALICE_SECRET).unwrap();
+        let bob = keypair_from_hex(BOB_SECRET).unwrap();
+
+        let shared = shared_secret_x(&alice.secret_key(), &bob.public_key_hex()).unwrap();
+        assert_eq!(hex::encode(shared), SHARED_SECRET);
+
+        let iv: [u8; 16] = core::array::from_fn(|i| i as u8);
+        assert_eq!(nip04_encrypt_with_iv(&shared, PLAINTEXT, &iv), CONTENT);
+
+        assert_eq!(bob.nip04_decrypt(&alice.public_key_hex(), CONTENT).unwrap(), PLAINTEXT);
+        assert_eq!(alice.nip04_decrypt(&bob.public_key_hex(), CONTENT).unwrap(), PLAINTEXT);
+    }
+
+    #[test]
+    fn test_legacy_messages_are_readable_by_both_sides() {
+        let alice = generate_keypair().unwrap();
+        let bob = generate_keypair().unwrap();
+        let event = NostrEvent::new_legacy_direct_message("old school", &alice, &bob.public_key_hex()).unwrap();
+
+        assert_eq!(event.kind, KIND_ENCRYPTED_DIRECT_MESSAGE);
+        assert_ne!(event.content, "old school");
+
+        for keypair in [&alice, &bob] {
+            let message = PrivateMessage::from_legacy_event(&event, keypair).unwrap();
+            assert_eq!(message.content, "old school");
+            assert_eq!(message.sender, alice.public_key_hex());
+            assert_eq!(message.recipients, vec![bob.public_key_hex()]);
+            assert!(message.legacy);
+        }
+
+        let stranger = generate_keypair().unwrap();
+        assert!(PrivateMessage::from_legacy_event(&event, &stranger).is_err());
+    }
+
+    #[test]
+    fn test_malformed_content_is_rejected() {
+        let alice = keypair_from_hex(ALICE_SECRET).unwrap();
+        let bob = keypair_from_hex(BOB_SECRET).unwrap();
+        let bob_pubkey = bob.public_key_hex();
+
+        assert!(alice.nip04_decrypt(&bob_pubkey, "VTlnyat/3BEjVl9fXyKzerdTSmoncNFBfMU1VBElB0Q=").is_err());
+        assert!(alice.nip04_decrypt(&bob_pubkey, "not base64?iv=AAECAwQFBgcICQoLDA0ODw==").is_err());
+        assert!(alice.nip04_decrypt(&bob_pubkey, "VTlnyat/3BEjVl9fXyKzerdTSmoncNFBfMU1VBElB0Q=?iv=AAEC").is_err());
+
+        // CBC has no MAC, so the wrong key fails on padding or yields garbage
+        let stranger = generate_keypair().unwrap();
+        assert_ne!(stranger.nip04_decrypt(&alice.public_key_hex(), CONTENT).ok().as_deref(), Some(PLAINTEXT));
+    }
+}
@@ -0,0 +1,188 @@
+use crate::error::{NostrError, Result};
+
+use crate::nostr::event::{NostrEvent, UnsignedEvent};
+use crate::nostr::keys::NostrKeypair;
+
+/// Deletion request (NIP-09)
+pub const KIND_DELETION: u16 = 5;
+
+/// A request to relays and clients to drop some of the author's own events.
+///
+/// Only events by the request's author are affected; anything else it
+/// references is ignored.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DeletionRequest {
+    /// Ids of the events to delete (`e` tags)
+    pub event_ids: Vec<String>,
+    /// Replaceable or addressable events to delete every version of, up to
+    /// the request's `created_at` (`a` tags)
+    pub addresses: Vec<String>,
+    /// Kinds of the deleted events (`k` tags)
+    pub kinds: Vec<u16>,
+    pub reason: Option<String>,
+}
+
+impl DeletionRequest {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Delete `event`, by id and, for replaceable and addressable events, by address
+    pub fn with_event(mut self, event: &NostrEvent) -> Self {
+        self = self.with_event_id(&event.id);
+        if let Some(address) = event.address() {
+            self = self.with_address(&address);
+        }
+        if !self.kinds.contains(&event.kind) {
+            self.kinds.push(event.kind);
+        }
+        self
+    }
+
+    /// Delete an event that isn't at hand by its id
+    pub fn with_event_id(mut self, event_id: &str) -> Self {
+        if !self.event_ids.iter().any(|id| id == event_id) {
+            self.event_ids.push(event_id.to_string());
+        }
+        self
+    }
+
+    /// Delete every version of the replaceable or addressable event at
+    /// `address` (`kind:pubkey:d-tag`)
+    pub fn with_address(mut self, address: &str) -> Self {
+        if !self.addresses.iter().any(|existing| existing == address) {
+            self.addresses.push(address.to_string());
+        }
+        self
+    }
+
+    pub fn with_reason(mut self, reason: &str) -> Self {
+        let reason = reason.trim();
+        self.reason = (!reason.is_empty()).then(|| reason.to_string());
+        self
+    }
+
+    /// Read a kind-5 event
+    pub fn from_event(event: &NostrEvent) -> Result<Self> {
+        if event.kind != KIND_DELETION {
+            return Err(NostrError::MalformedEvent(format!(
+                "Expected a kind {} deletion request, got kind {}",
+                KIND_DELETION, event.kind
+            )));
+        }
+
+        let values = |name: &str| -> Vec<&String> {
+            event.tags.iter().filter(|tag| tag.len() >= 2 && tag[0] == name).map(|tag| &tag[1]).collect()
+        };
+        Ok(Self {
+            event_ids: values("e").into_iter().cloned().collect(),
+            addresses: values("a").into_iter().cloned().collect(),
+            kinds: values("k").into_iter().filter_map(|kind| kind.parse().ok()).collect(),
+            reason: (!event.content.is_empty()).then(|| event.content.clone()),
+        })
+    }
+
+    /// Sign the request as a kind-5 event
+    pub fn to_event(&self, keypair: &NostrKeypair) -> Result<NostrEvent> {
+        if self.event_ids.is_empty() && self.addresses.is_empty() {
+            return Err(NostrError::InvalidInput(
+                "A deletion request needs at least one event to delete".to_string(),
+            ));
+        }
+
+        let tags = self
+            .event_ids
+            .iter()
+            .map(|id| vec!["e".to_string(), id.clone()])
+            .chain(self.addresses.iter().map(|address| vec!["a".to_string(), address.clone()]))
+            .chain(self.kinds.iter().map(|kind| vec!["k".to_string(), kind.to_string()]))
+            .collect();
+
+        UnsignedEvent::new_text_note(self.reason.clone().unwrap_or_default(), keypair.public_key_hex())
+            .with_kind(KIND_DELETION)
+            .with_tags(tags)
+            .sign(keypair)
+    }
+}
+
+/// Whether `deletion` is a request by the author of `event` to delete it:
+/// by id, or by address when the event isn't newer than the request
+pub fn deletes(deletion: &NostrEvent, event: &NostrEvent) -> bool {
+    if deletion.kind != KIND_DELETION || event.kind == KIND_DELETION || deletion.pubkey != event.pubkey {
+        return false;
+    }
+
+    let address = event.address();
+    deletion.tags.iter().any(|tag| {
+        tag.len() >= 2
+            && match tag[0].as_str() {
+                "e" => tag[1] == event.id,
+                "a" => address.as_deref() == Some(tag[1].as_str()) && event.created_at <= deletion.created_at,
+                _ => false,
+            }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nostr::keys::generate_keypair;
+
+    #[test]
+    fn test_deletion_request_targets_ids_and_addresses() {
+        let alice = generate_keypair().unwrap();
+        let note = NostrEvent::new_text_note("oops".to_string(), &alice).unwrap();
+        let article = UnsignedEvent::new_text_note("draft".to_string(), alice.public_key_hex())
+            .with_kind(30023)
+            .with_tags(vec![vec!["d".to_string(), "my-article".to_string()]])
+            .with_timestamp(note.created_at - 10)
+            .sign(&alice)
+            .unwrap();
+
+        let deletion = DeletionRequest::new()
+            .with_event(&note)
+            .with_event(&article)
+            .with_reason("posted by mistake")
+            .to_event(&alice)
+            .unwrap();
+        assert_eq!(deletion.content, "posted by mistake");
+
+        let request = DeletionRequest::from_event(&deletion).unwrap();
+        assert_eq!(request.event_ids, vec![note.id.clone(), article.id.clone()]);
+        assert_eq!(request.addresses, vec![format!("30023:{}:my-article", alice.public_key_hex())]);
+        assert_eq!(request.kinds, vec![1, 30023]);
+
+        assert!(deletes(&deletion, &note));
+        assert!(deletes(&deletion, &article));
+        assert!(DeletionRequest::new().with_reason("nothing").to_event(&alice).is_err());
+    }
+
+    #[test]
+    fn test_only_the_author_can_delete() {
+        let alice = generate_keypair().unwrap();
+        let mallory = generate_keypair().unwrap();
+        let note = NostrEvent::new_text_note("mine".to_string(), &alice).unwrap();
+
+        let forged = DeletionRequest::new().with_event(&note).to_event(&mallory).unwrap();
+        assert!(!deletes(&forged, &note));
+
+        // A newer version of an addressable event survives an older request
+        let address = format!("30023:{}:post", alice.public_key_hex());
+        let deletion = UnsignedEvent::new_text_note(String::new(), alice.public_key_hex())
+            .with_kind(KIND_DELETION)
+            .with_tags(vec![vec!["a".to_string(), address]])
+            .with_timestamp(100)
+            .sign(&alice)
+            .unwrap();
+        let version = |created_at: u64| {
+            UnsignedEvent::new_text_note("post".to_string(), alice.public_key_hex())
+                .with_kind(30023)
+                .with_tags(vec![vec!["d".to_string(), "post".to_string()]])
+                .with_timestamp(created_at)
+                .sign(&alice)
+                .unwrap()
+        };
+        assert!(deletes(&deletion, &version(50)));
+        assert!(!deletes(&deletion, &version(150)));
+    }
+}
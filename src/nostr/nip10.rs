@@ -0,0 +1,271 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::nostr::event::{NostrEvent, UnsignedEvent};
+
+/// An `e` tag pointing at another note of the thread
+#[derive(Debug, Clone, PartialEq)]
+pub struct EventRef {
+    pub id: String,
+    pub relay_url: Option<String>,
+    /// Author of the referenced note, when the tag carries it
+    pub pubkey: Option<String>,
+}
+
+/// Where a note sits in its thread (NIP-10)
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ThreadRefs {
+    /// The note that started the thread; `None` for a top-level note
+    pub root: Option<EventRef>,
+    /// The note this one answers, which is the root for direct replies
+    pub reply: Option<EventRef>,
+    /// Notes quoted or mentioned without being replied to
+    pub mentions: Vec<EventRef>,
+}
+
+impl ThreadRefs {
+    /// Read the `e` tags of a note. Tags with `root`/`reply` markers are used
+    /// when there are any; otherwise the deprecated positional scheme applies:
+    /// first tag root, last tag reply, anything between mentions.
+    pub fn from_event(event: &NostrEvent) -> Self {
+        let e_tags: Vec<&Vec<String>> = event.tags.iter().filter(|tag| tag.len() >= 2 && tag[0] == "e").collect();
+        let marker = |tag: &Vec<String>| tag.get(3).map(String::as_str).unwrap_or_default().to_string();
+
+        let mut refs = Self::default();
+        if e_tags.iter().any(|tag| matches!(marker(tag).as_str(), "root" | "reply")) {
+            for tag in e_tags {
+                match marker(tag).as_str() {
+                    "root" => refs.root = Some(event_ref(tag)),
+                    "reply" => refs.reply = Some(event_ref(tag)),
+                    _ => refs.mentions.push(event_ref(tag)),
+                }
+            }
+
+            // A direct reply only needs the root marker
+            if refs.reply.is_none() {
+                refs.reply = refs.root.clone();
+            }
+            if refs.root.is_none() {
+                refs.root = refs.reply.clone();
+            }
+            return refs;
+        }
+
+        if let (Some(first), Some(last)) = (e_tags.first(), e_tags.last()) {
+            refs.root = Some(event_ref(first));
+            refs.reply = Some(event_ref(last));
+            if e_tags.len() > 2 {
+                refs.mentions = e_tags[1..e_tags.len() - 1].iter().map(|tag| event_ref(tag)).collect();
+            }
+        }
+        refs
+    }
+
+    /// Id of the thread's root, which is the note itself for top-level notes
+    pub fn root_id<'a>(&'a self, event: &'a NostrEvent) -> &'a str {
+        self.root.as_ref().map_or(event.id.as_str(), |root| root.id.as_str())
+    }
+}
+
+fn event_ref(tag: &[String]) -> EventRef {
+    let non_empty = |index: usize| tag.get(index).filter(|value| !value.is_empty()).cloned();
+    EventRef {
+        id: tag[1].clone(),
+        relay_url: non_empty(2),
+        pubkey: non_empty(4),
+    }
+}
+
+impl UnsignedEvent {
+    /// Mark the note as a reply to `parent` with NIP-10 `root`/`reply` tags,
+    /// and tag the parent's author and everyone it tagged so they are notified.
+    /// `relay_url` is where the parent can be found.
+    pub fn with_reply_to(mut self, parent: &NostrEvent, relay_url: Option<&str>) -> Self {
+        let relay_url = relay_url.unwrap_or_default().to_string();
+        let parent_refs = ThreadRefs::from_event(parent);
+
+        match &parent_refs.root {
+            Some(root) => {
+                self.tags.push(vec![
+                    "e".to_string(),
+                    root.id.clone(),
+                    root.relay_url.clone().unwrap_or_default(),
+                    "root".to_string(),
+                    root.pubkey.clone().unwrap_or_default(),
+                ]);
+                self.tags.push(vec!["e".to_string(), parent.id.clone(), relay_url, "reply".to_string(), parent.pubkey.clone()]);
+            }
+            None => {
+                self.tags.push(vec!["e".to_string(), parent.id.clone(), relay_url, "root".to_string(), parent.pubkey.clone()]);
+            }
+        }
+
+        let participants = std::iter::once(&parent.pubkey).chain(
+            parent
+                .tags
+                .iter()
+                .filter(|tag| tag.len() >= 2 && tag[0] == "p")
+                .map(|tag| &tag[1]),
+        );
+        for pubkey in participants {
+            let tagged = self.tags.iter().any(|tag| tag.len() >= 2 && tag[0] == "p" && tag[1] == *pubkey);
+            if *pubkey != self.pubkey && !tagged {
+                self.tags.push(vec!["p".to_string(), pubkey.clone()]);
+            }
+        }
+
+        self
+    }
+}
+
+/// A note of a resolved thread and how deep it is nested
+#[derive(Debug, Clone, PartialEq)]
+pub struct ThreadEntry {
+    pub event: NostrEvent,
+    pub depth: usize,
+}
+
+/// Arrange the notes of the thread started by `root_id` as a tree, flattened
+/// depth first with replies oldest first. Notes answering a note that isn't
+/// in `events` hang off the root, or start at the top when the root is
+/// missing too. Notes from other threads are left out.
+pub fn resolve_thread(root_id: &str, events: &[NostrEvent]) -> Vec<ThreadEntry> {
+    let mut notes: HashMap<&str, &NostrEvent> = HashMap::new();
+    for event in events {
+        let refs = ThreadRefs::from_event(event);
+        if event.id == root_id || refs.root_id(event) == root_id {
+            notes.insert(event.id.as_str(), event);
+        }
+    }
+
+    let mut children: HashMap<Option<&str>, Vec<&NostrEvent>> = HashMap::new();
+    for event in notes.values() {
+        if event.id == root_id {
+            continue;
+        }
+
+        let refs = ThreadRefs::from_event(event);
+        let parent = refs.reply.as_ref().map_or(root_id, |reply| reply.id.as_str());
+        let parent = if notes.contains_key(parent) {
+            Some(notes[parent].id.as_str())
+        } else if notes.contains_key(root_id) {
+            Some(notes[root_id].id.as_str())
+        } else {
+            None
+        };
+        children.entry(parent).or_default().push(event);
+    }
+    for replies in children.values_mut() {
+        replies.sort_by(|a, b| a.created_at.cmp(&b.created_at).then_with(|| a.id.cmp(&b.id)));
+    }
+
+    let mut entries = Vec::new();
+    let mut visited = HashSet::new();
+    let mut stack: Vec<(&NostrEvent, usize)> = Vec::new();
+
+    let top_level: Vec<&NostrEvent> = match notes.get(root_id) {
+        Some(root) => vec![root],
+        None => children.get(&None).cloned().unwrap_or_default(),
+    };
+    stack.extend(top_level.into_iter().rev().map(|event| (event, 0)));
+
+    while let Some((event, depth)) = stack.pop() {
+        if !visited.insert(event.id.as_str()) {
+            continue;
+        }
+
+        entries.push(ThreadEntry { event: event.clone(), depth });
+        if let Some(replies) = children.get(&Some(event.id.as_str())) {
+            stack.extend(replies.iter().rev().map(|reply| (*reply, depth + 1)));
+        }
+    }
+
+    entries
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nostr::keys::{NostrKeypair, generate_keypair};
+
+    fn note(keypair: &NostrKeypair, content: &str, created_at: u64, parent: Option<&NostrEvent>) -> NostrEvent {
+        let unsigned = UnsignedEvent::new_text_note(content.to_string(), keypair.public_key_hex()).with_timestamp(created_at);
+        let unsigned = match parent {
+            Some(parent) => unsigned.with_reply_to(parent, Some("wss://relay.example.com")),
+            None => unsigned,
+        };
+        unsigned.sign(keypair).unwrap()
+    }
+
+    fn tag(parts: &[&str]) -> Vec<String> {
+        parts.iter().map(|part| part.to_string()).collect()
+    }
+
+    #[test]
+    fn test_reply_tags_mark_root_and_reply_and_carry_participants() {
+        let alice = generate_keypair().unwrap();
+        let bob = generate_keypair().unwrap();
+        let carol = generate_keypair().unwrap();
+
+        let root = note(&alice, "root", 100, None);
+        let first = note(&bob, "first", 200, Some(&root));
+        assert_eq!(first.tags, vec![
+            tag(&["e", &root.id, "wss://relay.example.com", "root", &alice.public_key_hex()]),
+            tag(&["p", &alice.public_key_hex()]),
+        ]);
+
+        let second = note(&carol, "second", 300, Some(&first));
+        let refs = ThreadRefs::from_event(&second);
+        assert_eq!(refs.root.as_ref().map(|root| root.id.as_str()), Some(root.id.as_str()));
+        assert_eq!(refs.reply.as_ref().map(|reply| reply.id.as_str()), Some(first.id.as_str()));
+        assert_eq!(refs.reply.unwrap().pubkey, Some(bob.public_key_hex()));
+
+        let tagged: Vec<&str> = second.tags.iter().filter(|tag| tag[0] == "p").map(|tag| tag[1].as_str()).collect();
+        assert_eq!(tagged, vec![bob.public_key_hex(), alice.public_key_hex()]);
+
+        // Replying to yourself doesn't tag yourself
+        let own = note(&alice, "own", 400, Some(&second));
+        assert!(!own.tags.contains(&tag(&["p", &alice.public_key_hex()])));
+    }
+
+    #[test]
+    fn test_legacy_positional_tags() {
+        let keypair = generate_keypair().unwrap();
+        let event = UnsignedEvent::new_text_note("legacy".to_string(), keypair.public_key_hex())
+            .with_tags(vec![tag(&["e", "root-id"]), tag(&["e", "mention-id"]), tag(&["e", "parent-id", "wss://r.example.com"])])
+            .sign(&keypair)
+            .unwrap();
+
+        let refs = ThreadRefs::from_event(&event);
+        assert_eq!(refs.root.unwrap().id, "root-id");
+        assert_eq!(refs.reply.as_ref().unwrap().id, "parent-id");
+        assert_eq!(refs.reply.unwrap().relay_url.as_deref(), Some("wss://r.example.com"));
+        assert_eq!(refs.mentions.len(), 1);
+
+        let single = UnsignedEvent::new_text_note("direct".to_string(), keypair.public_key_hex())
+            .with_tags(vec![tag(&["e", "root-id"])])
+            .sign(&keypair)
+            .unwrap();
+        let refs = ThreadRefs::from_event(&single);
+        assert_eq!((refs.root.unwrap().id, refs.reply.unwrap().id), ("root-id".to_string(), "root-id".to_string()));
+    }
+
+    #[test]
+    fn test_thread_is_resolved_into_a_tree() {
+        let alice = generate_keypair().unwrap();
+        let bob = generate_keypair().unwrap();
+
+        let root = note(&alice, "root", 100, None);
+        let late = note(&bob, "late reply", 500, Some(&root));
+        let early = note(&bob, "early reply", 200, Some(&root));
+        let nested = note(&alice, "nested", 300, Some(&early));
+        let missing_parent = note(&alice, "missing parent", 250, Some(&early));
+        let orphan = note(&bob, "orphan", 400, Some(&missing_parent));
+        let unrelated = note(&bob, "unrelated", 150, None);
+
+        let events = vec![late.clone(), nested.clone(), root.clone(), orphan.clone(), early.clone(), unrelated];
+        let entries = resolve_thread(&root.id, &events);
+        let thread: Vec<(&str, usize)> = entries.iter().map(|entry| (entry.event.content.as_str(), entry.depth)).collect();
+
+        assert_eq!(thread, vec![("root", 0), ("early reply", 1), ("nested", 2), ("orphan", 1), ("late reply", 1)]);
+    }
+}
@@ -0,0 +1,238 @@
+use crate::error::{NostrError, Result};
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use crate::nostr::event::NostrEvent;
+use crate::nostr::message::ClientMessage;
+use crate::nostr::nip13::difficulty;
+
+/// Media type relays answer with when asked for their information document
+pub const RELAY_INFORMATION_MEDIA_TYPE: &str = "application/nostr+json";
+
+/// A relay's NIP-11 information document. Every field is optional and relays
+/// add their own, so unknown fields are ignored.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RelayInformation {
+    pub name: Option<String>,
+    pub description: Option<String>,
+    /// Administrator's public key (hex)
+    pub pubkey: Option<String>,
+    pub contact: Option<String>,
+    pub supported_nips: Vec<u16>,
+    pub software: Option<String>,
+    pub version: Option<String>,
+    pub limitation: Option<RelayLimitation>,
+    pub payments_url: Option<String>,
+}
+
+/// Limits a relay announces; commands exceeding them will be refused
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RelayLimitation {
+    /// Longest websocket message the relay accepts, in bytes
+    pub max_message_length: Option<usize>,
+    /// Open subscriptions allowed per connection
+    pub max_subscriptions: Option<usize>,
+    /// Largest `limit` honored in a filter
+    pub max_limit: Option<u64>,
+    pub max_subid_length: Option<usize>,
+    pub max_event_tags: Option<usize>,
+    /// Longest event content, in characters
+    pub max_content_length: Option<usize>,
+    /// Leading zero bits event ids must have (NIP-13)
+    pub min_pow_difficulty: Option<u32>,
+    /// NIP-42 authentication is required before anything else
+    pub auth_required: bool,
+    pub payment_required: bool,
+    /// Only some events are accepted, e.g. from members
+    pub restricted_writes: bool,
+    /// Oldest `created_at` accepted, as seconds before now
+    pub created_at_lower_limit: Option<u64>,
+    /// Newest `created_at` accepted, as seconds after now
+    pub created_at_upper_limit: Option<u64>,
+}
+
+impl RelayInformation {
+    pub fn from_json(json: &str) -> Result<Self> {
+        Ok(serde_json::from_str(json)?)
+    }
+
+    #[allow(dead_code)]
+    pub fn supports(&self, nip: u16) -> bool {
+        self.supported_nips.contains(&nip)
+    }
+
+    /// The relay's limits, all unset if it announces none
+    pub fn limitation(&self) -> RelayLimitation {
+        self.limitation.clone().unwrap_or_default()
+    }
+
+    /// Check an event against the relay's announced limits, so it can be
+    /// refused before sending instead of by the relay. `now` is a unix timestamp.
+    pub fn check_event(&self, event: &NostrEvent, now: u64) -> Result<()> {
+        let limitation = self.limitation();
+
+        if let Some(max) = limitation.max_content_length {
+            let length = event.content.chars().count();
+            if length > max {
+                return Err(NostrError::RelayLimitExceeded(format!(
+                    "Content is {} characters, relay allows {}",
+                    length, max
+                )));
+            }
+        }
+
+        if let Some(max) = limitation.max_event_tags
+            && event.tags.len() > max
+        {
+            return Err(NostrError::RelayLimitExceeded(format!(
+                "Event has {} tags, relay allows {}",
+                event.tags.len(),
+                max
+            )));
+        }
+
+        if let Some(max) = limitation.max_message_length {
+            let length = ClientMessage::Event(event.clone()).to_json()?.len();
+            if length > max {
+                return Err(NostrError::RelayLimitExceeded(format!(
+                    "Message is {} bytes, relay allows {}",
+                    length, max
+                )));
+            }
+        }
+
+        if let Some(min) = limitation.min_pow_difficulty {
+            let pow = difficulty(&event.id);
+            if pow < min {
+                return Err(NostrError::RelayLimitExceeded(format!(
+                    "Event has proof of work {}, relay requires {}",
+                    pow, min
+                )));
+            }
+        }
+
+        if let Some(limit) = limitation.created_at_lower_limit
+            && event.created_at < now.saturating_sub(limit)
+        {
+            return Err(NostrError::RelayLimitExceeded("Event is older than the relay accepts".to_string()));
+        }
+
+        if let Some(limit) = limitation.created_at_upper_limit
+            && event.created_at > now.saturating_add(limit)
+        {
+            return Err(NostrError::RelayLimitExceeded(
+                "Event is further in the future than the relay accepts".to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+/// The HTTP(S) URL a relay serves its information document on
+pub fn information_url(relay_url: &str) -> Result<Url> {
+    let mut url = Url::parse(relay_url)?;
+    let scheme = match url.scheme() {
+        "ws" => "http",
+        "wss" => "https",
+        _ => return Err(NostrError::InvalidUrl(format!("{} is not a ws:// or wss:// URL", relay_url))),
+    };
+
+    url.set_scheme(scheme)
+        .map_err(|_| NostrError::InvalidUrl(format!("Cannot convert {} to an HTTP URL", relay_url)))?;
+    Ok(url)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nostr::event::UnsignedEvent;
+    use crate::nostr::keys::generate_keypair;
+
+    const DOCUMENT: &str = r#"{
+        "name": "Members relay",
+        "description": "Notes from members only",
+        "pubkey": "bf2bee5281149c7c350f5d12ae32f514c7864ff10805182f4178538c2c421007",
+        "supported_nips": [1, 11, 42],
+        "software": "git+https://example.com/relay.git",
+        "version": "1.2.3",
+        "limitation": {
+            "max_message_length": 16384,
+            "max_subscriptions": 20,
+            "max_content_length": 10,
+            "min_pow_difficulty": 0,
+            "auth_required": true,
+            "payment_required": false
+        },
+        "icon": "https://example.com/icon.png"
+    }"#;
+
+    #[test]
+    fn test_parse_information_document() {
+        let information = RelayInformation::from_json(DOCUMENT).unwrap();
+
+        assert_eq!(information.name.as_deref(), Some("Members relay"));
+        assert!(information.supports(42));
+        assert!(!information.supports(50));
+
+        let limitation = information.limitation();
+        assert_eq!(limitation.max_message_length, Some(16384));
+        assert_eq!(limitation.max_subscriptions, Some(20));
+        assert!(limitation.auth_required);
+        assert!(!limitation.payment_required);
+        assert_eq!(limitation.max_event_tags, None);
+    }
+
+    #[test]
+    fn test_empty_document_has_no_limits() {
+        let information = RelayInformation::from_json("{}").unwrap();
+        let keypair = generate_keypair().unwrap();
+        let event = NostrEvent::new_text_note("x".repeat(100_000), &keypair).unwrap();
+
+        assert_eq!(information.limitation(), RelayLimitation::default());
+        assert!(information.check_event(&event, event.created_at).is_ok());
+    }
+
+    #[test]
+    fn test_check_event_against_limits() {
+        let information = RelayInformation::from_json(DOCUMENT).unwrap();
+        let keypair = generate_keypair().unwrap();
+
+        let short = NostrEvent::new_text_note("hello".to_string(), &keypair).unwrap();
+        assert!(information.check_event(&short, short.created_at).is_ok());
+
+        let long = NostrEvent::new_text_note("hello, relay".to_string(), &keypair).unwrap();
+        let error = information.check_event(&long, long.created_at).unwrap_err();
+        assert!(error.to_string().contains("relay allows 10"));
+    }
+
+    #[test]
+    fn test_check_event_created_at_window() {
+        let information = RelayInformation {
+            limitation: Some(RelayLimitation {
+                created_at_lower_limit: Some(60),
+                created_at_upper_limit: Some(60),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let keypair = generate_keypair().unwrap();
+        let event = UnsignedEvent::new_text_note("hi".to_string(), keypair.public_key_hex())
+            .with_timestamp(1_000)
+            .sign(&keypair)
+            .unwrap();
+
+        assert!(information.check_event(&event, 1_000).is_ok());
+        assert!(information.check_event(&event, 1_061).is_err());
+        assert!(information.check_event(&event, 939).is_err());
+    }
+
+    #[test]
+    fn test_information_url() {
+        assert_eq!(information_url("wss://relay.example.com").unwrap().as_str(), "https://relay.example.com/");
+        assert_eq!(information_url("ws://127.0.0.1:7777/path").unwrap().as_str(), "http://127.0.0.1:7777/path");
+        assert!(information_url("https://relay.example.com").is_err());
+    }
+}
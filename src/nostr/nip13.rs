@@ -0,0 +1,230 @@
+use crate::error::{NostrError, Result};
+use std::num::NonZeroUsize;
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
+use std::sync::{Arc, mpsc};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::nostr::event::{NostrEvent, UnsignedEvent};
+
+/// How often a miner reports its progress
+const PROGRESS_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Proof of work of an event id (hex): its number of leading zero bits
+pub fn difficulty(event_id: &str) -> u32 {
+    let mut zero_bits = 0;
+
+    for c in event_id.chars() {
+        let Some(nibble) = c.to_digit(16) else {
+            break;
+        };
+        if nibble == 0 {
+            zero_bits += 4;
+        } else {
+            zero_bits += nibble.leading_zeros() - 28;
+            break;
+        }
+    }
+
+    zero_bits
+}
+
+/// The difficulty the author committed to in the `nonce` tag, if any
+pub fn committed_difficulty(event: &NostrEvent) -> Option<u32> {
+    event
+        .tags
+        .iter()
+        .find(|tag| tag.len() >= 3 && tag[0] == "nonce")
+        .and_then(|tag| tag[2].parse().ok())
+}
+
+/// Whether the event has at least `min` bits of proof of work. An event that
+/// committed to a lower target only got lucky and doesn't count. The id is
+/// taken as is; `NostrEvent::verify` checks it matches the event.
+pub fn meets_difficulty(event: &NostrEvent, min: u32) -> bool {
+    min == 0
+        || (difficulty(&event.id) >= min && committed_difficulty(event).is_none_or(|committed| committed >= min))
+}
+
+/// How far a miner got, reported while it runs
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MiningProgress {
+    /// Nonces tried so far, over all threads
+    pub attempts: u64,
+    /// Highest difficulty of the ids seen so far
+    pub best_difficulty: u32,
+    pub elapsed: Duration,
+}
+
+/// Stops a running miner from another thread or task
+#[derive(Debug, Clone, Default)]
+pub struct CancelMining(Arc<AtomicBool>);
+
+impl CancelMining {
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Searches for a `nonce` tag giving an event's id a target difficulty,
+/// on several threads at once
+pub struct Miner {
+    difficulty: u32,
+    threads: usize,
+    cancel: CancelMining,
+}
+
+impl Miner {
+    /// A miner for `difficulty` leading zero bits, with a thread per CPU
+    pub fn new(difficulty: u32) -> Self {
+        Self {
+            difficulty,
+            threads: thread::available_parallelism().map_or(1, NonZeroUsize::get),
+            cancel: CancelMining::default(),
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn with_threads(mut self, threads: usize) -> Self {
+        self.threads = threads.max(1);
+        self
+    }
+
+    /// A handle that makes `mine` give up
+    pub fn cancel_handle(&self) -> CancelMining {
+        self.cancel.clone()
+    }
+
+    /// Add a `nonce` tag committing to the target and search its value until
+    /// the event's id has the difficulty, replacing any earlier `nonce` tag.
+    /// Blocks until then, calling `on_progress` every second. Fails when
+    /// cancelled.
+    pub fn mine(&self, event: UnsignedEvent, mut on_progress: impl FnMut(MiningProgress)) -> Result<UnsignedEvent> {
+        if self.difficulty > 256 {
+            return Err(NostrError::InvalidInput(format!(
+                "An id has 256 bits, {} leading zero bits can't be mined",
+                self.difficulty
+            )));
+        }
+
+        let started = Instant::now();
+        let attempts = AtomicU64::new(0);
+        let best_difficulty = AtomicU32::new(0);
+        let stop = AtomicBool::new(false);
+        let (found_tx, found) = mpsc::channel();
+
+        thread::scope(|scope| {
+            for worker in 0..self.threads {
+                let found_tx = found_tx.clone();
+                let mut event = event.clone();
+                let (attempts, best_difficulty, stop) = (&attempts, &best_difficulty, &stop);
+
+                scope.spawn(move || {
+                    event.tags.retain(|tag| tag.first().is_none_or(|name| name != "nonce"));
+                    event.tags.push(vec!["nonce".to_string(), String::new(), self.difficulty.to_string()]);
+                    let nonce_tag = event.tags.len() - 1;
+
+                    // Each thread takes every `threads`th nonce, starting from its own
+                    let mut nonce = worker as u64;
+                    while !stop.load(Ordering::Relaxed) && !self.cancel.is_cancelled() {
+                        event.tags[nonce_tag][1] = nonce.to_string();
+                        let id = match event.calculate_id() {
+                            Ok(id) => id,
+                            Err(e) => {
+                                let _ = found_tx.send(Err(e));
+                                return;
+                            }
+                        };
+
+                        attempts.fetch_add(1, Ordering::Relaxed);
+                        let zero_bits = difficulty(&id);
+                        best_difficulty.fetch_max(zero_bits, Ordering::Relaxed);
+                        if zero_bits >= self.difficulty {
+                            let _ = found_tx.send(Ok(event));
+                            return;
+                        }
+                        nonce += self.threads as u64;
+                    }
+                });
+            }
+            // Only the workers hold senders now, so the channel closes once all gave up
+            drop(found_tx);
+
+            let result = loop {
+                match found.recv_timeout(PROGRESS_INTERVAL) {
+                    Ok(result) => break result,
+                    Err(mpsc::RecvTimeoutError::Timeout) => on_progress(MiningProgress {
+                        attempts: attempts.load(Ordering::Relaxed),
+                        best_difficulty: best_difficulty.load(Ordering::Relaxed),
+                        elapsed: started.elapsed(),
+                    }),
+                    Err(mpsc::RecvTimeoutError::Disconnected) => break Err(NostrError::Cancelled(
+                        "Mining was cancelled".to_string(),
+                    )),
+                }
+            };
+            stop.store(true, Ordering::Relaxed);
+            result
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nostr::keys::generate_keypair;
+
+    #[test]
+    fn test_difficulty() {
+        // Example from NIP-13
+        assert_eq!(difficulty("000000000e9d97a1ab09fc381030b346cdd7a142ad57e6df0b46dc9bef6c7e2d"), 36);
+        assert_eq!(difficulty("0f"), 4);
+        assert_eq!(difficulty("1f"), 3);
+        assert_eq!(difficulty("8f"), 0);
+        assert_eq!(difficulty(""), 0);
+    }
+
+    #[test]
+    fn test_mined_event_meets_its_committed_difficulty() {
+        let keypair = generate_keypair().unwrap();
+        let note = UnsignedEvent::new_text_note("It's just me mining my own business".to_string(), keypair.public_key_hex())
+            .with_tags(vec![vec!["nonce".to_string(), "1".to_string(), "1".to_string()]]);
+
+        let event = Miner::new(8).with_threads(2).mine(note, |_| {}).unwrap().sign(&keypair).unwrap();
+        assert!(difficulty(&event.id) >= 8);
+        assert_eq!(committed_difficulty(&event), Some(8));
+        assert_eq!(event.tags.len(), 1, "The old nonce tag is replaced");
+        assert!(event.verify());
+        assert!(meets_difficulty(&event, 8));
+        assert!(!meets_difficulty(&event, 9), "Committed to 8 bits only");
+    }
+
+    #[test]
+    fn test_lucky_ids_below_the_commitment_do_not_count() {
+        let keypair = generate_keypair().unwrap();
+        let mut event = NostrEvent::new_text_note("gm".to_string(), &keypair).unwrap();
+        event.id = format!("0000{}", &event.id[4..]);
+        event.tags = vec![vec!["nonce".to_string(), "42".to_string(), "4".to_string()]];
+        assert!(meets_difficulty(&event, 4));
+        assert!(!meets_difficulty(&event, 16), "Committed to 4 bits only");
+
+        event.tags.clear();
+        assert!(meets_difficulty(&event, 16));
+        assert!(meets_difficulty(&event, 0));
+    }
+
+    #[test]
+    fn test_cancelled_mining_gives_up() {
+        let keypair = generate_keypair().unwrap();
+        let miner = Miner::new(200).with_threads(2);
+        miner.cancel_handle().cancel();
+
+        let note = UnsignedEvent::new_text_note("never".to_string(), keypair.public_key_hex());
+        assert!(miner.mine(note, |_| {}).is_err());
+        assert!(Miner::new(257).mine(UnsignedEvent::new_text_note(String::new(), String::new()), |_| {}).is_err());
+    }
+}
@@ -0,0 +1,176 @@
+use crate::error::{NostrError, Result};
+
+use crate::nostr::event::{NostrEvent, UnsignedEvent};
+use crate::nostr::keys::NostrKeypair;
+use crate::nostr::nip59::UnwrappedGift;
+
+pub const KIND_PRIVATE_MESSAGE: u16 = 14;
+
+/// A decrypted kind-14 chat message
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PrivateMessage {
+    /// Id of the rumor, shared by every copy of the message
+    pub id: String,
+    pub sender: String,
+    /// Everyone the message was sent to, from its `p` tags
+    pub recipients: Vec<String>,
+    pub created_at: u64,
+    pub content: String,
+    /// Sent as a NIP-04 kind-4 event, which exposes sender, recipient and
+    /// timing to every relay that stores it
+    pub legacy: bool,
+}
+
+impl UnsignedEvent {
+    /// A kind-14 chat message addressed to `recipients`
+    pub fn new_private_message(content: String, sender_pubkey: String, recipients: &[String]) -> Self {
+        let tags = recipients
+            .iter()
+            .map(|recipient| vec!["p".to_string(), recipient.clone()])
+            .collect();
+
+        UnsignedEvent::new_text_note(content, sender_pubkey)
+            .with_kind(KIND_PRIVATE_MESSAGE)
+            .with_tags(tags)
+    }
+}
+
+/// Seal and gift wrap a chat message once for every recipient and once for the
+/// sender, so it shows up in the sender's other clients too. Returns each wrap
+/// with the public key it is addressed to, sender's copy last.
+pub fn wrap_private_message(
+    sender: &NostrKeypair,
+    recipients: &[String],
+    content: &str,
+) -> Result<Vec<(String, NostrEvent)>> {
+    if recipients.is_empty() {
+        return Err(NostrError::InvalidInput("A private message needs at least one recipient".to_string()));
+    }
+
+    let sender_pubkey = sender.public_key_hex();
+    let rumor = UnsignedEvent::new_private_message(content.to_string(), sender_pubkey.clone(), recipients)
+        .into_rumor()?;
+
+    let mut receivers: Vec<&String> = Vec::new();
+    for recipient in recipients {
+        if *recipient != sender_pubkey && !receivers.contains(&recipient) {
+            receivers.push(recipient);
+        }
+    }
+    receivers.push(&sender_pubkey);
+
+    receivers
+        .into_iter()
+        .map(|receiver| {
+            let seal = NostrEvent::seal(&rumor, sender, receiver)?;
+            Ok((receiver.clone(), NostrEvent::gift_wrap(&seal, receiver)?))
+        })
+        .collect()
+}
+
+impl PrivateMessage {
+    /// Read a chat message out of an opened gift wrap
+    pub fn from_gift(gift: UnwrappedGift) -> Result<Self> {
+        let rumor = gift.rumor;
+        if rumor.kind != KIND_PRIVATE_MESSAGE {
+            return Err(NostrError::MalformedEvent(format!(
+                "Expected a private message, got kind {}",
+                rumor.kind
+            )));
+        }
+
+        let recipients = rumor
+            .tags
+            .iter()
+            .filter(|tag| tag.len() >= 2 && tag[0] == "p")
+            .map(|tag| tag[1].clone())
+            .collect();
+
+        Ok(Self {
+            id: rumor.id,
+            sender: gift.sender,
+            recipients,
+            created_at: rumor.created_at,
+            content: rumor.content,
+            legacy: false,
+        })
+    }
+
+    /// The other members of the conversation as seen by `own_pubkey`, sorted.
+    /// NIP-17 defines a conversation by its full set of members, so this is
+    /// what messages are grouped by.
+    pub fn participants_except(&self, own_pubkey: &str) -> Vec<String> {
+        let mut participants: Vec<String> = std::iter::once(&self.sender)
+            .chain(&self.recipients)
+            .filter(|pubkey| *pubkey != own_pubkey)
+            .cloned()
+            .collect();
+        participants.sort();
+        participants.dedup();
+        participants
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nostr::keys::generate_keypair;
+    use crate::nostr::nip59::KIND_GIFT_WRAP;
+
+    #[test]
+    fn test_private_message_reaches_recipients_and_sender() {
+        let alice = generate_keypair().unwrap();
+        let bob = generate_keypair().unwrap();
+        let carol = generate_keypair().unwrap();
+        let recipients = vec![bob.public_key_hex(), carol.public_key_hex()];
+
+        let wraps = wrap_private_message(&alice, &recipients, "Lunch?").unwrap();
+        let receivers: Vec<&String> = wraps.iter().map(|(receiver, _)| receiver).collect();
+        assert_eq!(receivers, vec![&bob.public_key_hex(), &carol.public_key_hex(), &alice.public_key_hex()]);
+
+        let mut ids = Vec::new();
+        for ((receiver, wrap), keypair) in wraps.into_iter().zip([&bob, &carol, &alice]) {
+            assert_eq!(wrap.kind, KIND_GIFT_WRAP);
+            assert_eq!(wrap.tagged_pubkeys().collect::<Vec<_>>(), vec![receiver.as_str()]);
+
+            let message = PrivateMessage::from_gift(wrap.unwrap_gift(keypair).unwrap()).unwrap();
+            assert_eq!(message.sender, alice.public_key_hex());
+            assert_eq!(message.recipients, recipients);
+            assert_eq!(message.content, "Lunch?");
+            ids.push(message.id);
+        }
+
+        ids.dedup();
+        assert_eq!(ids.len(), 1, "Every copy carries the same rumor");
+    }
+
+    #[test]
+    fn test_conversation_participants() {
+        let message = PrivateMessage {
+            id: String::new(),
+            sender: "alice".to_string(),
+            recipients: vec!["carol".to_string(), "bob".to_string()],
+            created_at: 0,
+            content: String::new(),
+            legacy: false,
+        };
+
+        assert_eq!(message.participants_except("bob"), vec!["alice".to_string(), "carol".to_string()]);
+        assert_eq!(message.participants_except("alice"), vec!["bob".to_string(), "carol".to_string()]);
+    }
+
+    #[test]
+    fn test_only_chat_messages_are_accepted() {
+        let alice = generate_keypair().unwrap();
+        let bob = generate_keypair().unwrap();
+
+        let rumor = UnsignedEvent::new_text_note("not a DM".to_string(), alice.public_key_hex())
+            .into_rumor()
+            .unwrap();
+        let seal = NostrEvent::seal(&rumor, &alice, &bob.public_key_hex()).unwrap();
+        let wrap = NostrEvent::gift_wrap(&seal, &bob.public_key_hex()).unwrap();
+
+        assert!(PrivateMessage::from_gift(wrap.unwrap_gift(&bob).unwrap()).is_err());
+        assert!(wrap_private_message(&alice, &[], "nobody").is_err());
+    }
+}
@@ -0,0 +1,127 @@
+use crate::error::{NostrError, Result};
+
+use crate::nostr::event::{NostrEvent, UnsignedEvent};
+use crate::nostr::nip19::{EventPointer, Nip19};
+
+/// A repost of a text note
+pub const KIND_REPOST: u16 = 6;
+
+/// A repost of any other kind of event
+pub const KIND_GENERIC_REPOST: u16 = 16;
+
+/// Id of the event a repost reposts: its last `e` tag
+pub fn reposted_event_id(event: &NostrEvent) -> Option<&str> {
+    event
+        .tags
+        .iter()
+        .rev()
+        .find(|tag| tag.len() >= 2 && tag[0] == "e")
+        .map(|tag| tag[1].as_str())
+}
+
+impl UnsignedEvent {
+    /// A repost (NIP-18) of `target`. The original is embedded as JSON, so
+    /// clients can show it without fetching it. `relay_url` is where it can
+    /// be found.
+    pub fn new_repost(target: &NostrEvent, relay_url: Option<&str>, pubkey: String) -> Result<Self> {
+        let relay_url = relay_url.unwrap_or_default().to_string();
+        let mut tags = vec![
+            vec!["e".to_string(), target.id.clone(), relay_url.clone()],
+            vec!["p".to_string(), target.pubkey.clone(), relay_url],
+        ];
+
+        let kind = if target.kind == 1 {
+            KIND_REPOST
+        } else {
+            tags.push(vec!["k".to_string(), target.kind.to_string()]);
+            KIND_GENERIC_REPOST
+        };
+
+        Ok(UnsignedEvent::new_text_note(serde_json::to_string(target)?, pubkey)
+            .with_kind(kind)
+            .with_tags(tags))
+    }
+
+    /// Quote `quoted` in this note: a `q` tag, so the quote isn't mistaken for
+    /// a reply, and a `nostr:nevent` link at the end of the content for
+    /// clients to show it inline
+    pub fn with_quote(mut self, quoted: &NostrEvent, relay_url: Option<&str>) -> Result<Self> {
+        let nevent = Nip19::Event(EventPointer {
+            id: quoted.id.clone(),
+            relays: relay_url.map(str::to_string).into_iter().collect(),
+            author: Some(quoted.pubkey.clone()),
+            kind: Some(quoted.kind),
+        })
+        .to_bech32()
+        .map_err(|e| NostrError::InvalidEncoding(format!("Failed to encode quoted event: {}", e)))?;
+
+        self.tags.push(vec![
+            "q".to_string(),
+            quoted.id.clone(),
+            relay_url.unwrap_or_default().to_string(),
+            quoted.pubkey.clone(),
+        ]);
+        let tagged = self.tags.iter().any(|tag| tag.len() >= 2 && tag[0] == "p" && tag[1] == quoted.pubkey);
+        if quoted.pubkey != self.pubkey && !tagged {
+            self.tags.push(vec!["p".to_string(), quoted.pubkey.clone()]);
+        }
+
+        let content = self.content.trim_end();
+        self.content = if content.is_empty() {
+            format!("nostr:{}", nevent)
+        } else {
+            format!("{}\n\nnostr:{}", content, nevent)
+        };
+        Ok(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nostr::keys::generate_keypair;
+
+    #[test]
+    fn test_repost_embeds_the_original() {
+        let alice = generate_keypair().unwrap();
+        let bob = generate_keypair().unwrap();
+        let note = NostrEvent::new_text_note("gm".to_string(), &alice).unwrap();
+
+        let repost = UnsignedEvent::new_repost(&note, Some("wss://relay.example.com"), bob.public_key_hex())
+            .unwrap()
+            .sign(&bob)
+            .unwrap();
+        assert_eq!(repost.kind, KIND_REPOST);
+        assert_eq!(reposted_event_id(&repost), Some(note.id.as_str()));
+        assert_eq!(serde_json::from_str::<NostrEvent>(&repost.content).unwrap(), note);
+
+        let article = UnsignedEvent::new_text_note("long form".to_string(), alice.public_key_hex())
+            .with_kind(30023)
+            .sign(&alice)
+            .unwrap();
+        let repost = UnsignedEvent::new_repost(&article, None, bob.public_key_hex()).unwrap();
+        assert_eq!(repost.kind, KIND_GENERIC_REPOST);
+        assert!(repost.tags.contains(&vec!["k".to_string(), "30023".to_string()]));
+    }
+
+    #[test]
+    fn test_quote_tags_and_links_the_quoted_note() {
+        let alice = generate_keypair().unwrap();
+        let bob = generate_keypair().unwrap();
+        let note = NostrEvent::new_text_note("gm".to_string(), &alice).unwrap();
+
+        let quote = UnsignedEvent::new_text_note("so true".to_string(), bob.public_key_hex())
+            .with_quote(&note, None)
+            .unwrap();
+        assert_eq!(quote.tags, vec![
+            vec!["q".to_string(), note.id.clone(), String::new(), alice.public_key_hex()],
+            vec!["p".to_string(), alice.public_key_hex()],
+        ]);
+
+        let link = quote.content.strip_prefix("so true\n\nnostr:").unwrap();
+        match Nip19::from_bech32(link).unwrap() {
+            Nip19::Event(pointer) => assert_eq!(pointer.id, note.id),
+            other => panic!("Expected an nevent, got {:?}", other),
+        }
+    }
+}
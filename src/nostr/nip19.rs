@@ -0,0 +1,386 @@
+use crate::error::{NostrError, Result};
+use bech32::{Bech32, Hrp};
+
+/// TLV types used by the shareable identifiers
+const TLV_SPECIAL: u8 = 0;
+const TLV_RELAY: u8 = 1;
+const TLV_AUTHOR: u8 = 2;
+const TLV_KIND: u8 = 3;
+
+/// A NIP-19 bech32 entity. Keys and ids are kept as lowercase hex, the form
+/// the rest of the crate works with.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Nip19 {
+    /// `npub`: a public key
+    Pubkey(String),
+    /// `nsec`: a secret key
+    SecretKey(String),
+    /// `note`: an event id
+    Note(String),
+    /// `nprofile`: a public key with relays it can be found on
+    Profile(ProfilePointer),
+    /// `nevent`: an event id with optional relays, author and kind
+    Event(EventPointer),
+    /// `naddr`: an addressable event coordinate
+    Address(AddressPointer),
+    /// `nrelay`: a relay URL (deprecated by NIP-19, decoded for compatibility)
+    Relay(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProfilePointer {
+    pub pubkey: String,
+    pub relays: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EventPointer {
+    pub id: String,
+    pub relays: Vec<String>,
+    pub author: Option<String>,
+    pub kind: Option<u16>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AddressPointer {
+    /// The event's `d` tag
+    pub identifier: String,
+    pub pubkey: String,
+    pub kind: u16,
+    pub relays: Vec<String>,
+}
+
+impl Nip19 {
+    /// The bech32 prefix of this entity
+    pub fn prefix(&self) -> &'static str {
+        match self {
+            Nip19::Pubkey(_) => "npub",
+            Nip19::SecretKey(_) => "nsec",
+            Nip19::Note(_) => "note",
+            Nip19::Profile(_) => "nprofile",
+            Nip19::Event(_) => "nevent",
+            Nip19::Address(_) => "naddr",
+            Nip19::Relay(_) => "nrelay",
+        }
+    }
+
+    pub fn to_bech32(&self) -> Result<String> {
+        let data = match self {
+            Nip19::Pubkey(hex) | Nip19::SecretKey(hex) | Nip19::Note(hex) => decode_hex_32(hex)?.to_vec(),
+            Nip19::Profile(profile) => {
+                let mut tlv = Tlv::default();
+                tlv.push(TLV_SPECIAL, &decode_hex_32(&profile.pubkey)?)?;
+                tlv.push_relays(&profile.relays)?;
+                tlv.0
+            }
+            Nip19::Event(event) => {
+                let mut tlv = Tlv::default();
+                tlv.push(TLV_SPECIAL, &decode_hex_32(&event.id)?)?;
+                tlv.push_relays(&event.relays)?;
+                if let Some(author) = &event.author {
+                    tlv.push(TLV_AUTHOR, &decode_hex_32(author)?)?;
+                }
+                if let Some(kind) = event.kind {
+                    tlv.push(TLV_KIND, &u32::from(kind).to_be_bytes())?;
+                }
+                tlv.0
+            }
+            Nip19::Address(address) => {
+                let mut tlv = Tlv::default();
+                tlv.push(TLV_SPECIAL, address.identifier.as_bytes())?;
+                tlv.push_relays(&address.relays)?;
+                tlv.push(TLV_AUTHOR, &decode_hex_32(&address.pubkey)?)?;
+                tlv.push(TLV_KIND, &u32::from(address.kind).to_be_bytes())?;
+                tlv.0
+            }
+            Nip19::Relay(url) => {
+                let mut tlv = Tlv::default();
+                tlv.push(TLV_SPECIAL, url.as_bytes())?;
+                tlv.0
+            }
+        };
+
+        let hrp = Hrp::parse(self.prefix())
+            .map_err(|e| NostrError::InvalidEncoding(format!("Invalid HRP: {}", e)))?;
+        bech32::encode::<Bech32>(hrp, &data)
+            .map_err(|e| NostrError::InvalidEncoding(format!("Bech32 encoding failed: {}", e)))
+    }
+
+    /// Decode a bech32 entity, with or without a NIP-21 `nostr:` prefix
+    pub fn from_bech32(input: &str) -> Result<Self> {
+        let input = input.trim();
+        let input = input.strip_prefix("nostr:").unwrap_or(input);
+
+        let (hrp, data) = bech32::decode(input)
+            .map_err(|e| NostrError::InvalidEncoding(format!("Invalid bech32 string: {}", e)))?;
+
+        match hrp.as_str() {
+            "npub" => Ok(Nip19::Pubkey(hex_32(&data, "public key")?)),
+            "nsec" => Ok(Nip19::SecretKey(hex_32(&data, "secret key")?)),
+            "note" => Ok(Nip19::Note(hex_32(&data, "event id")?)),
+            "nprofile" => {
+                let entries = parse_tlv(&data)?;
+                Ok(Nip19::Profile(ProfilePointer {
+                    pubkey: hex_32(required(&entries, TLV_SPECIAL, "public key")?, "public key")?,
+                    relays: relays(&entries)?,
+                }))
+            }
+            "nevent" => {
+                let entries = parse_tlv(&data)?;
+                Ok(Nip19::Event(EventPointer {
+                    id: hex_32(required(&entries, TLV_SPECIAL, "event id")?, "event id")?,
+                    relays: relays(&entries)?,
+                    author: first(&entries, TLV_AUTHOR).map(|value| hex_32(value, "author")).transpose()?,
+                    kind: first(&entries, TLV_KIND).map(parse_kind).transpose()?,
+                }))
+            }
+            "naddr" => {
+                let entries = parse_tlv(&data)?;
+                Ok(Nip19::Address(AddressPointer {
+                    identifier: utf8(required(&entries, TLV_SPECIAL, "identifier")?)?,
+                    pubkey: hex_32(required(&entries, TLV_AUTHOR, "author")?, "author")?,
+                    kind: parse_kind(required(&entries, TLV_KIND, "kind")?)?,
+                    relays: relays(&entries)?,
+                }))
+            }
+            "nrelay" => {
+                let entries = parse_tlv(&data)?;
+                Ok(Nip19::Relay(utf8(required(&entries, TLV_SPECIAL, "relay URL")?)?))
+            }
+            other => Err(NostrError::InvalidEncoding(format!("Unsupported NIP-19 prefix: {}", other))),
+        }
+    }
+}
+
+/// Parse a public key given as hex, `npub` or `nprofile`, returning hex
+pub fn public_key_from_str(input: &str) -> Result<String> {
+    let input = input.trim();
+    if is_hex_32(input) {
+        return Ok(input.to_lowercase());
+    }
+
+    match Nip19::from_bech32(input)? {
+        Nip19::Pubkey(pubkey) => Ok(pubkey),
+        Nip19::Profile(profile) => Ok(profile.pubkey),
+        other => Err(NostrError::InvalidPublicKey(format!("Expected a public key, got {}", other.prefix()))),
+    }
+}
+
+/// Parse an event id given as hex, `note` or `nevent`, returning hex
+pub fn event_id_from_str(input: &str) -> Result<String> {
+    let input = input.trim();
+    if is_hex_32(input) {
+        return Ok(input.to_lowercase());
+    }
+
+    match Nip19::from_bech32(input)? {
+        Nip19::Note(id) => Ok(id),
+        Nip19::Event(event) => Ok(event.id),
+        other => Err(NostrError::InvalidEventId(format!("Expected an event id, got {}", other.prefix()))),
+    }
+}
+
+/// TLV entries in the order they were found
+type TlvEntries<'a> = Vec<(u8, &'a [u8])>;
+
+#[derive(Default)]
+struct Tlv(Vec<u8>);
+
+impl Tlv {
+    fn push(&mut self, kind: u8, value: &[u8]) -> Result<()> {
+        let length = u8::try_from(value.len())
+            .map_err(|_| NostrError::InvalidEncoding("TLV value longer than 255 bytes".to_string()))?;
+        self.0.push(kind);
+        self.0.push(length);
+        self.0.extend_from_slice(value);
+        Ok(())
+    }
+
+    fn push_relays(&mut self, relays: &[String]) -> Result<()> {
+        for relay in relays {
+            self.push(TLV_RELAY, relay.as_bytes())?;
+        }
+        Ok(())
+    }
+}
+
+fn parse_tlv(mut data: &[u8]) -> Result<TlvEntries<'_>> {
+    let mut entries = Vec::new();
+
+    while !data.is_empty() {
+        let [kind, length, rest @ ..] = data else {
+            return Err(NostrError::InvalidEncoding("Truncated TLV entry".to_string()));
+        };
+        let length = usize::from(*length);
+        if rest.len() < length {
+            return Err(NostrError::InvalidEncoding("Truncated TLV entry".to_string()));
+        }
+
+        entries.push((*kind, &rest[..length]));
+        data = &rest[length..];
+    }
+
+    Ok(entries)
+}
+
+fn first<'a>(entries: &TlvEntries<'a>, kind: u8) -> Option<&'a [u8]> {
+    entries.iter().find(|(entry_kind, _)| *entry_kind == kind).map(|(_, value)| *value)
+}
+
+fn required<'a>(entries: &TlvEntries<'a>, kind: u8, name: &str) -> Result<&'a [u8]> {
+    first(entries, kind).ok_or_else(|| NostrError::InvalidEncoding(format!("Missing {}", name)))
+}
+
+fn relays(entries: &TlvEntries<'_>) -> Result<Vec<String>> {
+    entries
+        .iter()
+        .filter(|(kind, _)| *kind == TLV_RELAY)
+        .map(|(_, value)| utf8(value))
+        .collect()
+}
+
+fn parse_kind(value: &[u8]) -> Result<u16> {
+    let bytes: [u8; 4] = value
+        .try_into()
+        .map_err(|_| NostrError::InvalidEncoding("Kind must be 4 bytes".to_string()))?;
+    u16::try_from(u32::from_be_bytes(bytes))
+        .map_err(|_| NostrError::InvalidEncoding("Kind out of range".to_string()))
+}
+
+fn utf8(value: &[u8]) -> Result<String> {
+    String::from_utf8(value.to_vec())
+        .map_err(|_| NostrError::InvalidEncoding("TLV value is not valid UTF-8".to_string()))
+}
+
+fn hex_32(value: &[u8], name: &str) -> Result<String> {
+    if value.len() != 32 {
+        return Err(NostrError::InvalidEncoding(format!("Invalid {} length: {} bytes", name, value.len())));
+    }
+    Ok(hex::encode(value))
+}
+
+fn decode_hex_32(value: &str) -> Result<[u8; 32]> {
+    hex::decode(value)?
+        .try_into()
+        .map_err(|_| NostrError::InvalidEncoding(format!("Expected 32 bytes of hex: {}", value)))
+}
+
+fn is_hex_32(value: &str) -> bool {
+    value.len() == 64 && value.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Examples from the NIP-19 specification
+    const NPUB: &str = "npub10elfcs4fr0l0r8af98jlmgdh9c8tcxjvz9qkw038js35mp4dma8qzvjptg";
+    const NPUB_HEX: &str = "7e7e9c42a91bfef19fa929e5fda1b72e0ebc1a4c1141673e2794234d86addf4e";
+    const NSEC: &str = "nsec1vl029mgpspedva04g90vltkh6fvh240zqtv9k0t9af8935ke9laqsnlfe5";
+    const NSEC_HEX: &str = "67dea2ed018072d675f5415ecfaed7d2597555e202d85b3d65ea4e58d2d92ffa";
+    const NPROFILE: &str = "nprofile1qqsrhuxx8l9ex335q7he0f09aej04zpazpl0ne2cgukyawd24mayt8gpp4mhxue69uhhytnc9e3k7mgpz4mhxue69uhkg6nzv9ejuumpv34kytnrdaksjlyr9p";
+
+    #[test]
+    fn test_specification_examples() {
+        assert_eq!(Nip19::from_bech32(NPUB).unwrap(), Nip19::Pubkey(NPUB_HEX.to_string()));
+        assert_eq!(Nip19::Pubkey(NPUB_HEX.to_string()).to_bech32().unwrap(), NPUB);
+
+        assert_eq!(Nip19::from_bech32(NSEC).unwrap(), Nip19::SecretKey(NSEC_HEX.to_string()));
+        assert_eq!(Nip19::SecretKey(NSEC_HEX.to_string()).to_bech32().unwrap(), NSEC);
+
+        let profile = Nip19::Profile(ProfilePointer {
+            pubkey: "3bf0c63fcb93463407af97a5e5ee64fa883d107ef9e558472c4eb9aaaefa459d".to_string(),
+            relays: vec!["wss://r.x.com".to_string(), "wss://djbas.sadkb.com".to_string()],
+        });
+        assert_eq!(Nip19::from_bech32(NPROFILE).unwrap(), profile);
+        assert_eq!(profile.to_bech32().unwrap(), NPROFILE);
+    }
+
+    #[test]
+    fn test_shareable_identifiers_round_trip() {
+        let entities = vec![
+            Nip19::Note(NPUB_HEX.to_string()),
+            Nip19::Event(EventPointer {
+                id: NSEC_HEX.to_string(),
+                relays: vec!["wss://relay.damus.io".to_string(), "wss://nos.lol".to_string()],
+                author: Some(NPUB_HEX.to_string()),
+                kind: Some(1),
+            }),
+            Nip19::Event(EventPointer {
+                id: NSEC_HEX.to_string(),
+                relays: vec![],
+                author: None,
+                kind: None,
+            }),
+            Nip19::Address(AddressPointer {
+                identifier: "my-article".to_string(),
+                pubkey: NPUB_HEX.to_string(),
+                kind: 30023,
+                relays: vec!["wss://relay.example.com".to_string()],
+            }),
+            Nip19::Relay("wss://relay.example.com".to_string()),
+        ];
+
+        for entity in entities {
+            let encoded = entity.to_bech32().unwrap();
+            assert!(encoded.starts_with(entity.prefix()));
+            assert_eq!(Nip19::from_bech32(&encoded).unwrap(), entity);
+            assert_eq!(Nip19::from_bech32(&format!("nostr:{}", encoded)).unwrap(), entity);
+        }
+    }
+
+    #[test]
+    fn test_decoding_rejects_malformed_input() {
+        // Wrong length payload for a key
+        let short = bech32::encode::<Bech32>(Hrp::parse("npub").unwrap(), &[1, 2, 3]).unwrap();
+        assert!(Nip19::from_bech32(&short).is_err());
+
+        // Truncated TLV
+        let truncated = bech32::encode::<Bech32>(Hrp::parse("nprofile").unwrap(), &[0, 32, 1]).unwrap();
+        assert!(Nip19::from_bech32(&truncated).is_err());
+
+        // naddr without kind
+        let mut tlv = Tlv::default();
+        tlv.push(TLV_SPECIAL, b"id").unwrap();
+        tlv.push(TLV_AUTHOR, &decode_hex_32(NPUB_HEX).unwrap()).unwrap();
+        let naddr = bech32::encode::<Bech32>(Hrp::parse("naddr").unwrap(), &tlv.0).unwrap();
+        assert!(Nip19::from_bech32(&naddr).is_err());
+
+        // Bad checksum and unknown prefix
+        assert!(Nip19::from_bech32(&NPUB.replace('g', "h")).is_err());
+        let unknown = bech32::encode::<Bech32>(Hrp::parse("nfoo").unwrap(), &[0; 32]).unwrap();
+        assert!(Nip19::from_bech32(&unknown).is_err());
+    }
+
+    #[test]
+    fn test_unknown_tlv_entries_are_ignored() {
+        let mut tlv = Tlv::default();
+        tlv.push(TLV_SPECIAL, &decode_hex_32(NPUB_HEX).unwrap()).unwrap();
+        tlv.push(42, b"from the future").unwrap();
+        let nprofile = bech32::encode::<Bech32>(Hrp::parse("nprofile").unwrap(), &tlv.0).unwrap();
+
+        assert_eq!(
+            Nip19::from_bech32(&nprofile).unwrap(),
+            Nip19::Profile(ProfilePointer {
+                pubkey: NPUB_HEX.to_string(),
+                relays: vec![],
+            })
+        );
+    }
+
+    #[test]
+    fn test_public_key_and_event_id_parsing() {
+        assert_eq!(public_key_from_str(NPUB).unwrap(), NPUB_HEX);
+        assert_eq!(public_key_from_str(&NPUB_HEX.to_uppercase()).unwrap(), NPUB_HEX);
+        assert_eq!(
+            public_key_from_str(NPROFILE).unwrap(),
+            "3bf0c63fcb93463407af97a5e5ee64fa883d107ef9e558472c4eb9aaaefa459d"
+        );
+        assert!(public_key_from_str(NSEC).is_err(), "A secret key is not a public key");
+
+        let note = Nip19::Note(NSEC_HEX.to_string()).to_bech32().unwrap();
+        assert_eq!(event_id_from_str(&note).unwrap(), NSEC_HEX);
+        assert_eq!(event_id_from_str(NSEC_HEX).unwrap(), NSEC_HEX);
+        assert!(event_id_from_str(NPUB).is_err());
+    }
+}
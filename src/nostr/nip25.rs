@@ -0,0 +1,175 @@
+use crate::error::{NostrError, Result};
+
+use crate::nostr::event::{NostrEvent, UnsignedEvent};
+
+/// A reaction to another event
+pub const KIND_REACTION: u16 = 7;
+
+/// What a reaction (NIP-25) says about the event it reacts to
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Reaction {
+    /// `+`, or an empty content
+    Like,
+    /// `-`
+    Dislike,
+    /// Any other content, usually a single emoji
+    Emoji(String),
+    /// A custom emoji (NIP-30): `:shortcode:` in the content, and an `emoji`
+    /// tag with the image to show for it
+    CustomEmoji { shortcode: String, url: String },
+}
+
+impl Reaction {
+    /// Read `+`, `-` or an emoji. `:shortcode:` is a custom emoji and needs
+    /// the URL of its image.
+    pub fn parse(content: &str, emoji_url: Option<&str>) -> Result<Self> {
+        let content = content.trim();
+        if let Some(url) = emoji_url {
+            let shortcode = content.trim_matches(':');
+            if !is_shortcode(shortcode) {
+                return Err(NostrError::MalformedEvent(format!(
+                    "Custom emoji shortcodes are letters, digits and underscores, e.g. :soapbox:, got {}",
+                    content
+                )));
+            }
+            return Ok(Reaction::CustomEmoji {
+                shortcode: shortcode.to_string(),
+                url: url.to_string(),
+            });
+        }
+
+        Ok(match content {
+            "" | "+" => Reaction::Like,
+            "-" => Reaction::Dislike,
+            _ if content.len() > 2 && content.starts_with(':') && content.ends_with(':') => {
+                return Err(NostrError::InvalidInput(format!(
+                    "{} is a custom emoji and needs the URL of its image",
+                    content
+                )));
+            }
+            _ => Reaction::Emoji(content.to_string()),
+        })
+    }
+
+    /// Read a kind-7 event
+    pub fn from_event(event: &NostrEvent) -> Result<Self> {
+        if event.kind != KIND_REACTION {
+            return Err(NostrError::MalformedEvent(format!(
+                "Expected a kind {} reaction, got kind {}",
+                KIND_REACTION, event.kind
+            )));
+        }
+
+        let content = event.content.trim();
+        let shortcode = content.strip_prefix(':').and_then(|rest| rest.strip_suffix(':'));
+        let emoji_url = shortcode.and_then(|shortcode| {
+            event
+                .tags
+                .iter()
+                .find(|tag| tag.len() >= 3 && tag[0] == "emoji" && tag[1] == shortcode)
+                .map(|tag| tag[2].clone())
+        });
+
+        match (shortcode, emoji_url) {
+            (Some(shortcode), Some(url)) => Ok(Reaction::CustomEmoji {
+                shortcode: shortcode.to_string(),
+                url,
+            }),
+            _ => Reaction::parse(content, None).or_else(|_| Ok(Reaction::Emoji(content.to_string()))),
+        }
+    }
+
+    /// The reaction's event content
+    pub fn content(&self) -> String {
+        match self {
+            Reaction::Like => "+".to_string(),
+            Reaction::Dislike => "-".to_string(),
+            Reaction::Emoji(emoji) => emoji.clone(),
+            Reaction::CustomEmoji { shortcode, .. } => format!(":{}:", shortcode),
+        }
+    }
+}
+
+fn is_shortcode(shortcode: &str) -> bool {
+    !shortcode.is_empty() && shortcode.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Id of the event a reaction reacts to: its last `e` tag
+pub fn reacted_event_id(event: &NostrEvent) -> Option<&str> {
+    event
+        .tags
+        .iter()
+        .rev()
+        .find(|tag| tag.len() >= 2 && tag[0] == "e")
+        .map(|tag| tag[1].as_str())
+}
+
+impl UnsignedEvent {
+    /// A reaction to `target`, tagging it and its author. `relay_url` is
+    /// where the target can be found.
+    pub fn new_reaction(target: &NostrEvent, reaction: &Reaction, relay_url: Option<&str>, pubkey: String) -> Self {
+        let relay_url = relay_url.unwrap_or_default().to_string();
+        let mut tags = vec![
+            vec!["e".to_string(), target.id.clone(), relay_url.clone(), target.pubkey.clone()],
+            vec!["p".to_string(), target.pubkey.clone(), relay_url],
+            vec!["k".to_string(), target.kind.to_string()],
+        ];
+        if let Reaction::CustomEmoji { shortcode, url } = reaction {
+            tags.push(vec!["emoji".to_string(), shortcode.clone(), url.clone()]);
+        }
+
+        UnsignedEvent::new_text_note(reaction.content(), pubkey)
+            .with_kind(KIND_REACTION)
+            .with_tags(tags)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nostr::keys::generate_keypair;
+
+    #[test]
+    fn test_reactions_round_trip() {
+        let alice = generate_keypair().unwrap();
+        let bob = generate_keypair().unwrap();
+        let note = NostrEvent::new_text_note("gm".to_string(), &alice).unwrap();
+
+        let reactions = [
+            Reaction::parse("+", None).unwrap(),
+            Reaction::parse("-", None).unwrap(),
+            Reaction::parse("🤙", None).unwrap(),
+            Reaction::parse(":soapbox:", Some("https://example.com/soapbox.png")).unwrap(),
+        ];
+        assert_eq!(reactions[0], Reaction::Like);
+        assert_eq!(reactions[1], Reaction::Dislike);
+
+        for reaction in &reactions {
+            let event = UnsignedEvent::new_reaction(&note, reaction, Some("wss://relay.example.com"), bob.public_key_hex())
+                .sign(&bob)
+                .unwrap();
+            assert_eq!(event.kind, KIND_REACTION);
+            assert_eq!(reacted_event_id(&event), Some(note.id.as_str()));
+            assert!(event.tags.contains(&vec!["p".to_string(), alice.public_key_hex(), "wss://relay.example.com".to_string()]));
+            assert_eq!(&Reaction::from_event(&event).unwrap(), reaction);
+        }
+    }
+
+    #[test]
+    fn test_reaction_content_from_other_clients() {
+        let keypair = generate_keypair().unwrap();
+        let reaction = |content: &str, tags: Vec<Vec<String>>| {
+            let event = UnsignedEvent::new_text_note(content.to_string(), keypair.public_key_hex())
+                .with_kind(KIND_REACTION)
+                .with_tags(tags)
+                .sign(&keypair)
+                .unwrap();
+            Reaction::from_event(&event).unwrap()
+        };
+
+        assert_eq!(reaction("", vec![]), Reaction::Like, "Empty content is a like");
+        assert_eq!(reaction(":blob:", vec![]), Reaction::Emoji(":blob:".to_string()), "No emoji tag, shown as text");
+        assert!(Reaction::parse(":blob:", None).is_err());
+        assert!(Reaction::parse(":not a shortcode:", Some("https://example.com/x.png")).is_err());
+    }
+}
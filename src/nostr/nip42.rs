@@ -0,0 +1,54 @@
+use crate::error::Result;
+
+use crate::nostr::event::{NostrEvent, UnsignedEvent};
+use crate::nostr::keys::NostrKeypair;
+
+/// Ephemeral event proving control of a key to a relay; relays must not store it
+pub const KIND_CLIENT_AUTH: u16 = 22242;
+
+/// Prefix of OK and CLOSED messages refused until the client authenticates
+pub const AUTH_REQUIRED_PREFIX: &str = "auth-required:";
+
+impl NostrEvent {
+    /// Sign the answer to a relay's AUTH `challenge`
+    pub fn new_auth(challenge: &str, relay_url: &str, keypair: &NostrKeypair) -> Result<Self> {
+        UnsignedEvent::new_text_note(String::new(), keypair.public_key_hex())
+            .with_kind(KIND_CLIENT_AUTH)
+            .with_tags(vec![
+                vec!["relay".to_string(), relay_url.to_string()],
+                vec!["challenge".to_string(), challenge.to_string()],
+            ])
+            .sign(keypair)
+    }
+}
+
+/// Whether a relay refused a command because the client has not authenticated
+pub fn is_auth_required(message: &str) -> bool {
+    message.starts_with(AUTH_REQUIRED_PREFIX)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nostr::keys::generate_keypair;
+
+    #[test]
+    fn test_auth_event_answers_the_challenge() {
+        let keypair = generate_keypair().unwrap();
+        let event = NostrEvent::new_auth("c-123", "wss://relay.example.com", &keypair).unwrap();
+
+        assert_eq!(event.kind, KIND_CLIENT_AUTH);
+        assert_eq!(event.pubkey, keypair.public_key_hex());
+        assert_eq!(event.tag_value("relay"), Some("wss://relay.example.com"));
+        assert_eq!(event.tag_value("challenge"), Some("c-123"));
+        assert!(event.content.is_empty());
+        assert!(event.verify());
+    }
+
+    #[test]
+    fn test_auth_required_prefix() {
+        assert!(is_auth_required("auth-required: we only serve members"));
+        assert!(!is_auth_required("restricted: auth-required for this kind"));
+        assert!(!is_auth_required(""));
+    }
+}
@@ -0,0 +1,296 @@
+use crate::error::{NostrError, Result};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use chacha20::ChaCha20;
+use chacha20::cipher::{KeyIvInit, StreamCipher};
+use hkdf::Hkdf;
+use hmac::{Hmac, Mac};
+use secp256k1::SecretKey;
+use sha2::Sha256;
+
+use crate::nostr::keys::{NostrKeypair, shared_secret_x};
+
+/// Version byte of the only supported payload format
+const VERSION: u8 = 2;
+
+/// HKDF salt for deriving conversation keys
+const SALT: &[u8] = b"nip44-v2";
+
+const MIN_PLAINTEXT_SIZE: usize = 1;
+const MAX_PLAINTEXT_SIZE: usize = 65535;
+
+/// Bounds for the base64 payload and its decoded form, from the NIP-44 spec
+const MIN_PAYLOAD_SIZE: usize = 132;
+const MAX_PAYLOAD_SIZE: usize = 87472;
+const MIN_DECODED_SIZE: usize = 99;
+const MAX_DECODED_SIZE: usize = 65603;
+
+/// Shared secret for a pair of keys; identical in both directions
+pub type ConversationKey = [u8; 32];
+
+impl NostrKeypair {
+    /// Encrypt `plaintext` for `recipient_pubkey` (hex) as a NIP-44 v2 payload
+    #[allow(dead_code)]
+    pub fn nip44_encrypt(&self, recipient_pubkey: &str, plaintext: &str) -> Result<String> {
+        let conversation_key = conversation_key(&self.secret_key(), recipient_pubkey)?;
+        encrypt(&conversation_key, plaintext)
+    }
+
+    /// Decrypt a NIP-44 v2 payload from `sender_pubkey` (hex)
+    #[allow(dead_code)]
+    pub fn nip44_decrypt(&self, sender_pubkey: &str, payload: &str) -> Result<String> {
+        let conversation_key = conversation_key(&self.secret_key(), sender_pubkey)?;
+        decrypt(&conversation_key, payload)
+    }
+}
+
+/// Derive the conversation key: HKDF-extract over the unhashed x coordinate
+/// of the ECDH shared point
+pub fn conversation_key(secret_key: &SecretKey, pubkey_hex: &str) -> Result<ConversationKey> {
+    let shared_x = shared_secret_x(secret_key, pubkey_hex)?;
+    let (conversation_key, _) = Hkdf::<Sha256>::extract(Some(SALT), &shared_x);
+
+    Ok(conversation_key.into())
+}
+
+/// Encrypt with a fresh random nonce
+pub fn encrypt(conversation_key: &ConversationKey, plaintext: &str) -> Result<String> {
+    let nonce: [u8; 32] = rand::random();
+    encrypt_with_nonce(conversation_key, plaintext, &nonce)
+}
+
+/// Encrypt with a caller-chosen nonce. Only for reproducing test vectors; a
+/// nonce must never be reused with the same conversation key.
+pub fn encrypt_with_nonce(conversation_key: &ConversationKey, plaintext: &str, nonce: &[u8; 32]) -> Result<String> {
+    let (chacha_key, chacha_nonce, hmac_key) = message_keys(conversation_key, nonce)?;
+
+    let mut ciphertext = pad(plaintext)?;
+    ChaCha20::new(&chacha_key.into(), &chacha_nonce.into()).apply_keystream(&mut ciphertext);
+
+    let mac = hmac_aad(&hmac_key, nonce, &ciphertext)?;
+
+    let mut payload = Vec::with_capacity(1 + nonce.len() + ciphertext.len() + mac.len());
+    payload.push(VERSION);
+    payload.extend_from_slice(nonce);
+    payload.extend_from_slice(&ciphertext);
+    payload.extend_from_slice(&mac);
+
+    Ok(BASE64.encode(payload))
+}
+
+pub fn decrypt(conversation_key: &ConversationKey, payload: &str) -> Result<String> {
+    if payload.starts_with('#') {
+        return Err(NostrError::DecryptionFailed("Unsupported encryption version".to_string()));
+    }
+    if !(MIN_PAYLOAD_SIZE..=MAX_PAYLOAD_SIZE).contains(&payload.len()) {
+        return Err(NostrError::DecryptionFailed(format!("Invalid payload size: {}", payload.len())));
+    }
+
+    let data = BASE64
+        .decode(payload)
+        .map_err(|e| NostrError::InvalidEncoding(format!("Invalid base64: {}", e)))?;
+    if !(MIN_DECODED_SIZE..=MAX_DECODED_SIZE).contains(&data.len()) {
+        return Err(NostrError::DecryptionFailed(format!("Invalid data size: {}", data.len())));
+    }
+    if data[0] != VERSION {
+        return Err(NostrError::DecryptionFailed(format!("Unknown encryption version: {}", data[0])));
+    }
+
+    let nonce: [u8; 32] = data[1..33].try_into()?;
+    let (ciphertext, mac) = data[33..].split_at(data.len() - 33 - 32);
+
+    let (chacha_key, chacha_nonce, hmac_key) = message_keys(conversation_key, &nonce)?;
+
+    let mut verifier = Hmac::<Sha256>::new_from_slice(&hmac_key)?;
+    verifier.update(&nonce);
+    verifier.update(ciphertext);
+    verifier.verify_slice(mac).map_err(|_| NostrError::DecryptionFailed("Invalid MAC".to_string()))?;
+
+    let mut padded = ciphertext.to_vec();
+    ChaCha20::new(&chacha_key.into(), &chacha_nonce.into()).apply_keystream(&mut padded);
+
+    unpad(&padded)
+}
+
+/// Length after padding: 32 bytes minimum, then chunks that grow with the
+/// message so the exact length is hidden
+pub fn calc_padded_len(unpadded_len: usize) -> usize {
+    if unpadded_len <= 32 {
+        return 32;
+    }
+
+    let next_power = 1 << (usize::BITS - (unpadded_len - 1).leading_zeros());
+    let chunk = if next_power <= 256 { 32 } else { next_power / 8 };
+
+    chunk * ((unpadded_len - 1) / chunk + 1)
+}
+
+/// Per-message keys: ChaCha20 key, ChaCha20 nonce and HMAC key
+fn message_keys(conversation_key: &ConversationKey, nonce: &[u8; 32]) -> Result<([u8; 32], [u8; 12], [u8; 32])> {
+    let hkdf = Hkdf::<Sha256>::from_prk(conversation_key)
+        .map_err(|_| NostrError::CryptographicError("Invalid conversation key".to_string()))?;
+
+    let mut keys = [0u8; 76];
+    hkdf.expand(nonce, &mut keys)
+        .map_err(|_| NostrError::CryptographicError("Key expansion failed".to_string()))?;
+
+    Ok((keys[0..32].try_into()?, keys[32..44].try_into()?, keys[44..76].try_into()?))
+}
+
+fn hmac_aad(hmac_key: &[u8; 32], aad: &[u8; 32], message: &[u8]) -> Result<[u8; 32]> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(hmac_key)?;
+    mac.update(aad);
+    mac.update(message);
+    Ok(mac.finalize().into_bytes().into())
+}
+
+fn pad(plaintext: &str) -> Result<Vec<u8>> {
+    let unpadded = plaintext.as_bytes();
+    if !(MIN_PLAINTEXT_SIZE..=MAX_PLAINTEXT_SIZE).contains(&unpadded.len()) {
+        return Err(NostrError::InvalidInput(format!("Invalid plaintext length: {}", unpadded.len())));
+    }
+
+    let mut padded = Vec::with_capacity(2 + calc_padded_len(unpadded.len()));
+    padded.extend_from_slice(&(unpadded.len() as u16).to_be_bytes());
+    padded.extend_from_slice(unpadded);
+    padded.resize(2 + calc_padded_len(unpadded.len()), 0);
+
+    Ok(padded)
+}
+
+fn unpad(padded: &[u8]) -> Result<String> {
+    let unpadded_len = usize::from(u16::from_be_bytes([padded[0], padded[1]]));
+
+    if unpadded_len < MIN_PLAINTEXT_SIZE || padded.len() != 2 + calc_padded_len(unpadded_len) {
+        return Err(NostrError::DecryptionFailed("Invalid padding".to_string()));
+    }
+
+    String::from_utf8(padded[2..2 + unpadded_len].to_vec())
+        .map_err(|_| NostrError::DecryptionFailed("Plaintext is not valid UTF-8".to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nostr::keys::{generate_keypair, keypair_from_hex};
+
+    fn secret_key(hex_key: &str) -> SecretKey {
+        keypair_from_hex(hex_key).unwrap().secret_key()
+    }
+
+    fn pubkey_of(hex_key: &str) -> String {
+        keypair_from_hex(hex_key).unwrap().public_key_hex()
+    }
+
+    fn decode_32(hex_value: &str) -> [u8; 32] {
+        hex::decode(hex_value).unwrap().try_into().unwrap()
+    }
+
+    // Vectors from the official NIP-44 test suite (nip44.vectors.json, v2)
+
+    #[test]
+    fn test_get_conversation_key_vectors() {
+        let vectors = [
+            (
+                "315e59ff51cb9209768cf7da80791ddcaae56ac9775eb25b6dee1234bc5d2268",
+                "c2f9d9948dc8c7c38321e4b85c8558872eafa0641cd269db76848a6073e69133",
+                "3dfef0ce2a4d80a25e7a328accf73448ef67096f65f79588e358d9a0eb9013f1",
+            ),
+            (
+                "a1e37752c9fdc1273be53f68c5f74be7c8905728e8de75800b94262f9497c86e",
+                "03bb7947065dde12ba991ea045132581d0954f042c84e06d8c00066e23c1a800",
+                "4d14f36e81b8452128da64fe6f1eae873baae2f444b02c950b90e43553f2178b",
+            ),
+        ];
+
+        for (sec1, pub2, expected) in vectors {
+            let key = conversation_key(&secret_key(sec1), pub2).unwrap();
+            assert_eq!(hex::encode(key), expected);
+        }
+    }
+
+    #[test]
+    fn test_encrypt_decrypt_vectors() {
+        let vectors = [
+            (
+                "0000000000000000000000000000000000000000000000000000000000000001",
+                "0000000000000000000000000000000000000000000000000000000000000002",
+                "c41c775356fd92eadc63ff5a0dc1da211b268cbea22316767095b2871ea1412d",
+                "0000000000000000000000000000000000000000000000000000000000000001",
+                "a",
+                "AgAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAABee0G5VSK0/9YypIObAtDKfYEAjD35uVkHyB0F4DwrcNaCXlCWZKaArsGrY6M9wnuTMxWfp1RTN9Xga8no+kF5Vsb",
+            ),
+            (
+                "0000000000000000000000000000000000000000000000000000000000000002",
+                "0000000000000000000000000000000000000000000000000000000000000001",
+                "c41c775356fd92eadc63ff5a0dc1da211b268cbea22316767095b2871ea1412d",
+                "f00000000000000000000000000000f00000000000000000000000000000000f",
+                "🍕🫃",
+                "AvAAAAAAAAAAAAAAAAAAAPAAAAAAAAAAAAAAAAAAAAAPSKSK6is9ngkX2+cSq85Th16oRTISAOfhStnixqZziKMDvB0QQzgFZdjLTPicCJaV8nDITO+QfaQ61+KbWQIOO2Yj",
+            ),
+        ];
+
+        for (sec1, sec2, expected_key, nonce, plaintext, payload) in vectors {
+            let key = conversation_key(&secret_key(sec1), &pubkey_of(sec2)).unwrap();
+            assert_eq!(hex::encode(key), expected_key);
+
+            // The key is the same in both directions
+            assert_eq!(conversation_key(&secret_key(sec2), &pubkey_of(sec1)).unwrap(), key);
+
+            assert_eq!(encrypt_with_nonce(&key, plaintext, &decode_32(nonce)).unwrap(), payload);
+            assert_eq!(decrypt(&key, payload).unwrap(), plaintext);
+        }
+    }
+
+    #[test]
+    fn test_calc_padded_len_vectors() {
+        let vectors = [
+            (16, 32), (32, 32), (33, 64), (37, 64), (45, 64), (49, 64), (64, 64), (65, 96),
+            (100, 128), (111, 128), (200, 224), (250, 256), (320, 320), (383, 384), (384, 384),
+            (400, 448), (500, 512), (512, 512), (515, 640), (700, 768), (800, 896), (900, 1024),
+            (1020, 1024), (65536, 65536),
+        ];
+
+        for (unpadded, padded) in vectors {
+            assert_eq!(calc_padded_len(unpadded), padded, "padded length of {}", unpadded);
+        }
+    }
+
+    #[test]
+    fn test_keypair_round_trip() {
+        let alice = generate_keypair().unwrap();
+        let bob = generate_keypair().unwrap();
+
+        for message in ["x", "Hello, Bob!", &"long message ".repeat(5000)] {
+            let payload = alice.nip44_encrypt(&bob.public_key_hex(), message).unwrap();
+            assert_eq!(bob.nip44_decrypt(&alice.public_key_hex(), &payload).unwrap(), message);
+        }
+
+        let eve = generate_keypair().unwrap();
+        let payload = alice.nip44_encrypt(&bob.public_key_hex(), "secret").unwrap();
+        assert!(eve.nip44_decrypt(&alice.public_key_hex(), &payload).is_err());
+    }
+
+    #[test]
+    fn test_invalid_payloads_are_rejected() {
+        let key = decode_32("c41c775356fd92eadc63ff5a0dc1da211b268cbea22316767095b2871ea1412d");
+        let payload = encrypt_with_nonce(&key, "hello", &[7; 32]).unwrap();
+
+        // Tampered ciphertext fails the MAC check
+        let mut data = BASE64.decode(&payload).unwrap();
+        data[40] ^= 1;
+        assert!(decrypt(&key, &BASE64.encode(&data)).is_err());
+
+        // Unknown versions
+        let mut data = BASE64.decode(&payload).unwrap();
+        data[0] = 1;
+        assert!(decrypt(&key, &BASE64.encode(&data)).is_err());
+        assert!(decrypt(&key, &format!("#{}", &payload[1..])).is_err());
+
+        // Size limits on payload and plaintext
+        assert!(decrypt(&key, &payload[..MIN_PAYLOAD_SIZE - 1]).is_err());
+        assert!(encrypt(&key, "").is_err());
+        assert!(encrypt(&key, &"a".repeat(MAX_PLAINTEXT_SIZE + 1)).is_err());
+        assert!(encrypt(&key, &"a".repeat(MAX_PLAINTEXT_SIZE)).is_ok());
+    }
+}
@@ -0,0 +1,224 @@
+use crate::error::{NostrError, Result};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::nostr::event::{NostrEvent, UnsignedEvent};
+use crate::nostr::keys::{NostrKeypair, generate_keypair};
+
+pub const KIND_SEAL: u16 = 13;
+pub const KIND_GIFT_WRAP: u16 = 1059;
+
+/// Seals and wraps are backdated by up to two days so their timestamps don't
+/// reveal when the message was actually sent
+const MAX_TIMESTAMP_TWEAK_SECS: u64 = 2 * 24 * 60 * 60;
+
+/// An unsigned event with its id: the innermost layer of a gift wrap. It is
+/// never signed, so a leaked rumor cannot be proven to come from its author.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Rumor {
+    pub id: String,
+    pub pubkey: String,
+    pub created_at: u64,
+    pub kind: u16,
+    pub tags: Vec<Vec<String>>,
+    pub content: String,
+}
+
+/// A gift wrap opened by its recipient
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnwrappedGift {
+    /// Id of the kind-1059 wrap it arrived in
+    pub wrap_id: String,
+    /// Author of the rumor, proven by the seal's signature
+    pub sender: String,
+    pub rumor: Rumor,
+}
+
+impl UnsignedEvent {
+    /// Compute the id and turn this event into a rumor that can be sealed
+    pub fn into_rumor(self) -> Result<Rumor> {
+        Ok(Rumor {
+            id: self.calculate_id()?,
+            pubkey: self.pubkey,
+            created_at: self.created_at,
+            kind: self.kind,
+            tags: self.tags,
+            content: self.content,
+        })
+    }
+}
+
+impl Rumor {
+    /// Whether the id matches the rumor's content
+    pub fn has_valid_id(&self) -> bool {
+        let unsigned = UnsignedEvent {
+            pubkey: self.pubkey.clone(),
+            created_at: self.created_at,
+            kind: self.kind,
+            tags: self.tags.clone(),
+            content: self.content.clone(),
+        };
+
+        unsigned.calculate_id().is_ok_and(|id| id == self.id)
+    }
+}
+
+impl NostrEvent {
+    /// Encrypt a rumor to `recipient_pubkey` in a kind-13 seal signed by its author
+    pub fn seal(rumor: &Rumor, sender: &NostrKeypair, recipient_pubkey: &str) -> Result<NostrEvent> {
+        if rumor.pubkey != sender.public_key_hex() {
+            return Err(NostrError::InvalidInput("Only the author of a rumor can seal it".to_string()));
+        }
+
+        let content = sender.nip44_encrypt(recipient_pubkey, &serde_json::to_string(rumor)?)?;
+
+        UnsignedEvent::new_text_note(content, sender.public_key_hex())
+            .with_kind(KIND_SEAL)
+            .with_timestamp(randomized_timestamp())
+            .sign(sender)
+    }
+
+    /// Wrap a seal in a kind-1059 event signed by a throwaway key, so relays
+    /// only learn who the message is for
+    pub fn gift_wrap(seal: &NostrEvent, recipient_pubkey: &str) -> Result<NostrEvent> {
+        let ephemeral = generate_keypair()?;
+        let content = ephemeral.nip44_encrypt(recipient_pubkey, &serde_json::to_string(seal)?)?;
+
+        UnsignedEvent::new_text_note(content, ephemeral.public_key_hex())
+            .with_kind(KIND_GIFT_WRAP)
+            .with_tags(vec![vec!["p".to_string(), recipient_pubkey.to_string()]])
+            .with_timestamp(randomized_timestamp())
+            .sign(&ephemeral)
+    }
+
+    /// Open a gift wrap addressed to `recipient`, checking both signatures and
+    /// that the seal's signer is the rumor's author
+    pub fn unwrap_gift(&self, recipient: &NostrKeypair) -> Result<UnwrappedGift> {
+        if self.kind != KIND_GIFT_WRAP {
+            return Err(NostrError::MalformedEvent(format!("Expected a gift wrap, got kind {}", self.kind)));
+        }
+        if !self.verify() {
+            return Err(NostrError::MalformedEvent("Gift wrap has an invalid id or signature".to_string()));
+        }
+
+        let seal: NostrEvent = serde_json::from_str(&recipient.nip44_decrypt(&self.pubkey, &self.content)?)?;
+        if seal.kind != KIND_SEAL {
+            return Err(NostrError::MalformedEvent(format!("Expected a seal, got kind {}", seal.kind)));
+        }
+        if !seal.verify() {
+            return Err(NostrError::MalformedEvent("Seal has an invalid id or signature".to_string()));
+        }
+
+        let rumor: Rumor = serde_json::from_str(&recipient.nip44_decrypt(&seal.pubkey, &seal.content)?)?;
+        if rumor.pubkey != seal.pubkey {
+            return Err(NostrError::MalformedEvent(
+                "Rumor author does not match the seal's signer".to_string(),
+            ));
+        }
+        if !rumor.has_valid_id() {
+            return Err(NostrError::MalformedEvent("Rumor has an invalid id".to_string()));
+        }
+
+        Ok(UnwrappedGift {
+            wrap_id: self.id.clone(),
+            sender: seal.pubkey,
+            rumor,
+        })
+    }
+
+    /// Public keys this event is addressed to through its `p` tags
+    pub fn tagged_pubkeys(&self) -> impl Iterator<Item = &str> {
+        self.tags
+            .iter()
+            .filter(|tag| tag.len() >= 2 && tag[0] == "p")
+            .map(|tag| tag[1].as_str())
+    }
+}
+
+/// A timestamp up to two days in the past
+fn randomized_timestamp() -> u64 {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0);
+
+    now.saturating_sub(rand::rng().random_range(0..=MAX_TIMESTAMP_TWEAK_SECS))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rumor(author: &NostrKeypair, content: &str) -> Rumor {
+        UnsignedEvent::new_text_note(content.to_string(), author.public_key_hex())
+            .with_kind(14)
+            .into_rumor()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_gift_wrap_round_trip() {
+        let sender = generate_keypair().unwrap();
+        let recipient = generate_keypair().unwrap();
+        let rumor = rumor(&sender, "Are you going to the party tonight?");
+
+        let seal = NostrEvent::seal(&rumor, &sender, &recipient.public_key_hex()).unwrap();
+        let wrap = NostrEvent::gift_wrap(&seal, &recipient.public_key_hex()).unwrap();
+
+        assert_eq!(seal.kind, KIND_SEAL);
+        assert!(seal.tags.is_empty());
+        assert_eq!(wrap.kind, KIND_GIFT_WRAP);
+        assert_ne!(wrap.pubkey, sender.public_key_hex(), "Wraps are signed by a throwaway key");
+        assert_eq!(wrap.tagged_pubkeys().collect::<Vec<_>>(), vec![recipient.public_key_hex()]);
+
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+        for event in [&seal, &wrap] {
+            assert!(event.created_at <= now && event.created_at + MAX_TIMESTAMP_TWEAK_SECS >= now);
+        }
+
+        let gift = wrap.unwrap_gift(&recipient).unwrap();
+        assert_eq!(gift.wrap_id, wrap.id);
+        assert_eq!(gift.sender, sender.public_key_hex());
+        assert_eq!(gift.rumor, rumor);
+
+        let eavesdropper = generate_keypair().unwrap();
+        assert!(wrap.unwrap_gift(&eavesdropper).is_err());
+    }
+
+    #[test]
+    fn test_unwrap_rejects_impersonation() {
+        let sender = generate_keypair().unwrap();
+        let victim = generate_keypair().unwrap();
+        let recipient = generate_keypair().unwrap();
+
+        // A rumor claiming to be from someone else, sealed by the real sender
+        let forged = rumor(&victim, "It's me, honest");
+        let content = sender
+            .nip44_encrypt(&recipient.public_key_hex(), &serde_json::to_string(&forged).unwrap())
+            .unwrap();
+        let seal = UnsignedEvent::new_text_note(content, sender.public_key_hex())
+            .with_kind(KIND_SEAL)
+            .sign(&sender)
+            .unwrap();
+        let wrap = NostrEvent::gift_wrap(&seal, &recipient.public_key_hex()).unwrap();
+
+        assert!(wrap.unwrap_gift(&recipient).is_err());
+        assert!(NostrEvent::seal(&forged, &sender, &recipient.public_key_hex()).is_err());
+    }
+
+    #[test]
+    fn test_unwrap_rejects_tampered_wraps() {
+        let sender = generate_keypair().unwrap();
+        let recipient = generate_keypair().unwrap();
+        let seal = NostrEvent::seal(&rumor(&sender, "hi"), &sender, &recipient.public_key_hex()).unwrap();
+        let wrap = NostrEvent::gift_wrap(&seal, &recipient.public_key_hex()).unwrap();
+
+        let mut tampered = wrap.clone();
+        tampered.created_at += 1;
+        assert!(tampered.unwrap_gift(&recipient).is_err());
+
+        // A seal delivered without its wrap is not a gift wrap
+        assert!(seal.unwrap_gift(&recipient).is_err());
+    }
+}
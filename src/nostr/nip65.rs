@@ -0,0 +1,182 @@
+use crate::error::{NostrError, Result};
+use url::Url;
+
+use crate::nostr::event::{NostrEvent, UnsignedEvent};
+use crate::nostr::keys::NostrKeypair;
+
+/// Relay list metadata: the relays an author writes to and reads mentions from
+pub const KIND_RELAY_LIST: u16 = 10002;
+
+/// What an author uses a relay for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RelayUsage {
+    /// The author reads events mentioning them here
+    Read,
+    /// The author publishes here
+    Write,
+    Both,
+}
+
+impl RelayUsage {
+    pub fn is_read(&self) -> bool {
+        matches!(self, RelayUsage::Read | RelayUsage::Both)
+    }
+
+    pub fn is_write(&self) -> bool {
+        matches!(self, RelayUsage::Write | RelayUsage::Both)
+    }
+}
+
+/// An author's relay list (NIP-65)
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RelayList {
+    pub relays: Vec<(String, RelayUsage)>,
+    pub created_at: u64,
+}
+
+impl RelayList {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[allow(dead_code)]
+    pub fn with_relay(mut self, relay_url: &str, usage: RelayUsage) -> Result<Self> {
+        self.add_relay(relay_url, usage)?;
+        Ok(self)
+    }
+
+    /// Add a relay, merging its usage if it is already listed
+    pub fn add_relay(&mut self, relay_url: &str, usage: RelayUsage) -> Result<()> {
+        let relay_url = normalize_relay_url(relay_url)?;
+
+        match self.relays.iter_mut().find(|(url, _)| *url == relay_url) {
+            Some((_, existing)) if *existing != usage => *existing = RelayUsage::Both,
+            Some(_) => {}
+            None => self.relays.push((relay_url, usage)),
+        }
+        Ok(())
+    }
+
+    /// Read a kind-10002 event. Invalid relay URLs are skipped.
+    pub fn from_event(event: &NostrEvent) -> Result<Self> {
+        if event.kind != KIND_RELAY_LIST {
+            return Err(NostrError::MalformedEvent(format!(
+                "Expected a kind {} relay list, got kind {}",
+                KIND_RELAY_LIST, event.kind
+            )));
+        }
+
+        let mut relay_list = Self { relays: Vec::new(), created_at: event.created_at };
+        for tag in &event.tags {
+            if tag.len() < 2 || tag[0] != "r" {
+                continue;
+            }
+
+            let usage = match tag.get(2).map(String::as_str) {
+                None | Some("") => RelayUsage::Both,
+                Some("read") => RelayUsage::Read,
+                Some("write") => RelayUsage::Write,
+                Some(_) => continue,
+            };
+            let _ = relay_list.add_relay(&tag[1], usage);
+        }
+
+        Ok(relay_list)
+    }
+
+    /// Sign the list as a kind-10002 event
+    pub fn to_event(&self, keypair: &NostrKeypair) -> Result<NostrEvent> {
+        let tags = self
+            .relays
+            .iter()
+            .map(|(url, usage)| match usage {
+                RelayUsage::Both => vec!["r".to_string(), url.clone()],
+                RelayUsage::Read => vec!["r".to_string(), url.clone(), "read".to_string()],
+                RelayUsage::Write => vec!["r".to_string(), url.clone(), "write".to_string()],
+            })
+            .collect();
+
+        UnsignedEvent::new_text_note(String::new(), keypair.public_key_hex())
+            .with_kind(KIND_RELAY_LIST)
+            .with_tags(tags)
+            .sign(keypair)
+    }
+
+    pub fn read_relays(&self) -> Vec<String> {
+        self.relays.iter().filter(|(_, usage)| usage.is_read()).map(|(url, _)| url.clone()).collect()
+    }
+
+    pub fn write_relays(&self) -> Vec<String> {
+        self.relays.iter().filter(|(_, usage)| usage.is_write()).map(|(url, _)| url.clone()).collect()
+    }
+}
+
+/// One spelling per relay, so lists from different clients can be compared:
+/// lowercase host, no default port and no trailing slash
+pub fn normalize_relay_url(relay_url: &str) -> Result<String> {
+    let url = Url::parse(relay_url.trim())?;
+    if url.scheme() != "ws" && url.scheme() != "wss" {
+        return Err(NostrError::InvalidUrl(format!("{} is not a ws:// or wss:// URL", relay_url)));
+    }
+    if url.host_str().is_none_or(str::is_empty) {
+        return Err(NostrError::InvalidUrl(format!("Relay URL has no host: {}", relay_url)));
+    }
+
+    Ok(url.as_str().trim_end_matches('/').to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nostr::keys::generate_keypair;
+
+    #[test]
+    fn test_relay_list_round_trip() {
+        let keypair = generate_keypair().unwrap();
+        let relay_list = RelayList::new()
+            .with_relay("wss://inbox.example.com", RelayUsage::Read)
+            .unwrap()
+            .with_relay("wss://outbox.example.com", RelayUsage::Write)
+            .unwrap()
+            .with_relay("wss://both.example.com", RelayUsage::Both)
+            .unwrap();
+
+        let event = relay_list.to_event(&keypair).unwrap();
+        assert_eq!(event.kind, KIND_RELAY_LIST);
+        assert!(event.verify());
+
+        let parsed = RelayList::from_event(&event).unwrap();
+        assert_eq!(parsed.relays, relay_list.relays);
+        assert_eq!(parsed.read_relays(), vec!["wss://inbox.example.com", "wss://both.example.com"]);
+        assert_eq!(parsed.write_relays(), vec!["wss://outbox.example.com", "wss://both.example.com"]);
+    }
+
+    #[test]
+    fn test_parse_skips_invalid_entries_and_merges_duplicates() {
+        let keypair = generate_keypair().unwrap();
+        let r = |parts: &[&str]| parts.iter().map(|part| part.to_string()).collect::<Vec<_>>();
+        let event = UnsignedEvent::new_text_note(String::new(), keypair.public_key_hex())
+            .with_kind(KIND_RELAY_LIST)
+            .with_tags(vec![
+                r(&["r", "wss://Relay.Example.com/", "read"]),
+                r(&["r", "wss://relay.example.com", "write"]),
+                r(&["r", "https://not-a-relay.example.com"]),
+                r(&["r", "wss://odd.example.com", "sometimes"]),
+                r(&["p", "wss://ignored.example.com"]),
+            ])
+            .sign(&keypair)
+            .unwrap();
+
+        let relay_list = RelayList::from_event(&event).unwrap();
+        assert_eq!(relay_list.relays, vec![("wss://relay.example.com".to_string(), RelayUsage::Both)]);
+    }
+
+    #[test]
+    fn test_normalize_relay_url() {
+        assert_eq!(normalize_relay_url("wss://Relay.Damus.io/").unwrap(), "wss://relay.damus.io");
+        assert_eq!(normalize_relay_url("wss://relay.example.com:443/").unwrap(), "wss://relay.example.com");
+        assert_eq!(normalize_relay_url("ws://127.0.0.1:7777/nostr/").unwrap(), "ws://127.0.0.1:7777/nostr");
+        assert!(normalize_relay_url("https://relay.example.com").is_err());
+        assert!(normalize_relay_url("not a url").is_err());
+    }
+}
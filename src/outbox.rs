@@ -0,0 +1,292 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use crate::nostr::NostrEvent;
+use crate::nostr::nip65::{KIND_RELAY_LIST, RelayList};
+
+/// How many of an author's write relays to read their notes from, so one
+/// relay being down or missing a note doesn't hide it
+const RELAYS_PER_AUTHOR: usize = 2;
+
+/// Routes reads and writes with the outbox model (NIP-65): an author's notes
+/// are read from the relays they write to, and events mentioning someone are
+/// also sent to the relays they read from.
+///
+/// Authors whose relay list is unknown are read from the fallback relays,
+/// which are also where relay lists are looked up.
+#[derive(Debug, Clone)]
+pub struct Outbox {
+    relay_lists: HashMap<String, RelayList>,
+    fallback_relays: Vec<String>,
+    /// Most relays a route may use, fallback relays included
+    max_relays: usize,
+}
+
+impl Outbox {
+    pub fn new(fallback_relays: Vec<String>, max_relays: usize) -> Self {
+        Self {
+            relay_lists: HashMap::new(),
+            fallback_relays,
+            max_relays,
+        }
+    }
+
+    pub fn fallback_relays(&self) -> &[String] {
+        &self.fallback_relays
+    }
+
+    /// Remember an author's kind-10002 relay list. Returns false when it isn't
+    /// a relay list or an equally new one is already known.
+    pub fn update(&mut self, event: &NostrEvent) -> bool {
+        if event.kind != KIND_RELAY_LIST
+            || self
+                .relay_lists
+                .get(&event.pubkey)
+                .is_some_and(|known| known.created_at >= event.created_at)
+        {
+            return false;
+        }
+
+        let Ok(relay_list) = RelayList::from_event(event) else {
+            return false;
+        };
+        self.relay_lists.insert(event.pubkey.clone(), relay_list);
+        true
+    }
+
+    pub fn relay_list(&self, pubkey: &str) -> Option<&RelayList> {
+        self.relay_lists.get(pubkey)
+    }
+
+    /// Which authors to read from which relay. Fallback relays are always
+    /// used; the remaining relays are picked so that few of them cover as many
+    /// authors as possible, and authors left without a relay are read from
+    /// the fallback relays.
+    pub fn route_authors(&self, authors: &[String]) -> BTreeMap<String, Vec<String>> {
+        let mut routes: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        let mut coverage: HashMap<&str, usize> = HashMap::new();
+
+        // Relays each author writes to, other than the fallback relays
+        let mut candidates: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+        for author in authors {
+            let Some(relay_list) = self.relay_lists.get(author) else {
+                continue;
+            };
+
+            for relay_url in relay_list.relays.iter().filter(|(_, usage)| usage.is_write()).map(|(url, _)| url) {
+                if self.fallback_relays.contains(relay_url) {
+                    routes.entry(relay_url.clone()).or_default().push(author.clone());
+                    *coverage.entry(author).or_default() += 1;
+                } else {
+                    candidates.entry(relay_url).or_default().push(author);
+                }
+            }
+        }
+
+        let budget = self.max_relays.saturating_sub(self.fallback_relays.len());
+        for _ in 0..budget {
+            let needs_relay = |author: &&str| coverage.get(*author).copied().unwrap_or(0) < RELAYS_PER_AUTHOR;
+
+            // Ties go to the first relay in URL order, so routes are stable
+            let best = candidates
+                .iter()
+                .map(|(relay_url, authors)| (*relay_url, authors.iter().filter(|author| needs_relay(author)).count()))
+                .filter(|(_, count)| *count > 0)
+                .fold(None, |best: Option<(&str, usize)>, candidate| match best {
+                    Some((_, best_count)) if best_count >= candidate.1 => best,
+                    _ => Some(candidate),
+                });
+            let Some((relay_url, _)) = best else {
+                break;
+            };
+
+            let covered: Vec<&str> = candidates
+                .remove(relay_url)
+                .unwrap_or_default()
+                .into_iter()
+                .filter(|author| needs_relay(author))
+                .collect();
+            for author in covered {
+                routes.entry(relay_url.to_string()).or_default().push(author.to_string());
+                *coverage.entry(author).or_default() += 1;
+            }
+        }
+
+        let uncovered: Vec<String> = authors
+            .iter()
+            .filter(|author| !coverage.contains_key(author.as_str()))
+            .cloned()
+            .collect();
+        if !uncovered.is_empty() {
+            for relay_url in &self.fallback_relays {
+                routes.entry(relay_url.clone()).or_default().extend(uncovered.iter().cloned());
+            }
+        }
+
+        routes
+    }
+
+    /// Relays to publish an event to: all of the author's write relays, then
+    /// the read relays of everyone it mentions, up to the relay limit
+    pub fn publish_relays(&self, write_relays: &[String], event: &NostrEvent) -> Vec<String> {
+        let mut relays: Vec<String> = Vec::new();
+        let mut seen = HashSet::new();
+        for relay_url in write_relays {
+            if seen.insert(relay_url.clone()) {
+                relays.push(relay_url.clone());
+            }
+        }
+
+        let mentioned = event
+            .tags
+            .iter()
+            .filter(|tag| tag.len() >= 2 && tag[0] == "p" && tag[1] != event.pubkey)
+            .filter_map(|tag| self.relay_lists.get(&tag[1]))
+            .flat_map(|relay_list| relay_list.read_relays().into_iter().take(RELAYS_PER_AUTHOR));
+
+        for relay_url in mentioned {
+            if relays.len() >= self.max_relays {
+                break;
+            }
+            if seen.insert(relay_url.clone()) {
+                relays.push(relay_url);
+            }
+        }
+
+        relays
+    }
+
+    /// Relays to send something only `pubkey` should get, like a private
+    /// message: the relays they read from, or `fallback` when their relay
+    /// list is unknown or names none
+    pub fn inbox_relays(&self, pubkey: &str, fallback: &[String]) -> Vec<String> {
+        let read_relays: Vec<String> = self
+            .relay_lists
+            .get(pubkey)
+            .map(|relay_list| relay_list.read_relays().into_iter().take(self.max_relays).collect())
+            .unwrap_or_default();
+
+        if read_relays.is_empty() { fallback.to_vec() } else { read_relays }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nostr::event::UnsignedEvent;
+    use crate::nostr::keys::{NostrKeypair, generate_keypair};
+    use crate::nostr::nip65::RelayUsage;
+
+    fn relay_list(keypair: &NostrKeypair, relays: &[(&str, RelayUsage)], created_at: u64) -> NostrEvent {
+        let mut list = RelayList::new();
+        for (url, usage) in relays {
+            list.add_relay(url, *usage).unwrap();
+        }
+        let mut event = list.to_event(keypair).unwrap();
+        event.created_at = created_at;
+        event
+    }
+
+    fn outbox(max_relays: usize) -> Outbox {
+        Outbox::new(vec!["wss://fallback.example.com".to_string()], max_relays)
+    }
+
+    #[test]
+    fn test_only_newer_relay_lists_are_kept() {
+        let mut outbox = outbox(10);
+        let alice = generate_keypair().unwrap();
+
+        assert!(outbox.update(&relay_list(&alice, &[("wss://a.example.com", RelayUsage::Both)], 200)));
+        assert!(!outbox.update(&relay_list(&alice, &[("wss://old.example.com", RelayUsage::Both)], 100)));
+        assert_eq!(
+            outbox.relay_list(&alice.public_key_hex()).unwrap().write_relays(),
+            vec!["wss://a.example.com"]
+        );
+    }
+
+    #[test]
+    fn test_authors_are_read_from_their_write_relays() {
+        let mut outbox = outbox(10);
+        let alice = generate_keypair().unwrap();
+        let bob = generate_keypair().unwrap();
+        let carol = generate_keypair().unwrap();
+
+        outbox.update(&relay_list(&alice, &[
+            ("wss://shared.example.com", RelayUsage::Write),
+            ("wss://inbox.example.com", RelayUsage::Read),
+        ], 100));
+        outbox.update(&relay_list(&bob, &[
+            ("wss://shared.example.com", RelayUsage::Both),
+            ("wss://small.example.com", RelayUsage::Write),
+        ], 100));
+
+        let authors = vec![alice.public_key_hex(), bob.public_key_hex(), carol.public_key_hex()];
+        let routes = outbox.route_authors(&authors);
+
+        assert_eq!(routes["wss://shared.example.com"], vec![alice.public_key_hex(), bob.public_key_hex()]);
+        assert_eq!(routes["wss://small.example.com"], vec![bob.public_key_hex()]);
+        assert_eq!(routes["wss://fallback.example.com"], vec![carol.public_key_hex()], "No relay list, so fallback");
+        assert!(!routes.contains_key("wss://inbox.example.com"), "Read relays are not used for reading notes");
+    }
+
+    #[test]
+    fn test_routes_respect_the_relay_limit() {
+        let mut outbox = outbox(3);
+        let keypairs: Vec<NostrKeypair> = (0..4).map(|_| generate_keypair().unwrap()).collect();
+
+        // Everyone writes to the popular relay and one relay of their own
+        for (i, keypair) in keypairs.iter().enumerate() {
+            let own = format!("wss://own{}.example.com", i);
+            outbox.update(&relay_list(keypair, &[
+                ("wss://popular.example.com", RelayUsage::Write),
+                (own.as_str(), RelayUsage::Write),
+            ], 100));
+        }
+
+        let authors: Vec<String> = keypairs.iter().map(NostrKeypair::public_key_hex).collect();
+        let routes = outbox.route_authors(&authors);
+
+        // One slot is kept for the fallback relay, which nobody needs here
+        let relays: Vec<&String> = routes.keys().collect();
+        assert_eq!(relays, vec!["wss://own0.example.com", "wss://popular.example.com"]);
+        assert_eq!(routes["wss://popular.example.com"].len(), 4, "The relay covering everyone is picked first");
+    }
+
+    #[test]
+    fn test_mentions_go_to_the_mentioned_users_read_relays() {
+        let mut outbox = outbox(3);
+        let alice = generate_keypair().unwrap();
+        let bob = generate_keypair().unwrap();
+
+        outbox.update(&relay_list(&bob, &[
+            ("wss://bob-inbox.example.com", RelayUsage::Read),
+            ("wss://bob-outbox.example.com", RelayUsage::Write),
+        ], 100));
+
+        let note = UnsignedEvent::new_text_note("hi bob".to_string(), alice.public_key_hex())
+            .with_tags(vec![vec!["p".to_string(), bob.public_key_hex()]])
+            .sign(&alice)
+            .unwrap();
+        let write_relays = vec!["wss://alice.example.com".to_string()];
+
+        assert_eq!(
+            outbox.publish_relays(&write_relays, &note),
+            vec!["wss://alice.example.com", "wss://bob-inbox.example.com"]
+        );
+    }
+
+    #[test]
+    fn test_private_messages_go_to_the_recipients_read_relays_only() {
+        let mut outbox = outbox(3);
+        let bob = generate_keypair().unwrap();
+        let carol = generate_keypair().unwrap();
+        let write_relays = vec!["wss://alice.example.com".to_string()];
+
+        outbox.update(&relay_list(&bob, &[
+            ("wss://bob-inbox.example.com", RelayUsage::Read),
+            ("wss://bob-outbox.example.com", RelayUsage::Write),
+        ], 100));
+
+        assert_eq!(outbox.inbox_relays(&bob.public_key_hex(), &write_relays), vec!["wss://bob-inbox.example.com"]);
+        assert_eq!(outbox.inbox_relays(&carol.public_key_hex(), &write_relays), write_relays, "No relay list, so fallback");
+    }
+}
@@ -0,0 +1,580 @@
+use crate::error::{NostrError, Remedy, Result};
+use futures_util::future::join_all;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tokio::sync::broadcast;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::time::timeout;
+use uuid::Uuid;
+
+use crate::connection::{ReconnectPolicy, Relay, RelayAuth, RelayNotification, RelayStatus, unix_now};
+use crate::nostr::nip11::RelayInformation;
+use crate::nostr::{Filter, NostrEvent, RelayMessage};
+use crate::relay_info::RelayInfoCache;
+
+/// How long to wait for a relay's OK after publishing
+const PUBLISH_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How often a relay is sent an event it refused with `error:` or
+/// `rate-limited:` before giving up
+const PUBLISH_ATTEMPTS: u32 = 3;
+
+/// Wait before sending an event again after a relay's own failure; rate
+/// limits wait a multiple of it
+const PUBLISH_RETRY_DELAY: Duration = Duration::from_millis(500);
+
+/// Buffered notifications per receiver before slow receivers start lagging
+const NOTIFICATION_CAPACITY: usize = 1024;
+
+/// Where a subscription is sent
+#[derive(Debug, Clone)]
+enum Subscription {
+    /// Every relay in the pool, including relays added later
+    Everywhere(Vec<Filter>),
+    /// Only the listed relays, each with its own filters
+    Routed(HashMap<String, Vec<Filter>>),
+}
+
+impl Subscription {
+    fn filters_for(&self, url: &str) -> Option<&Vec<Filter>> {
+        match self {
+            Subscription::Everywhere(filters) => Some(filters),
+            Subscription::Routed(routes) => routes.get(url),
+        }
+    }
+}
+
+/// The result of publishing an event to a single relay
+#[derive(Debug)]
+pub struct PublishOutcome {
+    pub relay_url: String,
+    /// The relay's OK message when the event was accepted
+    pub result: Result<String>,
+}
+
+impl PublishOutcome {
+    pub fn is_accepted(&self) -> bool {
+        self.result.is_ok()
+    }
+
+    /// Why the event wasn't accepted
+    pub fn rejection(&self) -> Option<&NostrError> {
+        self.result.as_ref().err()
+    }
+}
+
+/// The error for a publish no relay accepted, carrying the first relay's
+/// reason, so the exit code reflects it
+pub fn none_accepted(what: &str, outcomes: &[PublishOutcome]) -> NostrError {
+    NostrError::NoRelayAccepted {
+        what: what.to_string(),
+        rejection: outcomes.iter().find_map(PublishOutcome::rejection).cloned().map(Box::new),
+    }
+}
+
+/// A set of persistent relay connections shared by every part of the client.
+///
+/// Each relay gets one long-lived connection task. Subscriptions are sent to
+/// every relay in the pool, including relays added later, unless they are
+/// routed to specific relays, and everything the relays send back is broadcast
+/// through `notifications`. Dropped connections are re-established according
+/// to the pool's `ReconnectPolicy`, and relays that require NIP-42
+/// authentication are answered according to `auth`. Each relay's NIP-11
+/// information document is fetched in the background when it is added; once
+/// it has arrived, events exceeding its announced limits are refused without
+/// being sent, and subscriptions beyond them are reported closed. With
+/// `with_max_relays`, relays beyond the limit are refused instead of connected
+/// to. Cloning the pool is cheap and clones share the same connections.
+#[derive(Debug, Clone)]
+pub struct RelayPool {
+    relays: Arc<RwLock<HashMap<String, Relay>>>,
+    subscriptions: Arc<RwLock<HashMap<String, Subscription>>>,
+    notifications: broadcast::Sender<RelayNotification>,
+    /// Relays with a publish underway, which `prune_relays` leaves alone
+    publishing: Arc<RwLock<HashMap<String, usize>>>,
+    reconnect_policy: ReconnectPolicy,
+    auth: RelayAuth,
+    relay_info: RelayInfoCache,
+    /// Most relays connected at once, unlimited when unset
+    max_relays: Option<usize>,
+}
+
+impl RelayPool {
+    pub fn new() -> Self {
+        Self::with_reconnect_policy(ReconnectPolicy::default())
+    }
+
+    pub fn with_reconnect_policy(reconnect_policy: ReconnectPolicy) -> Self {
+        let (notifications, _) = broadcast::channel(NOTIFICATION_CAPACITY);
+
+        Self {
+            relays: Arc::new(RwLock::new(HashMap::new())),
+            subscriptions: Arc::new(RwLock::new(HashMap::new())),
+            notifications,
+            publishing: Arc::new(RwLock::new(HashMap::new())),
+            reconnect_policy,
+            auth: RelayAuth::new(),
+            relay_info: RelayInfoCache::new(),
+            max_relays: None,
+        }
+    }
+
+    /// Refuse to add relays once `max_relays` are in the pool, so routes,
+    /// publishes and fetches together can't open unbounded connections
+    pub fn with_max_relays(mut self, max_relays: usize) -> Self {
+        self.max_relays = Some(max_relays);
+        self
+    }
+
+    /// Add a relay and start connecting to it. Adding a relay twice is a no-op,
+    /// adding one to a full pool an error.
+    pub fn add_relay(&self, url: &str) -> Result<()> {
+        let mut relays = self
+            .relays
+            .write()
+            .map_err(|_| NostrError::Other("Relay pool lock poisoned".to_string()))?;
+
+        if relays.contains_key(url) {
+            return Ok(());
+        }
+        if let Some(max_relays) = self.max_relays
+            && relays.len() >= max_relays
+        {
+            return Err(NostrError::RelayConnectionFailed(format!(
+                "Not connecting to {}, already connected to {} relays",
+                url, max_relays
+            )));
+        }
+
+        let relay = Relay::spawn(
+            url,
+            self.notifications.clone(),
+            self.reconnect_policy.clone(),
+            self.auth.clone(),
+        )?;
+
+        for (subscription_id, filters) in self.subscriptions_for(url) {
+            self.open_subscription(&relay, &subscription_id, filters);
+        }
+
+        relays.insert(url.to_string(), relay);
+
+        // Fetched in the background, so nothing waits for it; until it arrives
+        // the relay's limits are not checked
+        let relay_info = self.relay_info.clone();
+        let url = url.to_string();
+        tokio::spawn(async move { relay_info.get(&url).await });
+
+        Ok(())
+    }
+
+    pub async fn remove_relay(&self, url: &str) {
+        let relay = self.relays.write().ok().and_then(|mut relays| relays.remove(url));
+
+        if let Some(relay) = relay {
+            relay.shutdown().await;
+        }
+    }
+
+    pub fn relay_urls(&self) -> Vec<String> {
+        let mut urls: Vec<String> = self
+            .relays
+            .read()
+            .map(|relays| relays.keys().cloned().collect())
+            .unwrap_or_default();
+        urls.sort();
+        urls
+    }
+
+    #[allow(dead_code)]
+    pub fn relay_status(&self, url: &str) -> Option<RelayStatus> {
+        self.relays.read().ok()?.get(url).map(Relay::status)
+    }
+
+    pub fn connected_relays(&self) -> Vec<String> {
+        let mut urls: Vec<String> = self
+            .relays
+            .read()
+            .map(|relays| {
+                relays
+                    .values()
+                    .filter(|relay| relay.status() == RelayStatus::Connected)
+                    .map(|relay| relay.url().to_string())
+                    .collect()
+            })
+            .unwrap_or_default();
+        urls.sort();
+        urls
+    }
+
+    /// The key and per-relay policies used when a relay requires authentication
+    pub fn auth(&self) -> &RelayAuth {
+        &self.auth
+    }
+
+    /// Allow or refuse authenticating to a relay after a
+    /// `RelayNotification::AuthRequested`
+    pub fn answer_auth(&self, relay_url: &str, approved: bool) -> Result<()> {
+        self.relay(relay_url)?.answer_auth(approved)
+    }
+
+    /// The relay's NIP-11 information document, if it serves one
+    #[allow(dead_code)]
+    pub async fn relay_information(&self, relay_url: &str) -> Option<RelayInformation> {
+        self.relay_info.get(relay_url).await
+    }
+
+    /// Receive messages and status changes from every relay in the pool
+    pub fn notifications(&self) -> broadcast::Receiver<RelayNotification> {
+        self.notifications.subscribe()
+    }
+
+    /// Publish an event to every relay in the pool
+    #[allow(dead_code)]
+    pub async fn publish(&self, event: &NostrEvent) -> Vec<PublishOutcome> {
+        let relay_urls = self.relay_urls();
+        self.publish_to(&relay_urls, event).await
+    }
+
+    /// Publish an event to the given relays concurrently, adding any that are
+    /// not in the pool yet, and collect each relay's answer
+    pub async fn publish_to(&self, relay_urls: &[String], event: &NostrEvent) -> Vec<PublishOutcome> {
+        let publishes = relay_urls.iter().map(|url| async move {
+            let _publishing = PublishingGuard::new(&self.publishing, url);
+            let result = match self.add_relay(url).and_then(|_| self.relay(url)) {
+                Ok(relay) => match self.check_limits(url, event) {
+                    Ok(()) => publish_with_retries(&relay, event).await,
+                    Err(e) => Err(e),
+                },
+                Err(e) => Err(e),
+            };
+
+            PublishOutcome {
+                relay_url: url.clone(),
+                result,
+            }
+        });
+
+        join_all(publishes).await
+    }
+
+    /// Open a subscription on every relay in the pool and return its id
+    pub fn subscribe(&self, filters: Vec<Filter>) -> Result<String> {
+        let subscription_id = format!("sub-{}", Uuid::new_v4().simple());
+        self.subscribe_with_id(&subscription_id, filters)?;
+        Ok(subscription_id)
+    }
+
+    /// Open or replace a subscription with a caller-chosen id on every relay
+    pub fn subscribe_with_id(&self, subscription_id: &str, filters: Vec<Filter>) -> Result<()> {
+        self.subscriptions
+            .write()
+            .map_err(|_| NostrError::Other("Relay pool lock poisoned".to_string()))?
+            .insert(subscription_id.to_string(), Subscription::Everywhere(filters.clone()));
+
+        for relay in self.relays_snapshot() {
+            self.open_subscription(&relay, subscription_id, filters.clone());
+        }
+
+        Ok(())
+    }
+
+    /// Open or replace a subscription with different filters per relay, adding
+    /// relays that are not in the pool yet. Relays left out of `routes` close
+    /// the subscription and don't get it when added later.
+    pub fn subscribe_routed(&self, subscription_id: &str, routes: HashMap<String, Vec<Filter>>) -> Result<()> {
+        for url in routes.keys() {
+            self.add_relay(url)?;
+        }
+
+        self.subscriptions
+            .write()
+            .map_err(|_| NostrError::Other("Relay pool lock poisoned".to_string()))?
+            .insert(subscription_id.to_string(), Subscription::Routed(routes.clone()));
+
+        for relay in self.relays_snapshot() {
+            match routes.get(relay.url()) {
+                Some(filters) => self.open_subscription(&relay, subscription_id, filters.clone()),
+                None => {
+                    let _ = relay.unsubscribe(subscription_id.to_string());
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Disconnect from relays that are neither in `keep`, used by a routed
+    /// subscription nor being published to, so routes that moved elsewhere
+    /// don't leave connections open
+    pub async fn prune_relays(&self, keep: &[String]) {
+        let routed: HashSet<String> = self
+            .subscriptions
+            .read()
+            .map(|subscriptions| {
+                subscriptions
+                    .values()
+                    .filter_map(|subscription| match subscription {
+                        Subscription::Routed(routes) => Some(routes.keys().cloned()),
+                        Subscription::Everywhere(_) => None,
+                    })
+                    .flatten()
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        // Decided under the lock, so a publish can't add back a relay in between
+        let pruned: Vec<Relay> = match (self.relays.write(), self.publishing.read()) {
+            (Ok(mut relays), Ok(publishing)) => {
+                let unused: Vec<String> = relays
+                    .keys()
+                    .filter(|url| !keep.contains(url) && !routed.contains(*url) && !publishing.contains_key(*url))
+                    .cloned()
+                    .collect();
+                unused.iter().filter_map(|url| relays.remove(url)).collect()
+            }
+            _ => Vec::new(),
+        };
+
+        join_all(pruned.iter().map(Relay::shutdown)).await;
+    }
+
+    /// Query every relay in the pool for stored events. Waits until each relay
+    /// has sent EOSE, closed the subscription or failed to connect, or until
+    /// `wait` runs out, and returns the valid events de-duplicated across relays.
+    pub async fn fetch(&self, filters: Vec<Filter>, wait: Duration) -> Result<Vec<NostrEvent>> {
+        let events = self.fetch_with_relays(filters, wait).await?;
+        Ok(events.into_iter().map(|(event, _)| event).collect())
+    }
+
+    /// Like `fetch`, but each event comes with the URL of the first relay
+    /// that sent it, e.g. for relay hints in tags pointing at it
+    pub async fn fetch_with_relays(&self, filters: Vec<Filter>, wait: Duration) -> Result<Vec<(NostrEvent, String)>> {
+        let notifications = self.notifications();
+        let pending_relays: HashSet<String> = self
+            .relays_snapshot()
+            .iter()
+            .filter(|relay| relay.status() != RelayStatus::Failed)
+            .map(|relay| relay.url().to_string())
+            .collect();
+
+        let subscription_id = self.subscribe(filters)?;
+        Ok(self.collect_stored(notifications, &subscription_id, pending_relays, wait).await)
+    }
+
+    /// Like `fetch`, but only query the given relays, adding any that are not
+    /// in the pool yet
+    pub async fn fetch_from(
+        &self,
+        relay_urls: &[String],
+        filters: Vec<Filter>,
+        wait: Duration,
+    ) -> Result<Vec<NostrEvent>> {
+        let notifications = self.notifications();
+        let subscription_id = format!("sub-{}", Uuid::new_v4().simple());
+        let routes = relay_urls.iter().map(|url| (url.clone(), filters.clone())).collect();
+        self.subscribe_routed(&subscription_id, routes)?;
+
+        let pending_relays: HashSet<String> = self
+            .relays_snapshot()
+            .iter()
+            .filter(|relay| relay_urls.iter().any(|url| url == relay.url()) && relay.status() != RelayStatus::Failed)
+            .map(|relay| relay.url().to_string())
+            .collect();
+        let events = self.collect_stored(notifications, &subscription_id, pending_relays, wait).await;
+        Ok(events.into_iter().map(|(event, _)| event).collect())
+    }
+
+    /// Gather the events of a fetch's subscription, with the relay each came
+    /// from first, until every pending relay is done or `wait` runs out, then
+    /// close it
+    async fn collect_stored(
+        &self,
+        mut notifications: broadcast::Receiver<RelayNotification>,
+        subscription_id: &str,
+        mut pending_relays: HashSet<String>,
+        wait: Duration,
+    ) -> Vec<(NostrEvent, String)> {
+        let mut seen_event_ids = HashSet::new();
+        let mut events = Vec::new();
+
+        let collect = async {
+            while !pending_relays.is_empty() {
+                match notifications.recv().await {
+                    Ok(RelayNotification::Message { relay_url, message }) => match message {
+                        RelayMessage::Event { subscription_id: id, event }
+                            if id == subscription_id && event.verify() && seen_event_ids.insert(event.id.clone()) =>
+                        {
+                            events.push((event, relay_url));
+                        }
+                        RelayMessage::EndOfStoredEvents { subscription_id: id }
+                        | RelayMessage::Closed { subscription_id: id, .. }
+                            if id == subscription_id =>
+                        {
+                            pending_relays.remove(&relay_url);
+                        }
+                        _ => {}
+                    },
+                    Ok(RelayNotification::StatusChanged { relay_url, status: RelayStatus::Failed }) => {
+                        pending_relays.remove(&relay_url);
+                    }
+                    Ok(_) | Err(RecvError::Lagged(_)) => {}
+                    Err(RecvError::Closed) => break,
+                }
+            }
+        };
+        let _ = timeout(wait, collect).await;
+
+        self.unsubscribe(subscription_id);
+        events
+    }
+
+    pub fn unsubscribe(&self, subscription_id: &str) {
+        if let Ok(mut subscriptions) = self.subscriptions.write() {
+            subscriptions.remove(subscription_id);
+        }
+
+        for relay in self.relays_snapshot() {
+            let _ = relay.unsubscribe(subscription_id.to_string());
+        }
+    }
+
+    /// Close every relay connection
+    pub async fn shutdown(&self) {
+        join_all(self.relays_snapshot().iter().map(Relay::shutdown)).await;
+    }
+
+    /// Refuse events the relay announced it would reject, if its information
+    /// document has arrived
+    fn check_limits(&self, url: &str, event: &NostrEvent) -> Result<()> {
+        let Some(information) = self.relay_info.cached(url) else {
+            return Ok(());
+        };
+
+        if information.limitation().auth_required && !self.auth.can_authenticate(url) {
+            return Err(NostrError::AuthRequired("Relay requires authentication".to_string()));
+        }
+
+        information
+            .check_event(event, unix_now())
+            .map_err(|e| NostrError::RelayLimitExceeded(format!("Not sent, exceeds relay limits: {}", e)))
+    }
+
+    /// Open a subscription on one relay, unless the relay announced it allows
+    /// no more. Then it is reported closed, as the relay would.
+    fn open_subscription(&self, relay: &Relay, subscription_id: &str, filters: Vec<Filter>) {
+        let max_subscriptions = self
+            .relay_info
+            .cached(relay.url())
+            .and_then(|information| information.limitation().max_subscriptions);
+        if let Some(max_subscriptions) = max_subscriptions
+            && self.subscriptions_for(relay.url()).iter().filter(|(id, _)| id != subscription_id).count()
+                >= max_subscriptions
+        {
+            let _ = self.notifications.send(RelayNotification::Message {
+                relay_url: relay.url().to_string(),
+                message: RelayMessage::Closed {
+                    subscription_id: subscription_id.to_string(),
+                    message: format!("Not sent, the relay allows {} subscriptions", max_subscriptions),
+                },
+            });
+            return;
+        }
+
+        let _ = relay.subscribe(subscription_id.to_string(), filters);
+    }
+
+    fn relay(&self, url: &str) -> Result<Relay> {
+        self.relays
+            .read()
+            .map_err(|_| NostrError::Other("Relay pool lock poisoned".to_string()))?
+            .get(url)
+            .cloned()
+            .ok_or_else(|| NostrError::RelayConnectionFailed(format!("Relay {} is not in the pool", url)))
+    }
+
+    fn relays_snapshot(&self) -> Vec<Relay> {
+        self.relays
+            .read()
+            .map(|relays| relays.values().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Subscriptions a relay should have open, with that relay's filters
+    fn subscriptions_for(&self, url: &str) -> Vec<(String, Vec<Filter>)> {
+        self.subscriptions
+            .read()
+            .map(|subscriptions| {
+                subscriptions
+                    .iter()
+                    .filter_map(|(id, subscription)| Some((id.clone(), subscription.filters_for(url)?.clone())))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
+impl Default for RelayPool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Marks a relay as having a publish underway until dropped
+struct PublishingGuard<'a> {
+    publishing: &'a RwLock<HashMap<String, usize>>,
+    url: &'a str,
+}
+
+impl<'a> PublishingGuard<'a> {
+    fn new(publishing: &'a RwLock<HashMap<String, usize>>, url: &'a str) -> Self {
+        if let Ok(mut publishing) = publishing.write() {
+            *publishing.entry(url.to_string()).or_default() += 1;
+        }
+        Self { publishing, url }
+    }
+}
+
+impl Drop for PublishingGuard<'_> {
+    fn drop(&mut self) {
+        if let Ok(mut publishing) = self.publishing.write()
+            && let Some(count) = publishing.get_mut(self.url)
+        {
+            *count -= 1;
+            if *count == 0 {
+                publishing.remove(self.url);
+            }
+        }
+    }
+}
+
+/// Publish to one relay and wait for its OK, sending the event again when the
+/// relay failed on its side or asked to slow down
+async fn publish_with_retries(relay: &Relay, event: &NostrEvent) -> Result<String> {
+    let mut attempt = 1;
+    loop {
+        let result = timeout(PUBLISH_TIMEOUT, relay.publish(event.clone()))
+            .await
+            .unwrap_or(Err(NostrError::RelayResponseTimeout));
+
+        let Err(e) = &result else {
+            return result;
+        };
+        let delay = match e {
+            // Waiting out another timeout would only make publishing slower
+            NostrError::RelayResponseTimeout => return result,
+            rejection => match rejection.remedy() {
+                Remedy::Retry => PUBLISH_RETRY_DELAY,
+                Remedy::BackOff => PUBLISH_RETRY_DELAY * 4 * attempt,
+                _ => return result,
+            },
+        };
+        if attempt >= PUBLISH_ATTEMPTS {
+            return result;
+        }
+
+        tokio::time::sleep(delay).await;
+        attempt += 1;
+    }
+}
@@ -0,0 +1,67 @@
+use crate::error::{NostrError, Result};
+use reqwest::header::ACCEPT;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tokio::sync::OnceCell;
+
+use crate::nostr::nip11::{RELAY_INFORMATION_MEDIA_TYPE, RelayInformation, information_url};
+
+/// How long to wait for a relay's information document
+const FETCH_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A relay's document once fetched, `None` if it has none
+type CachedInformation = Arc<OnceCell<Option<RelayInformation>>>;
+
+/// NIP-11 information documents, fetched once per relay.
+///
+/// Relays that don't serve a document, or can't be reached, are remembered as
+/// having none. Cloning is cheap and clones share the same cache.
+#[derive(Debug, Clone, Default)]
+pub struct RelayInfoCache {
+    client: reqwest::Client,
+    documents: Arc<RwLock<HashMap<String, CachedInformation>>>,
+}
+
+impl RelayInfoCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The relay's information document, fetched on first use
+    pub async fn get(&self, relay_url: &str) -> Option<RelayInformation> {
+        let cell = {
+            let Ok(mut documents) = self.documents.write() else {
+                return None;
+            };
+            documents.entry(relay_url.to_string()).or_default().clone()
+        };
+
+        cell.get_or_init(|| async { fetch_relay_information(&self.client, relay_url).await.ok() })
+            .await
+            .clone()
+    }
+
+    /// The relay's information document if it was already fetched, without
+    /// waiting for one
+    pub fn cached(&self, relay_url: &str) -> Option<RelayInformation> {
+        self.documents.read().ok()?.get(relay_url)?.get().cloned().flatten()
+    }
+}
+
+/// Fetch a relay's information document over HTTP(S), bypassing any cache
+pub async fn fetch_relay_information(client: &reqwest::Client, relay_url: &str) -> Result<RelayInformation> {
+    let response = client
+        .get(information_url(relay_url)?)
+        .header(ACCEPT, RELAY_INFORMATION_MEDIA_TYPE)
+        .timeout(FETCH_TIMEOUT)
+        .send()
+        .await?;
+
+    if !response.status().is_success() {
+        return Err(NostrError::HttpError(format!("Relay answered with HTTP {}", response.status())));
+    }
+
+    RelayInformation::from_json(&response.text().await?)
+        .map_err(|e| NostrError::SerializationFailed(format!("Invalid relay information document: {}", e)))
+}
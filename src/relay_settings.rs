@@ -0,0 +1,55 @@
+use crate::error::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::connection::{AuthPolicy, RelayAuth, settings_key};
+
+/// Per-relay preferences, stored as `relays.json` in the config directory
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RelaySettings {
+    /// How each relay's NIP-42 challenges are answered; relays not listed ask
+    #[serde(default)]
+    pub auth: BTreeMap<String, AuthPolicy>,
+}
+
+impl RelaySettings {
+    /// Load the settings, or the defaults if none were saved yet
+    pub fn load(config_dir: &Path) -> Result<Self> {
+        let path = settings_path(config_dir);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let mut settings: Self = serde_json::from_str(&fs::read_to_string(path)?)?;
+        // Keys edited by hand or saved by older versions may differ in form
+        settings.auth = settings.auth.into_iter().map(|(relay_url, policy)| (settings_key(&relay_url), policy)).collect();
+        Ok(settings)
+    }
+
+    pub fn save(&self, config_dir: &Path) -> Result<()> {
+        fs::create_dir_all(config_dir)?;
+        fs::write(settings_path(config_dir), serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn auth_policy(&self, relay_url: &str) -> AuthPolicy {
+        self.auth.get(&settings_key(relay_url)).copied().unwrap_or_default()
+    }
+
+    pub fn set_auth_policy(&mut self, relay_url: &str, policy: AuthPolicy) {
+        self.auth.insert(settings_key(relay_url), policy);
+    }
+
+    /// Use these policies for a pool's connections
+    pub fn apply(&self, auth: &RelayAuth) {
+        for (relay_url, policy) in &self.auth {
+            auth.set_policy(relay_url, *policy);
+        }
+    }
+}
+
+fn settings_path(config_dir: &Path) -> PathBuf {
+    config_dir.join("relays.json")
+}
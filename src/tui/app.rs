@@ -1,8 +1,69 @@
 use anyhow::Result;
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::Duration;
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
-use std::path::PathBuf;
+use secrecy::{ExposeSecret, SecretString};
+use tokio::sync::mpsc;
 
-use crate::accounts::AccountManager;
+use crate::accounts::{AccountManager, default_config_dir};
+use crate::connection::RelayNotification;
+use crate::event_store::EventStore;
+use crate::nostr::event::UnsignedEvent;
+use crate::nostr::metadata::{KIND_METADATA, METADATA_FIELDS, Metadata};
+use crate::nostr::nip02::KIND_CONTACT_LIST;
+use crate::nostr::nip04::KIND_ENCRYPTED_DIRECT_MESSAGE;
+use crate::nostr::nip09::KIND_DELETION;
+use crate::nostr::nip10::ThreadRefs;
+use crate::nostr::nip17::{PrivateMessage, wrap_private_message};
+use crate::nostr::nip18::{KIND_GENERIC_REPOST, KIND_REPOST};
+use crate::nostr::nip25::{KIND_REACTION, Reaction};
+use crate::nostr::nip59::KIND_GIFT_WRAP;
+use crate::nostr::nip65::KIND_RELAY_LIST;
+use crate::nostr::{Filter, NostrEvent, RelayMessage, keypair_from_secret, public_key_from_str};
+use crate::outbox::Outbox;
+use crate::pool::{PublishOutcome, RelayPool};
+use crate::relay_settings::RelaySettings;
+use crate::tui::events::AppEvent;
+use crate::tui::feed::Feed;
+use crate::tui::interactions::Interactions;
+use crate::tui::messages::Conversations;
+use crate::tui::thread::Thread;
+
+/// Relays used to find relay lists, to read authors that have none, and to
+/// post to until the active account's relay list is known
+const DEFAULT_RELAYS: [&str; 3] = ["wss://relay.damus.io", "wss://nos.lol", "wss://relay.snort.social"];
+
+/// Most relays the home feed connects to at once
+const MAX_RELAYS: usize = 12;
+
+/// Most relays the pool connects to at once: the home feed's, plus room for
+/// publishing to the relays of people a note mentions
+pub const MAX_CONNECTED_RELAYS: usize = MAX_RELAYS + 8;
+
+/// Subscription ids used by the home feed, replaced whenever it is restarted
+const HOME_CONTACTS_SUBSCRIPTION: &str = "home-contacts";
+const HOME_NOTES_SUBSCRIPTION: &str = "home-notes";
+const HOME_PROFILES_SUBSCRIPTION: &str = "home-profiles";
+const HOME_RELAY_LISTS_SUBSCRIPTION: &str = "home-relay-lists";
+const HOME_INTERACTIONS_SUBSCRIPTION: &str = "home-interactions";
+
+/// Subscription id for gift wraps addressed to the active account, and legacy
+/// NIP-04 messages sent by or to it
+const DIRECT_MESSAGES_SUBSCRIPTION: &str = "direct-messages";
+
+/// Subscription id for the root and replies of the thread being viewed
+const THREAD_SUBSCRIPTION: &str = "thread";
+
+/// Emoji reactions bound to the `1`, `2` and `3` keys in the feed
+const QUICK_REACTIONS: [&str; 3] = ["🤙", "❤️", "🔥"];
+
+/// How many stored notes to request when the home feed starts
+const HOME_FEED_LIMIT: u64 = 200;
+
+/// How long to wait for relays to send the active account's profile before
+/// the profile editor opens
+const PROFILE_FETCH_TIMEOUT: Duration = Duration::from_secs(10);
 
 /// Current view/screen in the application
 #[derive(Debug, Clone, PartialEq)]
@@ -11,6 +72,36 @@ pub enum CurrentView {
     AccountModal,
     ComposeModal,
     HelpModal,
+    Messages,
+    Thread,
+}
+
+/// What the account modal is currently doing
+#[derive(Debug, Clone, PartialEq)]
+pub enum AccountModalMode {
+    /// Browsing the account list
+    List,
+    /// Typing a name for a newly generated account
+    CreateName,
+    /// Pasting the nsec or hex private key of an account to import
+    ImportKey,
+    /// Typing a name for the account being imported
+    ImportName,
+    /// Waiting for confirmation before deleting the selected account
+    ConfirmDelete,
+    /// Editing the active account's profile metadata
+    EditProfile,
+}
+
+/// Where keystrokes go in the messages view
+#[derive(Debug, Clone, PartialEq)]
+pub enum MessagesFocus {
+    /// Browsing the conversation list
+    Conversations,
+    /// Typing the public key to start a conversation with
+    Recipient,
+    /// Typing a message to the selected or new conversation
+    Reply,
 }
 
 /// Application state and logic
@@ -24,6 +115,15 @@ pub struct App {
     /// Account manager for handling user accounts and secure storage
     pub account_manager: AccountManager,
 
+    /// Persistent connections to every relay the app talks to
+    pub relay_pool: RelayPool,
+
+    /// Events received from relays, so views start from what was seen before
+    event_store: EventStore,
+
+    /// Reports results of spawned tasks back to the UI loop
+    app_events: mpsc::UnboundedSender<AppEvent>,
+
     /// Whether the keystore is currently unlocked
     pub keystore_unlocked: bool,
 
@@ -33,17 +133,87 @@ pub struct App {
     /// Whether we're currently showing a password input prompt
     pub password_prompt_active: bool,
 
+    /// Keystore password for this session, needed to re-encrypt the keystore
+    /// when accounts are added or removed. Dropped when the keystore is locked.
+    session_password: Option<SecretString>,
+
+    /// Account modal state
+    pub account_mode: AccountModalMode,
+    pub account_index: usize,
+    pub account_name_input: String,
+    pub import_key_input: String,
+    /// Secret key (hex) validated in the import form, waiting for a name
+    pending_import_key: Option<SecretString>,
+    /// Profile editor: the metadata being edited, keeping fields the editor
+    /// doesn't show, one input per `METADATA_FIELDS` entry and the focused one.
+    /// The draft is only set once the newest profile was fetched from the
+    /// write relays, so a profile that isn't cached yet can't be overwritten.
+    profile_draft: Option<Metadata>,
+    pub profile_inputs: Vec<String>,
+    pub profile_field: usize,
+
     /// Status message to display to user
     pub status_message: Option<String>,
 
-    /// Feed content (placeholder for now)
-    pub feed_items: Vec<String>,
+    /// Relays waiting for y/n before we authenticate to them (NIP-42)
+    pub auth_requests: Vec<String>,
+
+    /// Notes from the authors the active account follows
+    pub feed: Feed,
+    /// Proof of work (NIP-13) the feed requires of notes, in leading zero bits
+    pub min_pow: u32,
+
+    /// Public key of the account the home feed was started for
+    home_pubkey: Option<String>,
+
+    /// Authors the home feed shows, the active account included
+    followed_authors: Vec<String>,
+
+    /// Whether the active account's contact list arrived, or a relay said it
+    /// has none, so following someone won't start a new list over one that
+    /// is still on its way
+    contacts_loaded: bool,
+
+    /// Known relay lists (NIP-65), deciding which relays the feed reads from
+    outbox: Outbox,
+
+    /// A relay list changed since the feed was last routed; re-routed on the
+    /// next tick so a burst of lists costs one re-route
+    routes_stale: bool,
+
+    /// Reactions and reposts of the notes in the feed
+    pub interactions: Interactions,
+
+    /// Notes arrived since their reactions and reposts were last requested;
+    /// requested on the next tick so a burst of notes costs one subscription
+    interactions_stale: bool,
+
+    /// Private conversations of the active account
+    pub conversations: Conversations,
+
+    /// Messages view state
+    pub conversation_index: usize,
+    pub messages_focus: MessagesFocus,
+    pub recipient_input: String,
+    pub message_input: String,
+    /// Participants of a conversation started from the recipient prompt that
+    /// has no messages yet
+    pub new_conversation: Option<Vec<String>>,
+
+    /// The thread opened from the feed, with the selected note and the reply
+    /// being typed to it
+    pub thread: Option<Thread>,
+    pub thread_index: usize,
+    pub thread_replying: bool,
+    pub thread_reply_input: String,
 
     /// Selected item index in current view
     pub selected_index: usize,
 
     /// Compose modal state
     pub compose_text: String,
+    /// The note being quoted by the composed post, if any (NIP-18)
+    pub compose_quote: Option<NostrEvent>,
     pub compose_relay_selection: Vec<(String, bool)>, // (relay_url, selected)
     pub compose_focus: ComposeFocus,
 }
@@ -57,38 +227,84 @@ pub enum ComposeFocus {
 
 impl App {
     /// Create a new application instance
-    pub fn new() -> Result<Self> {
-        // Get config directory (create if doesn't exist)
-        let config_dir = dirs::config_dir()
-            .unwrap_or_else(|| PathBuf::from("."))
-            .join("nosotros");
+    pub fn new(relay_pool: RelayPool, app_events: mpsc::UnboundedSender<AppEvent>) -> Result<Self> {
+        let app = Self::open(relay_pool, app_events, &default_config_dir())?;
 
-        let account_manager = AccountManager::new(config_dir)?;
+        for relay_url in DEFAULT_RELAYS {
+            app.relay_pool.add_relay(relay_url)?;
+        }
+
+        Ok(app)
+    }
+
+    /// An application keeping its accounts and cache in `config_dir`, not
+    /// connected to any relay yet
+    fn open(relay_pool: RelayPool, app_events: mpsc::UnboundedSender<AppEvent>, config_dir: &Path) -> Result<Self> {
+        let account_manager = AccountManager::new(config_dir.to_path_buf())?;
+        let event_store = EventStore::open(config_dir)?;
+        RelaySettings::load(config_dir)?.apply(relay_pool.auth());
 
         Ok(Self {
             current_view: CurrentView::Feed,
             should_quit: false,
             account_manager,
+            relay_pool,
+            event_store,
+            app_events,
             keystore_unlocked: false,
             password_input: String::new(),
             password_prompt_active: false,
+            session_password: None,
+            account_mode: AccountModalMode::List,
+            account_index: 0,
+            account_name_input: String::new(),
+            import_key_input: String::new(),
+            pending_import_key: None,
+            profile_draft: None,
+            profile_inputs: Vec::new(),
+            profile_field: 0,
             status_message: Some("Welcome to Nosotros! Press 'a' to manage accounts, '?' for help".to_string()),
-            feed_items: vec![
-                "No posts yet. Follow accounts or check relays.".to_string(),
-            ],
+            auth_requests: Vec::new(),
+            feed: Feed::new(),
+            min_pow: 0,
+            home_pubkey: None,
+            followed_authors: Vec::new(),
+            contacts_loaded: false,
+            outbox: Outbox::new(DEFAULT_RELAYS.map(String::from).to_vec(), MAX_RELAYS),
+            routes_stale: false,
+            interactions: Interactions::new(),
+            interactions_stale: false,
+            conversations: Conversations::new(),
+            conversation_index: 0,
+            messages_focus: MessagesFocus::Conversations,
+            recipient_input: String::new(),
+            message_input: String::new(),
+            new_conversation: None,
+            thread: None,
+            thread_index: 0,
+            thread_replying: false,
+            thread_reply_input: String::new(),
             selected_index: 0,
             compose_text: String::new(),
-            compose_relay_selection: vec![
-                ("wss://relay.damus.io".to_string(), true),
-                ("wss://nos.lol".to_string(), true),
-                ("wss://relay.snort.social".to_string(), false),
-            ],
+            compose_quote: None,
+            compose_relay_selection: default_compose_relays(),
             compose_focus: ComposeFocus::Text,
         })
     }
 
+    /// Leave notes with less than `min_pow` bits of proof of work out of the feed
+    pub fn with_min_pow(mut self, min_pow: u32) -> Self {
+        self.min_pow = min_pow;
+        self.feed = Feed::new().with_min_difficulty(min_pow);
+        self
+    }
+
     /// Handle keyboard input events
     pub fn handle_input(&mut self, key: KeyEvent) -> Result<bool> {
+        if self.handle_auth_answer(key) {
+            return Ok(false);
+        }
+
         // Handle global shortcuts first
         if self.handle_global_shortcuts(key)? {
             return Ok(true); // Exit requested
@@ -100,13 +316,64 @@ impl App {
             CurrentView::AccountModal => self.handle_account_modal_input(key)?,
             CurrentView::ComposeModal => self.handle_compose_modal_input(key)?,
             CurrentView::HelpModal => self.handle_help_modal_input(key)?,
+            CurrentView::Messages => self.handle_messages_input(key),
+            CurrentView::Thread => self.handle_thread_input(key),
         }
 
         Ok(false)
     }
 
+    /// The relay whose authentication request is shown in a prompt, which
+    /// waits while text is being typed
+    pub fn auth_prompt(&self) -> Option<&str> {
+        if self.is_editing_text() {
+            return None;
+        }
+        self.auth_requests.first().map(String::as_str)
+    }
+
+    /// While the authentication prompt is shown, it takes every key: y or
+    /// Enter answers the oldest request with yes, n or Esc with no
+    fn handle_auth_answer(&mut self, key: KeyEvent) -> bool {
+        if self.auth_prompt().is_none() {
+            return false;
+        }
+        // Ctrl+C still quits
+        if key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL) {
+            return false;
+        }
+
+        let approved = match key.code {
+            KeyCode::Char('y') | KeyCode::Enter => true,
+            KeyCode::Char('n') | KeyCode::Esc => false,
+            _ => return true,
+        };
+
+        let relay_url = self.auth_requests.remove(0);
+        if let Err(e) = self.relay_pool.answer_auth(&relay_url, approved) {
+            self.status_message = Some(format!("Failed to answer {}: {}", relay_url, e));
+            return true;
+        }
+
+        self.status_message = Some(match self.auth_requests.first() {
+            Some(next) => auth_request_prompt(next),
+            None if approved => format!("Authenticating to {}...", relay_url),
+            None => format!("Not authenticating to {}", relay_url),
+        });
+        true
+    }
+
     /// Handle global keyboard shortcuts available from any view
     fn handle_global_shortcuts(&mut self, key: KeyEvent) -> Result<bool> {
+        if self.is_editing_text() {
+            // Letters belong to the text field; Esc is handled by the view
+            if key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL) {
+                self.should_quit = true;
+                return Ok(true);
+            }
+            return Ok(false);
+        }
+
         match key.code {
             KeyCode::Char('q') => {
                 self.should_quit = true;
@@ -117,27 +384,36 @@ impl App {
                 return Ok(true);
             }
             KeyCode::Char('?') => {
-                self.current_view = CurrentView::HelpModal;
+                self.show_view(CurrentView::HelpModal);
             }
             KeyCode::Char('a') => {
-                self.current_view = CurrentView::AccountModal;
+                self.show_view(CurrentView::AccountModal);
             }
             KeyCode::Char('n') => {
                 if self.keystore_unlocked {
-                    self.current_view = CurrentView::ComposeModal;
+                    self.show_view(CurrentView::ComposeModal);
                     self.compose_text.clear();
+                    self.compose_quote = None;
                     self.compose_focus = ComposeFocus::Text;
                 } else {
                     self.status_message = Some("Please unlock accounts first (press 'a')".to_string());
                 }
             }
+            KeyCode::Char('m') => {
+                if self.keystore_unlocked {
+                    self.show_view(CurrentView::Messages);
+                    self.messages_focus = MessagesFocus::Conversations;
+                } else {
+                    self.status_message = Some("Please unlock accounts first (press 'a')".to_string());
+                }
+            }
             KeyCode::Char('r') => {
                 self.refresh_view();
             }
             KeyCode::Esc => {
                 // Return to feed from any modal
                 if self.current_view != CurrentView::Feed {
-                    self.current_view = CurrentView::Feed;
+                    self.show_view(CurrentView::Feed);
                     self.password_prompt_active = false;
                     self.password_input.clear();
                 }
@@ -148,59 +424,706 @@ impl App {
         Ok(false)
     }
 
+    /// Whether keystrokes go into a text field, so single-letter shortcuts must not fire
+    fn is_editing_text(&self) -> bool {
+        self.password_prompt_active
+            || (self.current_view == CurrentView::ComposeModal && self.compose_focus == ComposeFocus::Text)
+            || (self.current_view == CurrentView::AccountModal && self.account_mode != AccountModalMode::List)
+            || (self.current_view == CurrentView::Messages && self.messages_focus != MessagesFocus::Conversations)
+            || (self.current_view == CurrentView::Thread && self.thread_replying)
+    }
+
     /// Handle input when in feed view
     fn handle_feed_input(&mut self, key: KeyEvent) -> Result<()> {
         match key.code {
             KeyCode::Up | KeyCode::Char('k') => {
-                if self.selected_index > 0 {
-                    self.selected_index -= 1;
-                }
+                self.selected_index = self.selected_index.saturating_sub(1);
             }
             KeyCode::Down | KeyCode::Char('j') => {
-                if self.selected_index < self.feed_items.len().saturating_sub(1) {
-                    self.selected_index += 1;
-                }
+                self.selected_index = (self.selected_index + 1).min(self.feed.len().saturating_sub(1));
             }
             KeyCode::Home | KeyCode::Char('g') => {
                 self.selected_index = 0;
             }
             KeyCode::End | KeyCode::Char('G') => {
-                self.selected_index = self.feed_items.len().saturating_sub(1);
+                self.selected_index = self.feed.len().saturating_sub(1);
+            }
+            KeyCode::Char('f') => self.set_following_selected_author(true),
+            KeyCode::Char('u') => self.set_following_selected_author(false),
+            KeyCode::Char('+') => self.react_to_selected(Reaction::Like),
+            KeyCode::Char('-') => self.react_to_selected(Reaction::Dislike),
+            KeyCode::Char(c @ '1'..='3') => {
+                let emoji = QUICK_REACTIONS[c as usize - '1' as usize];
+                self.react_to_selected(Reaction::Emoji(emoji.to_string()));
+            }
+            KeyCode::Char('b') => self.repost_selected(),
+            KeyCode::Char('Q') => self.quote_selected(),
+            KeyCode::Enter => {
+                if let Some(note) = self.feed.notes().get(self.selected_index).cloned() {
+                    self.open_thread(&note);
+                }
             }
             _ => {}
         }
         Ok(())
     }
 
+    /// React to the selected note (NIP-25)
+    fn react_to_selected(&mut self, reaction: Reaction) {
+        let Some(note) = self.feed.notes().get(self.selected_index).cloned() else {
+            return;
+        };
+        let keypair = match self.account_manager.get_active_account() {
+            Ok(Some(account)) => account.keypair,
+            _ => {
+                self.status_message = Some("No active account. Create or import one first (press 'a')".to_string());
+                return;
+            }
+        };
+        let pubkey = keypair.public_key_hex();
+        if self.interactions.get(&note.id).is_some_and(|counts| counts.has_reacted(&pubkey, &reaction)) {
+            self.status_message = Some("You already reacted to this note".to_string());
+            return;
+        }
+
+        let relay_hint = self.outbox.relay_list(&note.pubkey).and_then(|list| list.write_relays().into_iter().next());
+        match UnsignedEvent::new_reaction(&note, &reaction, relay_hint.as_deref(), pubkey).sign(&keypair) {
+            Ok(event) => {
+                self.publish_interaction(event, |outcomes| AppEvent::ReactionPublished { outcomes });
+                self.status_message = Some(format!("Reacted to {}'s note", self.feed.display_name(&note.pubkey)));
+            }
+            Err(e) => self.status_message = Some(format!("Failed to sign reaction: {}", e)),
+        }
+    }
+
+    /// Repost the selected note (NIP-18)
+    fn repost_selected(&mut self) {
+        let Some(note) = self.feed.notes().get(self.selected_index).cloned() else {
+            return;
+        };
+        let keypair = match self.account_manager.get_active_account() {
+            Ok(Some(account)) => account.keypair,
+            _ => {
+                self.status_message = Some("No active account. Create or import one first (press 'a')".to_string());
+                return;
+            }
+        };
+        let pubkey = keypair.public_key_hex();
+        if self.interactions.get(&note.id).is_some_and(|counts| counts.has_reposted(&pubkey)) {
+            self.status_message = Some("You already reposted this note".to_string());
+            return;
+        }
+
+        let relay_hint = self.outbox.relay_list(&note.pubkey).and_then(|list| list.write_relays().into_iter().next());
+        match UnsignedEvent::new_repost(&note, relay_hint.as_deref(), pubkey).and_then(|repost| repost.sign(&keypair)) {
+            Ok(event) => {
+                self.publish_interaction(event, |outcomes| AppEvent::RepostPublished { outcomes });
+                self.status_message = Some(format!("Reposted {}'s note", self.feed.display_name(&note.pubkey)));
+            }
+            Err(e) => self.status_message = Some(format!("Failed to sign repost: {}", e)),
+        }
+    }
+
+    /// Count a reaction or repost of our own right away, as if a relay had
+    /// sent it, and publish it to our write relays and the note author's inboxes
+    fn publish_interaction(&mut self, event: NostrEvent, published: fn(Vec<PublishOutcome>) -> AppEvent) {
+        let _ = self.event_store.save(&event);
+        self.handle_event(HOME_INTERACTIONS_SUBSCRIPTION, event.clone());
+
+        let relay_urls = self.outbox.publish_relays(&self.write_relays(), &event);
+        let relay_pool = self.relay_pool.clone();
+        let app_events = self.app_events.clone();
+        tokio::spawn(async move {
+            let outcomes = relay_pool.publish_to(&relay_urls, &event).await;
+            let _ = app_events.send(published(outcomes));
+        });
+    }
+
+    /// Open the compose modal to write a post quoting the selected note
+    fn quote_selected(&mut self) {
+        let Some(note) = self.feed.notes().get(self.selected_index).cloned() else {
+            return;
+        };
+        if !self.keystore_unlocked {
+            self.status_message = Some("Please unlock accounts first (press 'a')".to_string());
+            return;
+        }
+
+        self.compose_quote = Some(note);
+        self.compose_text.clear();
+        self.compose_focus = ComposeFocus::Text;
+        self.show_view(CurrentView::ComposeModal);
+    }
+
+    /// Show the thread `note` belongs to, with `note` selected: whatever the
+    /// event store has right away, then the root and every reply to it the
+    /// relays send
+    fn open_thread(&mut self, note: &NostrEvent) {
+        let root_id = ThreadRefs::from_event(note).root_id(note).to_string();
+        let filters = vec![
+            Filter::new().with_ids(vec![root_id.clone(), note.id.clone()]),
+            Filter::new().with_kinds(vec![1]).with_event_refs(vec![root_id.clone()]),
+            // The root's deletion, even when its author isn't followed
+            Filter::new().with_kinds(vec![KIND_DELETION]).with_event_refs(vec![root_id.clone()]),
+        ];
+
+        self.thread = Some(Thread::new(root_id));
+        self.thread_replying = false;
+        self.thread_reply_input.clear();
+        self.handle_event(THREAD_SUBSCRIPTION, note.clone());
+        self.load_cached(THREAD_SUBSCRIPTION, &filters);
+        self.thread_index = self.thread.as_ref().and_then(|thread| thread.position(&note.id)).unwrap_or(0);
+        self.show_view(CurrentView::Thread);
+
+        if let Err(e) = self.relay_pool.subscribe_with_id(THREAD_SUBSCRIPTION, filters) {
+            self.status_message = Some(format!("Failed to load thread: {}", e));
+        }
+    }
+
+    /// Switch to another view. Leaving the thread view closes its
+    /// subscription, whichever view comes next.
+    fn show_view(&mut self, view: CurrentView) {
+        if self.current_view == CurrentView::Thread && view != CurrentView::Thread {
+            self.close_thread();
+        }
+        self.current_view = view;
+    }
+
+    fn close_thread(&mut self) {
+        self.relay_pool.unsubscribe(THREAD_SUBSCRIPTION);
+        self.thread = None;
+        self.thread_index = 0;
+        self.thread_replying = false;
+        self.thread_reply_input.clear();
+    }
+
+    /// Handle input in the thread view
+    fn handle_thread_input(&mut self, key: KeyEvent) {
+        if self.thread_replying {
+            match key.code {
+                KeyCode::Enter => self.send_thread_reply(),
+                KeyCode::Char(c) => self.thread_reply_input.push(c),
+                KeyCode::Backspace => {
+                    self.thread_reply_input.pop();
+                }
+                KeyCode::Esc => self.thread_replying = false,
+                _ => {}
+            }
+            return;
+        }
+
+        let len = self.thread.as_ref().map_or(0, Thread::len);
+        match key.code {
+            KeyCode::Up | KeyCode::Char('k') => {
+                self.thread_index = self.thread_index.saturating_sub(1);
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                self.thread_index = (self.thread_index + 1).min(len.saturating_sub(1));
+            }
+            KeyCode::Enter | KeyCode::Char('i') if len > 0 => {
+                if self.keystore_unlocked {
+                    self.thread_replying = true;
+                } else {
+                    self.status_message = Some("Please unlock accounts first (press 'a')".to_string());
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Publish the typed reply to the selected note of the thread, tagged
+    /// (NIP-10) so other clients place it in the same thread
+    fn send_thread_reply(&mut self) {
+        let content = self.thread_reply_input.trim().to_string();
+        if content.is_empty() {
+            return;
+        }
+        let Some(parent) = self
+            .thread
+            .as_ref()
+            .and_then(|thread| thread.get(self.thread_index))
+            .map(|entry| entry.event.clone())
+        else {
+            return;
+        };
+
+        let keypair = match self.account_manager.get_active_account() {
+            Ok(Some(account)) => account.keypair,
+            Ok(None) => {
+                self.status_message = Some("No active account. Create or import one first (press 'a')".to_string());
+                return;
+            }
+            Err(e) => {
+                self.status_message = Some(format!("Failed to load account: {}", e));
+                return;
+            }
+        };
+
+        let relay_hint = self.outbox.relay_list(&parent.pubkey).and_then(|list| list.write_relays().into_iter().next());
+        let event = match UnsignedEvent::new_text_note(content, keypair.public_key_hex())
+            .with_reply_to(&parent, relay_hint.as_deref())
+            .sign(&keypair)
+        {
+            Ok(event) => event,
+            Err(e) => {
+                self.status_message = Some(format!("Failed to sign reply: {}", e));
+                return;
+            }
+        };
+
+        // Show it in the thread right away, as if a relay had sent it
+        let _ = self.event_store.save(&event);
+        self.handle_event(THREAD_SUBSCRIPTION, event.clone());
+        if let Some(index) = self.thread.as_ref().and_then(|thread| thread.position(&event.id)) {
+            self.thread_index = index;
+        }
+        self.thread_reply_input.clear();
+        self.thread_replying = false;
+
+        // Also sent to the inboxes of everyone tagged, so they see the reply
+        let relay_urls = self.outbox.publish_relays(&self.write_relays(), &event);
+        self.status_message = Some(format!("Publishing reply to {} relays...", relay_urls.len()));
+
+        let relay_pool = self.relay_pool.clone();
+        let app_events = self.app_events.clone();
+        tokio::spawn(async move {
+            let outcomes = relay_pool.publish_to(&relay_urls, &event).await;
+            let _ = app_events.send(AppEvent::PublishFinished {
+                event_id: event.id,
+                outcomes,
+            });
+        });
+    }
+
+    /// Follow or unfollow the author of the selected note, publishing the
+    /// updated contact list (NIP-02) to the active account's write relays
+    fn set_following_selected_author(&mut self, follow: bool) {
+        let Some(pubkey) = self.feed.notes().get(self.selected_index).map(|note| note.pubkey.clone()) else {
+            return;
+        };
+        let keypair = match self.account_manager.get_active_account() {
+            Ok(Some(account)) => account.keypair,
+            _ => {
+                self.status_message = Some("No active account. Create or import one first (press 'a')".to_string());
+                return;
+            }
+        };
+        if pubkey == keypair.public_key_hex() {
+            self.status_message = Some("That's your own note".to_string());
+            return;
+        }
+        if !self.contacts_loaded {
+            self.status_message = Some("Your contact list hasn't loaded yet, try again in a moment".to_string());
+            return;
+        }
+
+        let published = self.feed.contact_list().cloned().unwrap_or_default();
+        let mut contact_list = published.clone();
+        let name = self.feed.display_name(&pubkey);
+        let changed = if follow {
+            let relay_hint = self.outbox.relay_list(&pubkey).and_then(|list| list.write_relays().into_iter().next());
+            contact_list.follow(&pubkey, relay_hint.as_deref(), None)
+        } else {
+            contact_list.unfollow(&pubkey)
+        };
+        if !changed {
+            self.status_message = Some(if follow {
+                format!("Already following {}", name)
+            } else {
+                format!("Not following {}", name)
+            });
+            return;
+        }
+        if let Err(e) = contact_list.check_replaces(&published) {
+            self.status_message = Some(format!("Contact list not published: {}", e));
+            return;
+        }
+
+        let event = match contact_list.to_event(&keypair) {
+            Ok(event) => event,
+            Err(e) => {
+                self.status_message = Some(format!("Failed to sign contact list: {}", e));
+                return;
+            }
+        };
+
+        // Apply it locally right away, as if a relay had sent it
+        let _ = self.event_store.save(&event);
+        self.handle_event(HOME_CONTACTS_SUBSCRIPTION, event.clone());
+
+        // Sent to our own relays only; tagging everyone we follow is not a mention
+        let relay_urls = self.write_relays();
+        let relay_pool = self.relay_pool.clone();
+        let app_events = self.app_events.clone();
+        tokio::spawn(async move {
+            let outcomes = relay_pool.publish_to(&relay_urls, &event).await;
+            let _ = app_events.send(AppEvent::ContactListPublished { outcomes });
+        });
+
+        self.status_message = Some(if follow {
+            format!("Following {}", name)
+        } else {
+            format!("Unfollowed {}", name)
+        });
+    }
+
     /// Handle input when in account modal
     fn handle_account_modal_input(&mut self, key: KeyEvent) -> Result<()> {
         if self.password_prompt_active {
-            self.handle_password_input(key)?;
-        } else {
-            match key.code {
-                KeyCode::Char('u') => {
-                    // Unlock keystore
-                    self.password_prompt_active = true;
-                    self.password_input.clear();
-                    self.status_message = Some("Enter password to unlock keystore:".to_string());
+            return self.handle_password_input(key);
+        }
+
+        match self.account_mode {
+            AccountModalMode::List => self.handle_account_list_input(key),
+            AccountModalMode::CreateName | AccountModalMode::ImportName => self.handle_account_name_input(key),
+            AccountModalMode::ImportKey => self.handle_import_key_input(key),
+            AccountModalMode::ConfirmDelete => self.handle_delete_confirmation_input(key),
+            AccountModalMode::EditProfile => self.handle_profile_input(key),
+        }
+
+        Ok(())
+    }
+
+    /// Handle input while browsing the account list
+    fn handle_account_list_input(&mut self, key: KeyEvent) {
+        match key.code {
+            KeyCode::Char('u') => {
+                // Unlock keystore
+                self.password_prompt_active = true;
+                self.password_input.clear();
+                self.status_message = Some("Enter password to unlock keystore:".to_string());
+            }
+            KeyCode::Char('l') => {
+                // Lock keystore
+                self.account_manager.lock_keystore();
+                self.keystore_unlocked = false;
+                self.session_password = None;
+                self.stop_home_feed();
+                self.status_message = Some("Keystore locked".to_string());
+            }
+            _ if !self.keystore_unlocked => {
+                if matches!(key.code, KeyCode::Char('c' | 'i' | 'd' | 's' | 'p') | KeyCode::Enter) {
+                    self.status_message = Some("Please unlock keystore first".to_string());
                 }
-                KeyCode::Char('l') => {
-                    // Lock keystore
-                    self.account_manager.lock_keystore();
-                    self.keystore_unlocked = false;
-                    self.status_message = Some("Keystore locked".to_string());
+            }
+            KeyCode::Char('c') => {
+                self.account_name_input.clear();
+                self.account_mode = AccountModalMode::CreateName;
+                self.status_message = Some("Name the new account".to_string());
+            }
+            KeyCode::Char('i') => {
+                self.import_key_input.clear();
+                self.account_mode = AccountModalMode::ImportKey;
+                self.status_message = Some("Paste the private key (nsec or hex) to import".to_string());
+            }
+            KeyCode::Up | KeyCode::Char('k') => {
+                self.account_index = self.account_index.saturating_sub(1);
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                let account_count = self.account_manager.list_accounts().len();
+                self.account_index = (self.account_index + 1).min(account_count.saturating_sub(1));
+            }
+            KeyCode::Enter | KeyCode::Char('s') => self.select_account(),
+            KeyCode::Char('p') => self.edit_profile(),
+            KeyCode::Char('d') => {
+                if let Some(account) = self.account_manager.list_accounts().get(self.account_index) {
+                    self.status_message = Some(format!("Delete account '{}'? (y/n)", account.name));
+                    self.account_mode = AccountModalMode::ConfirmDelete;
                 }
-                KeyCode::Char('c') => {
-                    if self.keystore_unlocked {
-                        self.status_message = Some("Create account feature coming soon!".to_string());
-                    } else {
-                        self.status_message = Some("Please unlock keystore first".to_string());
-                    }
+            }
+            _ => {}
+        }
+    }
+
+    /// Handle typing the name of an account being created or imported
+    fn handle_account_name_input(&mut self, key: KeyEvent) {
+        match key.code {
+            KeyCode::Enter => {
+                let name = self.account_name_input.trim().to_string();
+                if name.is_empty() {
+                    self.status_message = Some("Account name cannot be empty".to_string());
+                    return;
+                }
+
+                match self.account_mode {
+                    AccountModalMode::CreateName => self.create_account(&name),
+                    _ => self.import_account(&name),
                 }
-                _ => {}
             }
+            KeyCode::Char(c) => {
+                self.account_name_input.push(c);
+            }
+            KeyCode::Backspace => {
+                self.account_name_input.pop();
+            }
+            KeyCode::Esc => self.cancel_account_form(),
+            _ => {}
+        }
+    }
+
+    /// Handle pasting the private key of an account to import
+    fn handle_import_key_input(&mut self, key: KeyEvent) {
+        match key.code {
+            KeyCode::Enter => match keypair_from_secret(&self.import_key_input) {
+                Ok(keypair) => {
+                    self.import_key_input.clear();
+                    self.pending_import_key = Some(SecretString::from(keypair.secret_key_hex()));
+                    self.account_name_input.clear();
+                    self.account_mode = AccountModalMode::ImportName;
+                    self.status_message = Some("Name the imported account".to_string());
+                }
+                Err(e) => {
+                    self.import_key_input.clear();
+                    self.status_message = Some(format!("Invalid private key: {}", e));
+                }
+            },
+            KeyCode::Char(c) => {
+                self.import_key_input.push(c);
+            }
+            KeyCode::Backspace => {
+                self.import_key_input.pop();
+            }
+            KeyCode::Esc => self.cancel_account_form(),
+            _ => {}
+        }
+    }
+
+    /// Handle the y/n answer before deleting the selected account
+    fn handle_delete_confirmation_input(&mut self, key: KeyEvent) {
+        match key.code {
+            KeyCode::Char('y') | KeyCode::Char('Y') => self.delete_selected_account(),
+            KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => self.cancel_account_form(),
+            _ => {}
+        }
+    }
+
+    /// Handle typing in the profile editor
+    fn handle_profile_input(&mut self, key: KeyEvent) {
+        match key.code {
+            KeyCode::Enter => self.publish_profile(),
+            KeyCode::Up | KeyCode::BackTab => {
+                self.profile_field = self.profile_field.saturating_sub(1);
+            }
+            KeyCode::Down | KeyCode::Tab => {
+                self.profile_field = (self.profile_field + 1).min(METADATA_FIELDS.len() - 1);
+            }
+            KeyCode::Char(c) => {
+                if let Some(input) = self.profile_inputs.get_mut(self.profile_field) {
+                    input.push(c);
+                }
+            }
+            KeyCode::Backspace => {
+                if let Some(input) = self.profile_inputs.get_mut(self.profile_field) {
+                    input.pop();
+                }
+            }
+            KeyCode::Esc => self.cancel_account_form(),
+            _ => {}
+        }
+    }
+
+    fn cancel_account_form(&mut self) {
+        self.account_mode = AccountModalMode::List;
+        self.account_name_input.clear();
+        self.import_key_input.clear();
+        self.pending_import_key = None;
+        self.profile_draft = None;
+        self.profile_inputs.clear();
+        self.status_message = Some("Cancelled".to_string());
+    }
+
+    fn create_account(&mut self, name: &str) {
+        let Some(password) = self.session_password.clone() else {
+            self.status_message = Some("Please unlock keystore first".to_string());
+            return;
+        };
+
+        let was_active = self.account_manager.active_account_id().cloned();
+        match self.account_manager.create_account(name, &password) {
+            Ok(account) => {
+                self.status_message = Some(format!("Created account '{}'", account.name));
+                self.finish_account_change(was_active);
+            }
+            Err(e) => self.status_message = Some(format!("Failed to create account: {}", e)),
         }
-        Ok(())
+    }
+
+    fn import_account(&mut self, name: &str) {
+        let (Some(password), Some(secret_key)) = (self.session_password.clone(), self.pending_import_key.take()) else {
+            self.cancel_account_form();
+            return;
+        };
+
+        let was_active = self.account_manager.active_account_id().cloned();
+        match self.account_manager.import_account(name, secret_key.expose_secret(), &password) {
+            Ok(account) => {
+                self.status_message = Some(format!("Imported account '{}'", account.name));
+                self.finish_account_change(was_active);
+            }
+            Err(e) => {
+                self.account_mode = AccountModalMode::List;
+                self.status_message = Some(format!("Failed to import account: {}", e));
+            }
+        }
+    }
+
+    /// Make the highlighted account the active one
+    fn select_account(&mut self) {
+        let Some(account) = self.account_manager.list_accounts().get(self.account_index).cloned() else {
+            return;
+        };
+
+        match self.account_manager.set_active_account(&account.id) {
+            Ok(()) => {
+                self.status_message = Some(format!("Switched to account '{}'", account.name));
+                self.start_home_feed();
+            }
+            Err(e) => self.status_message = Some(format!("Failed to switch account: {}", e)),
+        }
+    }
+
+    fn delete_selected_account(&mut self) {
+        let Some(password) = self.session_password.clone() else {
+            self.cancel_account_form();
+            return;
+        };
+        let Some(account) = self.account_manager.list_accounts().get(self.account_index).cloned() else {
+            self.cancel_account_form();
+            return;
+        };
+
+        let was_active = self.account_manager.active_account_id().cloned();
+        match self.account_manager.delete_account(&account.id, &password) {
+            Ok(()) => {
+                self.status_message = Some(format!("Deleted account '{}'", account.name));
+                self.finish_account_change(was_active);
+            }
+            Err(e) => {
+                self.account_mode = AccountModalMode::List;
+                self.status_message = Some(format!("Failed to delete account: {}", e));
+            }
+        }
+    }
+
+    /// Return to the account list after an account was added or removed, and
+    /// restart the feed if that changed the active account
+    fn finish_account_change(&mut self, previously_active: Option<String>) {
+        self.account_mode = AccountModalMode::List;
+        self.account_name_input.clear();
+
+        let account_count = self.account_manager.list_accounts().len();
+        self.account_index = self.account_index.min(account_count.saturating_sub(1));
+
+        if self.account_manager.active_account_id() != previously_active.as_ref() {
+            self.start_home_feed();
+        }
+    }
+
+    /// Fetch the active account's newest profile from its write relays, then
+    /// open the profile editor with it, see `open_profile_editor`
+    fn edit_profile(&mut self) {
+        let pubkey = match self.account_manager.get_active_account() {
+            Ok(Some(account)) => account.info.public_key_hex,
+            _ => {
+                self.status_message = Some("Select an account first to edit its profile".to_string());
+                return;
+            }
+        };
+
+        self.profile_draft = None;
+        let relay_urls = self.write_relays();
+        let filter = Filter::new().with_kinds(vec![KIND_METADATA]).with_authors(vec![pubkey.clone()]);
+        let relay_pool = self.relay_pool.clone();
+        let app_events = self.app_events.clone();
+        tokio::spawn(async move {
+            let result = relay_pool.fetch_from(&relay_urls, vec![filter], PROFILE_FETCH_TIMEOUT).await;
+            let _ = app_events.send(AppEvent::ProfileFetched { pubkey, result });
+        });
+        self.status_message = Some("Loading your profile...".to_string());
+    }
+
+    /// Open the profile editor with the newest of the fetched and cached
+    /// profiles, unless the user moved on to another account or view meanwhile
+    fn open_profile_editor(&mut self, pubkey: String, fetched: Vec<NostrEvent>) {
+        let is_active = self
+            .account_manager
+            .get_active_account()
+            .is_ok_and(|account| account.is_some_and(|account| account.info.public_key_hex == pubkey));
+        if !is_active || self.current_view != CurrentView::AccountModal || self.account_mode != AccountModalMode::List {
+            return;
+        }
+
+        for event in fetched.iter().filter(|event| event.pubkey == pubkey && event.kind == KIND_METADATA) {
+            let _ = self.event_store.save(event);
+            self.feed.update_profile(event);
+        }
+        self.load_cached_profiles(std::slice::from_ref(&pubkey));
+
+        let draft = self.feed.profile(&pubkey).cloned().unwrap_or_default();
+        self.profile_inputs = METADATA_FIELDS
+            .iter()
+            .map(|field| draft.get(field).unwrap_or_default().to_string())
+            .collect();
+        self.profile_draft = Some(draft);
+        self.profile_field = 0;
+        self.account_mode = AccountModalMode::EditProfile;
+        self.status_message = Some("Edit your profile; Enter publishes it".to_string());
+    }
+
+    /// Sign the edited profile with the active account and publish it to its write relays
+    fn publish_profile(&mut self) {
+        let account = match self.account_manager.get_active_account() {
+            Ok(Some(account)) => account,
+            Ok(None) => {
+                self.status_message = Some("No active account".to_string());
+                return;
+            }
+            Err(e) => {
+                self.status_message = Some(format!("Failed to load account: {}", e));
+                return;
+            }
+        };
+
+        let Some(mut metadata) = self.profile_draft.clone() else {
+            self.status_message = Some("Your profile hasn't loaded yet, try again in a moment".to_string());
+            return;
+        };
+        for (field, input) in METADATA_FIELDS.iter().zip(&self.profile_inputs) {
+            let _ = metadata.set(field, input);
+        }
+        let event = match metadata.to_event(&account.keypair) {
+            Ok(event) => event,
+            Err(e) => {
+                self.status_message = Some(format!("Failed to sign profile: {}", e));
+                return;
+            }
+        };
+
+        let _ = self.event_store.save(&event);
+        self.feed.update_profile(&event);
+
+        let relay_urls = self.outbox.publish_relays(&self.write_relays(), &event);
+        let relay_pool = self.relay_pool.clone();
+        let app_events = self.app_events.clone();
+        tokio::spawn(async move {
+            let outcomes = relay_pool.publish_to(&relay_urls, &event).await;
+            let _ = app_events.send(AppEvent::ProfilePublished { outcomes });
+        });
+
+        self.account_mode = AccountModalMode::List;
+        self.profile_draft = None;
+        self.profile_inputs.clear();
+        self.status_message = Some("Publishing profile...".to_string());
+    }
+
+    /// The relays selected for posting: the active account's write relays
+    /// once its relay list is known
+    fn write_relays(&self) -> Vec<String> {
+        self.compose_relay_selection
+            .iter()
+            .filter(|(_, selected)| *selected)
+            .map(|(relay_url, _)| relay_url.clone())
+            .collect()
     }
 
     /// Handle password input
@@ -212,9 +1135,11 @@ impl App {
                 match self.account_manager.unlock_keystore(&password) {
                     Ok(()) => {
                         self.keystore_unlocked = true;
+                        self.session_password = Some(password);
                         self.password_prompt_active = false;
                         self.password_input.clear();
                         self.status_message = Some("Keystore unlocked successfully!".to_string());
+                        self.start_home_feed();
                     }
                     Err(e) => {
                         self.status_message = Some(format!("Failed to unlock: {}", e));
@@ -258,6 +1183,9 @@ impl App {
                     KeyCode::Backspace => {
                         self.compose_text.pop();
                     }
+                    KeyCode::Esc => {
+                        self.show_view(CurrentView::Feed);
+                    }
                     _ => {}
                 }
             }
@@ -267,14 +1195,11 @@ impl App {
                         self.compose_focus = ComposeFocus::Text;
                     }
                     KeyCode::Up | KeyCode::Char('k') => {
-                        if self.selected_index > 0 {
-                            self.selected_index -= 1;
-                        }
+                        self.selected_index = self.selected_index.saturating_sub(1);
                     }
                     KeyCode::Down | KeyCode::Char('j') => {
-                        if self.selected_index < self.compose_relay_selection.len().saturating_sub(1) {
-                            self.selected_index += 1;
-                        }
+                        self.selected_index = (self.selected_index + 1)
+                            .min(self.compose_relay_selection.len().saturating_sub(1));
                     }
                     KeyCode::Enter | KeyCode::Char(' ') => {
                         // Toggle selected relay
@@ -295,6 +1220,142 @@ impl App {
         Ok(())
     }
 
+    /// Handle input in the messages view
+    fn handle_messages_input(&mut self, key: KeyEvent) {
+        match self.messages_focus {
+            MessagesFocus::Conversations => match key.code {
+                KeyCode::Up | KeyCode::Char('k') => {
+                    self.conversation_index = self.conversation_index.saturating_sub(1);
+                    self.new_conversation = None;
+                }
+                KeyCode::Down | KeyCode::Char('j') => {
+                    self.conversation_index =
+                        (self.conversation_index + 1).min(self.conversations.len().saturating_sub(1));
+                    self.new_conversation = None;
+                }
+                KeyCode::Enter | KeyCode::Char('i') if self.selected_participants().is_some() => {
+                    self.messages_focus = MessagesFocus::Reply;
+                }
+                KeyCode::Char('c') => {
+                    self.recipient_input.clear();
+                    self.messages_focus = MessagesFocus::Recipient;
+                }
+                _ => {}
+            },
+            MessagesFocus::Recipient => match key.code {
+                KeyCode::Enter => match public_key_from_str(&self.recipient_input) {
+                    Ok(pubkey) => {
+                        let participants = vec![pubkey];
+                        match self.conversations.position(&participants) {
+                            Some(index) => {
+                                self.conversation_index = index;
+                                self.new_conversation = None;
+                            }
+                            None => self.new_conversation = Some(participants),
+                        }
+                        self.recipient_input.clear();
+                        self.messages_focus = MessagesFocus::Reply;
+                    }
+                    Err(e) => self.status_message = Some(format!("Invalid public key: {}", e)),
+                },
+                KeyCode::Char(c) => self.recipient_input.push(c),
+                KeyCode::Backspace => {
+                    self.recipient_input.pop();
+                }
+                KeyCode::Esc => self.messages_focus = MessagesFocus::Conversations,
+                _ => {}
+            },
+            MessagesFocus::Reply => match key.code {
+                KeyCode::Enter => self.send_direct_message(),
+                KeyCode::Char(c) => self.message_input.push(c),
+                KeyCode::Backspace => {
+                    self.message_input.pop();
+                }
+                KeyCode::Esc => self.messages_focus = MessagesFocus::Conversations,
+                _ => {}
+            },
+        }
+    }
+
+    /// Public key of the account whose feed and messages are shown
+    pub fn home_pubkey(&self) -> Option<&str> {
+        self.home_pubkey.as_deref()
+    }
+
+    /// Participants of the conversation shown in the messages view
+    pub fn selected_participants(&self) -> Option<Vec<String>> {
+        self.new_conversation.clone().or_else(|| {
+            self.conversations
+                .get(self.conversation_index)
+                .map(|conversation| conversation.participants.clone())
+        })
+    }
+
+    /// Gift wrap the typed message for everyone in the selected conversation
+    /// and publish it to every relay in the pool
+    fn send_direct_message(&mut self) {
+        let content = self.message_input.trim().to_string();
+        if content.is_empty() {
+            return;
+        }
+        let Some(recipients) = self.selected_participants() else {
+            return;
+        };
+
+        let account = match self.account_manager.get_active_account() {
+            Ok(Some(account)) => account,
+            Ok(None) => {
+                self.status_message = Some("No active account. Create or import one first (press 'a')".to_string());
+                return;
+            }
+            Err(e) => {
+                self.status_message = Some(format!("Failed to load account: {}", e));
+                return;
+            }
+        };
+
+        let wraps = match wrap_private_message(&account.keypair, &recipients, &content) {
+            Ok(wraps) => wraps,
+            Err(e) => {
+                self.status_message = Some(format!("Failed to encrypt message: {}", e));
+                return;
+            }
+        };
+
+        self.message_input.clear();
+        self.status_message = Some("Sending message...".to_string());
+
+        // Each wrap only goes where its receiver reads, so other relays in the
+        // pool never learn who is messaging whom; our own copy to our relays
+        let own_pubkey = account.keypair.public_key_hex();
+        let write_relays = self.write_relays();
+        let wraps: Vec<(String, Vec<String>, NostrEvent)> = wraps
+            .into_iter()
+            .map(|(receiver, wrap)| {
+                let relay_urls = if receiver == own_pubkey {
+                    write_relays.clone()
+                } else {
+                    self.outbox.inbox_relays(&receiver, &write_relays)
+                };
+                (receiver, relay_urls, wrap)
+            })
+            .collect();
+
+        let relay_pool = self.relay_pool.clone();
+        let app_events = self.app_events.clone();
+        tokio::spawn(async move {
+            let mut outcomes = Vec::new();
+            for (receiver, relay_urls, wrap) in wraps {
+                let wrap_outcomes = relay_pool.publish_to(&relay_urls, &wrap).await;
+                // Our own copy comes back through the subscription; only report the recipients'
+                if receiver != own_pubkey {
+                    outcomes.extend(wrap_outcomes);
+                }
+            }
+            let _ = app_events.send(AppEvent::MessageSent { outcomes });
+        });
+    }
+
     /// Publish the composed post
     fn publish_post(&mut self) -> Result<()> {
         if self.compose_text.trim().is_empty() {
@@ -314,27 +1375,435 @@ impl App {
             return Ok(());
         }
 
-        // For now, just show a placeholder message
+        let account = match self.account_manager.get_active_account() {
+            Ok(Some(account)) => account,
+            Ok(None) => {
+                self.status_message = Some("No active account. Create or import one first (press 'a')".to_string());
+                return Ok(());
+            }
+            Err(e) => {
+                self.status_message = Some(format!("Failed to load account: {}", e));
+                return Ok(());
+            }
+        };
+
+        let unsigned = UnsignedEvent::new_text_note(self.compose_text.clone(), account.keypair.public_key_hex());
+        let unsigned = match &self.compose_quote {
+            Some(quoted) => {
+                let relay_hint =
+                    self.outbox.relay_list(&quoted.pubkey).and_then(|list| list.write_relays().into_iter().next());
+                unsigned.with_quote(quoted, relay_hint.as_deref())?
+            }
+            None => unsigned,
+        };
+        let event = unsigned.sign(&account.keypair)?;
+        let _ = self.event_store.save(&event);
+        let selected_relays = self.outbox.publish_relays(&selected_relays, &event);
+
         self.status_message = Some(format!(
             "Publishing to {} relays: {}",
             selected_relays.len(),
             selected_relays.join(", ")
         ));
 
-        // TODO: Integrate with actual posting functionality
+        let relay_pool = self.relay_pool.clone();
+        let app_events = self.app_events.clone();
+        tokio::spawn(async move {
+            let outcomes = relay_pool.publish_to(&selected_relays, &event).await;
+            let _ = app_events.send(AppEvent::PublishFinished {
+                event_id: event.id,
+                outcomes,
+            });
+        });
+
         // Clear compose modal and return to feed
         self.compose_text.clear();
-        self.current_view = CurrentView::Feed;
+        self.compose_quote = None;
+        self.show_view(CurrentView::Feed);
 
         Ok(())
     }
 
+    /// Handle the result of background work
+    pub fn handle_app_event(&mut self, event: AppEvent) {
+        match event {
+            AppEvent::PublishFinished { event_id, outcomes } => {
+                // Where a later deletion request has to go
+                let accepted_by: Vec<String> = outcomes
+                    .iter()
+                    .filter(|outcome| outcome.is_accepted())
+                    .map(|outcome| outcome.relay_url.clone())
+                    .collect();
+                let _ = self.event_store.record_published(&event_id, &accepted_by);
+                self.status_message = Some(format_publish_results(&event_id, &outcomes));
+            }
+            AppEvent::ProfileFetched { pubkey, result } => match result {
+                Ok(events) => self.open_profile_editor(pubkey, events),
+                Err(e) => self.status_message = Some(format!("Failed to load your profile: {}", e)),
+            },
+            AppEvent::ProfilePublished { outcomes } => {
+                self.status_message = Some(format_acceptance("Profile", &outcomes));
+            }
+            AppEvent::ContactListPublished { outcomes } => {
+                self.status_message = Some(format_acceptance("Contact list", &outcomes));
+            }
+            AppEvent::ReactionPublished { outcomes } => {
+                self.status_message = Some(format_acceptance("Reaction", &outcomes));
+            }
+            AppEvent::RepostPublished { outcomes } => {
+                self.status_message = Some(format_acceptance("Repost", &outcomes));
+            }
+            AppEvent::MessageSent { outcomes } => {
+                let accepted = outcomes.iter().filter(|outcome| outcome.is_accepted()).count();
+                self.status_message = Some(if accepted > 0 {
+                    format!("Message sent ({}/{} relays accepted)", accepted, outcomes.len())
+                } else {
+                    "Message was not accepted by any relay".to_string()
+                });
+            }
+        }
+    }
+
+    /// Handle a message or status change from the relay pool
+    pub fn handle_relay_notification(&mut self, notification: RelayNotification) {
+        if let RelayNotification::AuthRequested { relay_url, .. } = &notification {
+            if !self.auth_requests.contains(relay_url) {
+                self.auth_requests.push(relay_url.clone());
+            }
+            self.status_message = Some(auth_request_prompt(&self.auth_requests[0]));
+            return;
+        }
+
+        let (subscription_id, event) = match notification {
+            RelayNotification::Message {
+                message: RelayMessage::Event { subscription_id, event },
+                ..
+            } => (subscription_id, event),
+            RelayNotification::Message {
+                message: RelayMessage::EndOfStoredEvents { subscription_id },
+                ..
+            } => {
+                if subscription_id == HOME_CONTACTS_SUBSCRIPTION {
+                    self.contacts_loaded = true;
+                }
+                return;
+            }
+            _ => return,
+        };
+
+        if !event.verify() {
+            return;
+        }
+
+        // The cache is best effort; a failed write only costs a slower start
+        let _ = self.event_store.save(&event);
+        self.handle_event(&subscription_id, event);
+    }
+
+    /// Show cached events matching a subscription's filters as if a relay had just sent them
+    fn load_cached(&mut self, subscription_id: &str, filters: &[Filter]) {
+        let Ok(events) = self.event_store.query(filters) else {
+            return;
+        };
+
+        for event in events {
+            self.handle_event(subscription_id, event);
+        }
+    }
+
+    /// Pick up stored metadata for authors the feed has no profile of, so
+    /// people outside the follow list still show by name
+    fn load_cached_profiles(&mut self, pubkeys: &[String]) {
+        let unknown: Vec<String> = pubkeys.iter().filter(|pubkey| self.feed.profile(pubkey).is_none()).cloned().collect();
+        if unknown.is_empty() {
+            return;
+        }
+
+        let filter = Filter::new().with_kinds(vec![KIND_METADATA]).with_authors(unknown);
+        for event in self.event_store.query(&[filter]).unwrap_or_default() {
+            self.feed.update_profile(&event);
+        }
+    }
+
+    /// Route a verified event to the view its subscription feeds
+    fn handle_event(&mut self, subscription_id: &str, event: NostrEvent) {
+        match subscription_id {
+            HOME_CONTACTS_SUBSCRIPTION
+                if event.kind == KIND_CONTACT_LIST && self.home_pubkey.as_ref() == Some(&event.pubkey) =>
+            {
+                self.contacts_loaded = true;
+                if let Some(mut follows) = self.feed.update_contacts(&event) {
+                    if !follows.contains(&event.pubkey) {
+                        follows.push(event.pubkey.clone());
+                    }
+                    self.follow_authors(follows);
+                }
+            }
+            HOME_NOTES_SUBSCRIPTION | THREAD_SUBSCRIPTION if event.kind == KIND_DELETION => self.apply_deletion(&event),
+            HOME_NOTES_SUBSCRIPTION if event.kind == 1 => {
+                // Keep the selection on the same note when newer ones arrive above it
+                let is_above_selection = self.selected_index > 0
+                    && self
+                        .feed
+                        .notes()
+                        .get(self.selected_index)
+                        .is_some_and(|selected| event.created_at >= selected.created_at);
+
+                if !self.feed.insert_note(event) {
+                    return;
+                }
+                self.interactions_stale = true;
+                if is_above_selection {
+                    self.selected_index += 1;
+                }
+            }
+            HOME_PROFILES_SUBSCRIPTION if event.kind == KIND_METADATA => self.feed.update_profile(&event),
+            HOME_INTERACTIONS_SUBSCRIPTION
+                if matches!(event.kind, KIND_REACTION | KIND_REPOST | KIND_GENERIC_REPOST)
+                    && !self.feed.is_deleted(&event) =>
+            {
+                self.interactions.insert(&event);
+            }
+            HOME_RELAY_LISTS_SUBSCRIPTION if event.kind == KIND_RELAY_LIST => {
+                if !self.outbox.update(&event) {
+                    return;
+                }
+
+                // Post where the account says it writes
+                if self.home_pubkey.as_ref() == Some(&event.pubkey)
+                    && let Some(relay_list) = self.outbox.relay_list(&event.pubkey)
+                    && !relay_list.write_relays().is_empty()
+                {
+                    self.compose_relay_selection =
+                        relay_list.write_relays().into_iter().map(|relay_url| (relay_url, true)).collect();
+                }
+                self.routes_stale = true;
+            }
+            DIRECT_MESSAGES_SUBSCRIPTION
+                if event.kind == KIND_GIFT_WRAP || event.kind == KIND_ENCRYPTED_DIRECT_MESSAGE =>
+            {
+                self.receive_private_message(&event)
+            }
+            THREAD_SUBSCRIPTION if event.kind == 1 && !self.feed.is_deleted(&event) => {
+                let Some(thread) = self.thread.as_mut() else {
+                    return;
+                };
+
+                // Keep the selection on the same note when replies land above it
+                let selected = thread.get(self.thread_index).map(|entry| entry.event.id.clone());
+                let author = event.pubkey.clone();
+                if !thread.insert(event) {
+                    return;
+                }
+                if let Some(index) = selected.and_then(|id| thread.position(&id)) {
+                    self.thread_index = index;
+                }
+                self.load_cached_profiles(&[author]);
+            }
+            _ => {}
+        }
+    }
+
+    /// Hide what a deletion request (NIP-09) deletes from the feed, the thread
+    /// being viewed and the reaction and repost counts
+    fn apply_deletion(&mut self, deletion: &NostrEvent) {
+        if self.feed.apply_deletion(deletion) {
+            self.selected_index = self.selected_index.min(self.feed.len().saturating_sub(1));
+        }
+        self.interactions.apply_deletion(deletion);
+        if let Some(thread) = self.thread.as_mut()
+            && thread.apply_deletion(deletion)
+        {
+            self.thread_index = self.thread_index.min(thread.len().saturating_sub(1));
+        }
+    }
+
+    /// Decrypt a gift wrap or legacy NIP-04 message for the active account and
+    /// file it under its conversation
+    fn receive_private_message(&mut self, event: &NostrEvent) {
+        let Some(own_pubkey) = self.home_pubkey.clone() else {
+            return;
+        };
+
+        let message = match event.kind {
+            KIND_GIFT_WRAP => self
+                .account_manager
+                .unwrap_gift_wrap(event)
+                .and_then(|(account, gift)| Ok((account, PrivateMessage::from_gift(gift)?))),
+            _ => self.account_manager.decrypt_legacy_message(event),
+        };
+        let Ok((account, message)) = message else {
+            return;
+        };
+        if account.public_key_hex != own_pubkey {
+            return;
+        }
+
+        // Keep the same conversation selected when the order changes
+        let selected = self.selected_participants();
+        let participants = message.participants_except(&own_pubkey);
+        if !self.conversations.insert(message, &own_pubkey) {
+            return;
+        }
+        self.load_cached_profiles(&participants);
+
+        if let Some(index) = selected.and_then(|participants| self.conversations.position(&participants)) {
+            self.conversation_index = index;
+            self.new_conversation = None;
+        }
+    }
+
+    /// (Re)start the home feed for the active account: fetch its contact list,
+    /// then keep subscriptions open for notes and names of everyone it follows,
+    /// read from the relays each author writes to (NIP-65). Everything already
+    /// in the event store is shown right away.
+    fn start_home_feed(&mut self) {
+        self.stop_home_feed();
+
+        let keypair = match self.account_manager.get_active_account() {
+            Ok(Some(account)) => account.keypair,
+            _ => return,
+        };
+        let pubkey = keypair.public_key_hex();
+
+        // Relays that require authentication see the active account
+        self.relay_pool.auth().set_keypair(Some(keypair));
+
+        // Show the account's own notes until its contact list arrives
+        self.home_pubkey = Some(pubkey.clone());
+        self.follow_authors(vec![pubkey.clone()]);
+
+        let contacts_filter = Filter::new()
+            .with_kinds(vec![KIND_CONTACT_LIST])
+            .with_authors(vec![pubkey.clone()])
+            .with_limit(1);
+        self.load_cached(HOME_CONTACTS_SUBSCRIPTION, std::slice::from_ref(&contacts_filter));
+        if let Err(e) = self.relay_pool.subscribe_with_id(HOME_CONTACTS_SUBSCRIPTION, vec![contacts_filter]) {
+            self.status_message = Some(format!("Failed to load contact list: {}", e));
+            return;
+        }
+
+        let messages_filters = vec![
+            Filter::new()
+                .with_kinds(vec![KIND_GIFT_WRAP, KIND_ENCRYPTED_DIRECT_MESSAGE])
+                .with_pubkey_refs(vec![pubkey.clone()]),
+            Filter::new()
+                .with_kinds(vec![KIND_ENCRYPTED_DIRECT_MESSAGE])
+                .with_authors(vec![pubkey]),
+        ];
+        self.load_cached(DIRECT_MESSAGES_SUBSCRIPTION, &messages_filters);
+        if let Err(e) = self.relay_pool.subscribe_with_id(DIRECT_MESSAGES_SUBSCRIPTION, messages_filters) {
+            self.status_message = Some(format!("Failed to load messages: {}", e));
+        }
+    }
+
+    fn stop_home_feed(&mut self) {
+        for subscription_id in [
+            HOME_CONTACTS_SUBSCRIPTION,
+            HOME_NOTES_SUBSCRIPTION,
+            HOME_PROFILES_SUBSCRIPTION,
+            HOME_RELAY_LISTS_SUBSCRIPTION,
+            HOME_INTERACTIONS_SUBSCRIPTION,
+            DIRECT_MESSAGES_SUBSCRIPTION,
+        ] {
+            self.relay_pool.unsubscribe(subscription_id);
+        }
+        if self.current_view == CurrentView::Thread {
+            self.show_view(CurrentView::Feed);
+        }
+
+        // Pending authentication requests were for the previous account
+        for relay_url in std::mem::take(&mut self.auth_requests) {
+            let _ = self.relay_pool.answer_auth(&relay_url, false);
+        }
+        self.relay_pool.auth().set_keypair(None);
+        self.home_pubkey = None;
+        self.followed_authors.clear();
+        self.contacts_loaded = false;
+        self.routes_stale = false;
+        self.compose_relay_selection = default_compose_relays();
+        self.feed = Feed::new().with_min_difficulty(self.min_pow);
+        self.selected_index = 0;
+        self.interactions = Interactions::new();
+        self.interactions_stale = false;
+        self.conversations = Conversations::new();
+        self.conversation_index = 0;
+        self.new_conversation = None;
+    }
+
+    fn follow_authors(&mut self, authors: Vec<String>) {
+        self.followed_authors = authors.clone();
+
+        let relay_lists_filter = Filter::new().with_kinds(vec![KIND_RELAY_LIST]).with_authors(authors.clone());
+        let notes_filters = home_notes_filters(authors.clone());
+        let profiles_filter = Filter::new().with_kinds(vec![KIND_METADATA]).with_authors(authors);
+
+        self.load_cached(HOME_RELAY_LISTS_SUBSCRIPTION, std::slice::from_ref(&relay_lists_filter));
+        self.load_cached(HOME_PROFILES_SUBSCRIPTION, std::slice::from_ref(&profiles_filter));
+        self.load_cached(HOME_NOTES_SUBSCRIPTION, &notes_filters);
+
+        if let Err(e) = self.relay_pool.subscribe_with_id(HOME_RELAY_LISTS_SUBSCRIPTION, vec![relay_lists_filter]) {
+            self.status_message = Some(format!("Failed to subscribe to feed: {}", e));
+            return;
+        }
+        // Routed on the next tick, which can wait for unused relays to disconnect
+        self.routes_stale = true;
+    }
+
+    /// Send the notes and profiles subscriptions to each followed author's
+    /// write relays. Relays no route uses anymore are disconnected first, so
+    /// they don't count against the pool's relay limit.
+    async fn route_home_feed(&mut self) {
+        self.routes_stale = false;
+
+        let mut notes_routes = HashMap::new();
+        let mut profiles_routes = HashMap::new();
+        for (relay_url, authors) in self.outbox.route_authors(&self.followed_authors) {
+            notes_routes.insert(relay_url.clone(), home_notes_filters(authors.clone()));
+            profiles_routes.insert(relay_url, vec![Filter::new().with_kinds(vec![KIND_METADATA]).with_authors(authors)]);
+        }
+
+        let mut keep: Vec<String> = self.outbox.fallback_relays().to_vec();
+        keep.extend(self.compose_relay_selection.iter().map(|(relay_url, _)| relay_url.clone()));
+        keep.extend(notes_routes.keys().cloned());
+        self.relay_pool.unsubscribe(HOME_NOTES_SUBSCRIPTION);
+        self.relay_pool.unsubscribe(HOME_PROFILES_SUBSCRIPTION);
+        self.relay_pool.prune_relays(&keep).await;
+
+        let subscribed = self
+            .relay_pool
+            .subscribe_routed(HOME_NOTES_SUBSCRIPTION, notes_routes)
+            .and_then(|_| self.relay_pool.subscribe_routed(HOME_PROFILES_SUBSCRIPTION, profiles_routes));
+        if let Err(e) = subscribed {
+            self.status_message = Some(format!("Failed to subscribe to feed: {}", e));
+        }
+    }
+
+    /// Ask for the reactions and reposts of the newest notes in the feed
+    fn subscribe_interactions(&mut self) {
+        self.interactions_stale = false;
+
+        let note_ids: Vec<String> =
+            self.feed.notes().iter().take(HOME_FEED_LIMIT as usize).map(|note| note.id.clone()).collect();
+        if note_ids.is_empty() {
+            return;
+        }
+
+        let filter = Filter::new()
+            .with_kinds(vec![KIND_REACTION, KIND_REPOST, KIND_GENERIC_REPOST])
+            .with_event_refs(note_ids);
+        self.load_cached(HOME_INTERACTIONS_SUBSCRIPTION, std::slice::from_ref(&filter));
+        if let Err(e) = self.relay_pool.subscribe_with_id(HOME_INTERACTIONS_SUBSCRIPTION, vec![filter]) {
+            self.status_message = Some(format!("Failed to load reactions: {}", e));
+        }
+    }
+
     /// Refresh the current view
     fn refresh_view(&mut self) {
         match self.current_view {
             CurrentView::Feed => {
+                self.start_home_feed();
                 self.status_message = Some("Feed refreshed".to_string());
-                // TODO: Refresh feed content
             }
             _ => {
                 self.status_message = Some("Refreshed".to_string());
@@ -343,7 +1812,14 @@ impl App {
     }
 
     /// Update application state (called on tick)
-    pub fn tick(&mut self) {
+    pub async fn tick(&mut self) {
+        if self.routes_stale && self.home_pubkey.is_some() {
+            self.route_home_feed().await;
+        }
+        if self.interactions_stale && self.home_pubkey.is_some() {
+            self.subscribe_interactions();
+        }
+
         // Clear status message after some time
         // TODO: Implement proper status message timeout
     }
@@ -371,7 +1847,147 @@ impl App {
 
     /// Get relay connection status for display
     pub fn get_relay_status_display(&self) -> String {
-        // TODO: Implement actual relay status checking
-        "🟡 0 relays".to_string()
+        let total = self.relay_pool.relay_urls().len();
+        let connected = self.relay_pool.connected_relays().len();
+
+        let indicator = if connected == 0 {
+            "🔴"
+        } else if connected < total {
+            "🟡"
+        } else {
+            "🟢"
+        };
+
+        format!("{} {}/{} relays", indicator, connected, total)
+    }
+}
+
+/// Notes of the home feed's authors, and their deletion requests (NIP-09)
+/// so deleted notes disappear
+fn home_notes_filters(authors: Vec<String>) -> Vec<Filter> {
+    vec![
+        Filter::new()
+            .with_kinds(vec![1])
+            .with_authors(authors.clone())
+            .with_limit(HOME_FEED_LIMIT),
+        Filter::new()
+            .with_kinds(vec![KIND_DELETION])
+            .with_authors(authors)
+            .with_limit(HOME_FEED_LIMIT),
+    ]
+}
+
+/// The compose modal's relays until the active account's relay list is known
+fn default_compose_relays() -> Vec<(String, bool)> {
+    DEFAULT_RELAYS.iter().map(|relay_url| (relay_url.to_string(), true)).collect()
+}
+
+fn auth_request_prompt(relay_url: &str) -> String {
+    format!("🔐 {} requires authentication", relay_url)
+}
+
+/// Summarize how many relays accepted a replaceable event like a profile
+fn format_acceptance(what: &str, outcomes: &[PublishOutcome]) -> String {
+    let accepted = outcomes.iter().filter(|outcome| outcome.is_accepted()).count();
+    if accepted > 0 {
+        format!("{} published ({}/{} relays accepted)", what, accepted, outcomes.len())
+    } else {
+        format!("{} was not accepted by any relay", what)
+    }
+}
+
+/// Summarize a publish as one status line with every relay's answer
+fn format_publish_results(event_id: &str, outcomes: &[PublishOutcome]) -> String {
+    let accepted = outcomes.iter().filter(|outcome| outcome.is_accepted()).count();
+
+    let results: Vec<String> = outcomes
+        .iter()
+        .map(|outcome| {
+            let relay = outcome.relay_url.trim_start_matches("wss://").trim_start_matches("ws://");
+            match &outcome.result {
+                Ok(_) => format!("✅ {}", relay),
+                Err(e) => format!("❌ {} ({})", relay, e),
+            }
+        })
+        .collect();
+
+    let short_id = &event_id[..event_id.len().min(8)];
+    format!("Note {} published to {}/{} relays: {}", short_id, accepted, outcomes.len(), results.join(", "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::connection::ReconnectPolicy;
+    use crate::temp_config_dir::TempConfigDir;
+
+    #[tokio::test]
+    async fn test_profile_is_not_published_before_it_was_fetched() {
+        let config_dir = TempConfigDir::new();
+        let (app_events, _app_events) = mpsc::unbounded_channel();
+        let relay_pool = RelayPool::with_reconnect_policy(ReconnectPolicy::none());
+        let mut app = App::open(relay_pool, app_events, &config_dir.0).unwrap();
+        let password = SecretString::from("correct horse battery staple".to_string());
+        let alice = app.account_manager.create_account("alice", &password).unwrap();
+        // Nothing to reach: the relays' answer is handed to the app below
+        app.compose_relay_selection.clear();
+        app.current_view = CurrentView::AccountModal;
+
+        let profiles = |app: &App| {
+            let filter = Filter::new().with_kinds(vec![KIND_METADATA]).with_authors(vec![alice.public_key_hex.clone()]);
+            app.event_store.query(&[filter]).unwrap()
+        };
+        let name_field = METADATA_FIELDS.iter().position(|field| *field == "name").unwrap();
+
+        // Nothing cached: publishing now would replace the real profile with an empty one
+        app.edit_profile();
+        assert_eq!(app.account_mode, AccountModalMode::List, "The editor waits for the relays");
+        app.profile_inputs = vec![String::new(); METADATA_FIELDS.len()];
+        app.profile_inputs[name_field] = "alice".to_string();
+        app.publish_profile();
+        assert!(profiles(&app).is_empty());
+
+        let keypair = app.account_manager.get_active_account().unwrap().unwrap().keypair;
+        let content = r#"{"name":"Alice","about":"Down the rabbit hole"}"#.to_string();
+        let published = UnsignedEvent::new_text_note(content, alice.public_key_hex.clone())
+            .with_kind(KIND_METADATA)
+            .with_timestamp(crate::connection::unix_now() - 60)
+            .sign(&keypair)
+            .unwrap();
+        app.handle_app_event(AppEvent::ProfileFetched {
+            pubkey: alice.public_key_hex.clone(),
+            result: Ok(vec![published]),
+        });
+        assert_eq!(app.account_mode, AccountModalMode::EditProfile);
+        assert_eq!(app.profile_inputs[name_field], "Alice");
+
+        app.profile_inputs[name_field] = "Alice Liddell".to_string();
+        app.publish_profile();
+        let edited = profiles(&app).iter().filter_map(|event| Metadata::from_event(event).ok()).any(|metadata| {
+            metadata.get("name") == Some("Alice Liddell") && metadata.get("about") == Some("Down the rabbit hole")
+        });
+        assert!(edited, "Fields the user didn't touch are kept");
     }
-}
\ No newline at end of file
+
+    #[tokio::test]
+    async fn test_leaving_the_thread_view_closes_the_thread() {
+        let config_dir = TempConfigDir::new();
+        let (app_events, _app_events) = mpsc::unbounded_channel();
+        let relay_pool = RelayPool::with_reconnect_policy(ReconnectPolicy::none());
+        let mut app = App::open(relay_pool, app_events, &config_dir.0).unwrap();
+        let keypair = crate::nostr::generate_keypair().unwrap();
+        let note = NostrEvent::new_text_note("gm".to_string(), &keypair).unwrap();
+        // Messages and New Post only open once accounts are unlocked
+        app.keystore_unlocked = true;
+
+        for key in ['a', '?', 'm', 'n'] {
+            app.open_thread(&note);
+            assert!(app.thread.is_some());
+
+            app.handle_input(KeyEvent::new(KeyCode::Char(key), KeyModifiers::NONE)).unwrap();
+            assert_ne!(app.current_view, CurrentView::Thread, "{} leaves the thread view", key);
+            assert!(app.thread.is_none(), "{} closes the thread", key);
+            app.current_view = CurrentView::Feed;
+        }
+    }
+}
@@ -1,6 +1,15 @@
-use anyhow::Result;
-use crossterm::event::{self, Event, KeyEvent};
-use std::time::{Duration, Instant};
+use anyhow::{Result, anyhow};
+use crossterm::event::{Event, EventStream, KeyEvent};
+use futures_util::StreamExt;
+use std::time::Duration;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::{broadcast, mpsc};
+use tokio::time::{Interval, MissedTickBehavior};
+
+use crate::connection::RelayNotification;
+use crate::error::NostrError;
+use crate::nostr::NostrEvent;
+use crate::pool::PublishOutcome;
 
 /// Events that can occur in the application
 #[derive(Debug)]
@@ -9,50 +18,104 @@ pub enum InputEvent {
     Input(KeyEvent),
     /// Periodic tick for updating UI
     Tick,
+    /// Result of background work started by the app
+    App(AppEvent),
+    /// Message or status change from the relay pool
+    Relay(RelayNotification),
+}
+
+/// Results sent back to the UI by tasks the app spawned
+#[derive(Debug)]
+pub enum AppEvent {
+    /// A composed note finished publishing, with each relay's answer
+    PublishFinished {
+        event_id: String,
+        outcomes: Vec<PublishOutcome>,
+    },
+    /// The active account's profiles (kind 0) its write relays sent, asked
+    /// for before opening the profile editor
+    ProfileFetched {
+        pubkey: String,
+        result: Result<Vec<NostrEvent>, NostrError>,
+    },
+    /// Edited profile metadata finished publishing, with each relay's answer
+    ProfilePublished {
+        outcomes: Vec<PublishOutcome>,
+    },
+    /// The contact list finished publishing after a follow or unfollow
+    ContactListPublished {
+        outcomes: Vec<PublishOutcome>,
+    },
+    /// A reaction to a feed note finished publishing
+    ReactionPublished {
+        outcomes: Vec<PublishOutcome>,
+    },
+    /// A repost of a feed note finished publishing
+    RepostPublished {
+        outcomes: Vec<PublishOutcome>,
+    },
+    /// A private message finished publishing, with each relay's answer for
+    /// the recipients' copies
+    MessageSent {
+        outcomes: Vec<PublishOutcome>,
+    },
 }
 
 /// Handles terminal events and provides a unified event stream
 pub struct EventHandler {
-    /// Duration between tick events
-    tick_rate: Duration,
-    /// Last time a tick event was sent
-    last_tick: Instant,
+    /// Terminal events read without blocking the runtime
+    terminal_events: EventStream,
+    /// Fires once per tick
+    ticks: Interval,
+    /// Everything the relay pool receives
+    relay_notifications: broadcast::Receiver<RelayNotification>,
+    /// Background task results, see `sender`
+    app_events: mpsc::UnboundedReceiver<AppEvent>,
+    app_events_tx: mpsc::UnboundedSender<AppEvent>,
 }
 
 impl EventHandler {
     /// Create a new event handler with the specified tick rate in milliseconds
-    pub fn new(tick_rate: u64) -> Self {
+    pub fn new(tick_rate: u64, relay_notifications: broadcast::Receiver<RelayNotification>) -> Self {
+        let mut ticks = tokio::time::interval(Duration::from_millis(tick_rate));
+        ticks.set_missed_tick_behavior(MissedTickBehavior::Skip);
+
+        let (app_events_tx, app_events) = mpsc::unbounded_channel();
+
         Self {
-            tick_rate: Duration::from_millis(tick_rate),
-            last_tick: Instant::now(),
+            terminal_events: EventStream::new(),
+            ticks,
+            relay_notifications,
+            app_events,
+            app_events_tx,
         }
     }
 
-    /// Get the next event, blocking until one is available
-    pub fn next(&mut self) -> Result<InputEvent> {
-        let timeout = self.tick_rate
-            .checked_sub(self.last_tick.elapsed())
-            .unwrap_or_else(|| Duration::from_secs(0));
-
-        if event::poll(timeout)? {
-            match event::read()? {
-                Event::Key(key) => {
-                    return Ok(InputEvent::Input(key));
-                }
-                // Ignore other events (mouse, resize, etc.) for now
-                _ => {}
-            }
-        }
+    /// Channel for background tasks to report back to the UI loop
+    pub fn sender(&self) -> mpsc::UnboundedSender<AppEvent> {
+        self.app_events_tx.clone()
+    }
 
-        // Check if we should send a tick
-        if self.last_tick.elapsed() >= self.tick_rate {
-            self.last_tick = Instant::now();
-            return Ok(InputEvent::Tick);
+    /// Wait for the next event
+    pub async fn next(&mut self) -> Result<InputEvent> {
+        loop {
+            tokio::select! {
+                _ = self.ticks.tick() => return Ok(InputEvent::Tick),
+                Some(app_event) = self.app_events.recv() => return Ok(InputEvent::App(app_event)),
+                notification = self.relay_notifications.recv() => match notification {
+                    Ok(notification) => return Ok(InputEvent::Relay(notification)),
+                    // Dropped notifications only cost a few feed entries
+                    Err(RecvError::Lagged(_)) => {}
+                    Err(RecvError::Closed) => return Err(anyhow!("Relay pool shut down")),
+                },
+                terminal_event = self.terminal_events.next() => match terminal_event {
+                    Some(Ok(Event::Key(key))) => return Ok(InputEvent::Input(key)),
+                    // Ignore other events (mouse, resize, etc.) for now
+                    Some(Ok(_)) => {}
+                    Some(Err(e)) => return Err(e.into()),
+                    None => return Err(anyhow!("Terminal event stream ended")),
+                },
+            }
         }
-
-        // If we get here, poll returned false and it's not time for a tick yet
-        // This should rarely happen, but we'll just wait a bit and try again
-        std::thread::sleep(Duration::from_millis(10));
-        self.next()
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,252 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::nostr::NostrEvent;
+use crate::nostr::metadata::Metadata;
+use crate::nostr::nip02::ContactList;
+use crate::nostr::nip09::deletes;
+use crate::nostr::nip13::meets_difficulty;
+
+/// Notes from followed authors, newest first, plus what is needed to render them
+#[derive(Debug, Default)]
+pub struct Feed {
+    notes: Vec<NostrEvent>,
+    seen_event_ids: HashSet<String>,
+    /// Deletion requests (NIP-09) from followed authors, so notes they
+    /// deleted stay hidden when a relay sends them anyway
+    deletions: Vec<NostrEvent>,
+    /// Kind-0 metadata per author, with the `created_at` it came from
+    profiles: HashMap<String, (u64, Metadata)>,
+    /// The contact list the feed is following
+    contact_list: Option<ContactList>,
+    /// Leading zero bits of proof of work (NIP-13) a note needs to be shown
+    min_difficulty: u32,
+}
+
+impl Feed {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Leave out notes with less than `min_difficulty` bits of proof of work
+    pub fn with_min_difficulty(mut self, min_difficulty: u32) -> Self {
+        self.min_difficulty = min_difficulty;
+        self
+    }
+
+    pub fn notes(&self) -> &[NostrEvent] {
+        &self.notes
+    }
+
+    pub fn len(&self) -> usize {
+        self.notes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.notes.is_empty()
+    }
+
+    /// Add a note, keeping the feed sorted by `created_at`. Returns false if the
+    /// note was already in the feed, e.g. because another relay sent it first,
+    /// its author deleted it, or it lacks the proof of work the feed requires.
+    pub fn insert_note(&mut self, event: NostrEvent) -> bool {
+        if !meets_difficulty(&event, self.min_difficulty) {
+            return false;
+        }
+        if self.is_deleted(&event) {
+            return false;
+        }
+        if !self.seen_event_ids.insert(event.id.clone()) {
+            return false;
+        }
+
+        let position = self.notes.partition_point(|note| note.created_at >= event.created_at);
+        self.notes.insert(position, event);
+        true
+    }
+
+    /// Remove the notes a deletion request deletes, and keep them out from
+    /// now on. Returns whether any note was removed.
+    pub fn apply_deletion(&mut self, deletion: &NostrEvent) -> bool {
+        if self.deletions.iter().any(|known| known.id == deletion.id) {
+            return false;
+        }
+        self.deletions.push(deletion.clone());
+
+        let count = self.notes.len();
+        self.notes.retain(|note| !deletes(deletion, note));
+        self.notes.len() != count
+    }
+
+    /// Whether a deletion request seen so far deletes the event, so other
+    /// views can keep it out too
+    pub fn is_deleted(&self, event: &NostrEvent) -> bool {
+        self.deletions.iter().any(|deletion| deletes(deletion, event))
+    }
+
+    /// Take the followed pubkeys from a kind-3 contact list. Returns `None` when
+    /// an equally new or newer contact list was already applied.
+    pub fn update_contacts(&mut self, event: &NostrEvent) -> Option<Vec<String>> {
+        if self.contact_list.as_ref().is_some_and(|list| list.created_at >= event.created_at) {
+            return None;
+        }
+
+        let contact_list = ContactList::from_event(event).ok()?;
+        let follows = contact_list.follows();
+        self.contact_list = Some(contact_list);
+        Some(follows)
+    }
+
+    /// The newest contact list seen, the one follows and unfollows build on
+    pub fn contact_list(&self) -> Option<&ContactList> {
+        self.contact_list.as_ref()
+    }
+
+    /// Remember the author's kind-0 metadata, keeping the newest
+    pub fn update_profile(&mut self, event: &NostrEvent) {
+        if self
+            .profiles
+            .get(&event.pubkey)
+            .is_some_and(|(created_at, _)| *created_at >= event.created_at)
+        {
+            return;
+        }
+
+        if let Ok(metadata) = Metadata::from_event(event) {
+            self.profiles.insert(event.pubkey.clone(), (event.created_at, metadata));
+        }
+    }
+
+    pub fn profile(&self, pubkey: &str) -> Option<&Metadata> {
+        self.profiles.get(pubkey).map(|(_, metadata)| metadata)
+    }
+
+    /// The author's display name, or a shortened pubkey when no metadata is known
+    pub fn display_name(&self, pubkey: &str) -> String {
+        match self.profile(pubkey).and_then(Metadata::display_name) {
+            Some(name) => name.to_string(),
+            None => format!("{}…", &pubkey[..pubkey.len().min(12)]),
+        }
+    }
+}
+
+/// Format a timestamp relative to `now`, e.g. "5m" or "3d"
+pub fn format_relative_time(created_at: u64, now: u64) -> String {
+    let elapsed = now.saturating_sub(created_at);
+
+    match elapsed {
+        0..60 => "now".to_string(),
+        60..3_600 => format!("{}m", elapsed / 60),
+        3_600..86_400 => format!("{}h", elapsed / 3_600),
+        86_400..604_800 => format!("{}d", elapsed / 86_400),
+        _ => chrono::DateTime::from_timestamp(created_at as i64, 0)
+            .map(|date| date.format("%b %d, %Y").to_string())
+            .unwrap_or_default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nostr::event::UnsignedEvent;
+    use crate::nostr::keys;
+    use crate::nostr::nip13::Miner;
+
+    fn event(kind: u16, content: &str, tags: Vec<Vec<String>>, created_at: u64) -> NostrEvent {
+        let keypair = keys::generate_keypair().unwrap();
+        UnsignedEvent::new_text_note(content.to_string(), keypair.public_key_hex())
+            .with_kind(kind)
+            .with_tags(tags)
+            .with_timestamp(created_at)
+            .sign(&keypair)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_notes_are_deduplicated_and_sorted_newest_first() {
+        let mut feed = Feed::new();
+        let middle = event(1, "middle", vec![], 200);
+
+        assert!(feed.insert_note(middle.clone()));
+        assert!(feed.insert_note(event(1, "oldest", vec![], 100)));
+        assert!(feed.insert_note(event(1, "newest", vec![], 300)));
+        assert!(!feed.insert_note(middle));
+
+        let contents: Vec<&str> = feed.notes().iter().map(|note| note.content.as_str()).collect();
+        assert_eq!(contents, vec!["newest", "middle", "oldest"]);
+    }
+
+    #[test]
+    fn test_deleted_notes_are_removed_and_kept_out() {
+        let alice = keys::generate_keypair().unwrap();
+        let mut feed = Feed::new();
+        let oops = NostrEvent::new_text_note("oops".to_string(), &alice).unwrap();
+        let kept = NostrEvent::new_text_note("kept".to_string(), &alice).unwrap();
+        feed.insert_note(oops.clone());
+        feed.insert_note(kept.clone());
+
+        let forged = event(5, "", vec![vec!["e".to_string(), oops.id.clone()]], 100);
+        let deletion = UnsignedEvent::new_text_note(String::new(), alice.public_key_hex())
+            .with_kind(5)
+            .with_tags(vec![vec!["e".to_string(), oops.id.clone()]])
+            .sign(&alice)
+            .unwrap();
+
+        assert!(!feed.apply_deletion(&forged), "Only the author can delete");
+        assert!(feed.apply_deletion(&deletion));
+        assert_eq!(feed.notes(), &[kept]);
+        assert!(!feed.insert_note(oops), "A relay sending it again doesn't bring it back");
+    }
+
+    #[test]
+    fn test_notes_without_enough_proof_of_work_are_left_out() {
+        let keypair = keys::generate_keypair().unwrap();
+        let mut feed = Feed::new().with_min_difficulty(4);
+
+        let note = UnsignedEvent::new_text_note("worked for it".to_string(), keypair.public_key_hex());
+        let mined = Miner::new(4).with_threads(1).mine(note, |_| {}).unwrap().sign(&keypair).unwrap();
+        assert!(feed.insert_note(mined));
+
+        let lazy = event(1, "lazy", vec![vec!["nonce".to_string(), "0".to_string(), "0".to_string()]], 100);
+        assert!(!feed.insert_note(lazy), "Committed to no work at all");
+        assert_eq!(feed.len(), 1);
+    }
+
+    #[test]
+    fn test_only_newer_contact_lists_are_applied() {
+        let mut feed = Feed::new();
+        let p = |pubkey: &str| vec!["p".to_string(), pubkey.to_string()];
+
+        let follows = feed.update_contacts(&event(3, "", vec![p("alice"), p("bob"), p("alice")], 100));
+        assert_eq!(follows, Some(vec!["alice".to_string(), "bob".to_string()]));
+
+        assert_eq!(feed.update_contacts(&event(3, "", vec![p("carol")], 50)), None);
+        assert_eq!(feed.update_contacts(&event(3, "", vec![p("carol")], 150)), Some(vec!["carol".to_string()]));
+    }
+
+    #[test]
+    fn test_display_name_prefers_newest_metadata() {
+        let mut feed = Feed::new();
+        let profile = event(0, r#"{"name": "alice", "display_name": "Alice"}"#, vec![], 100);
+        let pubkey = profile.pubkey.clone();
+
+        assert_eq!(feed.display_name(&pubkey), format!("{}…", &pubkey[..12]));
+
+        feed.update_profile(&profile);
+        assert_eq!(feed.display_name(&pubkey), "Alice");
+
+        let mut stale = event(0, r#"{"name": "old"}"#, vec![], 50);
+        stale.pubkey = pubkey.clone();
+        feed.update_profile(&stale);
+        assert_eq!(feed.display_name(&pubkey), "Alice");
+    }
+
+    #[test]
+    fn test_relative_time() {
+        assert_eq!(format_relative_time(1_000, 1_030), "now");
+        assert_eq!(format_relative_time(1_000, 1_000 + 5 * 60), "5m");
+        assert_eq!(format_relative_time(1_000, 1_000 + 3 * 3_600), "3h");
+        assert_eq!(format_relative_time(1_000, 1_000 + 2 * 86_400), "2d");
+        assert_eq!(format_relative_time(1_700_000_000, 1_700_000_000 + 30 * 86_400), "Nov 14, 2023");
+        assert_eq!(format_relative_time(2_000, 1_000), "now");
+    }
+}
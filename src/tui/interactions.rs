@@ -0,0 +1,196 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use crate::nostr::NostrEvent;
+use crate::nostr::nip09::DeletionRequest;
+use crate::nostr::nip18::{KIND_GENERIC_REPOST, KIND_REPOST, reposted_event_id};
+use crate::nostr::nip25::{KIND_REACTION, Reaction, reacted_event_id};
+
+/// Reactions and reposts of one note, counted once per author
+#[derive(Debug, Clone, Default)]
+pub struct NoteInteractions {
+    reactions: BTreeMap<Reaction, BTreeSet<String>>,
+    reposts: BTreeSet<String>,
+}
+
+impl NoteInteractions {
+    /// Each reaction with how many people sent it, the most common first
+    pub fn reaction_counts(&self) -> Vec<(&Reaction, usize)> {
+        let mut counts: Vec<(&Reaction, usize)> =
+            self.reactions.iter().map(|(reaction, authors)| (reaction, authors.len())).collect();
+        counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+        counts
+    }
+
+    pub fn repost_count(&self) -> usize {
+        self.reposts.len()
+    }
+
+    pub fn has_reacted(&self, pubkey: &str, reaction: &Reaction) -> bool {
+        self.reactions.get(reaction).is_some_and(|authors| authors.contains(pubkey))
+    }
+
+    pub fn has_reposted(&self, pubkey: &str) -> bool {
+        self.reposts.contains(pubkey)
+    }
+}
+
+/// A counted reaction or repost, kept so a deletion request can take it back
+#[derive(Debug)]
+struct Counted {
+    note_id: String,
+    author: String,
+    /// `None` for a repost
+    reaction: Option<Reaction>,
+}
+
+/// Reaction (NIP-25) and repost (NIP-18) counts per note, from the events
+/// the feed's subscriptions deliver
+#[derive(Debug, Default)]
+pub struct Interactions {
+    notes: HashMap<String, NoteInteractions>,
+    /// Counted events by id
+    counted: HashMap<String, Counted>,
+}
+
+impl Interactions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, note_id: &str) -> Option<&NoteInteractions> {
+        self.notes.get(note_id)
+    }
+
+    /// Count a reaction or repost. Returns false for other events, and for
+    /// ones whose author was already counted, e.g. because another relay
+    /// delivered the same event first.
+    pub fn insert(&mut self, event: &NostrEvent) -> bool {
+        let (note_id, reaction) = match event.kind {
+            KIND_REACTION => {
+                let (Some(note_id), Ok(reaction)) = (reacted_event_id(event), Reaction::from_event(event)) else {
+                    return false;
+                };
+                (note_id, Some(reaction))
+            }
+            KIND_REPOST | KIND_GENERIC_REPOST => {
+                let Some(note_id) = reposted_event_id(event) else {
+                    return false;
+                };
+                (note_id, None)
+            }
+            _ => return false,
+        };
+
+        let note = self.notes.entry(note_id.to_string()).or_default();
+        let inserted = match &reaction {
+            Some(reaction) => note.reactions.entry(reaction.clone()).or_default().insert(event.pubkey.clone()),
+            None => note.reposts.insert(event.pubkey.clone()),
+        };
+        if inserted {
+            let counted = Counted { note_id: note_id.to_string(), author: event.pubkey.clone(), reaction };
+            self.counted.insert(event.id.clone(), counted);
+        }
+        inserted
+    }
+
+    /// Stop counting the reactions and reposts a deletion request (NIP-09)
+    /// deletes. Returns whether any was counted.
+    pub fn apply_deletion(&mut self, deletion: &NostrEvent) -> bool {
+        let Ok(request) = DeletionRequest::from_event(deletion) else {
+            return false;
+        };
+
+        let mut removed = false;
+        for event_id in &request.event_ids {
+            // Only the author can take a reaction back
+            if self.counted.get(event_id).is_none_or(|counted| counted.author != deletion.pubkey) {
+                continue;
+            }
+            let Some(counted) = self.counted.remove(event_id) else {
+                continue;
+            };
+            let Some(note) = self.notes.get_mut(&counted.note_id) else {
+                continue;
+            };
+
+            match &counted.reaction {
+                Some(reaction) => {
+                    if let Some(authors) = note.reactions.get_mut(reaction) {
+                        authors.remove(&counted.author);
+                        if authors.is_empty() {
+                            note.reactions.remove(reaction);
+                        }
+                    }
+                }
+                None => {
+                    note.reposts.remove(&counted.author);
+                }
+            }
+            removed = true;
+        }
+        removed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nostr::event::UnsignedEvent;
+    use crate::nostr::keys::{NostrKeypair, generate_keypair};
+
+    #[test]
+    fn test_reactions_and_reposts_are_counted_once_per_author() {
+        let alice = generate_keypair().unwrap();
+        let bob = generate_keypair().unwrap();
+        let carol = generate_keypair().unwrap();
+        let note = NostrEvent::new_text_note("gm".to_string(), &alice).unwrap();
+
+        let react = |keypair: &NostrKeypair, reaction: &Reaction| {
+            UnsignedEvent::new_reaction(&note, reaction, None, keypair.public_key_hex())
+                .sign(keypair)
+                .unwrap()
+        };
+        let fire = Reaction::Emoji("🔥".to_string());
+
+        let mut interactions = Interactions::new();
+        let bob_likes = react(&bob, &Reaction::Like);
+        assert!(interactions.insert(&bob_likes));
+        assert!(!interactions.insert(&bob_likes), "Same event from another relay");
+        assert!(interactions.insert(&react(&carol, &Reaction::Like)));
+        assert!(interactions.insert(&react(&carol, &fire)));
+        assert!(!interactions.insert(&note));
+
+        let repost = UnsignedEvent::new_repost(&note, None, bob.public_key_hex()).unwrap().sign(&bob).unwrap();
+        assert!(interactions.insert(&repost));
+
+        let counts = interactions.get(&note.id).unwrap();
+        assert_eq!(counts.reaction_counts(), vec![(&Reaction::Like, 2), (&fire, 1)]);
+        assert_eq!(counts.repost_count(), 1);
+        assert!(counts.has_reacted(&bob.public_key_hex(), &Reaction::Like));
+        assert!(!counts.has_reacted(&bob.public_key_hex(), &fire));
+        assert!(counts.has_reposted(&bob.public_key_hex()));
+        assert!(interactions.get(&repost.id).is_none());
+    }
+
+    #[test]
+    fn test_deleted_reactions_and_reposts_stop_counting() {
+        let alice = generate_keypair().unwrap();
+        let bob = generate_keypair().unwrap();
+        let note = NostrEvent::new_text_note("gm".to_string(), &alice).unwrap();
+
+        let like = UnsignedEvent::new_reaction(&note, &Reaction::Like, None, bob.public_key_hex()).sign(&bob).unwrap();
+        let repost = UnsignedEvent::new_repost(&note, None, bob.public_key_hex()).unwrap().sign(&bob).unwrap();
+        let mut interactions = Interactions::new();
+        assert!(interactions.insert(&like));
+        assert!(interactions.insert(&repost));
+
+        let forged = DeletionRequest::new().with_event(&like).to_event(&alice).unwrap();
+        assert!(!interactions.apply_deletion(&forged), "Only the author can take a reaction back");
+
+        let deletion = DeletionRequest::new().with_event(&like).with_event(&repost).to_event(&bob).unwrap();
+        assert!(interactions.apply_deletion(&deletion));
+        let counts = interactions.get(&note.id).unwrap();
+        assert!(counts.reaction_counts().is_empty());
+        assert_eq!(counts.repost_count(), 0);
+    }
+}
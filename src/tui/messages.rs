@@ -0,0 +1,140 @@
+use std::collections::HashSet;
+
+use crate::nostr::nip17::PrivateMessage;
+
+/// A private conversation, identified by everyone in it except us
+#[derive(Debug, Clone)]
+pub struct Conversation {
+    /// The other members, sorted
+    pub participants: Vec<String>,
+    /// Messages oldest first
+    pub messages: Vec<PrivateMessage>,
+}
+
+impl Conversation {
+    pub fn last_activity(&self) -> u64 {
+        self.messages.last().map(|message| message.created_at).unwrap_or(0)
+    }
+}
+
+/// The active account's private messages grouped into conversations, the most
+/// recently active first
+#[derive(Debug, Default)]
+pub struct Conversations {
+    conversations: Vec<Conversation>,
+    seen_message_ids: HashSet<String>,
+}
+
+impl Conversations {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn list(&self) -> &[Conversation] {
+        &self.conversations
+    }
+
+    pub fn get(&self, index: usize) -> Option<&Conversation> {
+        self.conversations.get(index)
+    }
+
+    pub fn len(&self) -> usize {
+        self.conversations.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.conversations.is_empty()
+    }
+
+    /// Index of the conversation with exactly these (sorted) participants
+    pub fn position(&self, participants: &[String]) -> Option<usize> {
+        self.conversations
+            .iter()
+            .position(|conversation| conversation.participants == participants)
+    }
+
+    /// Add a message received by `own_pubkey`. Returns false if it was already
+    /// known, e.g. because another relay delivered the same wrap first.
+    pub fn insert(&mut self, message: PrivateMessage, own_pubkey: &str) -> bool {
+        if !self.seen_message_ids.insert(message.id.clone()) {
+            return false;
+        }
+
+        let mut participants = message.participants_except(own_pubkey);
+        if participants.is_empty() {
+            // A note to self
+            participants.push(own_pubkey.to_string());
+        }
+
+        let index = match self.position(&participants) {
+            Some(index) => index,
+            None => {
+                self.conversations.push(Conversation {
+                    participants,
+                    messages: Vec::new(),
+                });
+                self.conversations.len() - 1
+            }
+        };
+
+        let messages = &mut self.conversations[index].messages;
+        let position = messages.partition_point(|existing| existing.created_at <= message.created_at);
+        messages.insert(position, message);
+
+        self.conversations
+            .sort_by_key(|conversation| std::cmp::Reverse(conversation.last_activity()));
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(id: &str, sender: &str, recipients: &[&str], created_at: u64) -> PrivateMessage {
+        PrivateMessage {
+            id: id.to_string(),
+            sender: sender.to_string(),
+            recipients: recipients.iter().map(|recipient| recipient.to_string()).collect(),
+            created_at,
+            content: id.to_string(),
+            legacy: false,
+        }
+    }
+
+    #[test]
+    fn test_messages_are_grouped_by_participants() {
+        let mut conversations = Conversations::new();
+
+        assert!(conversations.insert(message("hi", "bob", &["me"], 100), "me"));
+        assert!(conversations.insert(message("hello", "me", &["bob"], 200), "me"));
+        assert!(conversations.insert(message("group", "carol", &["me", "bob"], 150), "me"));
+        assert!(conversations.insert(message("memo", "me", &["me"], 50), "me"));
+        assert!(!conversations.insert(message("hi", "bob", &["me"], 100), "me"));
+
+        assert_eq!(conversations.len(), 3);
+        let with_bob = conversations.get(conversations.position(&["bob".to_string()]).unwrap()).unwrap();
+        let contents: Vec<&str> = with_bob.messages.iter().map(|message| message.content.as_str()).collect();
+        assert_eq!(contents, vec!["hi", "hello"]);
+
+        assert!(conversations.position(&["bob".to_string(), "carol".to_string()]).is_some());
+        assert!(conversations.position(&["me".to_string()]).is_some());
+    }
+
+    #[test]
+    fn test_most_recently_active_conversation_comes_first() {
+        let mut conversations = Conversations::new();
+
+        conversations.insert(message("a", "alice", &["me"], 100), "me");
+        conversations.insert(message("b", "bob", &["me"], 200), "me");
+        assert_eq!(conversations.list()[0].participants, vec!["bob".to_string()]);
+
+        // An older message arriving late doesn't reorder anything
+        conversations.insert(message("c", "alice", &["me"], 50), "me");
+        assert_eq!(conversations.list()[0].participants, vec!["bob".to_string()]);
+        assert_eq!(conversations.list()[1].messages[0].content, "c");
+
+        conversations.insert(message("d", "alice", &["me"], 300), "me");
+        assert_eq!(conversations.list()[0].participants, vec!["alice".to_string()]);
+    }
+}
@@ -1,6 +1,10 @@
 pub mod app;
 pub mod ui;
 pub mod events;
+pub mod feed;
+pub mod interactions;
+pub mod messages;
+pub mod thread;
 
 pub use app::App;
 pub use events::{EventHandler, InputEvent};
@@ -17,6 +21,9 @@ use ratatui::{
 };
 use std::io;
 
+use crate::pool::RelayPool;
+use app::MAX_CONNECTED_RELAYS;
+
 /// Initialize the terminal for TUI mode
 pub fn init() -> Result<Terminal<CrosstermBackend<io::Stdout>>> {
     execute!(io::stdout(), EnterAlternateScreen, EnableMouseCapture)?;
@@ -35,25 +42,29 @@ pub fn restore() -> Result<()> {
     Ok(())
 }
 
-/// Run the TUI application
-pub fn run() -> Result<()> {
-    let mut terminal = init()?;
+/// Run the TUI application, showing notes with at least `min_pow` bits of
+/// proof of work
+pub async fn run(min_pow: u32) -> Result<()> {
+    let relay_pool = RelayPool::new().with_max_relays(MAX_CONNECTED_RELAYS);
+    let event_handler = EventHandler::new(250, relay_pool.notifications()); // 250ms tick rate
 
     // Create the application state
-    let mut app = App::new()?;
-    let event_handler = EventHandler::new(250); // 250ms tick rate
+    let mut app = App::new(relay_pool, event_handler.sender())?.with_min_pow(min_pow);
+
+    let mut terminal = init()?;
 
     // Main application loop
-    let result = run_app(&mut terminal, &mut app, event_handler);
+    let result = run_app(&mut terminal, &mut app, event_handler).await;
 
     // Restore terminal
     restore()?;
+    app.relay_pool.shutdown().await;
 
     result
 }
 
 /// Main application loop
-fn run_app(
+async fn run_app(
     terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
     app: &mut App,
     mut event_handler: EventHandler,
@@ -63,14 +74,20 @@ fn run_app(
         terminal.draw(|f| ui::draw(f, app))?;
 
         // Handle events
-        match event_handler.next()? {
+        match event_handler.next().await? {
             InputEvent::Input(event) => {
                 if app.handle_input(event)? {
                     break; // Exit requested
                 }
             }
             InputEvent::Tick => {
-                app.tick();
+                app.tick().await;
+            }
+            InputEvent::App(event) => {
+                app.handle_app_event(event);
+            }
+            InputEvent::Relay(notification) => {
+                app.handle_relay_notification(notification);
             }
         }
     }
@@ -0,0 +1,128 @@
+use std::collections::HashSet;
+
+use crate::nostr::NostrEvent;
+use crate::nostr::nip09::deletes;
+use crate::nostr::nip10::{ThreadEntry, ThreadRefs, resolve_thread};
+
+/// The notes of one conversation thread (NIP-10), as shown in the thread view
+#[derive(Debug)]
+pub struct Thread {
+    root_id: String,
+    events: Vec<NostrEvent>,
+    seen_event_ids: HashSet<String>,
+    /// `events` arranged as a tree, rebuilt whenever a note arrives
+    entries: Vec<ThreadEntry>,
+}
+
+impl Thread {
+    pub fn new(root_id: String) -> Self {
+        Self {
+            root_id,
+            events: Vec::new(),
+            seen_event_ids: HashSet::new(),
+            entries: Vec::new(),
+        }
+    }
+
+    pub fn entries(&self) -> &[ThreadEntry] {
+        &self.entries
+    }
+
+    pub fn get(&self, index: usize) -> Option<&ThreadEntry> {
+        self.entries.get(index)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Index of the note with this id
+    pub fn position(&self, event_id: &str) -> Option<usize> {
+        self.entries.iter().position(|entry| entry.event.id == event_id)
+    }
+
+    /// Add the root or a reply in this thread. Returns false for notes that
+    /// were already known or belong to another thread.
+    pub fn insert(&mut self, event: NostrEvent) -> bool {
+        let in_thread = event.id == self.root_id || ThreadRefs::from_event(&event).root_id(&event) == self.root_id;
+        if !in_thread || !self.seen_event_ids.insert(event.id.clone()) {
+            return false;
+        }
+
+        self.events.push(event);
+        self.entries = resolve_thread(&self.root_id, &self.events);
+        true
+    }
+
+    /// Remove the notes a deletion request (NIP-09) deletes. Returns whether
+    /// any was removed.
+    pub fn apply_deletion(&mut self, deletion: &NostrEvent) -> bool {
+        let count = self.events.len();
+        self.events.retain(|event| !deletes(deletion, event));
+        if self.events.len() == count {
+            return false;
+        }
+
+        self.entries = resolve_thread(&self.root_id, &self.events);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nostr::event::UnsignedEvent;
+    use crate::nostr::keys::generate_keypair;
+    use crate::nostr::nip09::DeletionRequest;
+
+    #[test]
+    fn test_replies_arriving_before_their_parent_move_under_it() {
+        let keypair = generate_keypair().unwrap();
+        let note = |content: &str, created_at: u64, parent: Option<&NostrEvent>| {
+            let unsigned = UnsignedEvent::new_text_note(content.to_string(), keypair.public_key_hex()).with_timestamp(created_at);
+            match parent {
+                Some(parent) => unsigned.with_reply_to(parent, None),
+                None => unsigned,
+            }
+            .sign(&keypair)
+            .unwrap()
+        };
+
+        let root = note("root", 100, None);
+        let reply = note("reply", 200, Some(&root));
+        let nested = note("nested", 300, Some(&reply));
+        let other = note("other thread", 150, None);
+
+        let mut thread = Thread::new(root.id.clone());
+        assert!(thread.insert(root.clone()));
+        assert!(thread.insert(nested.clone()));
+        assert!(!thread.insert(other));
+        assert_eq!(thread.get(1).map(|entry| entry.depth), Some(1), "Parent not known yet");
+
+        assert!(thread.insert(reply.clone()));
+        assert!(!thread.insert(reply.clone()));
+        assert_eq!(thread.len(), 3);
+        assert_eq!(thread.position(&reply.id), Some(1));
+        assert_eq!(thread.get(2).map(|entry| (entry.event.id.as_str(), entry.depth)), Some((nested.id.as_str(), 2)));
+    }
+
+    #[test]
+    fn test_deleted_replies_leave_the_thread() {
+        let keypair = generate_keypair().unwrap();
+        let root = NostrEvent::new_text_note("root".to_string(), &keypair).unwrap();
+        let reply = UnsignedEvent::new_text_note("reply".to_string(), keypair.public_key_hex())
+            .with_reply_to(&root, None)
+            .sign(&keypair)
+            .unwrap();
+
+        let mut thread = Thread::new(root.id.clone());
+        assert!(thread.insert(root.clone()));
+        assert!(thread.insert(reply.clone()));
+
+        let deletion = DeletionRequest::new().with_event(&reply).to_event(&keypair).unwrap();
+        assert!(thread.apply_deletion(&deletion));
+        assert!(!thread.apply_deletion(&deletion));
+        assert_eq!(thread.len(), 1);
+        assert_eq!(thread.position(&root.id), Some(0));
+    }
+}
@@ -3,12 +3,17 @@ use ratatui::{
     style::{Color, Modifier, Style},
     text::{Line, Span},
     widgets::{
-        Block, Borders, Clear, List, ListItem, Paragraph, Wrap,
+        Block, Borders, Clear, List, ListItem, ListState, Paragraph, Wrap,
     },
     Frame,
 };
+use std::time::{SystemTime, UNIX_EPOCH};
 
-use super::app::{App, ComposeFocus, CurrentView};
+use super::app::{AccountModalMode, App, ComposeFocus, CurrentView, MessagesFocus};
+use super::feed::format_relative_time;
+use super::interactions::NoteInteractions;
+use crate::nostr::metadata::METADATA_FIELDS;
+use crate::nostr::nip25::Reaction;
 
 /// Main UI drawing function
 pub fn draw(f: &mut Frame, app: &App) {
@@ -33,6 +38,8 @@ pub fn draw(f: &mut Frame, app: &App) {
         CurrentView::AccountModal => draw_account_modal(f, app, chunks[1]),
         CurrentView::ComposeModal => draw_compose_modal(f, app, chunks[1]),
         CurrentView::HelpModal => draw_help_modal(f, app, chunks[1]),
+        CurrentView::Messages => draw_messages_view(f, app, chunks[1]),
+        CurrentView::Thread => draw_thread_view(f, app, chunks[1]),
     }
 
     // Draw bottom status bar
@@ -42,6 +49,11 @@ pub fn draw(f: &mut Frame, app: &App) {
     if app.password_prompt_active {
         draw_password_prompt(f, app, size);
     }
+
+    // Relays asking to authenticate wait for an answer before anything else
+    if let Some(relay_url) = app.auth_prompt() {
+        draw_auth_prompt(f, app, relay_url, size);
+    }
 }
 
 /// Draw the top status bar showing current account and relay status
@@ -70,12 +82,21 @@ fn draw_top_status_bar(f: &mut Frame, app: &App, area: Rect) {
 /// Draw the bottom status bar with context-sensitive shortcuts
 fn draw_bottom_status_bar(f: &mut Frame, app: &App, area: Rect) {
     let shortcuts = match app.current_view {
+        _ if app.auth_prompt().is_some() => vec![
+            ("y/Enter", "Authenticate"),
+            ("n/Esc", "Refuse"),
+        ],
         CurrentView::Feed => vec![
             ("q", "Quit"),
             ("a", "Accounts"),
             ("n", "New Post"),
+            ("m", "Messages"),
             ("?", "Help"),
             ("↑↓", "Navigate"),
+            ("Enter", "Thread"),
+            ("+/-/1-3", "React"),
+            ("b/Q", "Repost/Quote"),
+            ("f/u", "Follow/Unfollow"),
         ],
         CurrentView::AccountModal => {
             if app.password_prompt_active {
@@ -84,12 +105,34 @@ fn draw_bottom_status_bar(f: &mut Frame, app: &App, area: Rect) {
                     ("Esc", "Cancel"),
                 ]
             } else {
-                vec![
-                    ("u", "Unlock"),
-                    ("l", "Lock"),
-                    ("c", "Create"),
-                    ("Esc", "Back"),
-                ]
+                match app.account_mode {
+                    AccountModalMode::List if !app.keystore_unlocked => vec![
+                        ("u", "Unlock"),
+                        ("Esc", "Back"),
+                    ],
+                    AccountModalMode::List => vec![
+                        ("c", "Create"),
+                        ("i", "Import"),
+                        ("Enter", "Select"),
+                        ("p", "Profile"),
+                        ("d", "Delete"),
+                        ("l", "Lock"),
+                        ("Esc", "Back"),
+                    ],
+                    AccountModalMode::ConfirmDelete => vec![
+                        ("y", "Delete"),
+                        ("n", "Keep"),
+                    ],
+                    AccountModalMode::EditProfile => vec![
+                        ("↑↓", "Field"),
+                        ("Enter", "Publish"),
+                        ("Esc", "Cancel"),
+                    ],
+                    _ => vec![
+                        ("Enter", "Confirm"),
+                        ("Esc", "Cancel"),
+                    ],
+                }
             }
         }
         CurrentView::ComposeModal => {
@@ -110,6 +153,31 @@ fn draw_bottom_status_bar(f: &mut Frame, app: &App, area: Rect) {
         CurrentView::HelpModal => vec![
             ("Esc", "Back"),
         ],
+        CurrentView::Messages => match app.messages_focus {
+            MessagesFocus::Conversations => vec![
+                ("↑↓", "Conversation"),
+                ("Enter", "Reply"),
+                ("c", "New"),
+                ("Esc", "Back"),
+            ],
+            MessagesFocus::Recipient => vec![
+                ("Enter", "Start"),
+                ("Esc", "Cancel"),
+            ],
+            MessagesFocus::Reply => vec![
+                ("Enter", "Send"),
+                ("Esc", "Done"),
+            ],
+        },
+        CurrentView::Thread if app.thread_replying => vec![
+            ("Enter", "Send"),
+            ("Esc", "Cancel"),
+        ],
+        CurrentView::Thread => vec![
+            ("↑↓", "Navigate"),
+            ("Enter", "Reply"),
+            ("Esc", "Back"),
+        ],
     };
 
     let shortcut_spans: Vec<Span> = shortcuts
@@ -138,36 +206,293 @@ fn draw_bottom_status_bar(f: &mut Frame, app: &App, area: Rect) {
 /// Draw the main feed view
 fn draw_feed_view(f: &mut Frame, app: &App, area: Rect) {
     let block = Block::default()
-        .title("Feed")
+        .title(format!("Home ({} notes)", app.feed.len()))
         .borders(Borders::ALL)
         .border_style(Style::default().fg(Color::White));
 
-    // Convert feed items to list items
-    let items: Vec<ListItem> = app.feed_items
-        .iter()
-        .enumerate()
-        .map(|(i, item)| {
-            let content = if i == app.selected_index {
-                format!("> {}", item)
-            } else {
-                format!("  {}", item)
-            };
+    if app.feed.is_empty() {
+        let placeholder = if app.keystore_unlocked {
+            "No posts yet. Follow the authors of notes you like (f) or check relays."
+        } else {
+            "Unlock your accounts (press 'a') to load your home feed."
+        };
 
-            let style = if i == app.selected_index {
-                Style::default().bg(Color::DarkGray).fg(Color::White)
-            } else {
-                Style::default().fg(Color::Gray)
+        let paragraph = Paragraph::new(placeholder)
+            .style(Style::default().fg(Color::Gray))
+            .block(block);
+        f.render_widget(paragraph, area);
+    } else {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or(0);
+
+        // Each note is the author and age on one line and its content on the next
+        let items: Vec<ListItem> = app.feed
+            .notes()
+            .iter()
+            .map(|note| {
+                let mut header = vec![
+                    Span::styled(
+                        app.feed.display_name(&note.pubkey),
+                        Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD),
+                    ),
+                    Span::styled(
+                        format!(" · {}", format_relative_time(note.created_at, now)),
+                        Style::default().fg(Color::DarkGray),
+                    ),
+                ];
+                if let Some(interactions) = app.interactions.get(&note.id) {
+                    header.push(Span::styled(
+                        format!(" · {}", interaction_summary(interactions)),
+                        Style::default().fg(Color::Magenta),
+                    ));
+                }
+                let content = Line::from(note.content.split_whitespace().collect::<Vec<_>>().join(" "));
+
+                ListItem::new(vec![Line::from(header), content, Line::from("")])
+            })
+            .collect();
+
+        let list = List::new(items)
+            .block(block)
+            .highlight_style(Style::default().bg(Color::DarkGray).fg(Color::White))
+            .highlight_symbol("> ");
+
+        let mut state = ListState::default().with_selected(Some(app.selected_index));
+        f.render_stateful_widget(list, area, &mut state);
+    }
+
+    // Draw status message if present
+    if let Some(ref message) = app.status_message {
+        draw_status_message(f, message, area);
+    }
+}
+
+/// Reaction and repost counts of a note, e.g. "♥ 3  🤙 1  ↻ 2"
+fn interaction_summary(interactions: &NoteInteractions) -> String {
+    let mut parts: Vec<String> = interactions
+        .reaction_counts()
+        .into_iter()
+        .map(|(reaction, count)| {
+            let label = match reaction {
+                Reaction::Like => "♥".to_string(),
+                Reaction::Dislike => "👎".to_string(),
+                other => other.content(),
             };
+            format!("{} {}", label, count)
+        })
+        .collect();
+    if interactions.repost_count() > 0 {
+        parts.push(format!("↻ {}", interactions.repost_count()));
+    }
+    parts.join("  ")
+}
 
-            ListItem::new(content).style(style)
+/// Draw private conversations: the conversation list on the left, the selected
+/// conversation and the message input on the right
+fn draw_messages_view(f: &mut Frame, app: &App, area: Rect) {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0);
+    let names = |participants: &[String]| {
+        participants
+            .iter()
+            .map(|pubkey| app.feed.display_name(pubkey))
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+
+    let columns = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(30), Constraint::Percentage(70)])
+        .split(area);
+
+    let list_block = Block::default()
+        .title(format!("Messages ({})", app.conversations.len()))
+        .borders(Borders::ALL)
+        .border_style(if app.messages_focus == MessagesFocus::Conversations {
+            Style::default().fg(Color::Yellow)
+        } else {
+            Style::default().fg(Color::White)
+        });
+
+    let items: Vec<ListItem> = app.conversations
+        .list()
+        .iter()
+        .map(|conversation| {
+            ListItem::new(Line::from(vec![
+                Span::styled(names(&conversation.participants), Style::default().fg(Color::Cyan)),
+                Span::styled(
+                    format!(" · {}", format_relative_time(conversation.last_activity(), now)),
+                    Style::default().fg(Color::DarkGray),
+                ),
+            ]))
         })
         .collect();
 
-    let list = List::new(items).block(block);
+    let selected = app.new_conversation.is_none().then_some(app.conversation_index);
+    let list = List::new(items)
+        .block(list_block)
+        .highlight_style(Style::default().bg(Color::DarkGray).fg(Color::White))
+        .highlight_symbol("> ");
+    f.render_stateful_widget(list, columns[0], &mut ListState::default().with_selected(selected));
 
-    f.render_widget(list, area);
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(0), Constraint::Length(3)])
+        .split(columns[1]);
+
+    let participants = app.selected_participants();
+    let thread_title = match &participants {
+        Some(participants) => format!("Private conversation with {}", names(participants)),
+        None => "No conversation selected".to_string(),
+    };
+    let thread_block = Block::default()
+        .title(thread_title)
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::White));
+
+    let messages = participants
+        .as_ref()
+        .and_then(|participants| app.conversations.position(participants))
+        .and_then(|index| app.conversations.get(index))
+        .map(|conversation| conversation.messages.as_slice())
+        .unwrap_or_default();
+
+    let lines: Vec<Line> = if messages.is_empty() {
+        let placeholder = if app.conversations.is_empty() && participants.is_none() {
+            "No private messages yet. Press 'c' to start a conversation."
+        } else {
+            "No messages yet. Press Enter to write one."
+        };
+        vec![Line::styled(placeholder, Style::default().fg(Color::Gray))]
+    } else {
+        messages
+            .iter()
+            .flat_map(|message| {
+                let is_own = app.home_pubkey() == Some(message.sender.as_str());
+                let sender = if is_own { "You".to_string() } else { app.feed.display_name(&message.sender) };
+                let mut header = vec![
+                    Span::styled(
+                        sender,
+                        Style::default()
+                            .fg(if is_own { Color::Green } else { Color::Cyan })
+                            .add_modifier(Modifier::BOLD),
+                    ),
+                    Span::styled(
+                        format!(" · {}", format_relative_time(message.created_at, now)),
+                        Style::default().fg(Color::DarkGray),
+                    ),
+                ];
+                if message.legacy {
+                    header.push(Span::styled(
+                        " · legacy NIP-04, metadata-leaking",
+                        Style::default().fg(Color::Yellow),
+                    ));
+                }
+                let header = Line::from(header);
+                std::iter::once(header).chain(message.content.lines().map(|line| Line::from(line.to_string())))
+            })
+            .collect()
+    };
+
+    // Keep the newest messages in view
+    let visible_rows = rows[0].height.saturating_sub(2) as usize;
+    let scroll = lines.len().saturating_sub(visible_rows) as u16;
+    f.render_widget(Paragraph::new(lines).block(thread_block).scroll((scroll, 0)), rows[0]);
+
+    let (input_title, input) = match app.messages_focus {
+        MessagesFocus::Recipient => ("To (npub or hex)", format!("{}_", app.recipient_input)),
+        MessagesFocus::Reply => ("Message", format!("{}_", app.message_input)),
+        MessagesFocus::Conversations => ("Message", app.message_input.clone()),
+    };
+    let input_block = Block::default()
+        .title(input_title)
+        .borders(Borders::ALL)
+        .border_style(if app.messages_focus == MessagesFocus::Conversations {
+            Style::default().fg(Color::Gray)
+        } else {
+            Style::default().fg(Color::Yellow)
+        });
+    f.render_widget(Paragraph::new(input).block(input_block), rows[1]);
+
+    if let Some(ref message) = app.status_message {
+        draw_status_message(f, message, area);
+    }
+}
+
+/// Draw the thread opened from the feed, replies indented under the note
+/// they answer, with the reply input below
+fn draw_thread_view(f: &mut Frame, app: &App, area: Rect) {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0);
+
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(0), Constraint::Length(3)])
+        .split(area);
+
+    let entries = app.thread.as_ref().map(|thread| thread.entries()).unwrap_or_default();
+    let block = Block::default()
+        .title(format!("Thread ({} notes)", entries.len()))
+        .borders(Borders::ALL)
+        .border_style(if app.thread_replying {
+            Style::default().fg(Color::White)
+        } else {
+            Style::default().fg(Color::Yellow)
+        });
+
+    let items: Vec<ListItem> = entries
+        .iter()
+        .map(|entry| {
+            let indent = "  ".repeat(entry.depth);
+            let header = Line::from(vec![
+                Span::raw(indent.clone()),
+                Span::styled(
+                    app.feed.display_name(&entry.event.pubkey),
+                    Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD),
+                ),
+                Span::styled(
+                    format!(" · {}", format_relative_time(entry.event.created_at, now)),
+                    Style::default().fg(Color::DarkGray),
+                ),
+            ]);
+            let content = Line::from(format!(
+                "{}{}",
+                indent,
+                entry.event.content.split_whitespace().collect::<Vec<_>>().join(" ")
+            ));
+
+            ListItem::new(vec![header, content, Line::from("")])
+        })
+        .collect();
+
+    let list = List::new(items)
+        .block(block)
+        .highlight_style(Style::default().bg(Color::DarkGray).fg(Color::White))
+        .highlight_symbol("> ");
+    let mut state = ListState::default().with_selected(Some(app.thread_index));
+    f.render_stateful_widget(list, rows[0], &mut state);
+
+    let replying_to = entries
+        .get(app.thread_index)
+        .map(|entry| format!("Reply to {}", app.feed.display_name(&entry.event.pubkey)))
+        .unwrap_or_else(|| "Reply".to_string());
+    let (input, border) = if app.thread_replying {
+        (format!("{}_", app.thread_reply_input), Style::default().fg(Color::Yellow))
+    } else {
+        (app.thread_reply_input.clone(), Style::default().fg(Color::Gray))
+    };
+    let input_block = Block::default()
+        .title(replying_to)
+        .borders(Borders::ALL)
+        .border_style(border);
+    f.render_widget(Paragraph::new(input).block(input_block), rows[1]);
 
-    // Draw status message if present
     if let Some(ref message) = app.status_message {
         draw_status_message(f, message, area);
     }
@@ -190,49 +515,52 @@ fn draw_account_modal(f: &mut Frame, app: &App, area: Rect) {
     f.render_widget(block, popup_area);
 
     // Account management content
-    let content = if app.keystore_unlocked {
-        let accounts = app.account_manager.list_accounts();
-        if accounts.is_empty() {
-            vec![
-                Line::from("No accounts found."),
-                Line::from(""),
-                Line::from("Available actions:"),
-                Line::from("  c - Create new account"),
-                Line::from("  i - Import existing account"),
-                Line::from("  l - Lock keystore"),
-            ]
-        } else {
-            let mut lines = vec![
-                Line::from("Accounts:"),
-                Line::from(""),
-            ];
-
-            for account in accounts {
-                let status = if account.is_active { " (active)" } else { "" };
-                lines.push(Line::from(format!("  {} - {}{}",
-                    account.name,
-                    &account.public_key_npub[..16],
-                    status
-                )));
-            }
-
-            lines.extend(vec![
-                Line::from(""),
-                Line::from("Available actions:"),
-                Line::from("  c - Create new account"),
-                Line::from("  i - Import existing account"),
-                Line::from("  l - Lock keystore"),
-            ]);
-
-            lines
-        }
-    } else {
+    let content = if !app.keystore_unlocked {
         vec![
             Line::from("Keystore is locked."),
             Line::from(""),
             Line::from("Available actions:"),
             Line::from("  u - Unlock keystore"),
         ]
+    } else {
+        match app.account_mode {
+            AccountModalMode::List => account_list_lines(app),
+            AccountModalMode::CreateName => account_form_lines(
+                "Name for the new account:",
+                format!("{}_", app.account_name_input),
+                "A new key pair is generated and stored in the keystore.",
+            ),
+            AccountModalMode::ImportKey => account_form_lines(
+                "Private key to import (nsec or hex):",
+                format!("{}_", "*".repeat(app.import_key_input.chars().count())),
+                "The key is encrypted in the keystore and never shown again.",
+            ),
+            AccountModalMode::ImportName => account_form_lines(
+                "Name for the imported account:",
+                format!("{}_", app.account_name_input),
+                "",
+            ),
+            AccountModalMode::ConfirmDelete => {
+                let name = app.account_manager
+                    .list_accounts()
+                    .get(app.account_index)
+                    .map(|account| account.name.clone())
+                    .unwrap_or_default();
+
+                vec![
+                    Line::from(Span::styled(
+                        format!("Delete account '{}'?", name),
+                        Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+                    )),
+                    Line::from(""),
+                    Line::from("Its private key is removed from the keystore and cannot be"),
+                    Line::from("recovered unless you have a backup."),
+                    Line::from(""),
+                    Line::from("  y - Delete    n - Keep"),
+                ]
+            }
+            AccountModalMode::EditProfile => profile_form_lines(app),
+        }
     };
 
     let paragraph = Paragraph::new(content)
@@ -246,6 +574,95 @@ fn draw_account_modal(f: &mut Frame, app: &App, area: Rect) {
     }
 }
 
+/// Account list with the highlighted account and available actions
+fn account_list_lines(app: &App) -> Vec<Line<'static>> {
+    let accounts = app.account_manager.list_accounts();
+
+    let mut lines = if accounts.is_empty() {
+        vec![Line::from("No accounts found.")]
+    } else {
+        let mut lines = vec![
+            Line::from("Accounts:"),
+            Line::from(""),
+        ];
+
+        for (i, account) in accounts.iter().enumerate() {
+            let status = if account.is_active { " (active)" } else { "" };
+            let marker = if i == app.account_index { "> " } else { "  " };
+            let style = if i == app.account_index {
+                Style::default().bg(Color::DarkGray).fg(Color::White)
+            } else {
+                Style::default()
+            };
+
+            lines.push(Line::styled(
+                format!("{}{} - {}{}", marker, account.name, &account.public_key_npub[..16], status),
+                style,
+            ));
+        }
+
+        lines
+    };
+
+    lines.extend(vec![
+        Line::from(""),
+        Line::from("Available actions:"),
+        Line::from("  c - Create new account"),
+        Line::from("  i - Import existing account"),
+    ]);
+    if !accounts.is_empty() {
+        lines.extend(vec![
+            Line::from("  Enter - Make selected account active"),
+            Line::from("  p - Edit the active account's profile"),
+            Line::from("  d - Delete selected account"),
+        ]);
+    }
+    lines.push(Line::from("  l - Lock keystore"));
+
+    lines
+}
+
+/// The profile editor, one line per metadata field with the focused one highlighted
+fn profile_form_lines(app: &App) -> Vec<Line<'static>> {
+    let mut lines = vec![
+        Line::from("Profile of the active account:"),
+        Line::from(""),
+    ];
+
+    for (i, (field, input)) in METADATA_FIELDS.iter().zip(&app.profile_inputs).enumerate() {
+        let line = if i == app.profile_field {
+            Line::styled(format!("> {:<13} {}_", field, input), Style::default().fg(Color::Yellow))
+        } else {
+            Line::from(format!("  {:<13} {}", field, input))
+        };
+        lines.push(line);
+    }
+
+    lines.extend(vec![
+        Line::from(""),
+        Line::from(Span::styled(
+            "Blank fields are removed. Fields set by other clients are kept.",
+            Style::default().fg(Color::Gray),
+        )),
+        Line::from(""),
+        Line::from("Press Enter to publish, Esc to cancel"),
+    ]);
+    lines
+}
+
+/// A single-field account form
+fn account_form_lines(label: &str, input: String, hint: &str) -> Vec<Line<'static>> {
+    vec![
+        Line::from(label.to_string()),
+        Line::from(""),
+        Line::from(Span::styled(input, Style::default().fg(Color::Yellow))),
+        Line::from(""),
+        Line::from(Span::styled(hint.to_string(), Style::default().fg(Color::Gray))),
+        Line::from(""),
+        Line::from("Press Enter to confirm, Esc to cancel"),
+    ]
+}
+
 /// Draw the compose post modal
 fn draw_compose_modal(f: &mut Frame, app: &App, area: Rect) {
     // Create a large centered modal
@@ -254,8 +671,12 @@ fn draw_compose_modal(f: &mut Frame, app: &App, area: Rect) {
     // Clear the background
     f.render_widget(Clear, popup_area);
 
+    let title = match &app.compose_quote {
+        Some(quoted) => format!("Quote {}'s note", app.feed.display_name(&quoted.pubkey)),
+        None => "Compose Post".to_string(),
+    };
     let block = Block::default()
-        .title("Compose Post")
+        .title(title)
         .borders(Borders::ALL)
         .border_style(Style::default().fg(Color::Green));
 
@@ -365,6 +786,7 @@ fn draw_help_modal(f: &mut Frame, _app: &App, area: Rect) {
         Line::from("  Ctrl+C            - Force quit"),
         Line::from("  a                 - Open account management"),
         Line::from("  n                 - Compose new post"),
+        Line::from("  m                 - Private messages"),
         Line::from("  r                 - Refresh current view"),
         Line::from("  ?                 - Show this help"),
         Line::from("  Esc               - Return to feed / Cancel"),
@@ -375,15 +797,37 @@ fn draw_help_modal(f: &mut Frame, _app: &App, area: Rect) {
         Line::from("  ↓/j               - Move selection down"),
         Line::from("  Home/g            - Jump to top"),
         Line::from("  End/G             - Jump to bottom"),
-        Line::from("  Enter             - Expand/interact with post"),
+        Line::from("  f / u             - Follow / unfollow the selected note's author"),
+        Line::from("  Enter             - Open the selected note's thread"),
+        Line::from("  + / -             - Like / dislike the selected note"),
+        Line::from("  1 / 2 / 3         - React with 🤙 / ❤️ / 🔥"),
+        Line::from("  b                 - Repost the selected note"),
+        Line::from("  Q                 - Quote the selected note in a new post"),
+        Line::from(""),
+        Line::from(Span::styled("Thread", Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD))),
+        Line::from(""),
+        Line::from("  ↑/k ↓/j           - Select note"),
+        Line::from("  Enter/i           - Reply to selected note"),
+        Line::from("  Enter (in input)  - Send reply"),
+        Line::from("  Esc               - Back to feed"),
         Line::from(""),
         Line::from(Span::styled("Account Management", Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD))),
         Line::from(""),
         Line::from("  u                 - Unlock keystore"),
         Line::from("  l                 - Lock keystore"),
         Line::from("  c                 - Create new account"),
-        Line::from("  i                 - Import existing account"),
-        Line::from("  Enter             - Select account"),
+        Line::from("  i                 - Import existing account (nsec or hex)"),
+        Line::from("  ↑/k ↓/j           - Move account selection"),
+        Line::from("  Enter/s           - Make selected account active"),
+        Line::from("  p                 - Edit the active account's profile"),
+        Line::from("  d                 - Delete selected account"),
+        Line::from(""),
+        Line::from(Span::styled("Private Messages", Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD))),
+        Line::from(""),
+        Line::from("  ↑/k ↓/j           - Select conversation"),
+        Line::from("  Enter/i           - Reply in selected conversation"),
+        Line::from("  c                 - Start a conversation (npub or hex)"),
+        Line::from("  Enter (in input)  - Send message"),
         Line::from(""),
         Line::from(Span::styled("Compose Post", Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD))),
         Line::from(""),
@@ -432,6 +876,39 @@ fn draw_password_prompt(f: &mut Frame, app: &App, area: Rect) {
     f.render_widget(paragraph, inner);
 }
 
+/// Draw the prompt asking whether to authenticate to a relay (NIP-42)
+fn draw_auth_prompt(f: &mut Frame, app: &App, relay_url: &str, area: Rect) {
+    let popup_area = centered_rect(60, 25, area);
+
+    // Clear the background
+    f.render_widget(Clear, popup_area);
+
+    let block = Block::default()
+        .title("Authentication Requested")
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Yellow));
+
+    let inner = block.inner(popup_area);
+    f.render_widget(block, popup_area);
+
+    let mut content = vec![
+        Line::from(format!("{} requires authentication.", relay_url)),
+        Line::from(""),
+        Line::from(format!("Authenticate as {}?", app.get_current_account_display())),
+        Line::from(""),
+        Line::from("Press y or Enter to authenticate, n or Esc to refuse"),
+    ];
+    if app.auth_requests.len() > 1 {
+        content.push(Line::from(format!("{} more relays are waiting", app.auth_requests.len() - 1)));
+    }
+
+    let paragraph = Paragraph::new(content)
+        .alignment(Alignment::Center)
+        .wrap(Wrap { trim: true });
+
+    f.render_widget(paragraph, inner);
+}
+
 /// Draw a status message overlay
 fn draw_status_message(f: &mut Frame, message: &str, area: Rect) {
     let popup_area = Rect {
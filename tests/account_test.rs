@@ -0,0 +1,79 @@
+mod temp_config_dir;
+
+use anyhow::Result;
+use secrecy::SecretString;
+
+use temp_config_dir::TempConfigDir;
+use nosotros::accounts::AccountManager;
+use nosotros::nostr::nip17::{PrivateMessage, wrap_private_message};
+use nosotros::nostr::{NostrEvent, generate_keypair};
+
+#[test]
+fn test_accounts_are_found_by_name_or_id() -> Result<()> {
+    let config_dir = TempConfigDir::new();
+    let password = SecretString::from("correct horse battery staple".to_string());
+
+    let mut account_manager = AccountManager::new(config_dir.0.clone())?;
+    assert!(!account_manager.has_keystore());
+
+    let alice = account_manager.create_account("alice", &password)?;
+    assert!(account_manager.has_keystore());
+
+    let imported_keypair = generate_keypair()?;
+    let bob = account_manager.import_account("bob", &imported_keypair.secret_key_hex(), &password)?;
+    let other_bob = account_manager.create_account("bob", &password)?;
+
+    assert_eq!(account_manager.find_account("alice")?.id, alice.id);
+    assert_eq!(account_manager.find_account(&bob.id)?.public_key_hex, imported_keypair.public_key_hex());
+    assert!(account_manager.find_account("bob").is_err(), "Ambiguous names must be rejected");
+    assert!(account_manager.find_account("carol").is_err());
+
+    // A fresh manager sees the same accounts and can unlock their keys
+    let mut reloaded = AccountManager::new(config_dir.0.clone())?;
+    reloaded.set_active_account(&bob.id)?;
+    reloaded.unlock_keystore(&password)?;
+    let active = reloaded.get_active_account()?.expect("bob should be active");
+    assert_eq!(active.keypair.public_key_hex(), imported_keypair.public_key_hex());
+
+    // Deleting the active account activates the first remaining one
+    reloaded.delete_account(&bob.id, &password)?;
+    reloaded.delete_account(&other_bob.id, &password)?;
+    assert_eq!(reloaded.active_account_id(), Some(&alice.id));
+    assert!(reloaded.find_account("alice")?.is_active);
+
+    Ok(())
+}
+
+#[test]
+fn test_private_messages_are_opened_with_the_addressed_account() -> Result<()> {
+    let config_dir = TempConfigDir::new();
+    let password = SecretString::from("correct horse battery staple".to_string());
+
+    let mut account_manager = AccountManager::new(config_dir.0.clone())?;
+    let alice_keypair = generate_keypair()?;
+    let alice = account_manager.import_account("alice", &alice_keypair.secret_key_hex(), &password)?;
+    account_manager.create_account("work", &password)?;
+
+    let friend = generate_keypair()?;
+    let (_, wrap) = wrap_private_message(&friend, std::slice::from_ref(&alice.public_key_hex), "Hi Alice")?.remove(0);
+    let legacy = NostrEvent::new_legacy_direct_message("Old reply", &alice_keypair, &friend.public_key_hex())?;
+
+    account_manager.lock_keystore();
+    assert!(account_manager.unwrap_gift_wrap(&wrap).is_err(), "Locked keys cannot open anything");
+
+    account_manager.unlock_keystore(&password)?;
+    let (account, gift) = account_manager.unwrap_gift_wrap(&wrap)?;
+    assert_eq!(account.id, alice.id);
+    assert_eq!(PrivateMessage::from_gift(gift)?.content, "Hi Alice");
+
+    // Alice sent this one, so it is decrypted with her key and the friend's pubkey
+    let (account, message) = account_manager.decrypt_legacy_message(&legacy)?;
+    assert_eq!(account.id, alice.id);
+    assert_eq!(message.content, "Old reply");
+    assert!(message.legacy);
+
+    let (_, stranger_wrap) = wrap_private_message(&friend, &[generate_keypair()?.public_key_hex()], "Not yours")?.remove(0);
+    assert!(account_manager.unwrap_gift_wrap(&stranger_wrap).is_err());
+
+    Ok(())
+}
@@ -0,0 +1,147 @@
+mod temp_config_dir;
+
+use anyhow::Result;
+
+use temp_config_dir::TempConfigDir;
+use nosotros::event_store::{EventStore, KIND_DELETION};
+use nosotros::nostr::event::UnsignedEvent;
+use nosotros::nostr::{Filter, NostrEvent, NostrKeypair, generate_keypair};
+
+fn event(keypair: &NostrKeypair, kind: u16, content: &str, tags: Vec<Vec<String>>, created_at: u64) -> NostrEvent {
+    UnsignedEvent::new_text_note(content.to_string(), keypair.public_key_hex())
+        .with_kind(kind)
+        .with_tags(tags)
+        .with_timestamp(created_at)
+        .sign(keypair)
+        .unwrap()
+}
+
+fn tag(name: &str, value: &str) -> Vec<String> {
+    vec![name.to_string(), value.to_string()]
+}
+
+fn contents(events: &[NostrEvent]) -> Vec<&str> {
+    events.iter().map(|event| event.content.as_str()).collect()
+}
+
+#[test]
+fn test_events_persist_and_answer_filters() -> Result<()> {
+    let config_dir = TempConfigDir::new();
+    let alice = generate_keypair()?;
+    let bob = generate_keypair()?;
+
+    let mut store = EventStore::open(&config_dir.0)?;
+    let first = event(&alice, 1, "first", vec![tag("t", "nostr")], 100);
+    assert!(store.save(&first)?);
+    assert!(!store.save(&first)?, "Duplicates are not stored twice");
+    store.save(&event(&alice, 1, "second", vec![tag("p", &bob.public_key_hex())], 200))?;
+    store.save(&event(&bob, 1, "from bob", vec![tag("t", "nostr")], 300))?;
+    store.save(&event(&bob, 7, "+", vec![tag("e", &first.id)], 400))?;
+    drop(store);
+
+    let store = EventStore::open(&config_dir.0)?;
+    assert_eq!(store.get(&first.id)?, Some(first.clone()));
+
+    let everything = store.query(&[Filter::new()])?;
+    assert_eq!(contents(&everything), vec!["+", "from bob", "second", "first"]);
+
+    let by_alice = Filter::new().with_authors(vec![alice.public_key_hex()]);
+    assert_eq!(contents(&store.query(&[by_alice])?), vec!["second", "first"]);
+
+    let by_prefix = Filter::new().with_ids(vec![first.id[..10].to_string()]);
+    assert_eq!(contents(&store.query(&[by_prefix])?), vec!["first"]);
+
+    let uppercase = Filter::new().with_ids(vec![first.id.to_uppercase()]);
+    assert_eq!(contents(&store.query(&[uppercase])?), vec!["first"], "Full ids match case-insensitively like prefixes");
+
+    let tagged = Filter::new().with_kinds(vec![1]).with_tag('t', vec!["nostr".to_string()]);
+    assert_eq!(contents(&store.query(&[tagged])?), vec!["from bob", "first"]);
+
+    let window = Filter::new().with_since(150).with_until(300).with_limit(1);
+    assert_eq!(contents(&store.query(&[window])?), vec!["from bob"]);
+
+    let mentions = Filter::new().with_pubkey_refs(vec![bob.public_key_hex()]);
+    let reactions = Filter::new().with_kinds(vec![7]);
+    assert_eq!(contents(&store.query(&[mentions, reactions])?), vec!["+", "second"]);
+
+    Ok(())
+}
+
+#[test]
+fn test_replaceable_events_keep_the_newest_version() -> Result<()> {
+    let mut store = EventStore::open_in_memory()?;
+    let alice = generate_keypair()?;
+    let profiles = Filter::new().with_kinds(vec![0]).with_authors(vec![alice.public_key_hex()]);
+
+    assert!(store.save(&event(&alice, 0, "v2", vec![], 200))?);
+    assert!(!store.save(&event(&alice, 0, "v1", vec![], 100))?, "Older versions are ignored");
+    assert!(store.save(&event(&alice, 0, "v3", vec![], 300))?);
+    assert_eq!(contents(&store.query(&[profiles])?), vec!["v3"]);
+
+    // Addressable events are replaced per d tag
+    let article = |d: &str, content: &str, created_at| event(&alice, 30023, content, vec![tag("d", d)], created_at);
+    store.save(&article("intro", "intro v1", 100))?;
+    store.save(&article("intro", "intro v2", 200))?;
+    store.save(&article("outro", "outro v1", 150))?;
+    let articles = store.query(&[Filter::new().with_kinds(vec![30023])])?;
+    assert_eq!(contents(&articles), vec!["intro v2", "outro v1"]);
+
+    // Ephemeral events are never stored
+    assert!(!store.save(&event(&alice, 22242, "", vec![], 100))?);
+
+    Ok(())
+}
+
+#[test]
+fn test_deletion_requests_remove_the_authors_events() -> Result<()> {
+    let mut store = EventStore::open_in_memory()?;
+    let alice = generate_keypair()?;
+    let mallory = generate_keypair()?;
+
+    let note = event(&alice, 1, "oops", vec![], 100);
+    let kept = event(&alice, 1, "keep me", vec![], 110);
+    let article = event(&alice, 30023, "draft", vec![tag("d", "post")], 120);
+    for stored in [&note, &kept, &article] {
+        store.save(stored)?;
+    }
+
+    // Only the author can delete
+    store.save(&event(&mallory, KIND_DELETION, "", vec![tag("e", &kept.id)], 200))?;
+    assert!(store.get(&kept.id)?.is_some());
+
+    let address = format!("30023:{}:post", alice.public_key_hex());
+    let deletion = event(&alice, KIND_DELETION, "mistake", vec![tag("e", &note.id), tag("a", &address)], 200);
+    store.save(&deletion)?;
+
+    assert!(store.get(&note.id)?.is_none());
+    assert!(store.get(&article.id)?.is_none());
+    assert!(store.get(&deletion.id)?.is_some(), "The deletion request itself is kept");
+
+    // Deleted events don't come back when a relay sends them again
+    assert!(!store.save(&note)?);
+    assert!(!store.save(&event(&alice, 30023, "older draft", vec![tag("d", "post")], 150))?);
+    assert!(store.save(&event(&alice, 30023, "new post", vec![tag("d", "post")], 250))?);
+
+    let notes = store.query(&[Filter::new().with_kinds(vec![1, 30023])])?;
+    assert_eq!(contents(&notes), vec!["new post", "keep me"]);
+
+    Ok(())
+}
+
+#[test]
+fn test_relays_that_accepted_a_post_are_remembered() -> Result<()> {
+    let mut store = EventStore::open_in_memory()?;
+    let alice = generate_keypair()?;
+
+    let note = event(&alice, 1, "hello", vec![], 100);
+    store.save(&note)?;
+    store.record_published(&note.id, &["wss://b.example.com".to_string(), "wss://a.example.com".to_string()])?;
+    store.record_published(&note.id, &["wss://a.example.com".to_string()])?;
+    assert_eq!(store.published_relays(&note.id)?, vec!["wss://a.example.com", "wss://b.example.com"]);
+
+    // Forgotten along with the event once it is deleted
+    store.save(&event(&alice, KIND_DELETION, "", vec![tag("e", &note.id)], 200))?;
+    assert!(store.published_relays(&note.id)?.is_empty());
+
+    Ok(())
+}
@@ -7,7 +7,9 @@ use std::time::Duration;
 use tokio::time::timeout;
 use tokio_tungstenite::{connect_async, tungstenite::Message};
 
-use nosotros::nostr::{NostrEvent, generate_keypair};
+use nosotros::commands::PostCommand;
+use nosotros::nostr::nip13::difficulty;
+use nosotros::nostr::{Filter, NostrEvent, RelayMessage, generate_keypair};
 use mock_relay::MockRelay;
 
 #[tokio::test]
@@ -52,7 +54,7 @@ async fn test_post_command_integration() -> Result<()> {
     if let Some(Ok(Message::Text(response_text))) = response {
         println!("📥 Received response: {}", response_text);
 
-        let response_json: Value = serde_json::from_str(&response_text.to_string())?;
+        let response_json: Value = serde_json::from_str(response_text.as_str())?;
 
         if let Some(response_array) = response_json.as_array() {
             assert_eq!(response_array[0], "OK", "Expected OK response");
@@ -106,7 +108,7 @@ async fn test_invalid_event_rejection() -> Result<()> {
     let response = timeout(Duration::from_secs(5), ws_stream.next()).await?;
 
     if let Some(Ok(Message::Text(response_text))) = response {
-        let response_json: Value = serde_json::from_str(&response_text.to_string())?;
+        let response_json: Value = serde_json::from_str(response_text.as_str())?;
 
         if let Some(response_array) = response_json.as_array() {
             assert_eq!(response_array[0], "OK");
@@ -148,4 +150,144 @@ async fn test_event_validation_components() -> Result<()> {
 
     println!("✅ All event validation components passed");
     Ok(())
-}
\ No newline at end of file
+}
+
+#[tokio::test]
+async fn test_subscription_returns_matching_stored_events() -> Result<()> {
+    println!("🚀 Starting filtered subscription test");
+
+    let mut relay = MockRelay::new().await?;
+    let relay_url = relay.websocket_url();
+
+    let keypair = generate_keypair()?;
+    let event = NostrEvent::new_text_note("Stored note".to_string(), &keypair)?;
+
+    let relay_task = tokio::spawn(async move {
+        if let Err(e) = relay.start().await {
+            eprintln!("Relay error: {}", e);
+        }
+    });
+
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    let (mut ws_stream, _) = connect_async(&relay_url).await?;
+
+    let message = serde_json::json!(["EVENT", event.to_json_value()?]);
+    ws_stream.send(Message::Text(serde_json::to_string(&message)?.into())).await?;
+    timeout(Duration::from_secs(5), ws_stream.next()).await?;
+
+    let matching = Filter::new().with_kinds(vec![1]).with_authors(vec![keypair.public_key_hex()]);
+    let other_author = Filter::new().with_authors(vec!["00".repeat(32)]);
+
+    let req = serde_json::json!(["REQ", "matching", matching]);
+    ws_stream.send(Message::Text(serde_json::to_string(&req)?.into())).await?;
+
+    let mut responses = Vec::new();
+    for _ in 0..2 {
+        if let Some(Ok(Message::Text(text))) = timeout(Duration::from_secs(5), ws_stream.next()).await? {
+            responses.push(serde_json::from_str::<Value>(text.as_str())?);
+        }
+    }
+
+    assert_eq!(responses[0][0], "EVENT");
+    assert_eq!(responses[0][1], "matching");
+    assert_eq!(responses[0][2]["id"], event.id);
+    assert_eq!(responses[1], serde_json::json!(["EOSE", "matching"]));
+
+    let req = serde_json::json!(["REQ", "other", other_author]);
+    ws_stream.send(Message::Text(serde_json::to_string(&req)?.into())).await?;
+
+    if let Some(Ok(Message::Text(text))) = timeout(Duration::from_secs(5), ws_stream.next()).await? {
+        let response: Value = serde_json::from_str(text.as_str())?;
+        assert_eq!(response, serde_json::json!(["EOSE", "other"]), "Non-matching filter should only get EOSE");
+    } else {
+        panic!("No response received from relay");
+    }
+
+    println!("✅ Relay answered subscriptions using filter matching");
+
+    ws_stream.close(None).await?;
+    relay_task.abort();
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_post_command_tolerates_interleaved_messages() -> Result<()> {
+    println!("🚀 Starting interleaved relay messages test");
+
+    let other_keypair = generate_keypair()?;
+    let unrelated_event = NostrEvent::new_text_note("Someone else's note".to_string(), &other_keypair)?;
+
+    let mut relay = MockRelay::new().await?.with_messages_before_ok(vec![
+        RelayMessage::Notice { message: "Welcome to the mock relay".to_string() },
+        RelayMessage::Event { subscription_id: "other-sub".to_string(), event: unrelated_event.clone() },
+        RelayMessage::Ok { event_id: unrelated_event.id.clone(), accepted: true, message: String::new() },
+    ]);
+    let relay_url = relay.websocket_url();
+
+    let relay_task = tokio::spawn(async move {
+        if let Err(e) = relay.start().await {
+            eprintln!("Relay error: {}", e);
+        }
+    });
+
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    let keypair = generate_keypair()?;
+    let post_command = PostCommand::new("Posted through the noise".to_string(), vec![relay_url], keypair);
+    let event_id = post_command.execute().await?;
+
+    assert_eq!(event_id.len(), 64, "Post should return the accepted event id");
+    assert_ne!(event_id, unrelated_event.id, "OK for another event must not be taken as ours");
+
+    println!("✅ Post command ignored unrelated messages and waited for its OK");
+
+    relay_task.abort();
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_post_command_mines_proof_of_work() -> Result<()> {
+    let mut relay = MockRelay::new().await?;
+    let relay_url = relay.websocket_url();
+
+    let relay_task = tokio::spawn(async move {
+        if let Err(e) = relay.start().await {
+            eprintln!("Relay error: {}", e);
+        }
+    });
+
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    let keypair = generate_keypair()?;
+    let post_command = PostCommand::new("Worth the work".to_string(), vec![relay_url], keypair).with_pow(8);
+    let event_id = post_command.execute().await?;
+
+    assert!(difficulty(&event_id) >= 8, "Published id should have the mined difficulty");
+
+    relay_task.abort();
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_post_command_mines_again_for_a_relay_wanting_more_work() -> Result<()> {
+    let mut relay = MockRelay::new().await?.with_rejections(&["pow: difficulty 0 is less than 8"]);
+    let relay_url = relay.websocket_url();
+
+    let relay_task = tokio::spawn(async move {
+        if let Err(e) = relay.start().await {
+            eprintln!("Relay error: {}", e);
+        }
+    });
+
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    let keypair = generate_keypair()?;
+    let post_command = PostCommand::new("Mined on demand".to_string(), vec![relay_url], keypair);
+    let event_id = post_command.execute().await?;
+
+    assert!(difficulty(&event_id) >= 8, "The copy the relay accepted should have the difficulty it asked for");
+
+    relay_task.abort();
+    Ok(())
+}
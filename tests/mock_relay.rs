@@ -1,16 +1,24 @@
 use anyhow::Result;
 use futures_util::{SinkExt, StreamExt};
-use serde_json::{json, Value};
 use std::net::SocketAddr;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc;
 use tokio_tungstenite::{accept_async, tungstenite::Message, WebSocketStream};
 
-use nosotros::nostr::NostrEvent;
+use nosotros::nostr::{ClientMessage, Filter, NostrEvent, RelayMessage};
 
 pub struct MockRelay {
     listener: TcpListener,
     addr: SocketAddr,
     events_received: Vec<NostrEvent>,
+    messages_before_ok: Vec<RelayMessage>,
+    drop_after_messages: Option<usize>,
+    message_log: Option<mpsc::UnboundedSender<ClientMessage>>,
+    auth_challenge: Option<String>,
+    authenticated: bool,
+    information: Option<String>,
+    rejections: Vec<String>,
 }
 
 impl MockRelay {
@@ -22,9 +30,62 @@ impl MockRelay {
             listener,
             addr,
             events_received: Vec::new(),
+            messages_before_ok: Vec::new(),
+            drop_after_messages: None,
+            message_log: None,
+            auth_challenge: None,
+            authenticated: false,
+            information: None,
+            rejections: Vec::new(),
         })
     }
 
+    /// Send these messages before every OK, like a busy relay interleaving
+    /// notices and subscription traffic with command results
+    #[allow(dead_code)]
+    pub fn with_messages_before_ok(mut self, messages: Vec<RelayMessage>) -> Self {
+        self.messages_before_ok = messages;
+        self
+    }
+
+    /// Drop the first connection without a close frame after answering this
+    /// many client messages, like a relay crashing or a network failure
+    #[allow(dead_code)]
+    pub fn with_drop_after_messages(mut self, count: usize) -> Self {
+        self.drop_after_messages = Some(count);
+        self
+    }
+
+    /// Forward every client message the relay receives to `log`
+    #[allow(dead_code)]
+    pub fn with_message_log(mut self, log: mpsc::UnboundedSender<ClientMessage>) -> Self {
+        self.message_log = Some(log);
+        self
+    }
+
+    /// Send a NIP-42 AUTH challenge on connect and refuse EVENT and REQ with
+    /// `auth-required:` until the client authenticates
+    #[allow(dead_code)]
+    pub fn with_auth_required(mut self, challenge: &str) -> Self {
+        self.auth_challenge = Some(challenge.to_string());
+        self
+    }
+
+    /// Serve this NIP-11 information document to HTTP requests asking for it
+    #[allow(dead_code)]
+    pub fn with_information(mut self, document: serde_json::Value) -> Self {
+        self.information = Some(document.to_string());
+        self
+    }
+
+    /// Refuse the next events with these OK messages, one each, before
+    /// accepting events again
+    #[allow(dead_code)]
+    pub fn with_rejections(mut self, messages: &[&str]) -> Self {
+        self.rejections = messages.iter().map(|message| message.to_string()).collect();
+        self
+    }
+
     pub fn websocket_url(&self) -> String {
         format!("ws://{}", self.addr)
     }
@@ -36,17 +97,74 @@ impl MockRelay {
     pub async fn start(&mut self) -> Result<()> {
         println!("Mock relay listening on {}", self.addr);
 
-        while let Ok((stream, _)) = self.listener.accept().await {
-            let ws_stream = accept_async(stream).await?;
+        // Handle one connection for testing
+        let ws_stream = self.accept_websocket().await?;
+        self.handle_connection(ws_stream).await?;
+
+        Ok(())
+    }
+
+    /// Handle `connections` consecutive connections, e.g. a client reconnecting
+    #[allow(dead_code)]
+    pub async fn serve(&mut self, connections: usize) -> Result<()> {
+        println!("Mock relay listening on {}", self.addr);
+
+        for _ in 0..connections {
+            let ws_stream = self.accept_websocket().await?;
             self.handle_connection(ws_stream).await?;
-            break; // Handle one connection for testing
         }
 
         Ok(())
     }
 
+    /// Accept the next websocket connection, answering plain HTTP requests
+    /// for the information document on the way
+    async fn accept_websocket(&self) -> Result<WebSocketStream<TcpStream>> {
+        loop {
+            let (mut stream, _) = self.listener.accept().await?;
+
+            let mut peeked = [0u8; 2048];
+            let length = stream.peek(&mut peeked).await?;
+            let request = String::from_utf8_lossy(&peeked[..length]).to_lowercase();
+
+            if request.contains("upgrade: websocket") {
+                return Ok(accept_async(stream).await?);
+            }
+
+            let mut request = Vec::new();
+            let mut buffer = [0u8; 2048];
+            while !request.ends_with(b"\r\n\r\n") {
+                let read = stream.read(&mut buffer).await?;
+                if read == 0 {
+                    break;
+                }
+                request.extend_from_slice(&buffer[..read]);
+            }
+            let request = String::from_utf8_lossy(&request).to_lowercase();
+            println!("Received HTTP request: {}", request.lines().next().unwrap_or_default());
+
+            let response = match &self.information {
+                Some(document) if request.contains("accept: application/nostr+json") => format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/nostr+json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    document.len(),
+                    document
+                ),
+                _ => "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_string(),
+            };
+            stream.write_all(response.as_bytes()).await?;
+            stream.shutdown().await?;
+        }
+    }
+
     async fn handle_connection(&mut self, mut ws_stream: WebSocketStream<TcpStream>) -> Result<()> {
         println!("New WebSocket connection established");
+        let mut answered_messages = 0;
+
+        self.authenticated = false;
+        if let Some(challenge) = &self.auth_challenge {
+            let auth = RelayMessage::Auth { challenge: challenge.clone() };
+            ws_stream.send(Message::Text(auth.to_json()?.into())).await?;
+        }
 
         while let Some(msg) = ws_stream.next().await {
             match msg? {
@@ -54,20 +172,27 @@ impl MockRelay {
                     println!("Received message: {}", text);
 
                     match self.process_message(&text).await {
-                        Ok(response) => {
-                            if let Some(resp) = response {
-                                let response_text = serde_json::to_string(&resp)?;
+                        Ok(responses) => {
+                            for resp in responses {
+                                let response_text = resp.to_json()?;
                                 println!("Sending response: {}", response_text);
                                 ws_stream.send(Message::Text(response_text.into())).await?;
                             }
                         }
                         Err(e) => {
                             println!("Error processing message: {}", e);
-                            let error_response = json!(["NOTICE", format!("Error: {}", e)]);
-                            let error_text = serde_json::to_string(&error_response)?;
+                            let error_response = RelayMessage::Notice { message: format!("Error: {}", e) };
+                            let error_text = error_response.to_json()?;
                             ws_stream.send(Message::Text(error_text.into())).await?;
                         }
                     }
+
+                    answered_messages += 1;
+                    if self.drop_after_messages == Some(answered_messages) {
+                        println!("Dropping connection");
+                        self.drop_after_messages = None;
+                        return Ok(());
+                    }
                 }
                 Message::Close(_) => {
                     println!("Connection closed");
@@ -80,71 +205,113 @@ impl MockRelay {
         Ok(())
     }
 
-    async fn process_message(&mut self, message: &str) -> Result<Option<Value>> {
-        let parsed: Value = serde_json::from_str(message)?;
+    async fn process_message(&mut self, message: &str) -> Result<Vec<RelayMessage>> {
+        let message = ClientMessage::from_json(message)?;
+        if let Some(log) = &self.message_log {
+            let _ = log.send(message.clone());
+        }
 
-        if let Some(array) = parsed.as_array() {
-            if array.is_empty() {
-                return Ok(None);
-            }
+        let auth_required = self.auth_challenge.is_some() && !self.authenticated;
+
+        match message {
+            ClientMessage::Event(event) if auth_required => Ok(vec![RelayMessage::Ok {
+                event_id: event.id,
+                accepted: false,
+                message: "auth-required: members only".to_string(),
+            }]),
+            ClientMessage::Req { subscription_id, .. } if auth_required => Ok(vec![RelayMessage::Closed {
+                subscription_id,
+                message: "auth-required: members only".to_string(),
+            }]),
+            ClientMessage::Event(event) if !self.rejections.is_empty() => Ok(vec![RelayMessage::Ok {
+                event_id: event.id,
+                accepted: false,
+                message: self.rejections.remove(0),
+            }]),
+            ClientMessage::Auth(event) => Ok(vec![self.handle_auth(event)]),
+            ClientMessage::Event(event) => Ok(self.handle_event(event).await),
+            ClientMessage::Req { subscription_id, filters } => Ok(self.handle_req(&subscription_id, &filters)),
+            ClientMessage::Close { subscription_id } => Ok(vec![RelayMessage::Closed {
+                subscription_id,
+                message: String::new(),
+            }]),
+            ClientMessage::Count { .. } => Ok(vec![RelayMessage::Notice {
+                message: "Unsupported message type".to_string(),
+            }]),
+        }
+    }
 
-            match array[0].as_str() {
-                Some("EVENT") => {
-                    if array.len() >= 2 {
-                        return self.handle_event(&array[1]).await;
-                    }
-                }
-                Some("REQ") => {
-                    if array.len() >= 3 {
-                        let subscription_id = array[1].as_str().unwrap_or("unknown");
-                        return Ok(Some(json!(["EOSE", subscription_id])));
-                    }
-                }
-                Some("CLOSE") => {
-                    if array.len() >= 2 {
-                        let subscription_id = array[1].as_str().unwrap_or("unknown");
-                        return Ok(Some(json!(["CLOSED", subscription_id, ""])));
-                    }
-                }
-                _ => {
-                    return Ok(Some(json!(["NOTICE", "Unknown message type"])));
+    /// Accept a signed kind-22242 event answering our challenge
+    fn handle_auth(&mut self, event: NostrEvent) -> RelayMessage {
+        let answers_challenge = event.tags.iter().any(|tag| {
+            tag.len() >= 2 && tag[0] == "challenge" && Some(&tag[1]) == self.auth_challenge.as_ref()
+        });
+        let accepted = event.kind == 22242 && answers_challenge && event.verify();
+        self.authenticated |= accepted;
+
+        RelayMessage::Ok {
+            event_id: event.id,
+            accepted,
+            message: if accepted { String::new() } else { "invalid: bad auth event".to_string() },
+        }
+    }
+
+    /// Answer a REQ with every stored event matching any of the filters, newest
+    /// first and honoring each filter's limit, followed by EOSE.
+    fn handle_req(&self, subscription_id: &str, filters: &[Filter]) -> Vec<RelayMessage> {
+        let mut stored: Vec<&NostrEvent> = self.events_received.iter().collect();
+        stored.sort_by_key(|event| std::cmp::Reverse(event.created_at));
+
+        let mut matched_ids = Vec::new();
+        let mut responses = Vec::new();
+
+        for filter in filters {
+            let limit = filter.limit.map_or(usize::MAX, |limit| limit as usize);
+
+            for event in stored.iter().filter(|event| filter.matches(event)).take(limit) {
+                if !matched_ids.contains(&event.id) {
+                    matched_ids.push(event.id.clone());
+                    responses.push(RelayMessage::Event {
+                        subscription_id: subscription_id.to_string(),
+                        event: (*event).clone(),
+                    });
                 }
             }
         }
 
-        Ok(None)
+        responses.push(RelayMessage::EndOfStoredEvents { subscription_id: subscription_id.to_string() });
+        responses
     }
 
-    async fn handle_event(&mut self, event_data: &Value) -> Result<Option<Value>> {
-        println!("Processing EVENT: {}", serde_json::to_string_pretty(event_data)?);
-
-        let event: NostrEvent = serde_json::from_value(event_data.clone())?;
+    async fn handle_event(&mut self, event: NostrEvent) -> Vec<RelayMessage> {
+        println!("Processing EVENT: {}", event.id);
 
         let validation_result = self.validate_event(&event).await;
+        let mut responses = self.messages_before_ok.clone();
 
         match validation_result {
             Ok(()) => {
                 println!("✅ Event validation successful");
                 self.events_received.push(event.clone());
 
-                Ok(Some(json!([
-                    "OK",
-                    event.id,
-                    true,
-                    "Event accepted"
-                ])))
+                responses.push(RelayMessage::Ok {
+                    event_id: event.id,
+                    accepted: true,
+                    message: "Event accepted".to_string(),
+                });
             }
             Err(e) => {
                 println!("❌ Event validation failed: {}", e);
 
-                Ok(Some(json!([
-                    "OK",
-                    event.id,
-                    false,
-                    format!("Event rejected: {}", e)
-                ])))
+                responses.push(RelayMessage::Ok {
+                    event_id: event.id,
+                    accepted: false,
+                    message: format!("Event rejected: {}", e),
+                });
             }
         }
+
+        responses
     }
 
     async fn validate_event(&self, event: &NostrEvent) -> Result<()> {
@@ -248,10 +415,12 @@ impl MockRelay {
         }
     }
 
+    #[allow(dead_code)]
     pub fn events_received(&self) -> &[NostrEvent] {
         &self.events_received
     }
 
+    #[allow(dead_code)]
     pub fn event_count(&self) -> usize {
         self.events_received.len()
     }
@@ -264,7 +433,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_mock_relay_basic() -> Result<()> {
-        let mut relay = MockRelay::new().await?;
+        let relay = MockRelay::new().await?;
         println!("Mock relay created on {}", relay.websocket_url());
         assert!(relay.port() > 0);
         Ok(())
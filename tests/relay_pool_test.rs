@@ -0,0 +1,600 @@
+mod mock_relay;
+
+use anyhow::Result;
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::mpsc;
+use tokio::time::timeout;
+
+use mock_relay::MockRelay;
+use nosotros::error::NostrError;
+use nosotros::connection::{AuthPolicy, ReconnectPolicy, RelayNotification, RelayStatus};
+use nosotros::nostr::nip17::{PrivateMessage, wrap_private_message};
+use nosotros::nostr::nip59::KIND_GIFT_WRAP;
+use nosotros::nostr::{ClientMessage, Filter, NostrEvent, RelayMessage, generate_keypair};
+use nosotros::pool::{RelayPool, none_accepted};
+use nosotros::relay_info::fetch_relay_information;
+
+async fn start_mock_relay() -> Result<String> {
+    let mut relay = MockRelay::new().await?;
+    let relay_url = relay.websocket_url();
+
+    tokio::spawn(async move {
+        if let Err(e) = relay.start().await {
+            eprintln!("Relay error: {}", e);
+        }
+    });
+
+    Ok(relay_url)
+}
+
+/// A relay that requires NIP-42 authentication, logging what the client sends
+async fn start_auth_relay() -> Result<(String, mpsc::UnboundedReceiver<ClientMessage>)> {
+    let (log_tx, log_rx) = mpsc::unbounded_channel();
+    let mut relay = MockRelay::new()
+        .await?
+        .with_auth_required("challenge-42")
+        .with_message_log(log_tx);
+    let relay_url = relay.websocket_url();
+
+    tokio::spawn(async move {
+        if let Err(e) = relay.start().await {
+            eprintln!("Relay error: {}", e);
+        }
+    });
+
+    Ok((relay_url, log_rx))
+}
+
+#[tokio::test]
+async fn test_publish_fans_out_to_every_relay() -> Result<()> {
+    let first_relay = start_mock_relay().await?;
+    let second_relay = start_mock_relay().await?;
+    let unreachable_relay = "ws://127.0.0.1:1".to_string();
+
+    let keypair = generate_keypair()?;
+    let event = NostrEvent::new_text_note("Fan-out test".to_string(), &keypair)?;
+
+    let relay_pool = RelayPool::with_reconnect_policy(ReconnectPolicy::none());
+    let outcomes = relay_pool
+        .publish_to(&[first_relay.clone(), second_relay.clone(), unreachable_relay.clone()], &event)
+        .await;
+
+    assert_eq!(outcomes.len(), 3);
+    for outcome in &outcomes {
+        if outcome.relay_url == unreachable_relay {
+            assert!(!outcome.is_accepted(), "Unreachable relay cannot accept the event");
+        } else {
+            assert!(outcome.is_accepted(), "{} should accept: {:?}", outcome.relay_url, outcome.result);
+        }
+    }
+
+    assert_eq!(relay_pool.relay_urls().len(), 3, "publish_to adds missing relays to the pool");
+    assert_eq!(relay_pool.connected_relays(), {
+        let mut connected = vec![first_relay, second_relay];
+        connected.sort();
+        connected
+    });
+
+    relay_pool.shutdown().await;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_subscription_is_multiplexed_over_every_relay() -> Result<()> {
+    let relay_urls = vec![start_mock_relay().await?, start_mock_relay().await?];
+
+    let keypair = generate_keypair()?;
+    let event = NostrEvent::new_text_note("Stored on both relays".to_string(), &keypair)?;
+
+    let relay_pool = RelayPool::new();
+    let outcomes = relay_pool.publish_to(&relay_urls, &event).await;
+    assert!(outcomes.iter().all(|outcome| outcome.is_accepted()));
+
+    let mut notifications = relay_pool.notifications();
+    let subscription_id = relay_pool.subscribe(vec![Filter::new().with_authors(vec![keypair.public_key_hex()])])?;
+
+    let mut events_from = HashSet::new();
+    let mut eose_from = HashSet::new();
+
+    timeout(Duration::from_secs(5), async {
+        while eose_from.len() < relay_urls.len() {
+            if let Ok(RelayNotification::Message { relay_url, message }) = notifications.recv().await {
+                match message {
+                    RelayMessage::Event { subscription_id: id, event: received } if id == subscription_id => {
+                        assert_eq!(received.id, event.id);
+                        events_from.insert(relay_url);
+                    }
+                    RelayMessage::EndOfStoredEvents { subscription_id: id } if id == subscription_id => {
+                        eose_from.insert(relay_url);
+                    }
+                    _ => {}
+                }
+            }
+        }
+    })
+    .await?;
+
+    assert_eq!(events_from.len(), 2, "Both relays should return the stored event");
+
+    relay_pool.shutdown().await;
+    Ok(())
+}
+
+/// A relay logging what the client sends
+async fn start_logged_relay() -> Result<(String, mpsc::UnboundedReceiver<ClientMessage>)> {
+    let (log_tx, log_rx) = mpsc::unbounded_channel();
+    let mut relay = MockRelay::new().await?.with_message_log(log_tx);
+    let relay_url = relay.websocket_url();
+
+    tokio::spawn(async move {
+        if let Err(e) = relay.start().await {
+            eprintln!("Relay error: {}", e);
+        }
+    });
+
+    Ok((relay_url, log_rx))
+}
+
+#[tokio::test]
+async fn test_routed_subscription_only_reaches_its_relays() -> Result<()> {
+    let (first_relay, mut first_log) = start_logged_relay().await?;
+    let (second_relay, mut second_log) = start_logged_relay().await?;
+
+    let relay_pool = RelayPool::new();
+    relay_pool.add_relay(&first_relay)?;
+    relay_pool.subscribe_routed("notes", HashMap::from([(first_relay.clone(), vec![Filter::new().with_kinds(vec![1])])]))?;
+
+    let Some(ClientMessage::Req { subscription_id, filters }) = timeout(Duration::from_secs(5), first_log.recv()).await? else {
+        panic!("Expected a REQ on the routed relay");
+    };
+    assert_eq!((subscription_id.as_str(), filters[0].kinds.clone()), ("notes", Some(vec![1])));
+
+    // Moving the route adds the new relay and closes the subscription on the old one
+    relay_pool.subscribe_routed("notes", HashMap::from([(second_relay.clone(), vec![Filter::new().with_kinds(vec![0])])]))?;
+
+    let Some(ClientMessage::Req { subscription_id, filters }) = timeout(Duration::from_secs(5), second_log.recv()).await? else {
+        panic!("Expected a REQ on the new relay");
+    };
+    assert_eq!((subscription_id.as_str(), filters[0].kinds.clone()), ("notes", Some(vec![0])));
+    let Some(ClientMessage::Close { subscription_id }) = timeout(Duration::from_secs(5), first_log.recv()).await? else {
+        panic!("Expected a CLOSE on the relay left out of the route");
+    };
+    assert_eq!(subscription_id, "notes");
+
+    // Relays no route uses are disconnected
+    relay_pool.prune_relays(&[]).await;
+    assert_eq!(relay_pool.relay_urls(), vec![second_relay]);
+
+    relay_pool.shutdown().await;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_pool_refuses_relays_beyond_its_limit() -> Result<()> {
+    let relay_urls = vec![start_mock_relay().await?, start_mock_relay().await?, start_mock_relay().await?];
+    let keypair = generate_keypair()?;
+    let event = NostrEvent::new_text_note("Capped".to_string(), &keypair)?;
+
+    let relay_pool = RelayPool::with_reconnect_policy(ReconnectPolicy::none()).with_max_relays(2);
+    relay_pool.add_relay(&relay_urls[0])?;
+    relay_pool.add_relay(&relay_urls[0])?;
+
+    let outcomes = relay_pool.publish_to(&relay_urls, &event).await;
+    assert_eq!(outcomes.iter().filter(|outcome| outcome.is_accepted()).count(), 2);
+    assert!(matches!(outcomes[2].rejection(), Some(NostrError::RelayConnectionFailed(_))));
+    assert_eq!(relay_pool.relay_urls().len(), 2);
+
+    // Pruning makes room again
+    relay_pool.prune_relays(std::slice::from_ref(&relay_urls[0])).await;
+    relay_pool.add_relay(&relay_urls[2])?;
+
+    relay_pool.shutdown().await;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_dropped_connection_is_reestablished_and_subscriptions_replayed() -> Result<()> {
+    let (log_tx, mut log_rx) = mpsc::unbounded_channel();
+    let mut relay = MockRelay::new().await?.with_drop_after_messages(1).with_message_log(log_tx);
+    let relay_url = relay.websocket_url();
+
+    tokio::spawn(async move {
+        if let Err(e) = relay.serve(2).await {
+            eprintln!("Relay error: {}", e);
+        }
+    });
+
+    let relay_pool = RelayPool::with_reconnect_policy(ReconnectPolicy {
+        initial_delay: Duration::from_millis(50),
+        max_delay: Duration::from_millis(200),
+        max_retries: None,
+    });
+    let mut notifications = relay_pool.notifications();
+
+    relay_pool.add_relay(&relay_url)?;
+    let subscription_id = relay_pool.subscribe(vec![Filter::new().with_kinds(vec![1])])?;
+
+    let Some(ClientMessage::Req { filters, .. }) = timeout(Duration::from_secs(5), log_rx.recv()).await? else {
+        panic!("Expected the initial REQ");
+    };
+    assert_eq!(filters[0].since, None);
+
+    // The relay answers the REQ with EOSE and then drops the connection
+    let mut statuses = Vec::new();
+    timeout(Duration::from_secs(5), async {
+        while statuses.last() != Some(&RelayStatus::Connected) || !statuses.contains(&RelayStatus::Disconnected) {
+            if let Ok(RelayNotification::StatusChanged { status, .. }) = notifications.recv().await {
+                statuses.push(status);
+            }
+        }
+    })
+    .await?;
+
+    let Some(ClientMessage::Req { subscription_id: replayed_id, filters }) =
+        timeout(Duration::from_secs(5), log_rx.recv()).await?
+    else {
+        panic!("Expected the subscription to be replayed");
+    };
+    assert_eq!(replayed_id, subscription_id);
+    assert_eq!(filters[0].kinds, Some(vec![1]));
+
+    let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+    let since = filters[0].since.expect("Replayed REQ should only ask for missed events");
+    assert!(since <= now && since + 120 >= now, "Unexpected replay since {}", since);
+
+    // The new connection is fully usable
+    let keypair = generate_keypair()?;
+    let event = NostrEvent::new_text_note("After reconnect".to_string(), &keypair)?;
+    let outcomes = relay_pool.publish_to(std::slice::from_ref(&relay_url), &event).await;
+    assert!(outcomes[0].is_accepted(), "{:?}", outcomes[0].result);
+
+    relay_pool.shutdown().await;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_fetch_collects_stored_gift_wraps_for_a_recipient() -> Result<()> {
+    let relay_urls = vec![start_mock_relay().await?, start_mock_relay().await?];
+    let unreachable_relay = "ws://127.0.0.1:1".to_string();
+
+    let alice = generate_keypair()?;
+    let bob = generate_keypair()?;
+    let wraps = wrap_private_message(&alice, &[bob.public_key_hex()], "Meet at noon")?;
+
+    let relay_pool = RelayPool::with_reconnect_policy(ReconnectPolicy::none());
+    for (_, wrap) in &wraps {
+        let outcomes = relay_pool.publish_to(&relay_urls, wrap).await;
+        assert!(outcomes.iter().all(|outcome| outcome.is_accepted()));
+    }
+    relay_pool.add_relay(&unreachable_relay)?;
+
+    let filter = Filter::new()
+        .with_kinds(vec![KIND_GIFT_WRAP])
+        .with_pubkey_refs(vec![bob.public_key_hex()]);
+    let fetched = timeout(Duration::from_secs(5), relay_pool.fetch(vec![filter], Duration::from_secs(10))).await??;
+
+    assert_eq!(fetched.len(), 1, "Bob's wrap is returned once, not once per relay, and Alice's copy not at all");
+    let message = PrivateMessage::from_gift(fetched[0].unwrap_gift(&bob)?)?;
+    assert_eq!(message.sender, alice.public_key_hex());
+    assert_eq!(message.content, "Meet at noon");
+
+    relay_pool.shutdown().await;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_fetch_from_only_queries_the_given_relays() -> Result<()> {
+    let queried_relay = start_mock_relay().await?;
+    let other_relay = start_mock_relay().await?;
+    let keypair = generate_keypair()?;
+
+    let relay_pool = RelayPool::with_reconnect_policy(ReconnectPolicy::none());
+    let only_elsewhere = NostrEvent::new_text_note("Only on the other relay".to_string(), &keypair)?;
+    assert!(relay_pool.publish_to(std::slice::from_ref(&other_relay), &only_elsewhere).await[0].is_accepted());
+    let queried = NostrEvent::new_text_note("On the queried relay".to_string(), &keypair)?;
+    assert!(relay_pool.publish_to(std::slice::from_ref(&queried_relay), &queried).await[0].is_accepted());
+
+    let filter = Filter::new().with_authors(vec![keypair.public_key_hex()]);
+    let fetched = timeout(
+        Duration::from_secs(5),
+        relay_pool.fetch_from(std::slice::from_ref(&queried_relay), vec![filter], Duration::from_secs(10)),
+    )
+    .await??;
+    assert_eq!(fetched.iter().map(|event| &event.id).collect::<Vec<_>>(), vec![&queried.id]);
+
+    relay_pool.shutdown().await;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_fetch_with_relays_reports_the_relay_each_event_came_from() -> Result<()> {
+    let relay_with_note = start_mock_relay().await?;
+    let empty_relay = start_mock_relay().await?;
+    let keypair = generate_keypair()?;
+
+    let relay_pool = RelayPool::with_reconnect_policy(ReconnectPolicy::none());
+    let note = NostrEvent::new_text_note("Stored on one relay".to_string(), &keypair)?;
+    assert!(relay_pool.publish_to(std::slice::from_ref(&relay_with_note), &note).await[0].is_accepted());
+    relay_pool.add_relay(&empty_relay)?;
+
+    let filter = Filter::new().with_ids(vec![note.id.clone()]);
+    let fetched =
+        timeout(Duration::from_secs(5), relay_pool.fetch_with_relays(vec![filter], Duration::from_secs(10))).await??;
+    assert_eq!(fetched.len(), 1);
+    assert_eq!(fetched[0].0.id, note.id);
+    assert_eq!(fetched[0].1, relay_with_note);
+
+    relay_pool.shutdown().await;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_publish_is_retried_after_authenticating() -> Result<()> {
+    let (relay_url, mut log) = start_auth_relay().await?;
+    let keypair = generate_keypair()?;
+    let event = NostrEvent::new_text_note("Members only".to_string(), &keypair)?;
+
+    let relay_pool = RelayPool::with_reconnect_policy(ReconnectPolicy::none());
+    relay_pool.auth().set_keypair(Some(keypair.clone()));
+    relay_pool.auth().set_policy(&relay_url, AuthPolicy::Always);
+
+    let outcomes = relay_pool.publish_to(std::slice::from_ref(&relay_url), &event).await;
+    assert!(outcomes[0].is_accepted(), "Accepted after authenticating: {:?}", outcomes[0].result);
+
+    let mut sent = Vec::new();
+    while let Ok(message) = log.try_recv() {
+        sent.push(message);
+    }
+    assert_eq!(sent.len(), 3, "EVENT, AUTH, then EVENT again: {:?}", sent);
+    match &sent[1] {
+        ClientMessage::Auth(auth) => {
+            assert_eq!(auth.kind, 22242);
+            assert_eq!(auth.pubkey, keypair.public_key_hex());
+            assert!(auth.tags.contains(&vec!["challenge".to_string(), "challenge-42".to_string()]));
+            assert!(auth.tags.contains(&vec!["relay".to_string(), relay_url.clone()]));
+        }
+        other => panic!("Expected AUTH, got {:?}", other),
+    }
+    assert!(matches!(&sent[2], ClientMessage::Event(retried) if retried.id == event.id));
+
+    relay_pool.shutdown().await;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_ask_policy_waits_for_an_answer() -> Result<()> {
+    let (relay_url, _log) = start_auth_relay().await?;
+    let keypair = generate_keypair()?;
+    let event = NostrEvent::new_text_note("Asking first".to_string(), &keypair)?;
+
+    let relay_pool = RelayPool::with_reconnect_policy(ReconnectPolicy::none());
+    relay_pool.auth().set_keypair(Some(keypair.clone()));
+    let mut notifications = relay_pool.notifications();
+
+    let publish = {
+        let relay_pool = relay_pool.clone();
+        let relay_url = relay_url.clone();
+        tokio::spawn(async move { relay_pool.publish_to(&[relay_url], &event).await })
+    };
+
+    let requested = timeout(Duration::from_secs(5), async {
+        loop {
+            if let Ok(RelayNotification::AuthRequested { relay_url, pubkey }) = notifications.recv().await {
+                return (relay_url, pubkey);
+            }
+        }
+    })
+    .await?;
+    assert_eq!(requested, (relay_url.clone(), keypair.public_key_hex()));
+
+    relay_pool.answer_auth(&relay_url, true)?;
+    let outcomes = publish.await?;
+    assert!(outcomes[0].is_accepted(), "Accepted once approved: {:?}", outcomes[0].result);
+
+    relay_pool.shutdown().await;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_never_policy_reports_the_refusal() -> Result<()> {
+    let (relay_url, mut log) = start_auth_relay().await?;
+    let keypair = generate_keypair()?;
+    let event = NostrEvent::new_text_note("Not authenticating".to_string(), &keypair)?;
+
+    let relay_pool = RelayPool::with_reconnect_policy(ReconnectPolicy::none());
+    relay_pool.auth().set_keypair(Some(keypair));
+    relay_pool.auth().set_policy(&relay_url, AuthPolicy::Never);
+
+    let outcomes = relay_pool.publish_to(std::slice::from_ref(&relay_url), &event).await;
+    assert!(!outcomes[0].is_accepted(), "Refused without authentication");
+    assert_eq!(outcomes[0].rejection(), Some(&NostrError::AuthRequired("members only".to_string())));
+
+    while let Ok(message) = log.try_recv() {
+        assert!(!matches!(message, ClientMessage::Auth(_)), "Never sends AUTH");
+    }
+
+    relay_pool.shutdown().await;
+    Ok(())
+}
+
+/// A relay refusing the next events with these messages
+async fn start_refusing_relay(messages: &[&str]) -> Result<String> {
+    let mut relay = MockRelay::new().await?.with_rejections(messages);
+    let relay_url = relay.websocket_url();
+
+    tokio::spawn(async move {
+        if let Err(e) = relay.start().await {
+            eprintln!("Relay error: {}", e);
+        }
+    });
+
+    Ok(relay_url)
+}
+
+#[tokio::test]
+async fn test_publish_is_retried_after_relay_errors_and_rate_limits() -> Result<()> {
+    let relay_url = start_refusing_relay(&["error: database busy", "rate-limited: slow down"]).await?;
+    let keypair = generate_keypair()?;
+    let event = NostrEvent::new_text_note("Third time lucky".to_string(), &keypair)?;
+
+    let relay_pool = RelayPool::with_reconnect_policy(ReconnectPolicy::none());
+    let outcomes = relay_pool.publish_to(std::slice::from_ref(&relay_url), &event).await;
+    assert!(outcomes[0].is_accepted(), "{:?}", outcomes[0].result);
+
+    relay_pool.shutdown().await;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_refusals_are_typed_by_their_prefix() -> Result<()> {
+    let blocked_relay = start_refusing_relay(&["blocked: not on the list", "blocked: not on the list"]).await?;
+    let pow_relay = start_refusing_relay(&["pow: difficulty 0 is less than 20"]).await?;
+    let keypair = generate_keypair()?;
+    let event = NostrEvent::new_text_note("Let me in".to_string(), &keypair)?;
+
+    let relay_pool = RelayPool::with_reconnect_policy(ReconnectPolicy::none());
+    let outcomes = relay_pool.publish_to(&[blocked_relay.clone(), pow_relay], &event).await;
+    assert_eq!(outcomes[0].rejection(), Some(&NostrError::Blocked("not on the list".to_string())));
+    assert_eq!(outcomes[1].rejection(), Some(&NostrError::ProofOfWorkRequired("difficulty 0 is less than 20".to_string())));
+
+    // Blocked isn't worth retrying, so the relay's second refusal is still unused
+    let outcomes = relay_pool.publish_to(std::slice::from_ref(&blocked_relay), &event).await;
+    assert!(!outcomes[0].is_accepted(), "Refused again");
+
+    let error = none_accepted("event", &outcomes);
+    assert_eq!(error.to_string(), "No relay accepted the event");
+    assert_eq!(error.exit_code(), 13);
+
+    relay_pool.shutdown().await;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_subscription_is_reopened_after_authenticating() -> Result<()> {
+    let (relay_url, mut log) = start_auth_relay().await?;
+
+    let relay_pool = RelayPool::with_reconnect_policy(ReconnectPolicy::none());
+    relay_pool.auth().set_keypair(Some(generate_keypair()?));
+    relay_pool.auth().set_policy(&relay_url, AuthPolicy::Always);
+    relay_pool.add_relay(&relay_url)?;
+
+    let filter = Filter::new().with_kinds(vec![1]);
+    let fetched = timeout(Duration::from_secs(5), relay_pool.fetch(vec![filter], Duration::from_secs(10))).await??;
+    assert!(fetched.is_empty());
+
+    let reqs = std::iter::from_fn(|| log.try_recv().ok())
+        .filter(|message| matches!(message, ClientMessage::Req { .. }))
+        .count();
+    assert_eq!(reqs, 2, "REQ is sent again after authenticating");
+
+    relay_pool.shutdown().await;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_relay_information_is_fetched_with_the_nostr_media_type() -> Result<()> {
+    let mut relay = MockRelay::new().await?.with_information(serde_json::json!({
+        "name": "Mock relay",
+        "supported_nips": [1, 11, 42],
+        "limitation": { "max_subscriptions": 20, "auth_required": true }
+    }));
+    let relay_url = relay.websocket_url();
+    tokio::spawn(async move { relay.start().await });
+
+    let information = fetch_relay_information(&reqwest::Client::new(), &relay_url).await?;
+    assert_eq!(information.name.as_deref(), Some("Mock relay"));
+    assert!(information.supports(42));
+    assert_eq!(information.limitation().max_subscriptions, Some(20));
+    assert!(information.limitation().auth_required);
+
+    let relay_pool = RelayPool::with_reconnect_policy(ReconnectPolicy::none());
+    relay_pool.add_relay(&relay_url)?;
+    assert_eq!(relay_pool.relay_information(&relay_url).await, Some(information));
+
+    relay_pool.shutdown().await;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_publish_refuses_events_over_relay_limits() -> Result<()> {
+    let (log_tx, mut log) = mpsc::unbounded_channel();
+    let mut relay = MockRelay::new()
+        .await?
+        .with_information(serde_json::json!({ "limitation": { "max_content_length": 20 } }))
+        .with_message_log(log_tx);
+    let relay_url = relay.websocket_url();
+    tokio::spawn(async move { relay.start().await });
+
+    let keypair = generate_keypair()?;
+    let short = NostrEvent::new_text_note("Short enough".to_string(), &keypair)?;
+    let long = NostrEvent::new_text_note("Far too long for this relay".to_string(), &keypair)?;
+
+    // Publishing doesn't wait for the document, so wait for it here
+    let relay_pool = RelayPool::with_reconnect_policy(ReconnectPolicy::none());
+    relay_pool.add_relay(&relay_url)?;
+    assert!(relay_pool.relay_information(&relay_url).await.is_some());
+
+    let outcomes = relay_pool.publish_to(std::slice::from_ref(&relay_url), &long).await;
+    let error = outcomes[0].result.as_ref().expect_err("Refused before sending");
+    assert!(error.to_string().contains("relay allows 20"), "{}", error);
+
+    let outcomes = relay_pool.publish_to(std::slice::from_ref(&relay_url), &short).await;
+    assert!(outcomes[0].is_accepted(), "{:?}", outcomes[0].result);
+
+    let sent: Vec<String> = std::iter::from_fn(|| log.try_recv().ok())
+        .filter_map(|message| match message {
+            ClientMessage::Event(event) => Some(event.id),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(sent, vec![short.id], "The long note never reaches the relay");
+
+    relay_pool.shutdown().await;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_subscriptions_beyond_the_relay_limit_are_reported_closed() -> Result<()> {
+    let (log_tx, mut log) = mpsc::unbounded_channel();
+    let mut relay = MockRelay::new()
+        .await?
+        .with_information(serde_json::json!({ "limitation": { "max_subscriptions": 1 } }))
+        .with_message_log(log_tx);
+    let relay_url = relay.websocket_url();
+    tokio::spawn(async move { relay.start().await });
+
+    let relay_pool = RelayPool::with_reconnect_policy(ReconnectPolicy::none());
+    relay_pool.add_relay(&relay_url)?;
+    assert!(relay_pool.relay_information(&relay_url).await.is_some());
+    let mut notifications = relay_pool.notifications();
+
+    relay_pool.subscribe_with_id("first", vec![Filter::new().with_kinds(vec![1])])?;
+    relay_pool.subscribe_with_id("second", vec![Filter::new().with_kinds(vec![0])])?;
+
+    let closed = timeout(Duration::from_secs(5), async {
+        loop {
+            if let Ok(RelayNotification::Message { message: RelayMessage::Closed { subscription_id, message }, .. }) =
+                notifications.recv().await
+            {
+                return (subscription_id, message);
+            }
+        }
+    })
+    .await?;
+    assert_eq!(closed.0, "second");
+    assert!(closed.1.contains("allows 1"), "{}", closed.1);
+
+    let Some(ClientMessage::Req { subscription_id, .. }) = timeout(Duration::from_secs(5), log.recv()).await? else {
+        panic!("Expected the first REQ");
+    };
+    assert_eq!(subscription_id, "first");
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert!(
+        !std::iter::from_fn(|| log.try_recv().ok()).any(|message| matches!(message, ClientMessage::Req { .. })),
+        "The second REQ never reaches the relay"
+    );
+
+    relay_pool.shutdown().await;
+    Ok(())
+}
@@ -0,0 +1,23 @@
+use std::path::PathBuf;
+use uuid::Uuid;
+
+/// A throwaway config directory, removed when dropped
+pub struct TempConfigDir(pub PathBuf);
+
+impl TempConfigDir {
+    pub fn new() -> Self {
+        Self(std::env::temp_dir().join(format!("nosotros-test-{}", Uuid::new_v4())))
+    }
+}
+
+impl Default for TempConfigDir {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for TempConfigDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}